@@ -139,7 +139,9 @@ impl From<EdgeVecError> for JsValue {
                 PersistenceError::Io(e) => ("ERR_IO", e.to_string()),
                 PersistenceError::ChecksumMismatch { .. }
                 | PersistenceError::Corrupted(_)
-                | PersistenceError::InvalidMagic { .. } => ("ERR_CORRUPTION", pe.to_string()),
+                | PersistenceError::InvalidMagic { .. }
+                | PersistenceError::Tampered { .. } => ("ERR_CORRUPTION", pe.to_string()),
+                PersistenceError::WrongKey => ("ERR_WRONG_KEY", pe.to_string()),
                 _ => ("ERR_PERSISTENCE", pe.to_string()),
             },
 
@@ -0,0 +1,524 @@
+//! Exact brute-force index.
+//!
+//! `FlatIndex` scans every live vector in [`VectorStorage`] on each query, so
+//! it always returns the true nearest neighbors (100% recall). Inserts are a
+//! plain storage append with no graph to maintain, which makes it a good fit
+//! for small collections (up to tens of thousands of vectors).
+//!
+//! Once a collection grows past the point where a linear scan is fast enough,
+//! [`FlatIndex::into_hnsw`] builds an [`HnswIndex`] over the same storage,
+//! keeping every `VectorId` and its metadata.
+//!
+//! # Soft Delete
+//!
+//! Deleted vectors are tombstoned in `VectorStorage` itself, so snapshots
+//! carry them in the existing tombstone bitvec.
+//!
+//! # Durability
+//!
+//! With a WAL attached to the storage, inserts, soft deletes and metadata
+//! are logged before they are applied. Recover a crashed index with
+//! [`recover_flat_index`](crate::persistence::recover_flat_index).
+//!
+//! # Example
+//!
+//! ```
+//! use edgevec::flat::FlatIndex;
+//! use edgevec::hnsw::HnswConfig;
+//! use edgevec::storage::VectorStorage;
+//!
+//! let config = HnswConfig::new(2);
+//! let mut storage = VectorStorage::new(&config, None);
+//! let mut index = FlatIndex::new(config, &storage).unwrap();
+//!
+//! let a = index.insert(&[0.0, 0.0], &mut storage).unwrap();
+//! let b = index.insert(&[1.0, 1.0], &mut storage).unwrap();
+//!
+//! let results = index.search(&[0.9, 0.9], 1, &storage).unwrap();
+//! assert_eq!(results[0].vector_id, b);
+//!
+//! index.soft_delete(b, &mut storage).unwrap();
+//! let results = index.search(&[0.9, 0.9], 1, &storage).unwrap();
+//! assert_eq!(results[0].vector_id, a);
+//! ```
+
+use crate::hnsw::graph::VectorProvider;
+use crate::hnsw::{GraphError, HnswConfig, HnswIndex, SearchResult, VectorId};
+use crate::metadata::{MetadataError, MetadataStore, MetadataValue};
+use crate::metric::{Cosine, L2Squared, Metric, NegativeDotProduct};
+use crate::persistence::entry::WalEntry;
+use crate::persistence::replay::{metadata_set_payload, soft_delete_payload};
+use crate::storage::VectorStorage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Exact nearest-neighbor index over `VectorStorage`.
+///
+/// Every vector in the storage belongs to the index; `VectorId`s are the
+/// storage-assigned IDs.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FlatIndex {
+    /// Index configuration (only `dimensions` and `metric` are used).
+    pub config: HnswConfig,
+
+    /// Metadata attached to vectors, keyed like `HnswIndex` metadata.
+    #[serde(default)]
+    pub(crate) metadata: MetadataStore,
+
+    /// Number of vectors in the index, including tombstones.
+    vector_count: usize,
+
+    /// Number of tombstoned vectors.
+    deleted_count: usize,
+}
+
+impl FlatIndex {
+    /// Creates a flat index over `storage`.
+    ///
+    /// Vectors already in `storage` become part of the index.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::ConfigMismatch` if the storage dimensions differ
+    /// from the config.
+    pub fn new(config: HnswConfig, storage: &VectorStorage) -> Result<Self, GraphError> {
+        if config.dimensions != storage.dimensions() {
+            return Err(GraphError::ConfigMismatch {
+                expected: storage.dimensions(),
+                actual: config.dimensions,
+            });
+        }
+
+        Ok(Self::from_parts(config, storage, MetadataStore::new()))
+    }
+
+    /// Assembles an index from already-validated parts (used by snapshots).
+    pub(crate) fn from_parts(
+        config: HnswConfig,
+        storage: &VectorStorage,
+        metadata: MetadataStore,
+    ) -> Self {
+        Self {
+            config,
+            metadata,
+            vector_count: storage.len(),
+            deleted_count: storage.deleted.count_ones(),
+        }
+    }
+
+    /// Appends a vector to the index.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::DimensionMismatch` if the vector has the wrong
+    /// length, or `GraphError::Storage` if the storage (or its WAL) rejects it.
+    pub fn insert(
+        &mut self,
+        vector: &[f32],
+        storage: &mut VectorStorage,
+    ) -> Result<VectorId, GraphError> {
+        let expected = self.config.dimensions as usize;
+        if vector.len() != expected {
+            return Err(GraphError::DimensionMismatch {
+                expected,
+                actual: vector.len(),
+            });
+        }
+
+        let vector_id = storage
+            .insert(vector)
+            .map_err(|e| GraphError::Storage(e.to_string()))?;
+        self.vector_count += 1;
+        Ok(vector_id)
+    }
+
+    /// Appends a vector with metadata attached.
+    ///
+    /// Metadata is validated before anything is written, as in
+    /// [`HnswIndex::insert_with_metadata`].
+    ///
+    /// # Errors
+    ///
+    /// * `GraphError::MetadataValidation` - Invalid metadata
+    /// * Any error from [`insert`](Self::insert)
+    #[allow(clippy::missing_panics_doc)] // Metadata is validated before insertion
+    pub fn insert_with_metadata(
+        &mut self,
+        storage: &mut VectorStorage,
+        vector: &[f32],
+        metadata: HashMap<String, MetadataValue>,
+    ) -> Result<VectorId, GraphError> {
+        use crate::metadata::validation::{validate_key_value, MAX_KEYS_PER_VECTOR};
+
+        if metadata.len() > MAX_KEYS_PER_VECTOR {
+            return Err(GraphError::MetadataValidation(MetadataError::TooManyKeys {
+                vector_id: 0, // Unknown yet
+                count: metadata.len(),
+                max: MAX_KEYS_PER_VECTOR,
+            }));
+        }
+        for (key, value) in &metadata {
+            validate_key_value(key, value)?;
+        }
+
+        let vector_id = self.insert(vector, storage)?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        let metadata_id = vector_id.0 as u32;
+        for (key, value) in metadata {
+            if storage.has_wal() {
+                let payload =
+                    metadata_set_payload(vector_id, &key, &value).map_err(GraphError::Storage)?;
+                storage
+                    .log_entry(WalEntry::METADATA_SET, &payload)
+                    .map_err(|e| GraphError::Storage(e.to_string()))?;
+            }
+
+            self.metadata
+                .insert(metadata_id, &key, value)
+                .expect("pre-validated metadata should not fail");
+        }
+
+        Ok(vector_id)
+    }
+
+    /// Marks a vector as deleted and drops its metadata.
+    ///
+    /// If the storage has a WAL, the delete is logged before anything is
+    /// changed.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - Vector was deleted
+    /// * `Ok(false)` - Vector was already deleted
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::InvalidVectorId` if the ID is not in the index,
+    /// or `GraphError::Storage` if the WAL write fails.
+    pub fn soft_delete(
+        &mut self,
+        vector_id: VectorId,
+        storage: &mut VectorStorage,
+    ) -> Result<bool, GraphError> {
+        if !self.contains_id(vector_id) {
+            return Err(GraphError::InvalidVectorId);
+        }
+        if storage.is_deleted(vector_id) {
+            return Ok(false);
+        }
+
+        storage
+            .log_entry(WalEntry::SOFT_DELETE, &soft_delete_payload(vector_id))
+            .map_err(|e| GraphError::Storage(e.to_string()))?;
+        storage.mark_deleted(vector_id);
+
+        self.deleted_count += 1;
+        #[allow(clippy::cast_possible_truncation)]
+        self.metadata.delete_all(vector_id.0 as u32);
+        Ok(true)
+    }
+
+    /// Returns true if `vector_id` belongs to the index (live or deleted).
+    #[must_use]
+    pub fn contains_id(&self, vector_id: VectorId) -> bool {
+        #[allow(clippy::cast_possible_truncation)]
+        let id = vector_id.0 as usize;
+        vector_id != VectorId::INVALID && id <= self.vector_count
+    }
+
+    /// Searches for the `k` nearest live vectors by exhaustive scan.
+    ///
+    /// Results are sorted by distance (ascending), with ties broken by
+    /// `VectorId`. For dot product the distance is the negated dot product,
+    /// so the most similar vector comes first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError` if the query dimension mismatches or the config
+    /// metric is invalid.
+    ///
+    /// # Complexity
+    ///
+    /// * Time: O(n · d) where n = vector count, d = dimensions
+    /// * Space: O(n)
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        storage: &VectorStorage,
+    ) -> Result<Vec<SearchResult>, GraphError> {
+        self.scan(query, k, storage, |_| true)
+    }
+
+    /// Exact search restricted to vectors whose metadata matches `filter`.
+    ///
+    /// Accepts the same filter syntax and returns the same shape as
+    /// [`HnswIndex::search_filtered`]. No overfetch is needed: the filter is
+    /// evaluated for every live vector, so up to `k` matches are always found
+    /// when they exist.
+    ///
+    /// # Errors
+    ///
+    /// * `GraphError::FilterParse` - Invalid filter syntax
+    /// * Other `GraphError` variants from [`search`](Self::search)
+    pub fn search_filtered(
+        &self,
+        storage: &VectorStorage,
+        query: &[f32],
+        filter: &str,
+        k: usize,
+    ) -> Result<Vec<(VectorId, f32)>, GraphError> {
+        use crate::filter::{evaluate, parse};
+
+        let expr = parse(filter).map_err(|e| GraphError::FilterParse(e.to_string()))?;
+        let empty = HashMap::new();
+
+        let results = self.scan(query, k, storage, |vector_id| {
+            #[allow(clippy::cast_possible_truncation)]
+            let metadata = self.metadata.get_all(vector_id.0 as u32).unwrap_or(&empty);
+
+            // Per RFC-002: evaluation errors count as a filter miss
+            evaluate(&expr, metadata).unwrap_or_else(|e| {
+                log::debug!("Filter evaluation failed for vector {}: {}", vector_id.0, e);
+                false
+            })
+        })?;
+
+        Ok(results
+            .into_iter()
+            .map(|r| (r.vector_id, r.distance))
+            .collect())
+    }
+
+    /// Dispatches the scan on the configured metric.
+    fn scan<F: Fn(VectorId) -> bool>(
+        &self,
+        query: &[f32],
+        k: usize,
+        storage: &VectorStorage,
+        accept: F,
+    ) -> Result<Vec<SearchResult>, GraphError> {
+        if query.len() != self.config.dimensions as usize {
+            return Err(GraphError::DimensionMismatch {
+                expected: self.config.dimensions as usize,
+                actual: query.len(),
+            });
+        }
+
+        match self.config.metric {
+            HnswConfig::METRIC_L2_SQUARED => {
+                Ok(self.scan_impl::<L2Squared, F>(query, k, storage, accept))
+            }
+            HnswConfig::METRIC_DOT_PRODUCT => {
+                Ok(self.scan_impl::<NegativeDotProduct, F>(query, k, storage, accept))
+            }
+            HnswConfig::METRIC_COSINE => Ok(self.scan_impl::<Cosine, F>(query, k, storage, accept)),
+            _ => Err(GraphError::InvalidConfig(format!(
+                "unsupported metric code: {}",
+                self.config.metric
+            ))),
+        }
+    }
+
+    /// Generic exhaustive scan for a specific metric.
+    ///
+    /// Distances match `HnswIndex::search`: quantized storage uses the u8
+    /// kernel for L2, everything else the f32 metric.
+    fn scan_impl<M: Metric<f32>, F: Fn(VectorId) -> bool>(
+        &self,
+        query: &[f32],
+        k: usize,
+        storage: &VectorStorage,
+        accept: F,
+    ) -> Vec<SearchResult> {
+        if k == 0 {
+            return Vec::new();
+        }
+
+        let mut quantized_query = Vec::new();
+        let use_quantized = storage
+            .quantize_query(query, &mut quantized_query)
+            .is_some();
+
+        let mut results = Vec::with_capacity(self.len());
+        for idx in 1..=self.vector_count {
+            let vector_id = VectorId(idx as u64);
+            if storage.is_deleted(vector_id) || !accept(vector_id) {
+                continue;
+            }
+
+            let distance = match VectorProvider::get_quantized_vector(storage, vector_id) {
+                Some(q_vec) if use_quantized => {
+                    storage.quantized_distance(self.config.metric, &quantized_query, q_vec)
+                }
+                _ => M::distance(query, &storage.get_vector(vector_id)),
+            };
+            results.push(SearchResult {
+                vector_id,
+                distance,
+            });
+        }
+
+        let by_distance = |a: &SearchResult, b: &SearchResult| {
+            a.distance
+                .total_cmp(&b.distance)
+                .then(a.vector_id.0.cmp(&b.vector_id.0))
+        };
+        if results.len() > k {
+            results.select_nth_unstable_by(k - 1, by_distance);
+            results.truncate(k);
+        }
+        results.sort_by(by_distance);
+        results
+    }
+
+    /// Builds an `HnswIndex` over the same storage.
+    ///
+    /// Every vector keeps its `VectorId` and metadata. Tombstoned vectors
+    /// become unlinked, deleted nodes so the graph still has one node per
+    /// storage slot (as snapshots require); `compact()` drops them.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError` if the graph cannot be built (e.g. invalid metric).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::flat::FlatIndex;
+    /// use edgevec::hnsw::HnswConfig;
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(2);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let mut flat = FlatIndex::new(config, &storage).unwrap();
+    /// let id = flat.insert(&[1.0, 2.0], &mut storage).unwrap();
+    ///
+    /// let hnsw = flat.into_hnsw(&storage).unwrap();
+    /// let results = hnsw.search(&[1.0, 2.0], 1, &storage).unwrap();
+    /// assert_eq!(results[0].vector_id, id);
+    /// ```
+    pub fn into_hnsw(self, storage: &VectorStorage) -> Result<HnswIndex, GraphError> {
+        let mut index = HnswIndex::new(self.config, storage)?;
+
+        for idx in 1..=self.vector_count {
+            let vector_id = VectorId(idx as u64);
+            if storage.is_deleted(vector_id) {
+                // Already persisted in the storage tombstones: mark without logging
+                index.add_node(vector_id, 0)?;
+                index.tombstone(vector_id)?;
+            } else {
+                index.link_existing(vector_id, storage)?;
+            }
+        }
+
+        index.metadata = self.metadata;
+        Ok(index)
+    }
+
+    /// Returns the number of live vectors.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.vector_count - self.deleted_count
+    }
+
+    /// Returns true if the index has no live vectors.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of tombstoned vectors.
+    #[must_use]
+    pub fn deleted_count(&self) -> usize {
+        self.deleted_count
+    }
+
+    /// Returns a reference to the metadata store.
+    #[must_use]
+    pub fn metadata(&self) -> &MetadataStore {
+        &self.metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(points: &[[f32; 2]]) -> (FlatIndex, VectorStorage) {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let mut index = FlatIndex::new(config, &storage).unwrap();
+        for p in points {
+            index.insert(p, &mut storage).unwrap();
+        }
+        (index, storage)
+    }
+
+    #[test]
+    fn test_search_is_exact_and_sorted() {
+        let (index, storage) = build(&[[0.0, 0.0], [3.0, 0.0], [1.0, 0.0], [2.0, 0.0]]);
+
+        let results = index.search(&[0.0, 0.0], 3, &storage).unwrap();
+        let ids: Vec<u64> = results.iter().map(|r| r.vector_id.0).collect();
+        assert_eq!(ids, vec![1, 3, 4]);
+        assert!((results[2].distance - 4.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_ties_broken_by_vector_id() {
+        let (index, storage) = build(&[[1.0, 0.0], [0.0, 1.0], [-1.0, 0.0]]);
+        let results = index.search(&[0.0, 0.0], 2, &storage).unwrap();
+        assert_eq!(results[0].vector_id, VectorId(1));
+        assert_eq!(results[1].vector_id, VectorId(2));
+    }
+
+    #[test]
+    fn test_soft_delete() {
+        let (mut index, mut storage) = build(&[[0.0, 0.0], [1.0, 1.0]]);
+
+        assert!(index.soft_delete(VectorId(1), &mut storage).unwrap());
+        assert!(!index.soft_delete(VectorId(1), &mut storage).unwrap());
+        assert_eq!(
+            index.soft_delete(VectorId(9), &mut storage),
+            Err(GraphError::InvalidVectorId)
+        );
+
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.deleted_count(), 1);
+        let results = index.search(&[0.0, 0.0], 5, &storage).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].vector_id, VectorId(2));
+    }
+
+    #[test]
+    fn test_dimension_checks() {
+        let (mut index, mut storage) = build(&[]);
+        assert!(matches!(
+            index.insert(&[1.0], &mut storage),
+            Err(GraphError::DimensionMismatch { .. })
+        ));
+        assert!(matches!(
+            index.search(&[1.0, 2.0, 3.0], 1, &storage),
+            Err(GraphError::DimensionMismatch { .. })
+        ));
+        assert!(index.search(&[1.0, 2.0], 1, &storage).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dot_product_metric_ranks_most_similar_first() {
+        let mut config = HnswConfig::new(2);
+        config.metric = HnswConfig::METRIC_DOT_PRODUCT;
+        let mut storage = VectorStorage::new(&config, None);
+        let mut index = FlatIndex::new(config, &storage).unwrap();
+        let low = index.insert(&[1.0, 0.0], &mut storage).unwrap();
+        let high = index.insert(&[5.0, 0.0], &mut storage).unwrap();
+
+        let results = index.search(&[2.0, 0.0], 2, &storage).unwrap();
+        let ids: Vec<VectorId> = results.iter().map(|r| r.vector_id).collect();
+        assert_eq!(ids, vec![high, low]);
+        let distances: Vec<f32> = results.iter().map(|r| r.distance).collect();
+        assert_eq!(distances, vec![-10.0, -2.0]);
+    }
+}
@@ -41,7 +41,9 @@ pub struct HnswConfig {
 impl HnswConfig {
     /// Metric code for L2 Squared distance.
     pub const METRIC_L2_SQUARED: u32 = 0;
-    /// Metric code for Cosine Similarity.
+    /// Metric code for Cosine distance (`1 - cosine_similarity`).
+    ///
+    /// Vectors and queries do not need to be normalized.
     pub const METRIC_COSINE: u32 = 1;
     /// Metric code for Dot Product.
     pub const METRIC_DOT_PRODUCT: u32 = 2;
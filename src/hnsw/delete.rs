@@ -0,0 +1,491 @@
+//! Hard delete with neighbor repair.
+//!
+//! `soft_delete` only flags a node; the tombstone keeps routing traffic until
+//! `compact()` rebuilds the whole index. `hard_delete` instead unlinks the
+//! node from every neighbor list, reconnects the neighbors that pointed to it
+//! and frees its slot in the `NeighborPool`.
+//!
+//! The `HnswNode` itself stays in `nodes` (node IDs are positional) with
+//! `deleted = 1` and an empty neighbor list, so it is unreachable and is
+//! dropped by the next `compact()`.
+//!
+//! Links are stored one way, so finding the nodes that point to the target
+//! needs `ReverseLinks`. They are derived with one scan on the first hard
+//! delete and kept up to date by every later neighbor list write.
+
+use super::config::HnswConfig;
+use super::graph::{GraphError, HnswIndex, NodeId, VectorId, VectorProvider};
+use super::search::Candidate;
+use crate::metric::{Cosine, L2Squared, Metric, NegativeDotProduct};
+use crate::persistence::entry::WalEntry;
+use crate::persistence::replay::soft_delete_payload;
+use crate::storage::VectorStorage;
+
+/// Incoming links of every node, used to find the lists to repair.
+///
+/// Not persisted: an index loaded from a snapshot derives them again on its
+/// first hard delete.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ReverseLinks {
+    /// `incoming[target]` holds `(source, layer)` for every link to `target`.
+    incoming: Vec<Vec<(NodeId, u8)>>,
+}
+
+impl ReverseLinks {
+    /// Derives the reverse links of every node with one scan of the graph.
+    fn build(index: &HnswIndex) -> Result<Self, GraphError> {
+        let mut links = Self::default();
+        for (idx, node) in index.nodes.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let source = NodeId(idx as u32);
+            if node.neighbor_len == 0 {
+                continue;
+            }
+            for lc in 0..=node.max_layer {
+                for target in index.get_neighbors_layer(node, lc)? {
+                    links.add(source, lc, target);
+                }
+            }
+        }
+        Ok(links)
+    }
+
+    fn add(&mut self, source: NodeId, layer: u8, target: NodeId) {
+        let idx = target.0 as usize;
+        if idx >= self.incoming.len() {
+            self.incoming.resize_with(idx + 1, Vec::new);
+        }
+        self.incoming[idx].push((source, layer));
+    }
+
+    fn remove(&mut self, source: NodeId, layer: u8, target: NodeId) {
+        if let Some(list) = self.incoming.get_mut(target.0 as usize) {
+            if let Some(pos) = list.iter().position(|&link| link == (source, layer)) {
+                list.swap_remove(pos);
+            }
+        }
+    }
+
+    /// Nodes linking to `target` on `layer`, in ascending order.
+    fn sources(&self, target: NodeId, layer: u8) -> Vec<NodeId> {
+        let mut sources: Vec<NodeId> = self
+            .incoming
+            .get(target.0 as usize)
+            .into_iter()
+            .flatten()
+            .filter(|&&(_, lc)| lc == layer)
+            .map(|&(source, _)| source)
+            .collect();
+        sources.sort_unstable_by_key(|n| n.0);
+        sources
+    }
+}
+
+impl HnswIndex {
+    /// Deletes a vector and repairs the graph around it.
+    ///
+    /// Unlike [`soft_delete`](Self::soft_delete), the node stops routing
+    /// searches immediately:
+    ///
+    /// 1. Every neighbor list that points to the node is rewritten without
+    ///    it. The orphaned neighbor is reconnected to the best candidates
+    ///    among its remaining neighbors and the deleted node's neighbors,
+    ///    chosen with the same heuristic as insertion.
+    /// 2. The node's own neighbor lists are freed in the `NeighborPool`.
+    /// 3. If the node was the entry point, the live node with the highest
+    ///    layer takes over.
+    ///
+    /// Metadata and the external ID are released as with `soft_delete`. The
+    /// node still counts as a tombstone until `compact()` reclaims its
+    /// vector slot. Calling this on a soft-deleted vector unlinks the
+    /// tombstone.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - Vector was live and is now deleted
+    /// * `Ok(false)` - Vector was already deleted (any remaining links are removed)
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidVectorId` - Vector ID not found
+    /// * `InvalidConfig` - Config metric is invalid
+    /// * `Storage` - The WAL write failed; the index is unchanged
+    /// * `NeighborError` / `NodeIdOutOfBounds` - Internal graph corruption
+    ///
+    /// # Complexity
+    ///
+    /// * Time: O(M²) repair per affected neighbor. The first call also
+    ///   scans the graph once, O(n · L), to derive the reverse links.
+    /// * Space: O(M) per layer, plus one entry per link once reverse links
+    ///   are derived
+    ///
+    /// # Durability
+    ///
+    /// If the storage has a WAL, the delete is logged before the graph is
+    /// touched, so it survives a crash before the next snapshot.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(2);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    ///
+    /// let a = index.insert(&[0.0, 0.0], &mut storage).unwrap();
+    /// let b = index.insert(&[1.0, 0.0], &mut storage).unwrap();
+    ///
+    /// assert!(index.hard_delete(a, &mut storage).unwrap());
+    /// assert!(index.is_deleted(a).unwrap());
+    ///
+    /// let results = index.search(&[0.0, 0.0], 1, &storage).unwrap();
+    /// assert_eq!(results[0].vector_id, b);
+    /// ```
+    pub fn hard_delete(
+        &mut self,
+        vector_id: VectorId,
+        storage: &mut VectorStorage,
+    ) -> Result<bool, GraphError> {
+        match self.config.metric {
+            HnswConfig::METRIC_L2_SQUARED => self.hard_delete_impl::<L2Squared>(vector_id, storage),
+            HnswConfig::METRIC_DOT_PRODUCT => {
+                self.hard_delete_impl::<NegativeDotProduct>(vector_id, storage)
+            }
+            HnswConfig::METRIC_COSINE => self.hard_delete_impl::<Cosine>(vector_id, storage),
+            _ => Err(GraphError::InvalidConfig(format!(
+                "unsupported metric code: {}",
+                self.config.metric
+            ))),
+        }
+    }
+
+    /// Generic implementation of hard delete for a specific metric.
+    fn hard_delete_impl<M: Metric<f32>>(
+        &mut self,
+        vector_id: VectorId,
+        storage: &mut VectorStorage,
+    ) -> Result<bool, GraphError> {
+        let target = self
+            .node_id_of(vector_id)
+            .ok_or(GraphError::InvalidVectorId)?;
+
+        storage
+            .log_entry(WalEntry::HARD_DELETE, &soft_delete_payload(vector_id))
+            .map_err(|e| GraphError::Storage(e.to_string()))?;
+
+        if self.reverse_links.is_none() {
+            self.reverse_links = Some(ReverseLinks::build(self)?);
+        }
+
+        // Step 1: Tombstone first so the node is never chosen during repair
+        let was_live = self.tombstone(vector_id)?;
+
+        let target_node = *self.get_node(target).ok_or(GraphError::NodeIdOutOfBounds)?;
+
+        // Step 2: Repair every node that links to the target, layer by layer
+        for lc in 0..=target_node.max_layer {
+            let orphans = self.get_neighbors_layer(&target_node, lc)?;
+            let sources = self
+                .reverse_links
+                .as_ref()
+                .map(|links| links.sources(target, lc))
+                .unwrap_or_default();
+
+            for source in sources {
+                if source == target {
+                    continue;
+                }
+                let node = *self.get_node(source).ok_or(GraphError::NodeIdOutOfBounds)?;
+                let current = self.get_neighbors_layer(&node, lc)?;
+                self.repair_neighbors::<M>(source, lc, target, &current, &orphans, storage)?;
+            }
+        }
+
+        // Step 3: Free the target's own lists
+        let before = self.links_before_write(target)?;
+        let node = &mut self.nodes.to_mut()[target.0 as usize];
+        if node.neighbor_len > 0 {
+            self.neighbors.free(node.neighbor_offset, node.neighbor_len);
+        }
+        node.neighbor_offset = 0;
+        node.neighbor_len = 0;
+        self.links_after_write(target, before)?;
+
+        // Step 4: Hand the entry point over if needed
+        if self.entry_point == Some(target) {
+            self.reassign_entry_point();
+        }
+
+        Ok(was_live)
+    }
+
+    /// Helper: Rebuild `source`'s list on `layer` without `removed`.
+    ///
+    /// Candidates are the current neighbors plus the removed node's live
+    /// neighbors; the best `M` are kept using the insertion heuristic.
+    fn repair_neighbors<M: Metric<f32>>(
+        &mut self,
+        source: NodeId,
+        layer: u8,
+        removed: NodeId,
+        current: &[NodeId],
+        orphans: &[NodeId],
+        storage: &VectorStorage,
+    ) -> Result<(), GraphError> {
+        let source_vid = self
+            .get_node(source)
+            .ok_or(GraphError::NodeIdOutOfBounds)?
+            .vector_id;
+
+        let use_quantized =
+            VectorProvider::get_quantized_vector(storage, VectorId::FIRST).is_some();
+        let source_vec = storage.get_vector(source_vid);
+
+        let mut candidates: Vec<Candidate> = Vec::with_capacity(current.len() + orphans.len());
+        for &n_id in current.iter().chain(orphans) {
+            if n_id == source || n_id == removed || candidates.iter().any(|c| c.node_id == n_id) {
+                continue;
+            }
+            let n_node = self.get_node(n_id).ok_or(GraphError::NodeIdOutOfBounds)?;
+            // Existing links are kept as-is; new links only go to live nodes
+            let is_existing = current.contains(&n_id);
+            if n_node.max_layer < layer || (!is_existing && n_node.deleted != 0) {
+                continue;
+            }
+
+            let distance = if use_quantized {
+                let a = VectorProvider::get_quantized_vector(storage, source_vid)
+                    .ok_or_else(|| GraphError::Storage("Missing quantized data".into()))?;
+                let b = VectorProvider::get_quantized_vector(storage, n_node.vector_id)
+                    .ok_or_else(|| GraphError::Storage("Missing quantized data".into()))?;
+                storage.quantized_distance(self.config.metric, a, b)
+            } else {
+                M::distance(&source_vec, &storage.get_vector(n_node.vector_id))
+            };
+
+            candidates.push(Candidate {
+                distance,
+                node_id: n_id,
+            });
+        }
+        candidates.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+
+        let m_max = if layer == 0 {
+            self.config.m0
+        } else {
+            self.config.m
+        } as usize;
+
+        let mut selected = Vec::with_capacity(m_max);
+        self.select_neighbors_heuristic::<M>(
+            &source_vec,
+            &candidates,
+            m_max,
+            layer,
+            storage,
+            &mut selected,
+        )?;
+
+        let raw: Vec<u32> = selected.iter().map(|n| n.0).collect();
+        self.replace_layer_neighbors(source, layer, &raw)
+    }
+
+    /// Helper: Capture `node`'s lists before they are rewritten.
+    ///
+    /// Returns `None` (and costs nothing) while reverse links are not
+    /// tracked. Pass the result to [`links_after_write`](Self::links_after_write).
+    pub(super) fn links_before_write(
+        &self,
+        node: NodeId,
+    ) -> Result<Option<Vec<Vec<NodeId>>>, GraphError> {
+        if self.reverse_links.is_none() {
+            return Ok(None);
+        }
+        self.layer_lists(node).map(Some)
+    }
+
+    /// Helper: Bring reverse links up to date after `node`'s lists changed.
+    pub(super) fn links_after_write(
+        &mut self,
+        node: NodeId,
+        before: Option<Vec<Vec<NodeId>>>,
+    ) -> Result<(), GraphError> {
+        let Some(before) = before else {
+            return Ok(());
+        };
+        let after = self.layer_lists(node)?;
+        let Some(links) = self.reverse_links.as_mut() else {
+            return Ok(());
+        };
+
+        for (lc, old) in before.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let layer = lc as u8;
+            let new = after.get(lc).map_or(&[][..], Vec::as_slice);
+            for &target in old.iter().filter(|t| !new.contains(t)) {
+                links.remove(node, layer, target);
+            }
+        }
+        for (lc, new) in after.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let layer = lc as u8;
+            let old = before.get(lc).map_or(&[][..], Vec::as_slice);
+            for &target in new.iter().filter(|t| !old.contains(t)) {
+                links.add(node, layer, target);
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper: Decode every layer of `node`'s neighbor lists.
+    fn layer_lists(&self, node: NodeId) -> Result<Vec<Vec<NodeId>>, GraphError> {
+        let node = self.get_node(node).ok_or(GraphError::NodeIdOutOfBounds)?;
+        if node.neighbor_len == 0 {
+            return Ok(Vec::new());
+        }
+        (0..=node.max_layer)
+            .map(|lc| self.get_neighbors_layer(node, lc))
+            .collect()
+    }
+
+    /// Helper: Point the entry point at the live node with the highest layer.
+    ///
+    /// Clears the entry point if no live node remains.
+    pub(crate) fn reassign_entry_point(&mut self) {
+        let best = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.deleted == 0)
+            .max_by_key(|(idx, n)| (n.max_layer, std::cmp::Reverse(*idx)));
+
+        if let Some((idx, node)) = best {
+            #[allow(clippy::cast_possible_truncation)]
+            let id = NodeId(idx as u32);
+            self.entry_point = Some(id);
+            self.max_layer = node.max_layer;
+        } else {
+            self.entry_point = None;
+            self.max_layer = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(count: usize) -> (HnswIndex, VectorStorage) {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let mut index = HnswIndex::new(config, &storage).unwrap();
+        for i in 0..count {
+            #[allow(clippy::cast_precision_loss)]
+            let v = [i as f32, (i % 7) as f32];
+            index.insert(&v, &mut storage).unwrap();
+        }
+        (index, storage)
+    }
+
+    fn links_to(index: &HnswIndex, target: NodeId) -> bool {
+        index.nodes.iter().any(|node| {
+            (0..=node.max_layer).any(|lc| {
+                index
+                    .get_neighbors_layer(node, lc)
+                    .unwrap()
+                    .contains(&target)
+            })
+        })
+    }
+
+    #[test]
+    fn test_hard_delete_unlinks_node() {
+        let (mut index, mut storage) = build(50);
+        let target = NodeId(10);
+        assert!(links_to(&index, target));
+
+        assert!(index.hard_delete(VectorId(11), &mut storage).unwrap());
+
+        assert!(!links_to(&index, target));
+        let node = index.get_node(target).unwrap();
+        assert_eq!(node.deleted, 1);
+        assert_eq!(node.neighbor_len, 0);
+        assert_eq!(index.deleted_count(), 1);
+    }
+
+    #[test]
+    fn test_hard_delete_entry_point_reassigned() {
+        let (mut index, mut storage) = build(30);
+        let ep = index.entry_point().unwrap();
+        let ep_vid = index.get_node(ep).unwrap().vector_id;
+
+        index.hard_delete(ep_vid, &mut storage).unwrap();
+
+        let new_ep = index.entry_point().unwrap();
+        assert_ne!(new_ep, ep);
+        assert_eq!(index.get_node(new_ep).unwrap().deleted, 0);
+        assert_eq!(index.max_layer(), index.get_node(new_ep).unwrap().max_layer);
+    }
+
+    #[test]
+    fn test_hard_delete_last_node_clears_entry_point() {
+        let (mut index, mut storage) = build(1);
+        index.hard_delete(VectorId(1), &mut storage).unwrap();
+
+        assert!(index.entry_point().is_none());
+        assert!(index.search(&[0.0, 0.0], 1, &storage).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hard_delete_tombstone_returns_false() {
+        let (mut index, mut storage) = build(10);
+        index.soft_delete(VectorId(3)).unwrap();
+
+        assert!(!index.hard_delete(VectorId(3), &mut storage).unwrap());
+        assert!(!links_to(&index, NodeId(2)));
+        assert_eq!(index.deleted_count(), 1);
+    }
+
+    #[test]
+    fn test_reverse_links_follow_later_writes() {
+        let (mut index, mut storage) = build(60);
+        index.hard_delete(VectorId(5), &mut storage).unwrap();
+        assert!(index.reverse_links.is_some());
+
+        // Inserts, updates and further deletes must keep the links in sync
+        for i in 0..40 {
+            #[allow(clippy::cast_precision_loss)]
+            let v = [i as f32 + 0.5, (i % 5) as f32];
+            index.insert(&v, &mut storage).unwrap();
+        }
+        index
+            .update(VectorId(20), &[3.0, 1.0], &mut storage)
+            .unwrap();
+        index.hard_delete(VectorId(30), &mut storage).unwrap();
+
+        let sorted = |links: &ReverseLinks| {
+            let mut incoming = links.incoming.clone();
+            for list in &mut incoming {
+                list.sort_by_key(|&(n, lc)| (n.0, lc));
+            }
+            while incoming.last().is_some_and(Vec::is_empty) {
+                incoming.pop();
+            }
+            incoming
+        };
+        let tracked = sorted(index.reverse_links.as_ref().unwrap());
+        let derived = sorted(&ReverseLinks::build(&index).unwrap());
+        assert_eq!(tracked, derived);
+    }
+
+    #[test]
+    fn test_hard_delete_unknown_id() {
+        let (mut index, mut storage) = build(3);
+        assert_eq!(
+            index.hard_delete(VectorId(99), &mut storage),
+            Err(GraphError::InvalidVectorId)
+        );
+    }
+}
@@ -0,0 +1,400 @@
+//! WAL-logged index mutations.
+//!
+//! `VectorStorage` logs inserts and updates itself, but tombstones,
+//! metadata, external IDs and BQ state live in the `HnswIndex` and are
+//! invisible to it. An index created or recovered with a WAL-backed
+//! storage shares that WAL, and its mutators write a record to it before
+//! changing anything, so [`crate::persistence::replay_wal`] can restore
+//! them after a crash:
+//!
+//! | Mutator | Record |
+//! |---------|--------|
+//! | `soft_delete`, `soft_delete_batch`, `delete_external` | `SOFT_DELETE` |
+//! | `hard_delete` | `HARD_DELETE` |
+//! | `metadata_logged_mut()` writes, `insert_with_metadata` | `METADATA_SET` / `METADATA_DELETE` |
+//! | `insert_with_external_id`, `upsert`, `batch_insert` | `EXTERNAL_ID_SET` |
+//! | `enable_bq` | `ENABLE_BQ` |
+//!
+//! The `*_logged` methods log to a given storage's WAL even when the index
+//! has none of its own. Without any WAL everything behaves exactly like
+//! the unlogged code paths. Writes through the plain
+//! [`HnswIndex::metadata_mut`] are never logged.
+
+use std::ops::Deref;
+
+use super::external_id::ExternalId;
+use super::graph::{GraphError, HnswIndex, VectorId};
+use crate::metadata::validation::{validate_key_value, validate_value, MAX_KEYS_PER_VECTOR};
+use crate::metadata::{MetadataError, MetadataStore, MetadataValue};
+use crate::persistence::entry::WalEntry;
+use crate::persistence::replay::{
+    external_id_payload, metadata_delete_payload, metadata_set_payload,
+};
+use crate::persistence::wal::WalAppender;
+use crate::storage::VectorStorage;
+
+/// Write access to an index's [`MetadataStore`], returned by
+/// [`HnswIndex::metadata_logged_mut`].
+///
+/// Every write is validated, then logged to the index's WAL, then applied,
+/// so the log never holds a record that would fail on replay. Without a
+/// WAL the methods behave exactly like their `MetadataStore` counterparts.
+/// Reads go through `Deref`.
+///
+/// Replay drops metadata of deleted vectors, so writes for a vector that
+/// is already deleted are not restored after a crash.
+#[derive(Debug)]
+pub struct MetadataMut<'a> {
+    store: &'a mut MetadataStore,
+    wal: Option<&'a mut WalAppender>,
+}
+
+impl Deref for MetadataMut<'_> {
+    type Target = MetadataStore;
+
+    fn deref(&self) -> &MetadataStore {
+        self.store
+    }
+}
+
+impl MetadataMut<'_> {
+    /// Inserts or updates a metadata key; see [`MetadataStore::insert`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`MetadataStore::insert`], plus `MetadataError::Wal` if the
+    /// WAL write failed (the store is unchanged).
+    pub fn insert(
+        &mut self,
+        vector_id: u32,
+        key: &str,
+        value: MetadataValue,
+    ) -> Result<(), MetadataError> {
+        validate_key_value(key, &value)?;
+        let count = self.store.key_count(vector_id);
+        if !self.store.has_key(vector_id, key) && count >= MAX_KEYS_PER_VECTOR {
+            return Err(MetadataError::TooManyKeys {
+                vector_id,
+                count,
+                max: MAX_KEYS_PER_VECTOR,
+            });
+        }
+
+        self.log_set(vector_id, key, &value)?;
+        self.store.insert(vector_id, key, value)
+    }
+
+    /// Updates an existing metadata key; see [`MetadataStore::update`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`MetadataStore::update`], plus `MetadataError::Wal` if the
+    /// WAL write failed (the store is unchanged).
+    pub fn update(
+        &mut self,
+        vector_id: u32,
+        key: &str,
+        value: MetadataValue,
+    ) -> Result<(), MetadataError> {
+        if !self.store.has_key(vector_id, key) {
+            // Fails without touching the store
+            return self.store.update(vector_id, key, value);
+        }
+        validate_value(&value)?;
+
+        self.log_set(vector_id, key, &value)?;
+        self.store.update(vector_id, key, value)
+    }
+
+    /// Removes a metadata key; see [`MetadataStore::delete`].
+    ///
+    /// Nothing is logged if the key does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataError::Wal` if the WAL write failed (the store is
+    /// unchanged).
+    pub fn delete(&mut self, vector_id: u32, key: &str) -> Result<bool, MetadataError> {
+        if !self.store.has_key(vector_id, key) {
+            return Ok(false);
+        }
+
+        self.log_delete(vector_id, key)?;
+        self.store.delete(vector_id, key)
+    }
+
+    /// Removes all metadata of a vector; see [`MetadataStore::delete_all`].
+    ///
+    /// Each key is logged and removed in turn.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataError::Wal` if a WAL write failed. The keys removed
+    /// before it stay removed, matching what replay will produce.
+    pub fn delete_all(&mut self, vector_id: u32) -> Result<bool, MetadataError> {
+        let keys: Vec<String> = match self.store.keys(vector_id) {
+            Some(keys) => keys.cloned().collect(),
+            None => return Ok(false),
+        };
+        for key in &keys {
+            self.delete(vector_id, key)?;
+        }
+        Ok(!keys.is_empty())
+    }
+
+    /// Removes all metadata; see [`MetadataStore::clear`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataError::Wal` if a WAL write failed, as for
+    /// [`delete_all`](Self::delete_all).
+    pub fn clear(&mut self) -> Result<(), MetadataError> {
+        let vector_ids: Vec<u32> = self.store.vector_ids().copied().collect();
+        for vector_id in vector_ids {
+            self.delete_all(vector_id)?;
+        }
+        Ok(())
+    }
+
+    /// Merges another store into this one; see [`MetadataStore::merge`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`MetadataStore::merge`] (nothing is logged or changed), plus
+    /// `MetadataError::Wal` if a WAL write failed. The keys merged before it
+    /// stay merged, matching what replay will produce.
+    pub fn merge(&mut self, other: MetadataStore) -> Result<(), MetadataError> {
+        self.store.check_merge(&other)?;
+        if self.wal.is_none() {
+            return self.store.merge(other);
+        }
+
+        for &vector_id in other.vector_ids() {
+            let Some(entries) = other.get_all(vector_id) else {
+                continue;
+            };
+            for (key, value) in entries {
+                self.log_set(vector_id, key, value)?;
+                self.store.insert(vector_id, key, value.clone())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn log_set(
+        &mut self,
+        vector_id: u32,
+        key: &str,
+        value: &MetadataValue,
+    ) -> Result<(), MetadataError> {
+        let Some(wal) = &mut self.wal else {
+            return Ok(());
+        };
+        let payload = metadata_set_payload(VectorId(u64::from(vector_id)), key, value)
+            .map_err(MetadataError::Serialization)?;
+        wal.append(WalEntry::METADATA_SET, &payload)
+            .map_err(|e| MetadataError::Wal(e.to_string()))
+    }
+
+    fn log_delete(&mut self, vector_id: u32, key: &str) -> Result<(), MetadataError> {
+        let Some(wal) = &mut self.wal else {
+            return Ok(());
+        };
+        let payload = metadata_delete_payload(VectorId(u64::from(vector_id)), key)
+            .map_err(MetadataError::Serialization)?;
+        wal.append(WalEntry::METADATA_DELETE, &payload)
+            .map_err(|e| MetadataError::Wal(e.to_string()))
+    }
+}
+
+impl HnswIndex {
+    /// Returns write access to the metadata store that logs every write.
+    ///
+    /// Writes through it are logged to the index's WAL, if it has one; see
+    /// [`MetadataMut`]. Use [`metadata_mut`](Self::metadata_mut) for
+    /// unlogged access to the full `MetadataStore` API.
+    ///
+    /// # Thread Safety
+    ///
+    /// Concurrent modification requires external synchronization (e.g., `Mutex`),
+    /// matching the existing pattern for other `HnswIndex` operations.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::metadata::MetadataValue;
+    /// use edgevec::persistence::wal::WalAppender;
+    /// use edgevec::persistence::{MemoryBackend, StorageBackend};
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(4);
+    /// let wal = MemoryBackend::new();
+    /// let storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    ///
+    /// index.metadata_logged_mut()
+    ///     .insert(1, "category", MetadataValue::String("books".into()))
+    ///     .unwrap();
+    ///
+    /// assert!(index.metadata().has_key(1, "category"));
+    /// assert!(!wal.read().unwrap().is_empty());
+    /// ```
+    pub fn metadata_logged_mut(&mut self) -> MetadataMut<'_> {
+        MetadataMut {
+            store: &mut self.metadata,
+            wal: self.wal.as_mut(),
+        }
+    }
+
+    /// Appends a record to the index's WAL, if it has one.
+    pub(crate) fn log_entry(&mut self, entry_type: u8, payload: &[u8]) -> Result<(), GraphError> {
+        match &mut self.wal {
+            Some(wal) => wal
+                .append(entry_type, payload)
+                .map_err(|e| GraphError::Storage(e.to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// Maps `external_id` to a freshly inserted `vector_id`, logging the
+    /// binding first.
+    pub(crate) fn bind_external_id(
+        &mut self,
+        external_id: ExternalId,
+        vector_id: VectorId,
+    ) -> Result<(), GraphError> {
+        if self.wal.is_some() {
+            let payload =
+                external_id_payload(vector_id, &external_id).map_err(GraphError::Storage)?;
+            self.log_entry(WalEntry::EXTERNAL_ID_SET, &payload)?;
+        }
+        self.external_ids.insert(external_id, vector_id);
+        Ok(())
+    }
+
+    /// Runs `f` with `storage`'s WAL attached if the index has none of its own.
+    fn with_wal_of<T>(&mut self, storage: &VectorStorage, f: impl FnOnce(&mut Self) -> T) -> T {
+        if self.wal.is_some() || storage.wal.is_none() {
+            return f(self);
+        }
+        self.wal.clone_from(&storage.wal);
+        let result = f(self);
+        self.wal = None;
+        result
+    }
+
+    /// Soft-deletes a vector, logging the delete to the storage's WAL first.
+    ///
+    /// Same as [`soft_delete`](Self::soft_delete), but also logs when the
+    /// index was not created with `storage`'s WAL.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(true)` - Vector was deleted
+    /// * `Ok(false)` - Vector was already deleted (nothing is logged)
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidVectorId` - Vector ID not found
+    /// * `Storage` - The WAL write failed; the index is unchanged
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(2);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    ///
+    /// let id = index.insert(&[1.0, 0.0], &mut storage).unwrap();
+    /// assert!(index.soft_delete_logged(id, &mut storage).unwrap());
+    /// assert!(index.is_deleted(id).unwrap());
+    /// ```
+    pub fn soft_delete_logged(
+        &mut self,
+        vector_id: VectorId,
+        storage: &mut VectorStorage,
+    ) -> Result<bool, GraphError> {
+        self.with_wal_of(storage, |index| index.soft_delete(vector_id))
+    }
+
+    /// Inserts or updates a metadata key on a live vector, logging it first.
+    ///
+    /// Same upsert semantics as [`MetadataStore::insert`](crate::metadata::MetadataStore::insert).
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidVectorId` - Vector ID not found or deleted
+    /// * `MetadataValidation` - Invalid key/value or too many keys
+    /// * `Storage` - The WAL write failed; the index is unchanged
+    pub fn set_metadata_logged(
+        &mut self,
+        vector_id: VectorId,
+        key: &str,
+        value: MetadataValue,
+        storage: &mut VectorStorage,
+    ) -> Result<(), GraphError> {
+        if self.is_deleted(vector_id)? {
+            return Err(GraphError::InvalidVectorId);
+        }
+
+        // Note: VectorId is u64 but MetadataStore uses u32 (see insert_with_metadata)
+        #[allow(clippy::cast_possible_truncation)]
+        let metadata_id = vector_id.0 as u32;
+
+        self.with_wal_of(storage, |index| {
+            index.metadata_logged_mut().insert(metadata_id, key, value)
+        })
+        .map_err(metadata_wal_error)
+    }
+
+    /// Removes a metadata key from a vector, logging the removal first.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the key existed, `false` otherwise (nothing is logged).
+    ///
+    /// # Errors
+    ///
+    /// * `Storage` - The WAL write failed; the index is unchanged
+    pub fn delete_metadata_logged(
+        &mut self,
+        vector_id: VectorId,
+        key: &str,
+        storage: &mut VectorStorage,
+    ) -> Result<bool, GraphError> {
+        #[allow(clippy::cast_possible_truncation)]
+        let metadata_id = vector_id.0 as u32;
+
+        self.with_wal_of(storage, |index| {
+            index.metadata_logged_mut().delete(metadata_id, key)
+        })
+        .map_err(metadata_wal_error)
+    }
+
+    /// Enables binary quantization and logs it to the storage's WAL.
+    ///
+    /// Same as [`enable_bq`](Self::enable_bq), but also logs when the index
+    /// was not created with `storage`'s WAL. On replay, BQ storage is
+    /// rebuilt from the vectors present at that point of the log.
+    ///
+    /// # Errors
+    ///
+    /// * `InvalidConfig` - Dimension is not divisible by 8
+    /// * `Storage` - The WAL write failed; BQ state is left as it was
+    pub fn enable_bq_logged(&mut self, storage: &mut VectorStorage) -> Result<(), GraphError> {
+        self.with_wal_of(storage, |index| index.enable_bq(storage))
+    }
+}
+
+/// Reports a failed WAL write as a storage error, like the other index
+/// mutators do.
+fn metadata_wal_error(e: MetadataError) -> GraphError {
+    match e {
+        MetadataError::Wal(message) => GraphError::Storage(message),
+        other => GraphError::MetadataValidation(other),
+    }
+}
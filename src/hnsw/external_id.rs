@@ -0,0 +1,274 @@
+//! Caller-supplied external IDs.
+//!
+//! `VectorId`s are assigned sequentially by `VectorStorage` and are renumbered
+//! by `compact()`. This module lets callers address vectors by their own keys
+//! instead: either a `u64` or a `String`. The mapping lives on `HnswIndex`,
+//! is carried through compaction and is persisted in snapshots.
+//!
+//! # Architecture
+//!
+//! ```text
+//! ExternalIdMap
+//! +-- to_internal: HashMap<ExternalId, VectorId>
+//! +-- to_external: HashMap<VectorId, ExternalId>
+//! ```
+//!
+//! Both directions are O(1). Only live vectors are mapped: deleting a vector
+//! releases its key so it can be reused by a later insert.
+
+use super::graph::VectorId;
+use crate::persistence::PersistenceError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// A caller-supplied identifier for a vector.
+///
+/// # Example
+///
+/// ```
+/// use edgevec::hnsw::ExternalId;
+///
+/// let by_number = ExternalId::from(42u64);
+/// let by_key = ExternalId::from("doc-42");
+/// assert_ne!(by_number, by_key);
+/// assert_eq!(by_key.to_string(), "doc-42");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ExternalId {
+    /// Numeric key.
+    Num(u64),
+    /// String key.
+    Key(String),
+}
+
+impl From<u64> for ExternalId {
+    fn from(id: u64) -> Self {
+        Self::Num(id)
+    }
+}
+
+impl From<&str> for ExternalId {
+    fn from(key: &str) -> Self {
+        Self::Key(key.to_string())
+    }
+}
+
+impl From<String> for ExternalId {
+    fn from(key: String) -> Self {
+        Self::Key(key)
+    }
+}
+
+impl fmt::Display for ExternalId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Num(id) => write!(f, "{id}"),
+            Self::Key(key) => f.write_str(key),
+        }
+    }
+}
+
+/// Bidirectional map between [`ExternalId`]s and internal `VectorId`s.
+///
+/// Serialized as a list of `(VectorId, ExternalId)` pairs sorted by
+/// `VectorId`, so snapshots are deterministic. A list that binds a key or
+/// a `VectorId` twice is rejected on load.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(
+    try_from = "Vec<(VectorId, ExternalId)>",
+    into = "Vec<(VectorId, ExternalId)>"
+)]
+pub struct ExternalIdMap {
+    to_internal: HashMap<ExternalId, VectorId>,
+    to_external: HashMap<VectorId, ExternalId>,
+}
+
+impl ExternalIdMap {
+    /// Creates an empty map.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of mapped vectors.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.to_internal.len()
+    }
+
+    /// Returns true if no vectors are mapped.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.to_internal.is_empty()
+    }
+
+    /// Returns true if `external` is mapped to a vector.
+    #[must_use]
+    pub fn contains(&self, external: &ExternalId) -> bool {
+        self.to_internal.contains_key(external)
+    }
+
+    /// Maps `external` to `vector_id`.
+    ///
+    /// Returns `false` (and leaves the map unchanged) if either side is
+    /// already mapped.
+    pub fn insert(&mut self, external: ExternalId, vector_id: VectorId) -> bool {
+        if self.to_internal.contains_key(&external) || self.to_external.contains_key(&vector_id) {
+            return false;
+        }
+        self.to_external.insert(vector_id, external.clone());
+        self.to_internal.insert(external, vector_id);
+        true
+    }
+
+    /// Returns the `VectorId` mapped to `external`, if any.
+    #[must_use]
+    pub fn vector_id(&self, external: &ExternalId) -> Option<VectorId> {
+        self.to_internal.get(external).copied()
+    }
+
+    /// Returns the `ExternalId` mapped to `vector_id`, if any.
+    #[must_use]
+    pub fn external_id(&self, vector_id: VectorId) -> Option<&ExternalId> {
+        self.to_external.get(&vector_id)
+    }
+
+    /// Removes the mapping for `vector_id`, returning its external ID.
+    pub fn remove_vector(&mut self, vector_id: VectorId) -> Option<ExternalId> {
+        let external = self.to_external.remove(&vector_id)?;
+        self.to_internal.remove(&external);
+        Some(external)
+    }
+
+    /// Iterates over all `(ExternalId, VectorId)` pairs in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&ExternalId, VectorId)> {
+        self.to_internal.iter().map(|(ext, &vid)| (ext, vid))
+    }
+
+    /// Returns a new map with every `VectorId` translated through `remap`.
+    ///
+    /// Entries whose `VectorId` is missing from `remap` are dropped. Used by
+    /// compaction, which renumbers all live vectors.
+    #[must_use]
+    pub fn remapped(&self, remap: &HashMap<VectorId, VectorId>) -> Self {
+        let mut out = Self::new();
+        for (old_id, external) in &self.to_external {
+            if let Some(&new_id) = remap.get(old_id) {
+                out.insert(external.clone(), new_id);
+            }
+        }
+        out
+    }
+
+    /// Serializes the map to Postcard format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if encoding fails.
+    pub fn to_postcard(&self) -> Result<Vec<u8>, String> {
+        postcard::to_allocvec(self).map_err(|e| e.to_string())
+    }
+
+    /// Deserializes a map from Postcard format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if the bytes are not a valid encoded map,
+    /// or if they bind an external ID or a `VectorId` twice.
+    pub fn from_postcard(bytes: &[u8]) -> Result<Self, String> {
+        // Decode the pairs first: postcard drops the message of a failed `try_from`
+        let entries: Vec<(VectorId, ExternalId)> =
+            postcard::from_bytes(bytes).map_err(|e| e.to_string())?;
+        Self::try_from(entries).map_err(|e| e.to_string())
+    }
+}
+
+impl TryFrom<Vec<(VectorId, ExternalId)>> for ExternalIdMap {
+    type Error = PersistenceError;
+
+    fn try_from(entries: Vec<(VectorId, ExternalId)>) -> Result<Self, Self::Error> {
+        let mut map = Self::new();
+        for (vector_id, external) in entries {
+            if !map.insert(external.clone(), vector_id) {
+                return Err(PersistenceError::Corrupted(format!(
+                    "external ID {external} or vector {} is bound twice",
+                    vector_id.0
+                )));
+            }
+        }
+        Ok(map)
+    }
+}
+
+impl From<ExternalIdMap> for Vec<(VectorId, ExternalId)> {
+    fn from(map: ExternalIdMap) -> Self {
+        let mut entries: Vec<_> = map.to_external.into_iter().collect();
+        entries.sort_unstable_by_key(|(vid, _)| vid.0);
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_lookup_both_directions() {
+        let mut map = ExternalIdMap::new();
+        assert!(map.insert(ExternalId::from(100u64), VectorId(1)));
+        assert!(map.insert(ExternalId::from("doc"), VectorId(2)));
+
+        assert_eq!(map.vector_id(&ExternalId::Num(100)), Some(VectorId(1)));
+        assert_eq!(map.vector_id(&"doc".into()), Some(VectorId(2)));
+        assert_eq!(map.external_id(VectorId(1)), Some(&ExternalId::Num(100)));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_insert_rejects_duplicates() {
+        let mut map = ExternalIdMap::new();
+        assert!(map.insert(ExternalId::Num(7), VectorId(1)));
+        assert!(!map.insert(ExternalId::Num(7), VectorId(2)));
+        assert!(!map.insert(ExternalId::Num(8), VectorId(1)));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_releases_key() {
+        let mut map = ExternalIdMap::new();
+        map.insert(ExternalId::Num(7), VectorId(1));
+
+        assert_eq!(map.remove_vector(VectorId(1)), Some(ExternalId::Num(7)));
+        assert!(!map.contains(&ExternalId::Num(7)));
+        assert!(map.insert(ExternalId::Num(7), VectorId(2)));
+    }
+
+    #[test]
+    fn test_remapped_drops_missing() {
+        let mut map = ExternalIdMap::new();
+        map.insert(ExternalId::Num(10), VectorId(1));
+        map.insert(ExternalId::Num(20), VectorId(2));
+        map.insert(ExternalId::Num(30), VectorId(3));
+
+        let remap: HashMap<_, _> = [(VectorId(1), VectorId(1)), (VectorId(3), VectorId(2))]
+            .into_iter()
+            .collect();
+        let out = map.remapped(&remap);
+
+        assert_eq!(out.len(), 2);
+        assert_eq!(out.vector_id(&ExternalId::Num(30)), Some(VectorId(2)));
+        assert!(!out.contains(&ExternalId::Num(20)));
+    }
+
+    #[test]
+    fn test_postcard_roundtrip() {
+        let mut map = ExternalIdMap::new();
+        map.insert(ExternalId::Num(u64::MAX), VectorId(1));
+        map.insert(ExternalId::from("user:42"), VectorId(5));
+
+        let bytes = map.to_postcard().unwrap();
+        let decoded = ExternalIdMap::from_postcard(&bytes).unwrap();
+        assert_eq!(decoded, map);
+    }
+}
@@ -22,10 +22,20 @@
 #![allow(clippy::missing_panics_doc)]
 
 use super::config::HnswConfig;
+use super::delete::ReverseLinks;
+use super::external_id::{ExternalId, ExternalIdMap};
 use super::neighbor::NeighborPool;
+use super::search::SearchResult;
 use crate::metadata::{MetadataError, MetadataStore, MetadataValue};
+use crate::persistence::entry::WalEntry;
+use crate::persistence::replay::{metadata_set_payload, soft_delete_payload};
+use crate::persistence::wal::WalAppender;
+use crate::quantization::product::PqConfig;
 use crate::quantization::variable::BinaryVector;
+use crate::quantization::ProductQuantizer;
 use crate::storage::binary::BinaryVectorStorage;
+use crate::storage::mapped::CowSlice;
+use crate::storage::product::PqCodeStorage;
 use crate::storage::VectorStorage;
 use bytemuck::{Pod, Zeroable};
 use rand::{Rng, SeedableRng};
@@ -156,11 +166,25 @@ pub enum GraphError {
     #[error("binary quantization is not enabled; use with_bq() or enable_bq() first")]
     BqNotEnabled,
 
+    /// Product quantization is not enabled.
+    ///
+    /// Returned when attempting PQ operations on an index without PQ codes.
+    #[error("product quantization is not enabled; use with_pq() or enable_pq() first")]
+    PqNotEnabled,
+
     /// Binary quantization failed (v0.7.0 - RFC-002 Phase 2).
     ///
     /// Returned when vector quantization fails during BQ operations.
     #[error("quantization error: {0}")]
     Quantization(String),
+
+    /// The external ID is already mapped to a live vector.
+    #[error("external id already exists: {0}")]
+    DuplicateExternalId(ExternalId),
+
+    /// The external ID is not mapped to any live vector.
+    #[error("external id not found: {0}")]
+    ExternalIdNotFound(ExternalId),
 }
 
 /// Result of a compaction operation.
@@ -171,8 +195,14 @@ pub enum GraphError {
 ///
 /// * `tombstones_removed` - Number of deleted vectors removed
 /// * `new_size` - Size of the new index (live vectors only)
-/// * `duration_ms` - Time taken for the operation in milliseconds
+/// * `duration_ms` - Time taken for the operation in milliseconds (the
+///   index is rebuilt even when there are no tombstones)
+/// * `id_map` - Old `VectorId` → new `VectorId` for every live vector
+///
+/// Fields may be added in future versions, so the struct cannot be built
+/// or exhaustively destructured outside this crate.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct CompactionResult {
     /// Number of tombstones (deleted vectors) removed during compaction.
     pub tombstones_removed: usize,
@@ -180,6 +210,10 @@ pub struct CompactionResult {
     pub new_size: usize,
     /// Time taken for the compaction operation in milliseconds.
     pub duration_ms: u64,
+    /// Mapping from each live vector's old `VectorId` to its new `VectorId`.
+    ///
+    /// Deleted vectors have no entry.
+    pub id_map: HashMap<VectorId, VectorId>,
 }
 
 /// A node in the HNSW graph with its adjacency information.
@@ -242,12 +276,13 @@ pub struct HnswNode {
 /// in the graph for routing but are excluded from search results.
 /// Use `compact()` to reclaim space from deleted nodes.
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "HnswIndexData")]
 pub struct HnswIndex {
     /// Algorithm configuration
     pub config: HnswConfig,
 
     /// Node metadata (fixed-size per node)
-    pub(crate) nodes: Vec<HnswNode>,
+    pub(crate) nodes: CowSlice<HnswNode>,
 
     /// Compressed neighbor lists
     pub(crate) neighbors: NeighborPool,
@@ -292,16 +327,100 @@ pub struct HnswIndex {
     /// reduction and 3-5x search speedup at the cost of some recall.
     ///
     /// Use `with_bq()` to create an index with BQ enabled, or `enable_bq()`
-    /// to add BQ support to an existing index.
+    /// to add BQ support to an existing index. Snapshots persist it in a
+    /// `BQVS` section, so `read_snapshot` restores it without re-quantizing.
     #[serde(skip)]
     pub(crate) bq_storage: Option<BinaryVectorStorage>,
+
+    /// Product quantization codes and codebooks.
+    ///
+    /// Optional, like `bq_storage`: when enabled, every node also has a PQ
+    /// code used by `search_pq()`. Use `with_pq()` or `enable_pq()` to turn
+    /// it on. Snapshots persist it in a `PQCB` section.
+    #[serde(skip)]
+    pub(crate) pq_storage: Option<PqCodeStorage>,
+
+    /// Caller-supplied external IDs for live vectors.
+    ///
+    /// Unlike `VectorId`s, external IDs survive `compact()` and snapshots.
+    #[serde(default)]
+    pub(crate) external_ids: ExternalIdMap,
+
+    /// VectorId → NodeId lookup for every node, including tombstones.
+    ///
+    /// Maintained by `add_node` and derived from `nodes` whenever an index
+    /// is loaded (`read_snapshot` or serde), so it is never serialized.
+    #[serde(skip)]
+    pub(crate) node_index: HashMap<VectorId, NodeId>,
+
+    /// Incoming links, derived on the first `hard_delete` and kept up to
+    /// date by every neighbor list write after that.
+    #[serde(skip)]
+    pub(crate) reverse_links: Option<ReverseLinks>,
+
+    /// The WAL of the storage this index was created or recovered with.
+    ///
+    /// Mutations that don't pass through `VectorStorage` (deletes, metadata,
+    /// external IDs, BQ) are logged here. Clones share it.
+    #[serde(skip)]
+    pub(crate) wal: Option<WalAppender>,
 }
 
 /// Default compaction threshold (30%)
-fn default_compaction_threshold() -> f64 {
+pub(crate) fn default_compaction_threshold() -> f64 {
     0.3
 }
 
+/// Serialized fields of an [`HnswIndex`].
+///
+/// Deserializing goes through this type so runtime lookups that are not
+/// serialized (`node_index`) are rebuilt on every load path.
+#[derive(Deserialize)]
+struct HnswIndexData {
+    config: HnswConfig,
+    nodes: CowSlice<HnswNode>,
+    neighbors: NeighborPool,
+    entry_point: Option<NodeId>,
+    max_layer: u8,
+    level_mult: f32,
+    rng: ChaCha8Rng,
+    #[serde(default)]
+    deleted_count: usize,
+    #[serde(default = "default_compaction_threshold")]
+    compaction_threshold: f64,
+    #[serde(default)]
+    metadata: MetadataStore,
+    #[serde(default)]
+    external_ids: ExternalIdMap,
+}
+
+impl TryFrom<HnswIndexData> for HnswIndex {
+    type Error = GraphError;
+
+    fn try_from(data: HnswIndexData) -> Result<Self, Self::Error> {
+        let mut index = Self {
+            config: data.config,
+            nodes: data.nodes,
+            neighbors: data.neighbors,
+            entry_point: data.entry_point,
+            max_layer: data.max_layer,
+            level_mult: data.level_mult,
+            rng: data.rng,
+            deleted_count: data.deleted_count,
+            compaction_threshold: data.compaction_threshold,
+            metadata: data.metadata,
+            bq_storage: None,
+            pq_storage: None,
+            external_ids: data.external_ids,
+            node_index: HashMap::new(),
+            reverse_links: None,
+            wal: None,
+        };
+        index.rebuild_node_index()?;
+        Ok(index)
+    }
+}
+
 /// Error type for individual batch delete failures
 /// [C4 FIX] Enables caller to distinguish failure reasons
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -421,7 +540,7 @@ impl HnswIndex {
 
         Ok(Self {
             config,
-            nodes: Vec::new(),
+            nodes: CowSlice::default(),
             neighbors: NeighborPool::new(),
             entry_point: None,
             max_layer: 0,
@@ -431,6 +550,11 @@ impl HnswIndex {
             compaction_threshold: default_compaction_threshold(), // v0.3.0: Default 30%
             metadata: MetadataStore::new(), // v0.6.0 RFC-002: Empty metadata store
             bq_storage: None, // v0.7.0 RFC-002 Phase 2: BQ disabled by default
+            pq_storage: None,
+            external_ids: ExternalIdMap::new(),
+            node_index: HashMap::new(),
+            reverse_links: None,
+            wal: storage.wal.clone(),
         })
     }
 
@@ -502,7 +626,7 @@ impl HnswIndex {
 
         Ok(Self {
             config,
-            nodes: Vec::new(),
+            nodes: CowSlice::default(),
             neighbors: NeighborPool::new(),
             entry_point: None,
             max_layer: 0,
@@ -512,6 +636,11 @@ impl HnswIndex {
             compaction_threshold: default_compaction_threshold(),
             metadata, // Use provided metadata
             bq_storage: None,
+            pq_storage: None,
+            external_ids: ExternalIdMap::new(),
+            node_index: HashMap::new(),
+            reverse_links: None,
+            wal: storage.wal.clone(),
         })
     }
 
@@ -573,7 +702,8 @@ impl HnswIndex {
     ///
     /// # Errors
     ///
-    /// Returns error if dimension is not divisible by 8.
+    /// Returns error if dimension is not divisible by 8, or `Storage` if
+    /// logging to the index's WAL failed (BQ is left as it was).
     ///
     /// # Example
     ///
@@ -623,6 +753,7 @@ impl HnswIndex {
                 .map_err(|e| GraphError::Storage(e.to_string()))?;
         }
 
+        self.log_entry(WalEntry::ENABLE_BQ, &[])?;
         self.bq_storage = Some(bq_storage);
         Ok(())
     }
@@ -641,6 +772,142 @@ impl HnswIndex {
         self.bq_storage.as_ref()
     }
 
+    /// Creates a new index with product quantization enabled, using
+    /// codebooks trained beforehand.
+    ///
+    /// Every inserted vector is also encoded with `quantizer` for
+    /// `search_pq()`. To train codebooks on vectors already in an index,
+    /// use [`enable_pq`](Self::enable_pq) instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::ConfigMismatch` if the quantizer or storage
+    /// dimensions differ from config.
+    /// Returns `GraphError::InvalidConfig` if configuration parameters are invalid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::quantization::{PqConfig, ProductQuantizer};
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let sample: Vec<Vec<f32>> = (0..100).map(|i| vec![i as f32; 16]).collect();
+    /// let refs: Vec<&[f32]> = sample.iter().map(Vec::as_slice).collect();
+    /// let quantizer = ProductQuantizer::train(&refs, &PqConfig::new(4)).unwrap();
+    ///
+    /// let config = HnswConfig::new(16);
+    /// let storage = VectorStorage::new(&config, None);
+    /// let index = HnswIndex::with_pq(config, &storage, quantizer).unwrap();
+    ///
+    /// assert!(index.has_pq());
+    /// ```
+    pub fn with_pq(
+        config: HnswConfig,
+        storage: &VectorStorage,
+        quantizer: ProductQuantizer,
+    ) -> Result<Self, GraphError> {
+        if quantizer.dimensions() != config.dimensions as usize {
+            return Err(GraphError::ConfigMismatch {
+                expected: config.dimensions,
+                // SAFETY: the quantizer was trained on vectors of this index's width.
+                #[allow(clippy::cast_possible_truncation)]
+                actual: quantizer.dimensions() as u32,
+            });
+        }
+
+        let metric = config.metric;
+        let mut index = Self::new(config, storage)?;
+        index.pq_storage = Some(PqCodeStorage::new(quantizer, metric));
+        Ok(index)
+    }
+
+    /// Trains PQ codebooks on the live vectors of this index and encodes
+    /// every node.
+    ///
+    /// Up to `pq_config.sample_size` live vectors are sampled for training.
+    /// Replaces any PQ codes the index already had.
+    /// Time complexity: O(s × k × d × iterations) for training on s samples
+    /// with k centroids, then O(n × k × d) to encode n nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError::Quantization` if the index has no live vectors
+    /// or `pq_config` does not fit the dimensions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::quantization::PqConfig;
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(16);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    /// for i in 0..100 {
+    ///     index.insert(&vec![i as f32; 16], &mut storage).unwrap();
+    /// }
+    ///
+    /// index.enable_pq(&storage, &PqConfig::new(4)).unwrap();
+    /// assert_eq!(index.pq_storage().unwrap().len(), 100);
+    /// ```
+    pub fn enable_pq(
+        &mut self,
+        storage: &VectorStorage,
+        pq_config: &PqConfig,
+    ) -> Result<(), GraphError> {
+        let live: Vec<VectorId> = self
+            .nodes
+            .iter()
+            .filter(|node| node.deleted == 0)
+            .map(|node| node.vector_id)
+            .collect();
+
+        // Sample before fetching, so SQ8 storage only dequantizes what is used
+        let mut rng = ChaCha8Rng::seed_from_u64(pq_config.seed);
+        let sample: Vec<Cow<'_, [f32]>> = if live.len() > pq_config.sample_size {
+            rand::seq::index::sample(&mut rng, live.len(), pq_config.sample_size)
+                .into_iter()
+                .map(|i| storage.get_vector(live[i]))
+                .collect()
+        } else {
+            live.iter().map(|&id| storage.get_vector(id)).collect()
+        };
+        let refs: Vec<&[f32]> = sample.iter().map(AsRef::as_ref).collect();
+
+        let mut pq_storage = PqCodeStorage::train(&refs, pq_config, self.config.metric)
+            .map_err(|e| GraphError::Quantization(e.to_string()))?;
+
+        for node in &self.nodes {
+            if node.deleted != 0 {
+                // Placeholder keeps code IDs aligned with node indices
+                pq_storage.insert_placeholder();
+                continue;
+            }
+            pq_storage
+                .insert(&storage.get_vector(node.vector_id))
+                .map_err(|e| GraphError::Quantization(e.to_string()))?;
+        }
+
+        self.pq_storage = Some(pq_storage);
+        Ok(())
+    }
+
+    /// Returns true if product quantization is enabled.
+    #[must_use]
+    #[inline]
+    pub fn has_pq(&self) -> bool {
+        self.pq_storage.is_some()
+    }
+
+    /// Returns a reference to the PQ codes, if enabled.
+    #[must_use]
+    #[inline]
+    pub fn pq_storage(&self) -> Option<&PqCodeStorage> {
+        self.pq_storage.as_ref()
+    }
+
     /// Generates a random level for a new node.
     ///
     /// Formula: `floor(-ln(uniform(0,1)) * m_l)`
@@ -689,7 +956,8 @@ impl HnswIndex {
 
         #[allow(clippy::cast_possible_truncation)]
         let id = NodeId(self.nodes.len() as u32);
-        self.nodes.push(node);
+        self.nodes.to_mut().push(node);
+        self.node_index.entry(vector_id).or_insert(id);
 
         // Update max layer if needed
         if max_layer > self.max_layer {
@@ -717,6 +985,8 @@ impl HnswIndex {
         let neighbor_u32s: Vec<u32> = neighbors.iter().map(|n| n.0).collect();
         let encoded = NeighborPool::encode_neighbors(&neighbor_u32s);
 
+        let before = self.links_before_write(node_id)?;
+
         // Alloc new space
         let (offset, capacity) = self.neighbors.alloc(encoded.len())?;
 
@@ -726,7 +996,7 @@ impl HnswIndex {
         self.neighbors.buffer[start..end].copy_from_slice(&encoded);
 
         // Update node and free old
-        let node = &mut self.nodes[node_id.0 as usize];
+        let node = &mut self.nodes.to_mut()[node_id.0 as usize];
 
         // Free old slot if it existed
         if node.neighbor_len > 0 {
@@ -736,7 +1006,7 @@ impl HnswIndex {
         node.neighbor_offset = offset;
         node.neighbor_len = capacity; // Store allocated capacity
 
-        Ok(())
+        self.links_after_write(node_id, before)
     }
 
     /// Retrieves a node by its ID.
@@ -793,6 +1063,15 @@ impl HnswIndex {
         self.nodes.len()
     }
 
+    /// Returns true if the node table is borrowed from a memory-mapped
+    /// snapshot (see `FileBackend::open_mmap`).
+    ///
+    /// The first mutation of any node copies the table to the heap.
+    #[must_use]
+    pub fn is_mapped(&self) -> bool {
+        self.nodes.is_mapped()
+    }
+
     /// Returns the entry point node ID, if any.
     #[must_use]
     pub fn entry_point(&self) -> Option<NodeId> {
@@ -843,7 +1122,10 @@ impl HnswIndex {
 
     /// Returns a mutable reference to the metadata store.
     ///
-    /// Use this method to modify metadata attached to vectors.
+    /// Use this method to modify metadata attached to vectors. Writes made
+    /// through it are not logged to the WAL; use
+    /// [`metadata_logged_mut`](Self::metadata_logged_mut) for writes that
+    /// must survive a crash before the next snapshot.
     ///
     /// # Thread Safety
     ///
@@ -951,14 +1233,22 @@ impl HnswIndex {
         // Step 2: Insert vector (this is atomic — either succeeds or fails)
         let vector_id = self.insert(vector, storage)?;
 
-        // Step 3: Store metadata for the newly inserted vector
-        // This cannot fail because we pre-validated everything
+        // Step 3: Store metadata for the newly inserted vector (WAL first, if attached)
+        // The store insert cannot fail because we pre-validated everything
         // Note: VectorId is u64 but MetadataStore uses u32. In practice,
         // vector IDs won't exceed u32::MAX (4B vectors).
         #[allow(clippy::cast_possible_truncation)]
         let metadata_id = vector_id.0 as u32;
 
         for (key, value) in metadata {
+            if storage.has_wal() {
+                let payload =
+                    metadata_set_payload(vector_id, &key, &value).map_err(GraphError::Storage)?;
+                storage
+                    .log_entry(WalEntry::METADATA_SET, &payload)
+                    .map_err(|e| GraphError::Storage(e.to_string()))?;
+            }
+
             // We've already validated, so insert should succeed
             self.metadata
                 .insert(metadata_id, &key, value)
@@ -1106,12 +1396,15 @@ impl HnswIndex {
     ///
     /// # Complexity
     ///
-    /// * Time: O(n) linear scan
+    /// * Time: O(1) via `node_index`
     /// * Space: O(1)
     fn get_node_mut(&mut self, vector_id: VectorId) -> Result<&mut HnswNode, GraphError> {
+        let node_id = self
+            .node_id_of(vector_id)
+            .ok_or(GraphError::InvalidVectorId)?;
         self.nodes
-            .iter_mut()
-            .find(|n| n.vector_id == vector_id)
+            .to_mut()
+            .get_mut(node_id.0 as usize)
             .ok_or(GraphError::InvalidVectorId)
     }
 
@@ -1119,19 +1412,50 @@ impl HnswIndex {
     ///
     /// # Complexity
     ///
-    /// * Time: O(n) linear scan
+    /// * Time: O(1) via `node_index`
     /// * Space: O(1)
     fn get_node_by_vector_id(&self, vector_id: VectorId) -> Result<&HnswNode, GraphError> {
-        self.nodes
-            .iter()
-            .find(|n| n.vector_id == vector_id)
+        self.node_id_of(vector_id)
+            .and_then(|id| self.nodes.get(id.0 as usize))
             .ok_or(GraphError::InvalidVectorId)
     }
 
+    /// Get the `NodeId` of the node holding a VectorId.
+    ///
+    /// # Complexity
+    ///
+    /// * Time: O(1) via `node_index`
+    /// * Space: O(1)
+    pub(crate) fn node_id_of(&self, vector_id: VectorId) -> Option<NodeId> {
+        self.node_index.get(&vector_id).copied()
+    }
+
+    /// Rebuild `node_index` from `nodes`.
+    ///
+    /// Called after `nodes` is replaced wholesale (`read_snapshot`, serde
+    /// deserialization).
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidVectorId` if two nodes share a `VectorId`.
+    pub(crate) fn rebuild_node_index(&mut self) -> Result<(), GraphError> {
+        self.node_index.clear();
+        self.node_index.reserve(self.nodes.len());
+        for (idx, node) in self.nodes.iter().enumerate() {
+            #[allow(clippy::cast_possible_truncation)]
+            let node_id = NodeId(idx as u32);
+            if self.node_index.insert(node.vector_id, node_id).is_some() {
+                return Err(GraphError::InvalidVectorId);
+            }
+        }
+        Ok(())
+    }
+
     /// Mark a vector as deleted (soft delete).
     ///
     /// The vector remains in the graph for routing but is excluded from
-    /// search results. Space is reclaimed via `compact()`.
+    /// search results. Space is reclaimed via `compact()`. Its metadata is
+    /// removed and its external ID (if any) is released for reuse.
     ///
     /// # Arguments
     ///
@@ -1142,17 +1466,14 @@ impl HnswIndex {
     /// * `Ok(true)` - Vector was deleted
     /// * `Ok(false)` - Vector was already deleted
     /// * `Err(InvalidVectorId)` - Vector ID not found
+    /// * `Err(Storage)` - The WAL write failed; the index is unchanged
     ///
     /// # Complexity
     ///
-    /// * Time: **O(n)** for VectorId → Node lookup via linear scan,
+    /// * Time: O(1) for VectorId → Node lookup via `node_index`,
     ///   plus O(1) for setting the deleted byte
     /// * Space: O(1)
     ///
-    /// **Note:** The O(n) lookup is a known limitation in v0.3.0.
-    /// A HashMap<VectorId, NodeId> index could provide O(1) lookup
-    /// but is deferred to v0.4.0 to avoid scope creep.
-    ///
     /// # Thread Safety (RFC-001 Design)
     ///
     /// This method requires `&mut self`, which means Rust's borrow checker
@@ -1164,8 +1485,9 @@ impl HnswIndex {
     ///
     /// # Persistence
     ///
-    /// **IMPORTANT:** Delete operations are in-memory only until `save()` is called.
-    /// If the process crashes before `save()`, the delete will be lost.
+    /// If the index has a WAL (see [`crate::hnsw::durable`]), the delete is
+    /// logged before the node is touched, and survives a crash before the
+    /// next snapshot. Otherwise it is in-memory only until then.
     ///
     /// # Example
     ///
@@ -1175,6 +1497,17 @@ impl HnswIndex {
     /// assert!(index.is_deleted(VectorId(42))?);
     /// ```
     pub fn soft_delete(&mut self, vector_id: VectorId) -> Result<bool, GraphError> {
+        if self.is_deleted(vector_id)? {
+            return Ok(false); // Already deleted
+        }
+
+        self.log_entry(WalEntry::SOFT_DELETE, &soft_delete_payload(vector_id))?;
+        self.tombstone(vector_id)
+    }
+
+    /// Marks a vector deleted and drops its metadata and external ID,
+    /// without logging. `hard_delete` logs its own record instead.
+    pub(crate) fn tombstone(&mut self, vector_id: VectorId) -> Result<bool, GraphError> {
         let node = self.get_node_mut(vector_id)?;
 
         if node.deleted != 0 {
@@ -1191,6 +1524,9 @@ impl HnswIndex {
         let metadata_id = vector_id.0 as u32;
         self.metadata.delete_all(metadata_id);
 
+        // Release the external ID so it can be reused by a later insert
+        self.external_ids.remove_vector(vector_id);
+
         Ok(true)
     }
 
@@ -1216,7 +1552,7 @@ impl HnswIndex {
     /// * `BatchDeleteResult` with counts and detailed errors
     ///
     /// # Complexity
-    /// * Time: O(N × M) where N = unique IDs, M = index size (for ID lookup)
+    /// * Time: O(N) where N = unique IDs
     /// * Space: O(N) for deduplication and validation structures
     ///
     /// # Example
@@ -1327,7 +1663,7 @@ impl HnswIndex {
                     result.already_deleted += 1;
                 }
                 Err(e) => {
-                    // Should not happen after validation
+                    // Only a failed WAL write can land here after validation
                     result.errors.push(BatchDeleteError::InternalError(
                         id,
                         format!("Unexpected error after validation: {e:?}"),
@@ -1357,7 +1693,7 @@ impl HnswIndex {
     /// * `callback` - Function called with (processed_unique, total_unique) counts
     ///
     /// # Complexity
-    /// * Time: O(N × M) + O(N × C) where N = unique IDs, M = index size, C = callback cost
+    /// * Time: O(N) + O(N × C) where N = unique IDs, C = callback cost
     /// * Space: O(N) for deduplication and validation
     ///
     /// # Example
@@ -1503,7 +1839,7 @@ impl HnswIndex {
     ///
     /// # Complexity
     ///
-    /// * Time: O(n) for lookup + O(1) for check
+    /// * Time: O(1) for lookup + O(1) for check
     /// * Space: O(1)
     pub fn is_deleted(&self, vector_id: VectorId) -> Result<bool, GraphError> {
         let node = self.get_node_by_vector_id(vector_id)?;
@@ -1758,8 +2094,11 @@ impl HnswIndex {
     ///
     /// Due to storage design constraints, vector IDs are remapped during
     /// compaction. New IDs are assigned sequentially starting from 1.
-    /// If you need to track the mapping, use the returned index to query
-    /// by position or content.
+    /// [`CompactionResult::id_map`] maps every live vector's old ID to its
+    /// new one. Metadata, external IDs (see
+    /// [`HnswIndex::insert_with_external_id`]) and binary quantization are
+    /// carried over to the new vectors, so callers that address vectors by
+    /// their own keys are unaffected.
     ///
     /// # Returns
     ///
@@ -1774,12 +2113,24 @@ impl HnswIndex {
     /// storage = new_storage;
     /// ```
     ///
+    /// # WAL: Compaction Detaches the Log
+    ///
+    /// **The new index and storage have no WAL.** Vector IDs are positional,
+    /// so the old log cannot describe the renumbered vectors, and mutations
+    /// made to the new pair are NOT logged. Save a snapshot of the new pair
+    /// right away and start a fresh, empty log from it, for example with
+    /// [`crate::persistence::write_snapshot`] followed by
+    /// [`crate::persistence::recover_index`] on the new snapshot and an empty
+    /// WAL backend. Until then, a crash loses every change since compaction.
+    ///
     /// # Algorithm
     ///
     /// 1. Collect all live vectors (non-deleted)
     /// 2. Create a new empty index and storage with the same config
+    ///    (with BQ enabled if the original had it)
     /// 3. Re-insert all live vectors using regular insert()
-    /// 4. Return the new pair
+    /// 4. Translate metadata and external IDs through the old → new ID map
+    /// 5. Return the new pair
     ///
     /// # Performance
     ///
@@ -1810,60 +2161,12 @@ impl HnswIndex {
             .unwrap_or(0.0);
 
         let original_deleted = self.deleted_count;
-        let original_total = self.node_count();
-
-        // Fast path: no tombstones — return copy
-        if original_deleted == 0 {
-            // Clone manually since VectorStorage doesn't implement Clone
-            let config = self.config.clone();
-            let mut new_storage = VectorStorage::new(&config, None);
-            let mut new_index = HnswIndex::new(config, &new_storage)?;
-            new_index.compaction_threshold = self.compaction_threshold;
-
-            // Re-insert all vectors (this is a rebuild, but preserves order)
-            for node in &self.nodes {
-                let vec = storage.get_vector(node.vector_id);
-                new_index.insert(&vec, &mut new_storage)?;
-            }
-
-            return Ok((
-                new_index,
-                new_storage,
-                CompactionResult {
-                    tombstones_removed: 0,
-                    new_size: original_total,
-                    duration_ms: 0,
-                },
-            ));
-        }
 
-        // Collect live vectors' data
-        let live_vectors: Vec<Vec<f32>> = self
-            .nodes
-            .iter()
-            .filter(|node| node.deleted == 0)
-            .map(|node| {
-                let vec = storage.get_vector(node.vector_id);
-                vec.into_owned()
-            })
-            .collect();
-
-        let new_size = live_vectors.len();
-
-        // Build new index AND new storage with same config
-        let config = self.config.clone();
-        let mut new_storage = VectorStorage::new(&config, None);
-        let mut new_index = HnswIndex::new(config, &new_storage)?;
+        let (new_index, new_storage, id_map) = self.rebuild_live(storage)?;
+        let new_size = id_map.len();
 
-        // Copy compaction threshold from original
-        new_index.compaction_threshold = self.compaction_threshold;
-
-        // Re-insert all live vectors (IDs will be remapped)
-        for vector in live_vectors {
-            new_index.insert(&vector, &mut new_storage)?;
-        }
-
-        // Calculate duration based on target
+        // Calculate duration based on target. The pair is rebuilt even
+        // without tombstones (storage is not Clone), so it is always measured.
         #[cfg(not(target_arch = "wasm32"))]
         let duration_ms = start.elapsed().as_millis() as u64;
         #[cfg(target_arch = "wasm32")]
@@ -1879,26 +2182,67 @@ impl HnswIndex {
                 tombstones_removed: original_deleted,
                 new_size,
                 duration_ms,
+                id_map,
             },
         ))
     }
 
-    /// Insert a vector with a specific ID (validation only).
+    /// Re-inserts all live vectors into a fresh index and storage.
     ///
-    /// This method validates that the specified ID doesn't conflict with
-    /// existing IDs, then delegates to the standard `insert()` method.
-    ///
-    /// **Important:** Due to storage design constraints where VectorIds must
-    /// match storage slot indices, the returned VectorId is the one assigned
-    /// by storage, NOT the requested ID. The `id` parameter is only used
-    /// for duplicate validation.
+    /// Carries over the compaction threshold, BQ, PQ, metadata and external IDs,
+    /// and returns the old → new `VectorId` map.
+    fn rebuild_live(
+        &self,
+        storage: &VectorStorage,
+    ) -> Result<(HnswIndex, VectorStorage, HashMap<VectorId, VectorId>), GraphError> {
+        // Build new index AND new storage with same config
+        let config = self.config.clone();
+        let mut new_storage = VectorStorage::new(&config, None);
+        // Keep SQ8 and dual storage; codes are re-encoded on insert
+        new_storage.set_storage_type(storage.storage_type().clone());
+        let mut new_index = if self.has_bq() {
+            HnswIndex::with_bq(config, &new_storage)?
+        } else {
+            HnswIndex::new(config, &new_storage)?
+        };
+
+        // Copy compaction threshold from original
+        new_index.compaction_threshold = self.compaction_threshold;
+
+        // Keep the trained codebooks; only the codes are rebuilt
+        new_index.pq_storage = self.pq_storage.as_ref().map(PqCodeStorage::emptied);
+
+        // Re-insert all live vectors in order (IDs will be remapped).
+        // insert() also fills BQ and PQ storage when enabled.
+        let mut id_map =
+            HashMap::with_capacity(self.nodes.len().saturating_sub(self.deleted_count));
+        for node in self.nodes.iter().filter(|node| node.deleted == 0) {
+            let vector = storage.get_vector(node.vector_id);
+            let new_id = new_index.insert(&vector, &mut new_storage)?;
+            id_map.insert(node.vector_id, new_id);
+        }
+
+        // Carry metadata and external IDs over to the renumbered vectors
+        let metadata_map: HashMap<u32, u32> = id_map
+            .iter()
+            .map(|(old, new)| (old.0 as u32, new.0 as u32))
+            .collect();
+        new_index.metadata = self.metadata.remapped(&metadata_map);
+        new_index.external_ids = self.external_ids.remapped(&id_map);
+
+        Ok((new_index, new_storage, id_map))
+    }
+
+    /// Insert a vector under a caller-supplied numeric ID.
     ///
-    /// For actual ID preservation during compaction, the storage would need
-    /// to support sparse ID assignment, which is not currently implemented.
+    /// Shorthand for [`insert_with_external_id`](Self::insert_with_external_id)
+    /// with `ExternalId::Num(id.0)`. The returned VectorId is the one assigned
+    /// by storage (sequential); the requested `id` is recorded as the vector's
+    /// external ID and stays valid across `compact()` and snapshots.
     ///
     /// # Arguments
     ///
-    /// * `id` - The specific VectorId to validate (not actually used)
+    /// * `id` - The caller's ID for this vector
     /// * `vector` - The vector data (must match configured dimensions)
     /// * `storage` - Mutable reference to vector storage
     ///
@@ -1908,7 +2252,8 @@ impl HnswIndex {
     ///
     /// # Errors
     ///
-    /// * `InvalidVectorId` - If ID already exists in index or is the sentinel value
+    /// * `InvalidVectorId` - If `id` is the sentinel value
+    /// * `DuplicateExternalId` - If `id` is already mapped to a live vector
     /// * `DimensionMismatch` - If vector dimensions don't match config
     /// * `Storage` - If storage operation fails
     pub fn insert_with_id(
@@ -1922,12 +2267,68 @@ impl HnswIndex {
             return Err(GraphError::InvalidVectorId);
         }
 
-        // Validate ID doesn't already exist
-        if self.nodes.iter().any(|n| n.vector_id == id) {
-            return Err(GraphError::InvalidVectorId);
+        self.insert_with_external_id(id.0, vector, storage)
+    }
+}
+
+// ============================================================================
+// External ID API
+// ============================================================================
+
+/// A search result addressed by external ID.
+///
+/// Returned by [`HnswIndex::search_external`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalSearchResult {
+    /// The caller-supplied ID, or `None` if the vector was inserted without one.
+    pub external_id: Option<ExternalId>,
+    /// The internal ID of the matching vector.
+    pub vector_id: VectorId,
+    /// The distance from the query vector.
+    pub distance: f32,
+}
+
+impl HnswIndex {
+    /// Inserts a vector under a caller-supplied external ID.
+    ///
+    /// The external ID can be a `u64` or a string. It is kept in sync with the
+    /// storage-assigned `VectorId` and survives `compact()` and snapshots.
+    ///
+    /// # Errors
+    ///
+    /// * `DuplicateExternalId` - If `external_id` is already mapped to a live vector
+    /// * `DimensionMismatch` - If vector dimensions don't match config
+    /// * `Storage` - If storage operation fails, or logging the binding to
+    ///   the WAL fails (the vector is inserted but has no external ID)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{ExternalId, HnswConfig, HnswIndex};
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(4);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    ///
+    /// index.insert_with_external_id("doc-1", &[1.0, 0.0, 0.0, 0.0], &mut storage).unwrap();
+    /// index.insert_with_external_id(500u64, &[0.0, 1.0, 0.0, 0.0], &mut storage).unwrap();
+    ///
+    /// let results = index.search_external(&[1.0, 0.0, 0.0, 0.0], 1, &storage).unwrap();
+    /// assert_eq!(results[0].external_id, Some(ExternalId::from("doc-1")));
+    /// ```
+    pub fn insert_with_external_id(
+        &mut self,
+        external_id: impl Into<ExternalId>,
+        vector: &[f32],
+        storage: &mut VectorStorage,
+    ) -> Result<VectorId, GraphError> {
+        let external_id = external_id.into();
+        if self.external_ids.contains(&external_id) {
+            return Err(GraphError::DuplicateExternalId(external_id));
         }
 
-        // Validate dimensions
+        // Validate dimensions before touching storage
         if vector.len() != self.config.dimensions as usize {
             return Err(GraphError::DimensionMismatch {
                 expected: self.config.dimensions as usize,
@@ -1935,8 +2336,147 @@ impl HnswIndex {
             });
         }
 
-        // Delegate to regular insert (which handles all the graph connection logic)
-        self.insert(vector, storage)
+        let vector_id = self.insert(vector, storage)?;
+        self.bind_external_id(external_id, vector_id)?;
+        Ok(vector_id)
+    }
+
+    /// Searches for the `k` nearest neighbors and reports their external IDs.
+    ///
+    /// Same ranking as [`search`](Self::search); each result additionally
+    /// carries the external ID of the matched vector.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`search`](Self::search).
+    pub fn search_external(
+        &self,
+        query: &[f32],
+        k: usize,
+        storage: &VectorStorage,
+    ) -> Result<Vec<ExternalSearchResult>, GraphError> {
+        let results = self.search(query, k, storage)?;
+        Ok(self.with_external_ids(results))
+    }
+
+    /// Attaches external IDs to plain search results.
+    pub(crate) fn with_external_ids(
+        &self,
+        results: Vec<SearchResult>,
+    ) -> Vec<ExternalSearchResult> {
+        results
+            .into_iter()
+            .map(|r| ExternalSearchResult {
+                external_id: self.external_ids.external_id(r.vector_id).cloned(),
+                vector_id: r.vector_id,
+                distance: r.distance,
+            })
+            .collect()
+    }
+
+    /// Soft-deletes the vector mapped to `external_id`.
+    ///
+    /// The external ID is released and may be reused by a later insert.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExternalIdNotFound` if no live vector has this external ID,
+    /// or `Storage` if the WAL write failed (see [`soft_delete`](Self::soft_delete)).
+    pub fn delete_external(
+        &mut self,
+        external_id: impl Into<ExternalId>,
+    ) -> Result<bool, GraphError> {
+        let external_id = external_id.into();
+        let vector_id = self
+            .external_ids
+            .vector_id(&external_id)
+            .ok_or(GraphError::ExternalIdNotFound(external_id))?;
+        self.soft_delete(vector_id)
+    }
+
+    /// Inserts or updates the vector mapped to `external_id`.
+    ///
+    /// If `external_id` is already mapped, the vector is replaced in place via
+    /// [`update`](Self::update), keeping its `VectorId` and metadata. Otherwise
+    /// it is inserted as by [`insert_with_external_id`](Self::insert_with_external_id).
+    ///
+    /// # Returns
+    ///
+    /// The `VectorId` now holding the vector.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`update`](Self::update) and
+    /// [`insert_with_external_id`](Self::insert_with_external_id).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(2);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    ///
+    /// let first = index.upsert("doc", &[1.0, 0.0], &mut storage).unwrap();
+    /// let second = index.upsert("doc", &[0.0, 1.0], &mut storage).unwrap();
+    ///
+    /// assert_eq!(first, second);
+    /// assert_eq!(index.len(), 1);
+    /// assert_eq!(&storage.get_vector(first)[..], &[0.0, 1.0]);
+    /// ```
+    pub fn upsert(
+        &mut self,
+        external_id: impl Into<ExternalId>,
+        vector: &[f32],
+        storage: &mut VectorStorage,
+    ) -> Result<VectorId, GraphError> {
+        let external_id = external_id.into();
+        match self.external_ids.vector_id(&external_id) {
+            Some(vector_id) => {
+                self.update(vector_id, vector, storage)?;
+                Ok(vector_id)
+            }
+            None => self.insert_with_external_id(external_id, vector, storage),
+        }
+    }
+
+    /// Fetches the vector data for `external_id`.
+    ///
+    /// Returns `None` if no live vector has this external ID.
+    #[must_use]
+    pub fn get_external<'a>(
+        &self,
+        external_id: &ExternalId,
+        storage: &'a VectorStorage,
+    ) -> Option<Cow<'a, [f32]>> {
+        let vector_id = self.external_ids.vector_id(external_id)?;
+        Some(storage.get_vector(vector_id))
+    }
+
+    /// Returns the `VectorId` currently mapped to `external_id`.
+    #[must_use]
+    pub fn resolve_external_id(&self, external_id: &ExternalId) -> Option<VectorId> {
+        self.external_ids.vector_id(external_id)
+    }
+
+    /// Returns the external ID attached to `vector_id`, if any.
+    #[must_use]
+    pub fn external_id(&self, vector_id: VectorId) -> Option<&ExternalId> {
+        self.external_ids.external_id(vector_id)
+    }
+
+    /// Returns true if `external_id` is mapped to a live vector.
+    #[must_use]
+    pub fn contains_external_id(&self, external_id: &ExternalId) -> bool {
+        self.external_ids.contains(external_id)
+    }
+
+    /// Returns the external ID map.
+    #[must_use]
+    pub fn external_ids(&self) -> &ExternalIdMap {
+        &self.external_ids
     }
 }
 
@@ -1978,6 +2518,23 @@ pub trait VectorProvider {
         let _ = output;
         None
     }
+
+    /// Distance between two quantized vectors from this provider under an
+    /// HNSW metric code (see [`ScalarQuantizer::distance`]).
+    ///
+    /// Defaults to treating the codes as plain `0..=255` values. Providers
+    /// override this to account for their quantizer's ranges.
+    ///
+    /// [`ScalarQuantizer::distance`]: crate::quantization::ScalarQuantizer::distance
+    fn quantized_distance(&self, metric: u32, a: &[u8], b: &[u8]) -> f32 {
+        use crate::quantization::{QuantizerConfig, ScalarQuantizer};
+
+        let identity = ScalarQuantizer::new(QuantizerConfig {
+            min: 0.0,
+            max: 255.0,
+        });
+        identity.distance(metric, a, b)
+    }
 }
 
 // ============================================================================
@@ -2017,7 +2574,7 @@ impl HnswIndex {
     /// Returns `true` if the ID exists, `false` otherwise.
     #[must_use]
     pub fn contains_id(&self, id: u64) -> bool {
-        self.nodes.iter().any(|n| n.vector_id.0 == id)
+        self.node_index.contains_key(&VectorId(id))
     }
 
     /// Checks if a vector contains invalid floating-point values.
@@ -2061,11 +2618,37 @@ impl BatchInsertable for HnswIndex {
     ///   - DuplicateId (within batch or existing in index)
     ///   - InvalidVector (NaN, Inf)
     ///   - DimensionMismatch on subsequent vectors
+    ///
+    /// # External IDs
+    ///
+    /// The caller's `u64` IDs are recorded as external IDs (see
+    /// [`HnswIndex::insert_with_external_id`]); the returned `Vec<u64>` holds
+    /// the storage-assigned `VectorId`s in the same order.
     fn batch_insert<I, F>(
+        &mut self,
+        vectors: I,
+        storage: &mut VectorStorage,
+        progress_callback: Option<F>,
+    ) -> Result<Vec<u64>, BatchError>
+    where
+        I: IntoIterator<Item = (u64, Vec<f32>)>,
+        F: FnMut(usize, usize),
+    {
+        self.batch_insert_impl(vectors, storage, progress_callback, true)
+    }
+}
+
+impl HnswIndex {
+    /// Shared batch insertion logic.
+    ///
+    /// When `register_ids` is false the supplied IDs are treated as
+    /// placeholders: they are neither validated nor recorded as external IDs.
+    pub(crate) fn batch_insert_impl<I, F>(
         &mut self,
         vectors: I,
         storage: &mut VectorStorage,
         mut progress_callback: Option<F>,
+        register_ids: bool,
     ) -> Result<Vec<u64>, BatchError>
     where
         I: IntoIterator<Item = (u64, Vec<f32>)>,
@@ -2116,22 +2699,24 @@ impl BatchInsertable for HnswIndex {
 
         // Step 5: Process each vector
         for (id, vector) in batch {
-            // Check for duplicate ID within this batch
-            if !seen_ids.insert(id) {
-                // Duplicate within batch - skip (non-fatal)
-                continue;
-            }
+            if register_ids {
+                // Check for duplicate ID within this batch
+                if !seen_ids.insert(id) {
+                    // Duplicate within batch - skip (non-fatal)
+                    continue;
+                }
 
-            // Check for ID 0 (reserved sentinel)
-            if id == 0 {
-                // Skip invalid ID (non-fatal)
-                continue;
-            }
+                // Check for ID 0 (reserved sentinel)
+                if id == 0 {
+                    // Skip invalid ID (non-fatal)
+                    continue;
+                }
 
-            // Check for duplicate ID in existing index [M1 fix]
-            if self.contains_id(id) {
-                // Duplicate in existing index - skip (non-fatal)
-                continue;
+                // Check for duplicate ID in existing index [M1 fix]
+                if self.external_ids.contains(&ExternalId::Num(id)) {
+                    // Duplicate in existing index - skip (non-fatal)
+                    continue;
+                }
             }
 
             // Validate dimensionality
@@ -2149,6 +2734,12 @@ impl BatchInsertable for HnswIndex {
             // Step 6: Actually insert the vector into the HNSW graph [C1 fix]
             match self.insert(&vector, storage) {
                 Ok(assigned_id) => {
+                    if register_ids {
+                        self.bind_external_id(ExternalId::Num(id), assigned_id)
+                            .map_err(|e| BatchError::InternalError {
+                                message: format!("binding external ID {id} failed: {e}"),
+                            })?;
+                    }
                     // Use the ID assigned by the insert method
                     inserted_ids.push(assigned_id.0);
                 }
@@ -2777,6 +3368,55 @@ mod tests {
             assert!(bad.is_err());
         }
 
+        #[test]
+        fn test_node_index_matches_positions() {
+            let (mut index, mut storage) = create_test_index();
+            for i in 0..20 {
+                index.insert(&[i as f32; 4], &mut storage).unwrap();
+            }
+            index.soft_delete(VectorId(7)).unwrap();
+
+            assert_eq!(index.node_index.len(), 20);
+            for (idx, node) in index.nodes.iter().enumerate() {
+                assert_eq!(index.node_id_of(node.vector_id), Some(NodeId(idx as u32)));
+            }
+            assert!(index.contains_id(7), "tombstones stay addressable");
+            assert!(!index.contains_id(21));
+        }
+
+        #[test]
+        fn test_rebuild_node_index_rejects_duplicates() {
+            let (mut index, mut storage) = create_test_index();
+            index.insert(&[1.0; 4], &mut storage).unwrap();
+            index.insert(&[2.0; 4], &mut storage).unwrap();
+
+            index.node_index.clear();
+            assert!(index.rebuild_node_index().is_ok());
+            assert_eq!(index.node_id_of(VectorId(2)), Some(NodeId(1)));
+
+            index.nodes.to_mut()[1].vector_id = VectorId(1);
+            assert_eq!(index.rebuild_node_index(), Err(GraphError::InvalidVectorId));
+        }
+
+        #[test]
+        fn test_node_index_rebuilt_on_deserialize() {
+            let (mut index, mut storage) = create_test_index();
+            for i in 0..10 {
+                index.insert(&[i as f32; 4], &mut storage).unwrap();
+            }
+            index.soft_delete(VectorId(4)).unwrap();
+
+            let bytes = postcard::to_allocvec(&index).unwrap();
+            let loaded: HnswIndex = postcard::from_bytes(&bytes).unwrap();
+            assert_eq!(loaded.node_index, index.node_index);
+            assert!(loaded.is_deleted(VectorId(4)).unwrap());
+            assert!(loaded.contains_id(10));
+
+            // The lookup itself is not part of the serialized form
+            index.node_index.clear();
+            assert_eq!(postcard::to_allocvec(&index).unwrap(), bytes);
+        }
+
         #[test]
         fn test_deleted_field_is_zero_by_default() {
             let (mut index, mut storage) = create_test_index();
@@ -2,8 +2,7 @@ use super::config::HnswConfig;
 use super::graph::{GraphError, HnswIndex, NodeId, VectorId, VectorProvider};
 use super::search::{Candidate, SearchContext, Searcher};
 use crate::hnsw::neighbor::NeighborPool;
-use crate::metric::simd::l2_squared_u8;
-use crate::metric::{DotProduct, L2Squared, Metric};
+use crate::metric::{Cosine, L2Squared, Metric, NegativeDotProduct};
 use crate::quantization::variable::BinaryVector;
 use crate::storage::VectorStorage;
 
@@ -99,9 +98,10 @@ impl HnswIndex {
         // Dispatch based on metric
         match self.config.metric {
             HnswConfig::METRIC_L2_SQUARED => self.insert_impl::<L2Squared>(vector, storage),
-            HnswConfig::METRIC_DOT_PRODUCT | HnswConfig::METRIC_COSINE => {
-                self.insert_impl::<DotProduct>(vector, storage)
+            HnswConfig::METRIC_DOT_PRODUCT => {
+                self.insert_impl::<NegativeDotProduct>(vector, storage)
             }
+            HnswConfig::METRIC_COSINE => self.insert_impl::<Cosine>(vector, storage),
             _ => Err(GraphError::InvalidConfig(format!(
                 "unsupported metric code: {}",
                 self.config.metric
@@ -164,6 +164,216 @@ impl HnswIndex {
         self.insert(vector, storage)
     }
 
+    /// Replaces the vector of an existing node in place.
+    ///
+    /// The `VectorId` is kept, so metadata and external IDs stay attached.
+    /// The new vector overwrites the slot in `VectorStorage` (logged to the
+    /// WAL when one is configured) and the node's neighbor lists are rebuilt
+    /// on every layer it lives in, as if it had just been inserted at its
+    /// existing level.
+    ///
+    /// Incoming edges from other nodes are kept. They stay valid for routing
+    /// and are pruned naturally as those nodes gain closer neighbors.
+    ///
+    /// # Arguments
+    ///
+    /// * `vector_id` - The live vector to update.
+    /// * `vector` - The new vector data (must match configured dimensions).
+    /// * `storage` - The storage backend holding the vector.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError` if:
+    /// - `vector` has the wrong dimensions (`DimensionMismatch`).
+    /// - `vector_id` does not exist or is deleted (`InvalidVectorId`).
+    /// - The storage or WAL write fails (`Storage`).
+    /// - Config metric is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::storage::VectorStorage;
+    ///
+    /// let config = HnswConfig::new(2);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    ///
+    /// let id = index.insert(&[1.0, 0.0], &mut storage).unwrap();
+    /// index.insert(&[0.0, 1.0], &mut storage).unwrap();
+    ///
+    /// index.update(id, &[0.0, 0.9], &mut storage).unwrap();
+    /// assert_eq!(&storage.get_vector(id)[..], &[0.0, 0.9]);
+    /// ```
+    pub fn update(
+        &mut self,
+        vector_id: VectorId,
+        vector: &[f32],
+        storage: &mut VectorStorage,
+    ) -> Result<(), GraphError> {
+        match self.config.metric {
+            HnswConfig::METRIC_L2_SQUARED => {
+                self.update_impl::<L2Squared>(vector_id, vector, storage)
+            }
+            HnswConfig::METRIC_DOT_PRODUCT => {
+                self.update_impl::<NegativeDotProduct>(vector_id, vector, storage)
+            }
+            HnswConfig::METRIC_COSINE => self.update_impl::<Cosine>(vector_id, vector, storage),
+            _ => Err(GraphError::InvalidConfig(format!(
+                "unsupported metric code: {}",
+                self.config.metric
+            ))),
+        }
+    }
+
+    /// Generic implementation of update for a specific metric.
+    fn update_impl<M: Metric<f32>>(
+        &mut self,
+        vector_id: VectorId,
+        vector: &[f32],
+        storage: &mut VectorStorage,
+    ) -> Result<(), GraphError> {
+        // Step 1: Validate before any mutation
+        let expected_dim = self.config.dimensions as usize;
+        if vector.len() != expected_dim {
+            return Err(GraphError::DimensionMismatch {
+                expected: expected_dim,
+                actual: vector.len(),
+            });
+        }
+
+        let node_id = self
+            .node_id_of(vector_id)
+            .ok_or(GraphError::InvalidVectorId)?;
+        let node_layer = {
+            let node = self
+                .get_node(node_id)
+                .ok_or(GraphError::NodeIdOutOfBounds)?;
+            if node.deleted != 0 {
+                return Err(GraphError::InvalidVectorId);
+            }
+            node.max_layer
+        };
+
+        // Step 2: Overwrite storage (WAL first, then memory)
+        storage
+            .update(vector_id, vector)
+            .map_err(|e| GraphError::Storage(e.to_string()))?;
+
+        if let Some(ref mut bq_storage) = self.bq_storage {
+            let bv = BinaryVector::quantize(vector)
+                .map_err(|e| GraphError::Quantization(e.to_string()))?;
+            bq_storage
+                .update(u64::from(node_id.0), &bv)
+                .map_err(|e| GraphError::Storage(e.to_string()))?;
+        }
+        if let Some(ref mut pq_storage) = self.pq_storage {
+            pq_storage
+                .update(u64::from(node_id.0), vector)
+                .map_err(|e| GraphError::Quantization(e.to_string()))?;
+        }
+
+        let Some(entry_point_id) = self.entry_point() else {
+            return Ok(());
+        };
+        let entry_max_layer = self
+            .get_node(entry_point_id)
+            .ok_or(GraphError::NodeIdOutOfBounds)?
+            .max_layer;
+
+        let mut search_ctx = SearchContext::new();
+        let mut curr_ep = entry_point_id;
+
+        // Step 3: Greedy descent to the node's top layer
+        for lc in (node_layer + 1..=entry_max_layer).rev() {
+            let searcher = Searcher::<M, VectorStorage>::new(self, storage);
+            searcher.search_layer(&mut search_ctx, [curr_ep], vector, 1, lc)?;
+
+            if let Some(best) = search_ctx.scratch.first() {
+                curr_ep = best.node_id;
+            }
+        }
+
+        // Step 4: Rebuild the node's neighbor list on each layer it lives in
+        for lc in (0..=node_layer.min(entry_max_layer)).rev() {
+            let ef = self.config.ef_construction as usize;
+
+            let searcher = Searcher::<M, VectorStorage>::new(self, storage);
+            searcher.search_layer(&mut search_ctx, [curr_ep], vector, ef, lc)?;
+
+            // The node itself is always found at distance 0; it cannot be its own neighbor
+            search_ctx.scratch.retain(|c| c.node_id != node_id);
+            let next_ep = search_ctx.scratch.first().map(|c| c.node_id);
+
+            let m_max = if lc == 0 {
+                self.config.m0
+            } else {
+                self.config.m
+            } as usize;
+
+            {
+                let SearchContext {
+                    ref scratch,
+                    ref mut neighbor_scratch,
+                    ..
+                } = search_ctx;
+
+                self.select_neighbors_heuristic::<M>(
+                    vector,
+                    scratch,
+                    m_max,
+                    lc,
+                    storage,
+                    neighbor_scratch,
+                )?;
+            }
+
+            let neighbors = search_ctx.neighbor_scratch.clone();
+            let raw: Vec<u32> = neighbors.iter().map(|n| n.0).collect();
+            self.replace_layer_neighbors(node_id, lc, &raw)?;
+
+            for &neighbor_id in &neighbors {
+                self.add_connection::<M>(neighbor_id, node_id, lc, storage, &mut search_ctx)?;
+            }
+
+            if let Some(best_id) = next_ep {
+                curr_ep = best_id;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds a vector that is already in `storage` to the graph.
+    ///
+    /// Used when building a graph over existing storage (e.g. promoting a
+    /// `FlatIndex`), so the vector keeps its `VectorId`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GraphError` if the config metric is invalid or the graph is
+    /// corrupted.
+    pub(crate) fn link_existing(
+        &mut self,
+        vector_id: VectorId,
+        storage: &VectorStorage,
+    ) -> Result<(), GraphError> {
+        let vector = storage.get_vector(vector_id).into_owned();
+        match self.config.metric {
+            HnswConfig::METRIC_L2_SQUARED => {
+                self.link_impl::<L2Squared>(vector_id, &vector, storage)
+            }
+            HnswConfig::METRIC_DOT_PRODUCT => {
+                self.link_impl::<NegativeDotProduct>(vector_id, &vector, storage)
+            }
+            HnswConfig::METRIC_COSINE => self.link_impl::<Cosine>(vector_id, &vector, storage),
+            _ => Err(GraphError::InvalidConfig(format!(
+                "unsupported metric code: {}",
+                self.config.metric
+            ))),
+        }
+    }
+
     /// Generic implementation of insert for a specific metric.
     fn insert_impl<M: Metric<f32>>(
         &mut self,
@@ -175,6 +385,17 @@ impl HnswIndex {
             .insert(vector)
             .map_err(|e| GraphError::Storage(e.to_string()))?;
 
+        self.link_impl::<M>(vector_id, vector, storage)?;
+        Ok(vector_id)
+    }
+
+    /// Helper: Create a node for a stored vector and connect it (steps 2-6 of insert).
+    fn link_impl<M: Metric<f32>>(
+        &mut self,
+        vector_id: VectorId,
+        vector: &[f32],
+        storage: &VectorStorage,
+    ) -> Result<(), GraphError> {
         // Step 2: Determine random level L
         let level = self.get_random_level();
 
@@ -304,11 +525,18 @@ impl HnswIndex {
                 .map_err(|e| GraphError::Storage(e.to_string()))?;
         }
 
-        Ok(vector_id)
+        // Step 7: Likewise encode the PQ code when PQ is enabled
+        if let Some(ref mut pq_storage) = self.pq_storage {
+            pq_storage
+                .insert(vector)
+                .map_err(|e| GraphError::Quantization(e.to_string()))?;
+        }
+
+        Ok(())
     }
 
     /// Helper: Select neighbors using HNSW heuristic.
-    fn select_neighbors_heuristic<M: Metric<f32>>(
+    pub(super) fn select_neighbors_heuristic<M: Metric<f32>>(
         &self,
         _query: &[f32],
         candidates: &[Candidate],
@@ -324,13 +552,10 @@ impl HnswIndex {
 
         // Check if we should use quantized distance
         // Logic matches search_layer in search.rs
-        let use_quantized = if self.config.metric == HnswConfig::METRIC_L2_SQUARED {
-            // Use VectorProvider trait method to check if quantized data is available
-            // storage is &mut VectorStorage, which implements VectorProvider
-            VectorProvider::get_quantized_vector(storage, VectorId::FIRST).is_some()
-        } else {
-            false
-        };
+        // Use VectorProvider trait method to check if quantized data is available
+        // storage is &mut VectorStorage, which implements VectorProvider
+        let use_quantized =
+            VectorProvider::get_quantized_vector(storage, VectorId::FIRST).is_some();
 
         for c in candidates {
             if output.len() >= m {
@@ -356,10 +581,7 @@ impl HnswIndex {
                         VectorProvider::get_quantized_vector(storage, r_node.vector_id)
                             .ok_or_else(|| GraphError::Storage("Missing quantized data".into()))?;
 
-                    // SAFETY: u32 -> f32 precision loss is acceptable for distance comparison.
-                    // Max u32 is ~4e9, well within f32's representation range (~3.4e38).
-                    #[allow(clippy::cast_precision_loss)]
-                    let dist_c_r = l2_squared_u8(c_q_vec, r_q_vec) as f32;
+                    let dist_c_r = storage.quantized_distance(self.config.metric, c_q_vec, r_q_vec);
 
                     if dist_c_r < dist_q_c {
                         closer_to_existing = true;
@@ -462,11 +684,8 @@ impl HnswIndex {
 
         if ctx.neighbor_id_scratch.len() > m_max {
             // Check if we should use quantized distance
-            let use_quantized = if self.config.metric == HnswConfig::METRIC_L2_SQUARED {
-                VectorProvider::get_quantized_vector(storage, VectorId::FIRST).is_some()
-            } else {
-                false
-            };
+            let use_quantized =
+                VectorProvider::get_quantized_vector(storage, VectorId::FIRST).is_some();
 
             // Use scratch buffer for candidates
             ctx.scratch.clear();
@@ -486,9 +705,8 @@ impl HnswIndex {
                         VectorProvider::get_quantized_vector(storage, n_node.vector_id)
                             .ok_or_else(|| GraphError::Storage("Missing quantized data".into()))?;
 
-                    // SAFETY: u32 -> f32 precision loss is acceptable for distance comparison.
-                    #[allow(clippy::cast_precision_loss)]
-                    let dist = l2_squared_u8(source_q_vec, n_q_vec) as f32;
+                    let dist =
+                        storage.quantized_distance(self.config.metric, source_q_vec, n_q_vec);
                     ctx.scratch.push(Candidate {
                         distance: dist,
                         node_id: n_id,
@@ -554,6 +772,8 @@ impl HnswIndex {
 
         let new_size = start + gap_count + encoded_new_layer.len() + (valid_end - end);
 
+        let before = self.links_before_write(source)?;
+
         // 6. Alloc new space
         let (new_offset, new_capacity) = self.neighbors.alloc(new_size)?;
 
@@ -598,14 +818,61 @@ impl HnswIndex {
         }
 
         // 8. Update node and free old memory
-        let node = &mut self.nodes[node_idx];
+        let node = &mut self.nodes.to_mut()[node_idx];
         if old_len > 0 {
             self.neighbors.free(old_offset, old_len);
         }
         node.neighbor_offset = new_offset;
         node.neighbor_len = new_capacity;
 
-        Ok(())
+        self.links_after_write(source, before)
+    }
+}
+
+impl HnswIndex {
+    /// Helper: Replace a node's neighbor list on one layer, keeping all other layers.
+    pub(super) fn replace_layer_neighbors(
+        &mut self,
+        node_id: NodeId,
+        layer: u8,
+        neighbors: &[u32],
+    ) -> Result<(), GraphError> {
+        let (max_layer, old_offset, old_len) = {
+            let node = self
+                .get_node(node_id)
+                .ok_or(GraphError::NodeIdOutOfBounds)?;
+            (node.max_layer, node.neighbor_offset, node.neighbor_len)
+        };
+
+        let mut encoded = Vec::new();
+        {
+            let blob =
+                &self.neighbors.buffer[old_offset as usize..old_offset as usize + old_len as usize];
+            for lc in 0..=max_layer {
+                if lc == layer {
+                    NeighborPool::encode_neighbors_to_buf(neighbors, &mut encoded);
+                } else {
+                    let existing = NeighborPool::decode_layer(blob, lc);
+                    NeighborPool::encode_neighbors_to_buf(&existing, &mut encoded);
+                }
+            }
+        }
+
+        let before = self.links_before_write(node_id)?;
+        let (new_offset, new_capacity) = self.neighbors.alloc(encoded.len())?;
+        let start = new_offset as usize;
+        let allocated_end = start + new_capacity as usize;
+        self.neighbors.buffer[start..start + encoded.len()].copy_from_slice(&encoded);
+        self.neighbors.buffer[start + encoded.len()..allocated_end].fill(0);
+
+        let node = &mut self.nodes.to_mut()[node_id.0 as usize];
+        if old_len > 0 {
+            self.neighbors.free(old_offset, old_len);
+        }
+        node.neighbor_offset = new_offset;
+        node.neighbor_len = new_capacity;
+
+        self.links_after_write(node_id, before)
     }
 }
 
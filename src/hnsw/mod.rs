@@ -2,6 +2,12 @@
 
 /// Configuration types.
 pub mod config;
+/// Hard delete with neighbor repair.
+pub mod delete;
+/// WAL-logged index mutations.
+pub mod durable;
+/// Caller-supplied external IDs.
+pub mod external_id;
 /// Graph data structures.
 pub mod graph;
 /// Insertion algorithms.
@@ -14,11 +20,15 @@ pub mod rescore;
 pub mod search;
 /// Binary quantization search algorithms (v0.7.0 - RFC-002 Phase 2).
 pub mod search_bq;
+/// Product quantization search with ADC lookup tables.
+pub mod search_pq;
 
 pub use config::HnswConfig;
+pub use durable::MetadataMut;
+pub use external_id::{ExternalId, ExternalIdMap};
 pub use graph::{
-    BatchDeleteError, BatchDeleteResult, CompactionResult, GraphError, HnswIndex, HnswNode, NodeId,
-    VectorId, VectorProvider,
+    BatchDeleteError, BatchDeleteResult, CompactionResult, ExternalSearchResult, GraphError,
+    HnswIndex, HnswNode, NodeId, VectorId, VectorProvider,
 };
 pub use neighbor::NeighborPool;
 pub use search::{Candidate, SearchContext, SearchResult, Searcher};
@@ -1,41 +1,66 @@
 #![allow(clippy::cast_precision_loss)]
 
-//! F32 rescoring for BQ search results (v0.7.0 - RFC-002 Phase 2).
+//! Exact rescoring for BQ search results (v0.7.0 - RFC-002 Phase 2).
 //!
-//! This module provides rescoring functions that recompute exact F32
-//! distances for BQ search candidates, recovering recall lost during
-//! binary quantization.
+//! This module provides rescoring functions that recompute exact distances
+//! for BQ search candidates, recovering recall lost during binary
+//! quantization. PQ search (`search_pq_rescored`) rescores its candidates
+//! the same way.
 //!
 //! # Algorithm
 //!
-//! 1. Take BQ search results (approximate similarity)
-//! 2. Load F32 vectors for each candidate
-//! 3. Compute exact L2 squared distance
+//! 1. Take BQ or PQ search results (approximate similarity)
+//! 2. Load the stored vector for each candidate
+//! 3. Compute the exact distance under the index metric
 //! 4. Sort by exact distance and return top-k
 //!
+//! # Vector Source
+//!
+//! Candidates are rescored from F32 data when the storage keeps it, as F32
+//! and dual (`StorageType::DualU8`) storage do. SQ8 storage
+//! (`StorageType::QuantizedU8`) does not keep F32 vectors, so the codes are
+//! dequantized and rescored against the unquantized query instead.
+//!
+//! # Distances
+//!
+//! Rescored distances are always "lower is better":
+//!
+//! | Metric | Distance |
+//! |--------|----------|
+//! | `METRIC_L2_SQUARED` | squared L2 |
+//! | `METRIC_COSINE` | `1 - cosine_similarity` |
+//! | `METRIC_DOT_PRODUCT` | `-dot` |
+//!
+//! Use [`similarity`] to turn them back into "higher is better" scores.
+//!
 //! # Performance
 //!
 //! Rescoring is O(n × d) where n = candidates, d = dimensions.
 //! With rescore_factor=3, typical recall improves from ~0.85 to >0.95.
 
-use crate::hnsw::VectorId;
-use crate::metric::{L2Squared, Metric};
+use crate::hnsw::{GraphError, HnswConfig, VectorId};
+use crate::metric::{Cosine, L2Squared, Metric, NegativeDotProduct};
 use crate::storage::VectorStorage;
 
-/// Rescores BQ candidates using exact F32 L2 squared distance.
+/// Rescores BQ candidates using the exact distance for `metric`.
 ///
 /// Takes approximate BQ results and recomputes the exact distance
-/// for each candidate, returning them sorted by F32 distance.
+/// for each candidate, returning them sorted by that distance.
 ///
 /// # Arguments
 ///
 /// * `candidates` - BQ search results (VectorId, approximate_similarity).
 /// * `query` - The original query vector.
-/// * `storage` - F32 vector storage.
+/// * `storage` - Vector storage (F32 or SQ8).
+/// * `metric` - Index metric code (`HnswConfig::METRIC_*`).
 ///
 /// # Returns
 ///
-/// Candidates sorted by exact L2 squared distance (ascending = most similar first).
+/// Candidates sorted by exact distance (ascending = most similar first).
+///
+/// # Errors
+///
+/// Returns `GraphError::InvalidConfig` if `metric` is not a known metric code.
 ///
 /// # Performance
 ///
@@ -46,15 +71,120 @@ use crate::storage::VectorStorage;
 ///
 /// ```ignore
 /// let bq_results = index.search_bq(&query, k * 3, &storage)?;
-/// let rescored = rescore(&bq_results, &query, &storage);
+/// let rescored = rescore_with_metric(&bq_results, &query, &storage, index.config.metric)?;
 /// let final_results: Vec<_> = rescored.into_iter().take(k).collect();
 /// ```
+pub fn rescore_with_metric(
+    candidates: &[(VectorId, f32)],
+    query: &[f32],
+    storage: &VectorStorage,
+    metric: u32,
+) -> Result<Vec<(VectorId, f32)>, GraphError> {
+    match metric {
+        HnswConfig::METRIC_L2_SQUARED => Ok(rescore_impl(candidates, query, storage, |q, v| {
+            L2Squared::distance(q, v)
+        })),
+        HnswConfig::METRIC_DOT_PRODUCT => Ok(rescore_impl(candidates, query, storage, |q, v| {
+            NegativeDotProduct::distance(q, v)
+        })),
+        HnswConfig::METRIC_COSINE => Ok(rescore_impl(candidates, query, storage, |q, v| {
+            Cosine::distance(q, v)
+        })),
+        _ => Err(GraphError::InvalidConfig(format!(
+            "unsupported metric code: {metric}"
+        ))),
+    }
+}
+
+/// Rescores candidates under `metric` and returns top-k.
+///
+/// Convenience function combining [`rescore_with_metric`] + truncate.
+///
+/// # Arguments
+///
+/// * `candidates` - BQ search results.
+/// * `query` - The query vector.
+/// * `storage` - Vector storage (F32 or SQ8).
+/// * `metric` - Index metric code (`HnswConfig::METRIC_*`).
+/// * `k` - Number of results to return.
+///
+/// # Returns
+///
+/// Top-k candidates sorted by exact distance.
+///
+/// # Errors
+///
+/// Returns `GraphError::InvalidConfig` if `metric` is not a known metric code.
+pub fn rescore_top_k_with_metric(
+    candidates: &[(VectorId, f32)],
+    query: &[f32],
+    storage: &VectorStorage,
+    metric: u32,
+    k: usize,
+) -> Result<Vec<(VectorId, f32)>, GraphError> {
+    let mut rescored = rescore_with_metric(candidates, query, storage, metric)?;
+    rescored.truncate(k);
+    Ok(rescored)
+}
+
+/// Rescores BQ candidates using exact L2 squared distance.
+///
+/// Same as [`rescore_with_metric`] with `METRIC_L2_SQUARED`. Use that for
+/// indexes built with another metric.
+///
+/// # Returns
+///
+/// Candidates sorted by exact L2 squared distance (ascending = most similar first).
 #[must_use]
 pub fn rescore(
     candidates: &[(VectorId, f32)],
     query: &[f32],
     storage: &VectorStorage,
 ) -> Vec<(VectorId, f32)> {
+    rescore_impl(candidates, query, storage, L2Squared::distance)
+}
+
+/// Rescores candidates using exact L2 squared distance and returns top-k.
+///
+/// Same as [`rescore_top_k_with_metric`] with `METRIC_L2_SQUARED`.
+#[must_use]
+pub fn rescore_top_k(
+    candidates: &[(VectorId, f32)],
+    query: &[f32],
+    storage: &VectorStorage,
+    k: usize,
+) -> Vec<(VectorId, f32)> {
+    let mut rescored = rescore(candidates, query, storage);
+    rescored.truncate(k);
+    rescored
+}
+
+/// Converts a rescored distance into a similarity score (higher = better).
+///
+/// * L2: `1 / (1 + distance)`
+/// * Cosine: cosine similarity (`1 - distance`)
+/// * Dot product: the raw dot product (`-distance`)
+///
+/// Unknown metric codes fall back to the L2 conversion.
+#[must_use]
+pub fn similarity(metric: u32, distance: f32) -> f32 {
+    match metric {
+        HnswConfig::METRIC_COSINE => 1.0 - distance,
+        HnswConfig::METRIC_DOT_PRODUCT => -distance,
+        _ => 1.0 / (1.0 + distance),
+    }
+}
+
+/// Shared rescoring loop for a metric-specific distance function.
+fn rescore_impl<F>(
+    candidates: &[(VectorId, f32)],
+    query: &[f32],
+    storage: &VectorStorage,
+    distance: F,
+) -> Vec<(VectorId, f32)>
+where
+    F: Fn(&[f32], &[f32]) -> f32,
+{
     let mut rescored: Vec<(VectorId, f32)> = candidates
         .iter()
         .filter_map(|(id, _approx_score)| {
@@ -68,13 +198,10 @@ pub fn rescore(
                 return None;
             }
 
-            // Load F32 vector from storage
+            // Load the vector (dequantized from SQ8 if F32 isn't kept)
             let vector = storage.get_vector(*id);
 
-            // Compute exact L2 squared distance using SIMD
-            let distance = L2Squared::distance(query, &vector);
-
-            Some((*id, distance))
+            Some((*id, distance(query, &vector)))
         })
         .collect();
 
@@ -84,36 +211,11 @@ pub fn rescore(
     rescored
 }
 
-/// Rescores candidates and returns top-k.
-///
-/// Convenience function combining rescore + truncate.
-///
-/// # Arguments
-///
-/// * `candidates` - BQ search results.
-/// * `query` - The query vector.
-/// * `storage` - F32 vector storage.
-/// * `k` - Number of results to return.
-///
-/// # Returns
-///
-/// Top-k candidates sorted by exact F32 distance.
-#[must_use]
-pub fn rescore_top_k(
-    candidates: &[(VectorId, f32)],
-    query: &[f32],
-    storage: &VectorStorage,
-    k: usize,
-) -> Vec<(VectorId, f32)> {
-    let mut rescored = rescore(candidates, query, storage);
-    rescored.truncate(k);
-    rescored
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::hnsw::HnswConfig;
+    use crate::quantization::QuantizerConfig;
+    use crate::storage::StorageType;
 
     #[test]
     fn test_rescore_empty() {
@@ -235,4 +337,106 @@ mod tests {
         assert_eq!(rescored.len(), 1);
         assert_eq!(rescored[0].0, id2);
     }
+
+    #[test]
+    fn test_rescore_dot_product_prefers_larger_dot() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+
+        let small = storage.insert(&[1.0, 0.0]).unwrap();
+        let large = storage.insert(&[5.0, 0.0]).unwrap();
+        let opposite = storage.insert(&[-1.0, 0.0]).unwrap();
+
+        let candidates = vec![(opposite, 0.9), (small, 0.9), (large, 0.9)];
+        let rescored = rescore_with_metric(
+            &candidates,
+            &[1.0, 0.0],
+            &storage,
+            HnswConfig::METRIC_DOT_PRODUCT,
+        )
+        .unwrap();
+
+        let ids: Vec<_> = rescored.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![large, small, opposite]);
+        assert!((similarity(HnswConfig::METRIC_DOT_PRODUCT, rescored[0].1) - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rescore_cosine_ignores_magnitude() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+
+        // Closer in L2 but at 45 degrees
+        let near = storage.insert(&[1.0, 1.0]).unwrap();
+        // Far in L2 but perfectly aligned
+        let aligned = storage.insert(&[50.0, 0.0]).unwrap();
+
+        let candidates = vec![(near, 0.9), (aligned, 0.1)];
+        let rescored = rescore_with_metric(
+            &candidates,
+            &[1.0, 0.0],
+            &storage,
+            HnswConfig::METRIC_COSINE,
+        )
+        .unwrap();
+
+        assert_eq!(rescored[0].0, aligned);
+        assert!(rescored[0].1.abs() < 1e-6);
+        assert!((similarity(HnswConfig::METRIC_COSINE, rescored[0].1) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rescore_from_sq8_storage() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        storage.set_storage_type(StorageType::QuantizedU8(QuantizerConfig {
+            min: -10.0,
+            max: 10.0,
+        }));
+
+        let small = storage.insert(&[1.0, 0.0]).unwrap();
+        let large = storage.insert(&[8.0, 0.0]).unwrap();
+
+        let candidates = vec![(small, 0.9), (large, 0.1)];
+        let rescored = rescore_with_metric(
+            &candidates,
+            &[1.0, 0.0],
+            &storage,
+            HnswConfig::METRIC_DOT_PRODUCT,
+        )
+        .unwrap();
+
+        assert_eq!(rescored[0].0, large);
+        // Dequantization error is bounded by half a quantization step
+        assert!((rescored[0].1 + 8.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_rescore_rejects_unknown_metric() {
+        let config = HnswConfig::new(4);
+        let storage = VectorStorage::new(&config, None);
+
+        assert!(matches!(
+            rescore_with_metric(&[], &[0.0; 4], &storage, 99),
+            Err(GraphError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_rescore_matches_l2_metric() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let a = storage.insert(&[1.0, 0.0]).unwrap();
+        let b = storage.insert(&[3.0, 4.0]).unwrap();
+
+        let candidates = vec![(b, 0.9), (a, 0.1)];
+        let with_metric = rescore_with_metric(
+            &candidates,
+            &[0.0, 0.0],
+            &storage,
+            HnswConfig::METRIC_L2_SQUARED,
+        )
+        .unwrap();
+        assert_eq!(rescore(&candidates, &[0.0, 0.0], &storage), with_metric);
+    }
 }
@@ -1,7 +1,6 @@
 use super::config::HnswConfig;
 use super::graph::{GraphError, HnswIndex, NodeId, VectorId, VectorProvider};
-use crate::metric::simd::l2_squared_u8;
-use crate::metric::{DotProduct, L2Squared, Metric};
+use crate::metric::{Cosine, L2Squared, Metric, NegativeDotProduct};
 use crate::storage::VectorStorage;
 use std::cmp::{Ordering, Reverse};
 use std::collections::{BinaryHeap, HashSet};
@@ -139,16 +138,13 @@ where
         ctx.clear();
 
         // 0. Prepare quantization if applicable
-        // Only use quantized path for L2Squared metric currently
-        let use_quantized = if self.graph.config.metric == HnswConfig::METRIC_L2_SQUARED {
-            if ctx.quantized_query.is_empty() {
-                self.provider
-                    .quantize_query(query, &mut ctx.quantized_query);
-            }
-            !ctx.quantized_query.is_empty()
-        } else {
-            false
-        };
+        // Quantized distances follow the index metric (L2, dot or cosine)
+        if ctx.quantized_query.is_empty() {
+            self.provider
+                .quantize_query(query, &mut ctx.quantized_query);
+        }
+        let use_quantized = !ctx.quantized_query.is_empty();
+        let metric = self.graph.config.metric;
 
         // 1. Initialize
         for ep in entry_points {
@@ -162,7 +158,8 @@ where
 
             let dist = if use_quantized {
                 if let Some(q_vec) = self.provider.get_quantized_vector(node.vector_id) {
-                    l2_squared_u8(&ctx.quantized_query, q_vec) as f32
+                    self.provider
+                        .quantized_distance(metric, &ctx.quantized_query, q_vec)
                 } else {
                     let vector = self.provider.get_vector(node.vector_id);
                     M::distance(query, &vector)
@@ -240,7 +237,8 @@ where
                         if let Some(q_vec) =
                             self.provider.get_quantized_vector(neighbor_node.vector_id)
                         {
-                            l2_squared_u8(&ctx.quantized_query, q_vec) as f32
+                            self.provider
+                                .quantized_distance(metric, &ctx.quantized_query, q_vec)
                         } else {
                             let vector_data = self.provider.get_vector(neighbor_node.vector_id);
                             M::distance(query, &vector_data)
@@ -303,7 +301,13 @@ impl HnswIndex {
     ///
     /// # Returns
     ///
-    /// A list of `SearchResult`s, sorted by distance (ascending).
+    /// A list of `SearchResult`s, sorted by distance (ascending). Dot-product
+    /// indexes report the negated dot product ([`NegativeDotProduct`]), so the
+    /// most similar vector comes first.
+    /// The rescored and quantized variants ([`search_bq_rescored`](Self::search_bq_rescored),
+    /// [`search_sq8_rescored`](Self::search_sq8_rescored),
+    /// [`search_pq`](Self::search_pq), [`search_pq_rescored`](Self::search_pq_rescored))
+    /// return similarity scores instead, where higher is better.
     /// Returns at most `k` results, or fewer if:
     /// - The index has fewer than `k` live (non-deleted) vectors
     /// - Not enough neighbors were found during traversal
@@ -387,9 +391,10 @@ impl HnswIndex {
         // Dispatch based on metric
         match self.config.metric {
             HnswConfig::METRIC_L2_SQUARED => self.search_impl::<L2Squared>(query, k, storage, ctx),
-            HnswConfig::METRIC_DOT_PRODUCT | HnswConfig::METRIC_COSINE => {
-                self.search_impl::<DotProduct>(query, k, storage, ctx)
+            HnswConfig::METRIC_DOT_PRODUCT => {
+                self.search_impl::<NegativeDotProduct>(query, k, storage, ctx)
             }
+            HnswConfig::METRIC_COSINE => self.search_impl::<Cosine>(query, k, storage, ctx),
             _ => Err(GraphError::InvalidConfig(format!(
                 "unsupported metric code: {}",
                 self.config.metric
@@ -397,6 +402,77 @@ impl HnswIndex {
         }
     }
 
+    /// Searches with SQ8 codes, then rescores the candidates with exact F32.
+    ///
+    /// Meant for dual storage (`StorageType::DualU8`): the graph is traversed
+    /// with u8 distances, and the top `k × rescore_factor` candidates are
+    /// re-ranked with the stored F32 vectors. This gives close to F32 recall
+    /// at SQ8 traversal cost. Other storage types also work: F32 storage
+    /// rescores with the vectors it searched, and SQ8-only storage rescores
+    /// from dequantized codes.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query vector.
+    /// * `k` - Number of results to return.
+    /// * `rescore_factor` - Overfetch multiplier (recommended: 3-5).
+    /// * `storage` - The vector storage.
+    ///
+    /// # Returns
+    ///
+    /// Top-k results as `(id, similarity)`, most similar first. Exact
+    /// distances are converted with [`rescore::similarity`](super::rescore::similarity),
+    /// so unlike [`search`](Self::search), which returns distances (lower is
+    /// better), higher scores are better here.
+    ///
+    /// # Errors
+    ///
+    /// - `GraphError::DimensionMismatch` if query dimension is wrong.
+    /// - `GraphError::InvalidConfig` if the index metric is unknown.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::{HnswConfig, HnswIndex};
+    /// use edgevec::quantization::{QuantizerConfig, ScalarQuantizer};
+    /// use edgevec::storage::{StorageType, VectorStorage};
+    ///
+    /// let config = HnswConfig::new(16);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let quantizer = ScalarQuantizer::new(QuantizerConfig { min: 0.0, max: 100.0 });
+    /// storage.set_storage_type(StorageType::dual_from_quantizer(&quantizer));
+    /// let mut index = HnswIndex::new(config, &storage).unwrap();
+    /// for i in 0..100 {
+    ///     index.insert(&vec![i as f32; 16], &mut storage).unwrap();
+    /// }
+    ///
+    /// let results = index.search_sq8_rescored(&vec![42.0; 16], 5, 4, &storage).unwrap();
+    /// assert_eq!(results.len(), 5);
+    /// ```
+    pub fn search_sq8_rescored(
+        &self,
+        query: &[f32],
+        k: usize,
+        rescore_factor: usize,
+        storage: &VectorStorage,
+    ) -> Result<Vec<(VectorId, f32)>, GraphError> {
+        use super::rescore::{rescore_top_k_with_metric, similarity};
+
+        let overfetched_k = k.saturating_mul(rescore_factor.max(1));
+        let candidates: Vec<(VectorId, f32)> = self
+            .search(query, overfetched_k, storage)?
+            .into_iter()
+            .map(|r| (r.vector_id, r.distance))
+            .collect();
+
+        let metric = self.config.metric;
+        let rescored = rescore_top_k_with_metric(&candidates, query, storage, metric, k)?;
+        Ok(rescored
+            .into_iter()
+            .map(|(id, distance)| (id, similarity(metric, distance)))
+            .collect())
+    }
+
     fn search_impl<M: Metric<f32>>(
         &self,
         query: &[f32],
@@ -164,11 +164,11 @@ impl HnswIndex {
         Ok(results)
     }
 
-    /// Searches using binary quantization with F32 rescoring.
+    /// Searches using binary quantization with exact rescoring.
     ///
     /// This provides the best of both worlds:
     /// - Fast BQ search for candidate generation
-    /// - Accurate F32 rescoring for final ranking
+    /// - Exact rescoring under the index metric for final ranking
     ///
     /// # Arguments
     ///
@@ -176,16 +176,20 @@ impl HnswIndex {
     /// * `k` - Number of results to return.
     /// * `rescore_factor` - Overfetch multiplier (recommended: 3).
     ///   Higher values improve recall but increase latency.
-    /// * `storage` - F32 vector storage.
+    /// * `storage` - Vector storage. SQ8 storage is rescored from its
+    ///   dequantized codes.
     ///
     /// # Returns
     ///
-    /// Top-k results sorted by exact F32 distance (converted to similarity).
+    /// Top-k results sorted by exact distance, converted to similarity with
+    /// [`rescore::similarity`](super::rescore::similarity): `1 / (1 + d)` for
+    /// L2, cosine similarity for cosine and the raw dot product for dot.
     ///
     /// # Errors
     ///
     /// - `GraphError::BqNotEnabled` if BQ storage is not initialized.
     /// - `GraphError::DimensionMismatch` if query dimension is wrong.
+    /// - `GraphError::InvalidConfig` if the index metric is unknown.
     ///
     /// # Performance
     ///
@@ -217,7 +221,7 @@ impl HnswIndex {
         rescore_factor: usize,
         storage: &VectorStorage,
     ) -> Result<Vec<(VectorId, f32)>, GraphError> {
-        use super::rescore::rescore_top_k;
+        use super::rescore::{rescore_top_k_with_metric, similarity};
 
         // Validate inputs
         let rescore_factor = rescore_factor.max(1); // At least 1×
@@ -226,19 +230,15 @@ impl HnswIndex {
         let overfetched_k = k.saturating_mul(rescore_factor);
         let bq_candidates = self.search_bq(query, overfetched_k, storage)?;
 
-        // Step 2: Rescore with F32 and return top-k
-        let rescored = rescore_top_k(&bq_candidates, query, storage, k);
+        // Step 2: Rescore with the index metric and return top-k
+        let metric = self.config.metric;
+        let rescored = rescore_top_k_with_metric(&bq_candidates, query, storage, metric, k)?;
 
         // Convert distance to similarity for consistent API
         // (Lower distance = higher similarity)
         let results: Vec<_> = rescored
             .into_iter()
-            .map(|(id, distance)| {
-                // Convert distance to similarity
-                // Using inverse: similarity = 1 / (1 + distance)
-                let similarity = 1.0 / (1.0 + distance);
-                (id, similarity)
-            })
+            .map(|(id, distance)| (id, similarity(metric, distance)))
             .collect();
 
         Ok(results)
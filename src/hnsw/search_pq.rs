@@ -0,0 +1,338 @@
+//! Product quantization search for HNSW.
+//!
+//! Traverses the HNSW graph with asymmetric distance computation (ADC):
+//! the query stays in F32 and is compared against the PQ code of each
+//! visited node through a per-query lookup table, so each distance costs
+//! `sub_vectors` table reads instead of `dimensions` multiply-adds.
+//!
+//! # Algorithm
+//!
+//! 1. Build the ADC table for the query (`sub_vectors × centroids` entries)
+//! 2. Greedy descent through the upper layers using table distances
+//! 3. Beam search on layer 0 with `ef = max(ef_search, k)`
+//! 4. Convert distances to similarity with [`rescore::similarity`]
+//!
+//! Approximate distances follow the conventions of [`rescore`], so
+//! `search_pq_rescored` can re-rank candidates with exact F32 distances.
+//!
+//! [`rescore`]: crate::hnsw::rescore
+//! [`rescore::similarity`]: crate::hnsw::rescore::similarity
+//!
+//! # Example
+//!
+//! ```
+//! use edgevec::hnsw::{HnswConfig, HnswIndex};
+//! use edgevec::quantization::PqConfig;
+//! use edgevec::storage::VectorStorage;
+//!
+//! let config = HnswConfig::new(16);
+//! let mut storage = VectorStorage::new(&config, None);
+//! let mut index = HnswIndex::new(config, &storage).unwrap();
+//! for i in 0..100 {
+//!     index.insert(&vec![i as f32; 16], &mut storage).unwrap();
+//! }
+//! index.enable_pq(&storage, &PqConfig::new(4)).unwrap();
+//!
+//! let results = index.search_pq_rescored(&vec![42.0; 16], 5, 4, &storage).unwrap();
+//! assert_eq!(results.len(), 5);
+//! ```
+
+use super::graph::{GraphError, HnswIndex, NodeId, VectorId};
+use super::search::Candidate;
+use crate::quantization::product::DistanceTable;
+use crate::storage::product::PqCodeStorage;
+use crate::storage::VectorStorage;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+impl HnswIndex {
+    /// Searches the index using PQ codes and per-query ADC tables.
+    ///
+    /// Faster than F32 search on large vectors, with recall limited by the
+    /// codebooks. Use `search_pq_rescored()` to re-rank with exact distances.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query vector.
+    /// * `k` - Number of results to return.
+    ///
+    /// # Returns
+    ///
+    /// Top-k results sorted by approximate similarity (higher is better),
+    /// on the same scale as [`rescore::similarity`](super::rescore::similarity).
+    /// Unlike [`search`](Self::search), which returns distances (lower is
+    /// better), higher scores are better here.
+    ///
+    /// # Errors
+    ///
+    /// - `GraphError::PqNotEnabled` if PQ storage is not initialized.
+    /// - `GraphError::DimensionMismatch` if query dimension is wrong.
+    /// - `GraphError::Quantization` if the index metric is unknown.
+    pub fn search_pq(&self, query: &[f32], k: usize) -> Result<Vec<(VectorId, f32)>, GraphError> {
+        use super::rescore::similarity;
+
+        let candidates = self.search_pq_distances(query, k)?;
+        let metric = self.config.metric;
+        Ok(candidates
+            .into_iter()
+            .map(|(id, distance)| (id, similarity(metric, distance)))
+            .collect())
+    }
+
+    /// Searches using PQ codes, then rescores with exact F32 distances.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The query vector.
+    /// * `k` - Number of results to return.
+    /// * `rescore_factor` - Overfetch multiplier (recommended: 4).
+    /// * `storage` - Vector storage. SQ8 storage is rescored from its
+    ///   dequantized codes.
+    ///
+    /// # Returns
+    ///
+    /// Top-k results as `(id, similarity)`, most similar first. Exact
+    /// distances are converted with [`rescore::similarity`](super::rescore::similarity),
+    /// so unlike [`search`](Self::search), which returns distances (lower is
+    /// better), higher scores are better here.
+    ///
+    /// # Errors
+    ///
+    /// - `GraphError::PqNotEnabled` if PQ storage is not initialized.
+    /// - `GraphError::DimensionMismatch` if query dimension is wrong.
+    /// - `GraphError::Quantization` if the index metric is unknown.
+    pub fn search_pq_rescored(
+        &self,
+        query: &[f32],
+        k: usize,
+        rescore_factor: usize,
+        storage: &VectorStorage,
+    ) -> Result<Vec<(VectorId, f32)>, GraphError> {
+        use super::rescore::{rescore_top_k_with_metric, similarity};
+
+        let overfetched_k = k.saturating_mul(rescore_factor.max(1));
+        let candidates = self.search_pq_distances(query, overfetched_k)?;
+
+        let metric = self.config.metric;
+        let rescored = rescore_top_k_with_metric(&candidates, query, storage, metric, k)?;
+        Ok(rescored
+            .into_iter()
+            .map(|(id, distance)| (id, similarity(metric, distance)))
+            .collect())
+    }
+
+    /// Validates the query and returns the top-k live nodes by ADC distance.
+    fn search_pq_distances(
+        &self,
+        query: &[f32],
+        k: usize,
+    ) -> Result<Vec<(VectorId, f32)>, GraphError> {
+        let pq_storage = self.pq_storage.as_ref().ok_or(GraphError::PqNotEnabled)?;
+
+        let expected_dim = self.config.dimensions as usize;
+        if query.len() != expected_dim {
+            return Err(GraphError::DimensionMismatch {
+                expected: expected_dim,
+                actual: query.len(),
+            });
+        }
+
+        let Some(entry) = self.entry_point else {
+            return Ok(Vec::new());
+        };
+
+        let table = pq_storage
+            .distance_table(query)
+            .map_err(|e| GraphError::Quantization(e.to_string()))?;
+
+        let mut current = entry;
+        for layer in (1..=self.max_layer).rev() {
+            current = self.greedy_pq(current, &table, layer, pq_storage)?;
+        }
+        self.search_layer_pq(current, &table, k, pq_storage)
+    }
+
+    /// Greedy descent on one layer using ADC distances.
+    fn greedy_pq(
+        &self,
+        start: NodeId,
+        table: &DistanceTable,
+        layer: u8,
+        pq_storage: &PqCodeStorage,
+    ) -> Result<NodeId, GraphError> {
+        let mut current = start;
+        let mut current_dist = Self::adc_to_node(table, current, pq_storage)?;
+
+        loop {
+            let node = self
+                .get_node(current)
+                .ok_or(GraphError::NodeIdOutOfBounds)?;
+            if node.max_layer < layer {
+                break;
+            }
+
+            let mut changed = false;
+            for neighbor in self.get_neighbors_layer(node, layer)? {
+                let dist = Self::adc_to_node(table, neighbor, pq_storage)?;
+                if dist < current_dist {
+                    current = neighbor;
+                    current_dist = dist;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        Ok(current)
+    }
+
+    /// Beam search on layer 0 using ADC distances.
+    ///
+    /// Deleted nodes are traversed but never returned.
+    fn search_layer_pq(
+        &self,
+        entry: NodeId,
+        table: &DistanceTable,
+        k: usize,
+        pq_storage: &PqCodeStorage,
+    ) -> Result<Vec<(VectorId, f32)>, GraphError> {
+        let ef = (self.config.ef_search as usize).max(k);
+
+        let mut visited: HashSet<NodeId> = HashSet::new();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        let entry_candidate = Candidate {
+            distance: Self::adc_to_node(table, entry, pq_storage)?,
+            node_id: entry,
+        };
+        visited.insert(entry);
+        candidates.push(Reverse(entry_candidate));
+        if self.is_live(entry) {
+            results.push(entry_candidate);
+        }
+
+        while let Some(Reverse(candidate)) = candidates.pop() {
+            if results.len() >= ef && results.peek().is_some_and(|w| candidate > *w) {
+                break;
+            }
+
+            let Some(node) = self.get_node(candidate.node_id) else {
+                continue;
+            };
+            for neighbor in self.get_neighbors_layer(node, 0)? {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let next = Candidate {
+                    distance: Self::adc_to_node(table, neighbor, pq_storage)?,
+                    node_id: neighbor,
+                };
+                if results.len() < ef || results.peek().is_some_and(|w| next < *w) {
+                    candidates.push(Reverse(next));
+                    if self.is_live(neighbor) {
+                        results.push(next);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut found = results.into_sorted_vec();
+        found.truncate(k);
+        Ok(found
+            .into_iter()
+            .filter_map(|c| {
+                let node = self.get_node(c.node_id)?;
+                Some((node.vector_id, c.distance))
+            })
+            .collect())
+    }
+
+    /// Returns true if `node` exists and is not deleted.
+    fn is_live(&self, node: NodeId) -> bool {
+        self.get_node(node).is_some_and(|n| n.deleted == 0)
+    }
+
+    /// ADC distance from the query to a node's code.
+    ///
+    /// PQ codes are indexed by node index, like BQ storage.
+    fn adc_to_node(
+        table: &DistanceTable,
+        node: NodeId,
+        pq_storage: &PqCodeStorage,
+    ) -> Result<f32, GraphError> {
+        let code = pq_storage
+            .get(u64::from(node.0))
+            .ok_or(GraphError::NodeIdOutOfBounds)?;
+        Ok(table.distance(code))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::cast_precision_loss)]
+mod tests {
+    use super::*;
+    use crate::hnsw::HnswConfig;
+    use crate::quantization::PqConfig;
+
+    fn vector(seed: u32) -> Vec<f32> {
+        (0..16)
+            .map(|d| ((seed * 37 + d * 11) % 101) as f32 / 101.0)
+            .collect()
+    }
+
+    fn build(count: u32) -> (HnswIndex, VectorStorage) {
+        let config = HnswConfig::new(16);
+        let mut storage = VectorStorage::new(&config, None);
+        let mut index = HnswIndex::new(config, &storage).unwrap();
+        for i in 0..count {
+            index.insert(&vector(i), &mut storage).unwrap();
+        }
+        (index, storage)
+    }
+
+    #[test]
+    fn test_search_pq_requires_pq() {
+        let (index, _) = build(10);
+        assert!(matches!(
+            index.search_pq(&vector(0), 5),
+            Err(GraphError::PqNotEnabled)
+        ));
+    }
+
+    #[test]
+    fn test_search_pq_dimension_mismatch() {
+        let (mut index, storage) = build(10);
+        index.enable_pq(&storage, &PqConfig::new(4)).unwrap();
+        assert!(matches!(
+            index.search_pq(&[1.0; 8], 5),
+            Err(GraphError::DimensionMismatch {
+                expected: 16,
+                actual: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn test_search_pq_finds_stored_vector() {
+        let (mut index, storage) = build(200);
+        index.enable_pq(&storage, &PqConfig::new(8)).unwrap();
+
+        let results = index.search_pq(&vector(17), 10).unwrap();
+        assert_eq!(results.len(), 10);
+        assert!(results.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert!(results.iter().any(|(id, _)| *id == VectorId(18)));
+
+        let rescored = index
+            .search_pq_rescored(&vector(17), 1, 4, &storage)
+            .unwrap();
+        assert_eq!(rescored[0].0, VectorId(18));
+    }
+}
@@ -103,6 +103,9 @@ pub mod batch;
 /// HNSW Graph implementation.
 pub mod hnsw;
 
+/// Exact brute-force index.
+pub mod flat;
+
 /// Distance metrics.
 pub mod metric;
 
@@ -130,7 +133,10 @@ pub mod sparse;
 
 pub use batch::BatchInsertable;
 pub use error::BatchError;
-pub use hnsw::{BatchDeleteError, BatchDeleteResult, HnswConfig, HnswIndex, SearchResult};
+pub use flat::FlatIndex;
+pub use hnsw::{
+    BatchDeleteError, BatchDeleteResult, ExternalId, HnswConfig, HnswIndex, SearchResult,
+};
 pub use metric::Metric;
 pub use persistence::ChunkedWriter;
 pub use quantization::{BinaryQuantizer, QuantizedVector, QuantizerConfig, ScalarQuantizer};
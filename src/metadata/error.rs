@@ -99,6 +99,10 @@ pub enum MetadataError {
     /// Deserialization failed.
     #[error("deserialization error: {0}")]
     Deserialization(String),
+
+    /// Logging the change to the index's WAL failed; nothing was changed.
+    #[error("WAL write failed: {0}")]
+    Wal(String),
 }
 
 #[cfg(test)]
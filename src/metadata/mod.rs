@@ -87,6 +87,7 @@ pub mod validation;
 
 // Re-export public types at module level
 pub use error::MetadataError;
+pub(crate) use serialize::PostcardValue;
 pub use serialize::SerializationError;
 pub use store::MetadataStore;
 pub use types::MetadataValue;
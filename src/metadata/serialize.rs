@@ -39,7 +39,7 @@ use super::types::MetadataValue;
 /// (`#[serde(tag = "type", content = "value")]`) which postcard doesn't support.
 /// This enum uses simple tuple variants compatible with postcard.
 #[derive(Serialize, Deserialize)]
-enum PostcardValue {
+pub(crate) enum PostcardValue {
     /// String value (tag = 0)
     S(String),
     /// Integer value (tag = 1)
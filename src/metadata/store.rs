@@ -598,6 +598,20 @@ impl MetadataStore {
     /// ```
     pub fn merge(&mut self, other: MetadataStore) -> Result<(), MetadataError> {
         // Phase 1: Validate that no vector will exceed limits
+        self.check_merge(&other)?;
+
+        // Phase 2: Perform merge (validation passed)
+        for (vector_id, other_metadata) in other.data {
+            let entry = self.data.entry(vector_id).or_default();
+            entry.extend(other_metadata);
+        }
+
+        Ok(())
+    }
+
+    /// Checks that merging `other` would keep every vector within the key
+    /// limit, without changing anything.
+    pub(crate) fn check_merge(&self, other: &MetadataStore) -> Result<(), MetadataError> {
         for (vector_id, other_metadata) in &other.data {
             let current_keys = self.data.get(vector_id);
 
@@ -621,13 +635,6 @@ impl MetadataStore {
                 });
             }
         }
-
-        // Phase 2: Perform merge (validation passed)
-        for (vector_id, other_metadata) in other.data {
-            let entry = self.data.entry(vector_id).or_default();
-            entry.extend(other_metadata);
-        }
-
         Ok(())
     }
 
@@ -670,6 +677,39 @@ impl MetadataStore {
         }
     }
 
+    /// Returns a copy of this store with every vector ID translated through `remap`.
+    ///
+    /// Vectors whose ID is missing from `remap` are dropped. Used by
+    /// `HnswIndex::compact()`, which renumbers all live vectors.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use edgevec::metadata::{MetadataStore, MetadataValue};
+    /// use std::collections::HashMap;
+    ///
+    /// let mut store = MetadataStore::new();
+    /// store.insert(3, "a", MetadataValue::Integer(1)).unwrap();
+    /// store.insert(7, "b", MetadataValue::Integer(2)).unwrap();
+    ///
+    /// let remap = HashMap::from([(7, 1)]);
+    /// let moved = store.remapped(&remap);
+    ///
+    /// assert!(moved.has_key(1, "b"));
+    /// assert_eq!(moved.vector_count(), 1);
+    /// ```
+    #[must_use]
+    pub fn remapped(&self, remap: &HashMap<u32, u32>) -> Self {
+        let data = self
+            .data
+            .iter()
+            .filter_map(|(old_id, entries)| {
+                remap.get(old_id).map(|&new_id| (new_id, entries.clone()))
+            })
+            .collect();
+        Self { data }
+    }
+
     /// Returns an iterator over all vector IDs that have metadata.
     ///
     /// # Example
@@ -0,0 +1,59 @@
+//! Cosine distance metric.
+
+use super::Metric;
+
+/// Cosine distance metric.
+///
+/// Calculates `1 - cosine_similarity(a, b)`, so identical directions have
+/// distance `0.0`, orthogonal vectors `1.0` and opposite vectors `2.0`.
+/// Vectors do not need to be normalized. A zero vector has distance `1.0`
+/// to everything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cosine;
+
+impl Metric<f32> for Cosine {
+    #[inline]
+    fn distance(a: &[f32], b: &[f32]) -> f32 {
+        assert_eq!(
+            a.len(),
+            b.len(),
+            "dimension mismatch: {} != {}",
+            a.len(),
+            b.len()
+        );
+
+        let similarity = super::simd::cosine_similarity(a, b);
+        // NaN inputs fail the kernels' zero-norm check and come back as 0.0
+        if similarity == 0.0 {
+            assert!(
+                !a.iter().chain(b).any(|x| x.is_nan()),
+                "NaN detected in input"
+            );
+        }
+        1.0 - similarity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cosine_distance_ignores_magnitude() {
+        let d = Cosine::distance(&[1.0, 2.0, 3.0], &[10.0, 20.0, 30.0]);
+        assert!(d.abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cosine_distance_range() {
+        assert!((Cosine::distance(&[1.0, 0.0], &[0.0, 5.0]) - 1.0).abs() < 1e-6);
+        assert!((Cosine::distance(&[1.0, 0.0], &[-3.0, 0.0]) - 2.0).abs() < 1e-6);
+        assert!((Cosine::distance(&[0.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN detected")]
+    fn test_cosine_distance_nan_panics() {
+        let _ = Cosine::distance(&[f32::NAN, 0.0], &[1.0, 0.0]);
+    }
+}
@@ -58,3 +58,18 @@ impl Metric<f32> for DotProduct {
         }
     }
 }
+
+/// Dot product as a ranking distance.
+///
+/// Calculates `-sum(a_i * b_i)`, so the most similar vector has the
+/// smallest distance. Indexes configured with `METRIC_DOT_PRODUCT` rank
+/// with this metric; [`DotProduct`] returns the raw similarity.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NegativeDotProduct;
+
+impl Metric<f32> for NegativeDotProduct {
+    #[inline]
+    fn distance(a: &[f32], b: &[f32]) -> f32 {
+        -DotProduct::distance(a, b)
+    }
+}
@@ -1,7 +1,7 @@
 //! Distance metrics for vector comparison.
 //!
 //! This module defines the `Metric` trait and implements standard distance
-//! metrics used in HNSW indexing (L2 Squared, Dot Product, Hamming).
+//! metrics used in HNSW indexing (L2 Squared, Dot Product, Cosine, Hamming).
 //!
 //! # SIMD Acceleration
 //!
@@ -10,13 +10,15 @@
 //!
 //! To enable WASM SIMD, compile with `RUSTFLAGS="-C target-feature=+simd128"`.
 
+pub mod cosine;
 pub mod dot;
 pub mod hamming;
 pub mod l2;
 pub mod scalar;
 pub mod simd;
 
-pub use dot::DotProduct;
+pub use cosine::Cosine;
+pub use dot::{DotProduct, NegativeDotProduct};
 pub use hamming::Hamming;
 pub use l2::L2Squared;
 
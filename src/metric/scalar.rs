@@ -59,6 +59,42 @@ pub fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
     sum
 }
 
+/// Cosine similarity for f32 vectors (Scalar fallback).
+///
+/// Computes `dot(a, b) / (norm(a) * norm(b))`. Returns `0.0` if either
+/// vector has zero norm, matching the SIMD implementations.
+///
+/// # Panics
+///
+/// Panics if `a` and `b` have different lengths.
+///
+/// # Example
+///
+/// ```
+/// use edgevec::metric::scalar::cosine_similarity;
+/// let a = vec![2.0f32, 0.0];
+/// let b = vec![5.0f32, 0.0];
+/// assert!((cosine_similarity(&a, &b) - 1.0).abs() < 1e-6);
+/// ```
+#[inline]
+#[must_use]
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    let mut dot: f32 = 0.0;
+    let mut aa: f32 = 0.0;
+    let mut bb: f32 = 0.0;
+    for i in 0..a.len() {
+        dot += a[i] * b[i];
+        aa += a[i] * a[i];
+        bb += b[i] * b[i];
+    }
+    if aa > 0.0 && bb > 0.0 {
+        dot / (aa.sqrt() * bb.sqrt())
+    } else {
+        0.0
+    }
+}
+
 /// L2 Squared distance for u8 vectors (Scalar fallback).
 ///
 /// # Panics
@@ -96,6 +132,90 @@ pub fn dot_product_u8(a: &[u8], b: &[u8]) -> u32 {
     sum
 }
 
+/// Weighted L2 Squared distance for u8 codes (Scalar fallback).
+///
+/// Computes `sum(weights[i] * (a[i] - b[i])^2)`. With `weights[i]` set to
+/// the squared step of dimension `i`, this is the L2 distance between the
+/// dequantized vectors of per-dimension SQ8 codes.
+///
+/// # Panics
+///
+/// Panics if `a`, `b` and `weights` have different lengths.
+#[inline]
+#[must_use]
+pub fn l2_squared_u8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), weights.len());
+    let mut sum: f32 = 0.0;
+    for i in 0..a.len() {
+        let diff = f32::from(a[i]) - f32::from(b[i]);
+        sum += weights[i] * diff * diff;
+    }
+    sum
+}
+
+/// Weighted Dot Product for u8 codes (Scalar fallback).
+///
+/// Computes `sum(weights[i] * a[i] * b[i])`.
+///
+/// # Panics
+///
+/// Panics if `a`, `b` and `weights` have different lengths.
+#[inline]
+#[must_use]
+pub fn dot_product_u8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), weights.len());
+    let mut sum: f32 = 0.0;
+    for i in 0..a.len() {
+        sum += weights[i] * f32::from(a[i]) * f32::from(b[i]);
+    }
+    sum
+}
+
+/// Weighted L2 Squared distance for i8 codes stored as bytes (Scalar fallback).
+///
+/// Each byte is read as a two's-complement `i8`, as written by symmetric
+/// SQ8. Computes `sum(weights[i] * (a[i] - b[i])^2)`.
+///
+/// # Panics
+///
+/// Panics if `a`, `b` and `weights` have different lengths.
+#[inline]
+#[must_use]
+#[allow(clippy::cast_possible_wrap)]
+pub fn l2_squared_i8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), weights.len());
+    let mut sum: f32 = 0.0;
+    for i in 0..a.len() {
+        let diff = f32::from(a[i] as i8) - f32::from(b[i] as i8);
+        sum += weights[i] * diff * diff;
+    }
+    sum
+}
+
+/// Weighted Dot Product for i8 codes stored as bytes (Scalar fallback).
+///
+/// Each byte is read as a two's-complement `i8`, as written by symmetric
+/// SQ8. Computes `sum(weights[i] * a[i] * b[i])`.
+///
+/// # Panics
+///
+/// Panics if `a`, `b` and `weights` have different lengths.
+#[inline]
+#[must_use]
+#[allow(clippy::cast_possible_wrap)]
+pub fn dot_product_i8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    assert_eq!(a.len(), weights.len());
+    let mut sum: f32 = 0.0;
+    for i in 0..a.len() {
+        sum += weights[i] * f32::from(a[i] as i8) * f32::from(b[i] as i8);
+    }
+    sum
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,6 +228,13 @@ mod tests {
         assert_eq!(l2_squared_u8(&a, &b), 13);
     }
 
+    #[test]
+    fn test_cosine_similarity_scalar() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[0.0, 3.0])).abs() < 1e-6);
+        assert!((cosine_similarity(&[1.0, 1.0], &[-2.0, -2.0]) + 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]).abs() < f32::EPSILON);
+    }
+
     #[test]
     fn test_dot_product_u8_scalar() {
         let a = vec![1, 2, 3];
@@ -116,6 +243,30 @@ mod tests {
         assert_eq!(dot_product_u8(&a, &b), 11);
     }
 
+    #[test]
+    fn test_scaled_u8_kernels() {
+        let a = [1, 2, 3];
+        let b = [4, 2, 1];
+        let w = [1.0, 10.0, 0.5];
+        // 1*9 + 10*0 + 0.5*4 = 11
+        assert!((l2_squared_u8_scaled(&a, &b, &w) - 11.0).abs() < 1e-6);
+        // 1*4 + 10*4 + 0.5*3 = 45.5
+        assert!((dot_product_u8_scaled(&a, &b, &w) - 45.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_scaled_i8_kernels() {
+        // -1, 2, -128 and 3, -2, 127 as two's-complement bytes
+        let a = [0xFF, 2, 0x80];
+        let b = [3, 0xFE, 0x7F];
+        let w = [1.0, 0.5, 0.25];
+        // 16 + 0.5*16 + 0.25*255^2
+        let l2 = 16.0 + 8.0 + 0.25 * 65_025.0;
+        assert!((l2_squared_i8_scaled(&a, &b, &w) - l2).abs() < 1e-2);
+        // -3 + 0.5*(-4) + 0.25*(-16256)
+        assert!((dot_product_i8_scaled(&a, &b, &w) + 4069.0).abs() < 1e-3);
+    }
+
     #[test]
     fn test_overflow_protection() {
         // 255 * 255 = 65025.
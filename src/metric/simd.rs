@@ -828,6 +828,117 @@ pub mod x86 {
         sum
     }
 
+    /// Weighted u8/i8 kernel shared by the per-dimension SQ8 distances.
+    ///
+    /// Widens 8 codes per step to f32 (sign-extending when `SIGNED`), then
+    /// accumulates `w * (a - b)^2` when `L2`, or `w * a * b` otherwise.
+    ///
+    /// Signed codes are two's-complement bytes, so the `u8 -> i8` wrap is the
+    /// intended decode.
+    #[inline(always)]
+    #[allow(clippy::cast_possible_wrap, clippy::many_single_char_names)]
+    unsafe fn weighted_u8<const SIGNED: bool, const L2: bool>(
+        a: &[u8],
+        b: &[u8],
+        weights: &[f32],
+    ) -> f32 {
+        use std::arch::x86_64::{
+            _mm256_add_ps, _mm256_cvtepi32_ps, _mm256_cvtepi8_epi32, _mm256_cvtepu8_epi32,
+            _mm256_mul_ps, _mm_loadl_epi64,
+        };
+
+        assert_eq!(a.len(), b.len());
+        assert_eq!(a.len(), weights.len());
+        let n = a.len();
+        let mut i = 0;
+        let mut sum256 = _mm256_setzero_ps();
+
+        while i + 8 <= n {
+            let a_bytes = _mm_loadl_epi64(a.as_ptr().add(i) as *const __m128i);
+            let b_bytes = _mm_loadl_epi64(b.as_ptr().add(i) as *const __m128i);
+            let (a_wide, b_wide) = if SIGNED {
+                (_mm256_cvtepi8_epi32(a_bytes), _mm256_cvtepi8_epi32(b_bytes))
+            } else {
+                (_mm256_cvtepu8_epi32(a_bytes), _mm256_cvtepu8_epi32(b_bytes))
+            };
+            let va = _mm256_cvtepi32_ps(a_wide);
+            let vb = _mm256_cvtepi32_ps(b_wide);
+            let vw = _mm256_loadu_ps(weights.as_ptr().add(i));
+            let term = if L2 {
+                let diff = _mm256_sub_ps(va, vb);
+                _mm256_mul_ps(diff, diff)
+            } else {
+                _mm256_mul_ps(va, vb)
+            };
+            sum256 = _mm256_add_ps(sum256, _mm256_mul_ps(vw, term));
+            i += 8;
+        }
+
+        let mut sum = hsum256_ps_avx(sum256);
+
+        while i < n {
+            let (x, y) = if SIGNED {
+                (
+                    f32::from(*a.get_unchecked(i) as i8),
+                    f32::from(*b.get_unchecked(i) as i8),
+                )
+            } else {
+                (
+                    f32::from(*a.get_unchecked(i)),
+                    f32::from(*b.get_unchecked(i)),
+                )
+            };
+            let term = if L2 { (x - y) * (x - y) } else { x * y };
+            sum += *weights.get_unchecked(i) * term;
+            i += 1;
+        }
+        sum
+    }
+
+    /// Weighted L2 Squared distance for u8 codes using AVX2.
+    ///
+    /// # Safety
+    ///
+    /// Requires `avx2` target feature.
+    #[inline]
+    #[must_use]
+    pub unsafe fn l2_squared_u8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        weighted_u8::<false, true>(a, b, weights)
+    }
+
+    /// Weighted Dot Product for u8 codes using AVX2.
+    ///
+    /// # Safety
+    ///
+    /// Requires `avx2` target feature.
+    #[inline]
+    #[must_use]
+    pub unsafe fn dot_product_u8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        weighted_u8::<false, false>(a, b, weights)
+    }
+
+    /// Weighted L2 Squared distance for i8 codes using AVX2.
+    ///
+    /// # Safety
+    ///
+    /// Requires `avx2` target feature.
+    #[inline]
+    #[must_use]
+    pub unsafe fn l2_squared_i8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        weighted_u8::<true, true>(a, b, weights)
+    }
+
+    /// Weighted Dot Product for i8 codes using AVX2.
+    ///
+    /// # Safety
+    ///
+    /// Requires `avx2` target feature.
+    #[inline]
+    #[must_use]
+    pub unsafe fn dot_product_i8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        weighted_u8::<true, false>(a, b, weights)
+    }
+
     /// Dot Product using AVX2.
     ///
     /// # Safety
@@ -1065,9 +1176,9 @@ pub mod x86 {
     #[must_use]
     pub unsafe fn hamming_distance(a: &[u8], b: &[u8]) -> u32 {
         use std::arch::x86_64::{
-            __m256i, _mm256_add_epi64, _mm256_add_epi8, _mm256_and_si256, _mm256_loadu_si256,
-            _mm256_sad_epu8, _mm256_set1_epi8, _mm256_setzero_si256, _mm256_shuffle_epi8,
-            _mm256_srli_epi16, _mm256_xor_si256,
+            __m256i, _mm256_add_epi64, _mm256_add_epi8, _mm256_and_si256, _mm256_extract_epi64,
+            _mm256_loadu_si256, _mm256_sad_epu8, _mm256_set1_epi8, _mm256_setzero_si256,
+            _mm256_shuffle_epi8, _mm256_srli_epi16, _mm256_xor_si256,
         };
 
         assert_eq!(a.len(), b.len());
@@ -1102,7 +1213,7 @@ pub mod x86 {
         let mut sum256 = _mm256_setzero_si256();
 
         // Process 64 bytes (2 x 32-byte vectors) per iteration
-        while i + 64 <= n {
+        while i + AVX2_U8_UNROLL_BYTES <= n {
             let ptr_a = a.as_ptr().add(i) as *const __m256i;
             let ptr_b = b.as_ptr().add(i) as *const __m256i;
 
@@ -1135,11 +1246,11 @@ pub mod x86 {
             sum256 = _mm256_add_epi64(sum256, _mm256_sad_epu8(cnt0, zero));
             sum256 = _mm256_add_epi64(sum256, _mm256_sad_epu8(cnt1, zero));
 
-            i += 64;
+            i += AVX2_U8_UNROLL_BYTES;
         }
 
         // Handle remaining chunks of 32 bytes
-        while i + 32 <= n {
+        while i + AVX2_U8_VECTOR_WIDTH <= n {
             let va = _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i);
             let vb = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
             let xor = _mm256_xor_si256(va, vb);
@@ -1153,11 +1264,13 @@ pub mod x86 {
             let zero = _mm256_setzero_si256();
             sum256 = _mm256_add_epi64(sum256, _mm256_sad_epu8(cnt, zero));
 
-            i += 32;
+            i += AVX2_U8_VECTOR_WIDTH;
         }
 
-        // Extract and sum the 4 u64 lanes
-        use std::arch::x86_64::_mm256_extract_epi64;
+        // Extract and sum the 4 u64 lanes.
+        // SAD lanes are non-negative and the total is bounded by n * 8 bits,
+        // which fits in u32 for any slice length we accept.
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
         let mut sum = (_mm256_extract_epi64(sum256, 0) as u64
             + _mm256_extract_epi64(sum256, 1) as u64
             + _mm256_extract_epi64(sum256, 2) as u64
@@ -1292,6 +1405,125 @@ pub fn l2_squared_u8(a: &[u8], b: &[u8]) -> u32 {
     }
 }
 
+crate::simd_dispatch! {
+    /// Dispatcher for Dot Product (u8).
+    ///
+    /// Automatically selects the best implementation based on available features.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a.len() != b.len()`.
+    #[inline]
+    #[must_use]
+    pub fn dot_product_u8(a: &[u8], b: &[u8]) -> u32 {
+        avx2: unsafe { x86::dot_product_u8(a, b) },
+        fallback: crate::metric::scalar::dot_product_u8(a, b),
+    }
+}
+
+crate::simd_dispatch! {
+    /// Dispatcher for weighted L2 Squared distance (u8 codes).
+    ///
+    /// Computes `sum(weights[i] * (a[i] - b[i])^2)`. Used by per-dimension SQ8, where the
+    /// weights fold each dimension's quantization step into the distance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a`, `b` and `weights` have different lengths.
+    #[inline]
+    #[must_use]
+    pub fn l2_squared_u8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        avx2: unsafe { x86::l2_squared_u8_scaled(a, b, weights) },
+        fallback: crate::metric::scalar::l2_squared_u8_scaled(a, b, weights),
+    }
+}
+
+crate::simd_dispatch! {
+    /// Dispatcher for weighted Dot Product (u8 codes).
+    ///
+    /// Computes `sum(weights[i] * a[i] * b[i])`. Used by per-dimension SQ8, where the
+    /// weights fold each dimension's quantization step into the distance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a`, `b` and `weights` have different lengths.
+    #[inline]
+    #[must_use]
+    pub fn dot_product_u8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        avx2: unsafe { x86::dot_product_u8_scaled(a, b, weights) },
+        fallback: crate::metric::scalar::dot_product_u8_scaled(a, b, weights),
+    }
+}
+
+crate::simd_dispatch! {
+    /// Dispatcher for weighted L2 Squared distance (i8 codes stored as bytes).
+    ///
+    /// Computes `sum(weights[i] * (a[i] - b[i])^2)`. Used by per-dimension SQ8, where the
+    /// weights fold each dimension's quantization step into the distance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a`, `b` and `weights` have different lengths.
+    #[inline]
+    #[must_use]
+    pub fn l2_squared_i8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        avx2: unsafe { x86::l2_squared_i8_scaled(a, b, weights) },
+        fallback: crate::metric::scalar::l2_squared_i8_scaled(a, b, weights),
+    }
+}
+
+crate::simd_dispatch! {
+    /// Dispatcher for weighted Dot Product (i8 codes stored as bytes).
+    ///
+    /// Computes `sum(weights[i] * a[i] * b[i])`. Used by per-dimension SQ8, where the
+    /// weights fold each dimension's quantization step into the distance.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a`, `b` and `weights` have different lengths.
+    #[inline]
+    #[must_use]
+    pub fn dot_product_i8_scaled(a: &[u8], b: &[u8], weights: &[f32]) -> f32 {
+        avx2: unsafe { x86::dot_product_i8_scaled(a, b, weights) },
+        fallback: crate::metric::scalar::dot_product_i8_scaled(a, b, weights),
+    }
+}
+
+// Uses simd_dispatch! macro for compile-time platform selection
+crate::simd_dispatch! {
+    /// Dispatcher for cosine similarity (f32 vectors).
+    ///
+    /// Automatically selects the best SIMD implementation based on available features:
+    /// - WASM SIMD128 for WebAssembly targets
+    /// - AVX2 for x86_64 targets
+    /// - Scalar fallback for other platforms
+    ///
+    /// Returns `dot(a, b) / (norm(a) * norm(b))`, or `0.0` if either vector
+    /// has zero norm.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a.len() != b.len()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::metric::simd::cosine_similarity;
+    ///
+    /// let a = vec![1.0f32, 0.0, 1.0, 0.0];
+    /// let b = vec![1.0f32, 0.0, 0.0, 0.0];
+    /// let sim = cosine_similarity(&a, &b);
+    /// assert!((sim - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-5);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+        wasm_simd: wasm::cosine_similarity(a, b),
+        avx2: x86::cosine_similarity(a, b),
+        fallback: crate::metric::scalar::cosine_similarity(a, b),
+    }
+}
+
 /// Dispatcher for Hamming distance (u8 binary vectors).
 ///
 /// Automatically selects the best SIMD implementation based on available features:
@@ -1628,3 +1860,70 @@ mod euclidean_tests {
         let _ = euclidean_distance(&a, &b);
     }
 }
+
+// =============================================================================
+// Unit Tests for Weighted SQ8 Kernels
+// =============================================================================
+
+#[cfg(test)]
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+mod scaled_u8_tests {
+    use super::*;
+    use crate::metric::scalar;
+
+    fn inputs(len: usize) -> (Vec<u8>, Vec<u8>, Vec<f32>) {
+        let a = (0..len).map(|i| (i * 37 + 11) as u8).collect();
+        let b = (0..len).map(|i| (i * 91 + 200) as u8).collect();
+        let w = (0..len).map(|i| 0.001 + (i % 7) as f32 * 0.01).collect();
+        (a, b, w)
+    }
+
+    #[test]
+    fn test_scaled_kernels_match_scalar() {
+        // Covers full 8-byte blocks, tails, and the empty case
+        for len in [0, 1, 7, 8, 9, 31, 128, 769] {
+            let (a, b, w) = inputs(len);
+            let pairs = [
+                (
+                    l2_squared_u8_scaled(&a, &b, &w),
+                    scalar::l2_squared_u8_scaled(&a, &b, &w),
+                ),
+                (
+                    dot_product_u8_scaled(&a, &b, &w),
+                    scalar::dot_product_u8_scaled(&a, &b, &w),
+                ),
+                (
+                    l2_squared_i8_scaled(&a, &b, &w),
+                    scalar::l2_squared_i8_scaled(&a, &b, &w),
+                ),
+                (
+                    dot_product_i8_scaled(&a, &b, &w),
+                    scalar::dot_product_i8_scaled(&a, &b, &w),
+                ),
+            ];
+            for (simd, expected) in pairs {
+                let tolerance = 1e-4 * expected.abs().max(1.0);
+                assert!(
+                    (simd - expected).abs() < tolerance,
+                    "len {len}: {simd} vs {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_dot_product_u8_matches_scalar() {
+        for len in [0, 1, 15, 16, 17, 768] {
+            let (a, b, _) = inputs(len);
+            assert_eq!(dot_product_u8(&a, &b), scalar::dot_product_u8(&a, &b));
+        }
+        let max = vec![255u8; 1024];
+        assert_eq!(dot_product_u8(&max, &max), 1024 * 255 * 255);
+    }
+
+    #[test]
+    #[should_panic(expected = "assertion")]
+    fn test_scaled_weights_length_mismatch_panics() {
+        let _ = l2_squared_u8_scaled(&[1, 2], &[3, 4], &[1.0]);
+    }
+}
@@ -10,10 +10,19 @@
 //! of the file header. Chunk sizes smaller than this are automatically clamped
 //! to the minimum to ensure the header can be written in a single chunk.
 
-use crate::hnsw::HnswIndex;
-use crate::persistence::header::{FileHeader, Flags, MetadataSectionHeader};
-use crate::storage::VectorStorage;
+use crate::flat::FlatIndex;
+use crate::hnsw::{ExternalIdMap, HnswConfig, HnswIndex, HnswNode};
+use crate::metadata::MetadataStore;
+use crate::persistence::header::{
+    BqSectionHeader, CheckpointSection, ConfigSection, ExternalIdSectionHeader, FileHeader, Flags,
+    MetadataSectionHeader, PqSectionHeader, QuantizerSectionHeader, SectionKind,
+};
+use crate::quantization::{QuantizerConfig, Sq8Ranges};
+use crate::storage::binary::BinaryVectorStorage;
+use crate::storage::product::PqCodeStorage;
+use crate::storage::{StorageType, VectorStorage};
 use std::cmp::min;
+use std::mem::size_of_val;
 
 /// Minimum allowed chunk size in bytes.
 ///
@@ -46,14 +55,28 @@ pub trait ChunkedWriter {
 ///
 /// This iterator manages the state machine for serializing:
 /// 1. File Header
-/// 2. Vector Data
+/// 2. Vector Data (F32, or one `u8` per dimension for SQ8 storage)
 /// 3. HNSW Index Nodes
 /// 4. HNSW Neighbor Pool
 /// 5. Tombstone bitvec
-/// 6. Metadata section (v0.4+, if non-empty)
+/// 6. Config section (v0.5+)
+/// 7. Metadata section (v0.4+, if non-empty)
+/// 8. External ID section (v0.5+, if non-empty)
+/// 9. Binary quantization section (v0.5+, if BQ is enabled)
+/// 10. Quantizer section (v0.5+, if the storage is SQ8-quantized)
+/// 11. Product quantization section (v0.5+, if PQ is enabled)
+/// 12. Checkpoint section (v0.5+, if the storage has a WAL position)
+///
+/// A `FlatIndex` snapshot has no nodes or neighbors (steps 3 and 4 are empty).
+///
+/// `write_snapshot` lays steps 2 onwards out as separate sections behind a
+/// [section table](crate::persistence::sections); the stream itself keeps
+/// them back to back.
 pub struct ChunkIter<'a> {
     storage: &'a VectorStorage,
-    index: &'a HnswIndex,
+    vector_bytes: &'a [u8],
+    nodes: &'a [HnswNode],
+    neighbors: &'a [u8],
     chunk_size: usize,
     state: SerializationState,
     buffer: Vec<u8>,
@@ -62,12 +85,25 @@ pub struct ChunkIter<'a> {
     header_bytes: [u8; 64],
 
     // State tracking
-    vector_data_offset: usize, // Offset in f32 slice
+    vector_data_offset: usize, // Offset in vector_bytes
     node_index: usize,
     neighbor_offset: usize,
     tombstone_offset: usize, // Offset in deleted bits bytes (renamed for clarity)
+    config_section: [u8; ConfigSection::SIZE], // v0.5+: Full HnswConfig + compaction threshold
+    config_section_offset: usize,
     metadata_section: Vec<u8>, // Pre-serialized metadata section (header + data)
     metadata_section_offset: usize, // Current offset in metadata_section
+    external_id_section: Vec<u8>, // Pre-serialized external ID section (header + data)
+    external_id_section_offset: usize,
+    bq_section: Vec<u8>, // Pre-serialized BQ section (header + vectors + tombstones)
+    bq_section_offset: usize,
+    quantizer_section: Vec<u8>, // Pre-serialized quantizer section (header + config)
+    quantizer_section_offset: usize,
+    pq_section: Vec<u8>, // Pre-serialized PQ section (header + codebooks + codes)
+    pq_section_offset: usize,
+    checkpoint_section: Vec<u8>, // Empty, or one CheckpointSection
+    checkpoint_section_offset: usize,
+    error: Option<String>, // Set if a section failed to serialize
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -77,13 +113,70 @@ enum SerializationState {
     IndexNodes,
     IndexNeighbors,
     Tombstones,      // Deleted bitvec (was "Metadata" - renamed for clarity)
+    ConfigSection,   // v0.5+: ConfigSection (fixed 48 bytes)
     MetadataSection, // v0.4+: MetadataSectionHeader + serialized MetadataStore
+    ExternalIds,     // v0.5+: ExternalIdSectionHeader + serialized ExternalIdMap
+    BqSection,       // v0.5+: BqSectionHeader + packed BQ vectors + tombstones
+    Quantizer,       // v0.5+: QuantizerSectionHeader + serialized QuantizerConfig or Sq8Ranges
+    PqSection,       // v0.5+: PqSectionHeader + codebooks + PQ codes
+    Checkpoint,      // v0.5+: CheckpointSection (fixed 24 bytes)
     Done,
 }
 
+/// Index-side contents of a snapshot, borrowed from either index type.
+struct SnapshotSource<'a> {
+    config: &'a HnswConfig,
+    compaction_threshold: f64,
+    deleted_count: usize,
+    nodes: &'a [HnswNode],
+    neighbors: &'a [u8],
+    metadata: &'a MetadataStore,
+    external_ids: Option<&'a ExternalIdMap>,
+    bq_storage: Option<&'a BinaryVectorStorage>,
+    pq_storage: Option<&'a PqCodeStorage>,
+    flags: u16,
+}
+
 impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
     fn export_chunked(&self, chunk_size: usize) -> ChunkIter<'a> {
-        let (storage, index) = self;
+        let (storage, index) = *self;
+        let source = SnapshotSource {
+            config: &index.config,
+            compaction_threshold: index.compaction_threshold(),
+            deleted_count: index.deleted_count,
+            nodes: &index.nodes,
+            neighbors: &index.neighbors.buffer,
+            metadata: &index.metadata,
+            external_ids: Some(&index.external_ids),
+            bq_storage: index.bq_storage.as_ref(),
+            pq_storage: index.pq_storage.as_ref(),
+            flags: 0,
+        };
+        ChunkIter::new(storage, &source, chunk_size)
+    }
+}
+
+impl<'a> ChunkedWriter for (&'a VectorStorage, &'a FlatIndex) {
+    fn export_chunked(&self, chunk_size: usize) -> ChunkIter<'a> {
+        let (storage, index) = *self;
+        let source = SnapshotSource {
+            config: &index.config,
+            compaction_threshold: crate::hnsw::graph::default_compaction_threshold(),
+            deleted_count: index.deleted_count(),
+            nodes: &[],
+            neighbors: &[],
+            metadata: &index.metadata,
+            external_ids: None,
+            bq_storage: None,
+            pq_storage: None,
+            flags: Flags::FLAT_INDEX,
+        };
+        ChunkIter::new(storage, &source, chunk_size)
+    }
+}
+
+impl<'a> ChunkIter<'a> {
+    fn new(storage: &'a VectorStorage, index: &SnapshotSource<'a>, chunk_size: usize) -> Self {
         // Clamp chunk size to minimum (header must fit in one chunk).
         // See MIN_CHUNK_SIZE documentation for rationale.
         let chunk_size = chunk_size.max(MIN_CHUNK_SIZE);
@@ -92,10 +185,30 @@ impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
         let dimensions = storage.dimensions();
         let vector_count = storage.len() as u64;
 
+        // SQ8 storage keeps only u8 codes; write those instead of F32.
+        // Dual storage writes F32 and re-encodes the codes on load.
+        let (vector_bytes, quantizer_section): (&'a [u8], Vec<u8>) = match &storage.config {
+            StorageType::Float32 => (bytemuck::cast_slice(storage.raw_data()), Vec::new()),
+            StorageType::QuantizedU8(q_config) => {
+                (&storage.quantized_data, quantizer_section(*q_config))
+            }
+            StorageType::QuantizedU8Ranges(ranges) => {
+                (&storage.quantized_data, quantizer_ranges_section(ranges))
+            }
+            StorageType::DualU8(q_config) => (
+                bytemuck::cast_slice(storage.raw_data()),
+                quantizer_section(*q_config),
+            ),
+            StorageType::DualU8Ranges(ranges) => (
+                bytemuck::cast_slice(storage.raw_data()),
+                quantizer_ranges_section(ranges),
+            ),
+        };
+
         // Size calculations
-        let vector_data_size = (storage.raw_data().len() * 4) as u64;
-        let nodes_size = (index.node_count() * 16) as u64; // 16 bytes per HnswNode
-        let neighbors_size = index.neighbors.buffer.len() as u64;
+        let vector_data_size = vector_bytes.len() as u64;
+        let nodes_size = (index.nodes.len() * 16) as u64; // 16 bytes per HnswNode
+        let neighbors_size = index.neighbors.len() as u64;
 
         // Offsets
         // Header: 0..64
@@ -104,6 +217,9 @@ impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
         // Tombstone offset: After index nodes + neighbors
         let tombstone_offset_start = index_offset + nodes_size + neighbors_size;
 
+        // Set if a section fails to serialize; reported by the snapshot writer
+        let mut error = None;
+
         // v0.4: Serialize metadata section if non-empty (RFC-002)
         let metadata_section = if index.metadata.is_empty() {
             Vec::new()
@@ -122,9 +238,9 @@ impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
                     section.extend_from_slice(&serialized);
                     section
                 }
-                Err(_) => {
-                    // If serialization fails, proceed without metadata
-                    // This should not happen with valid data
+                // Dropping the section would silently lose every entry
+                Err(e) => {
+                    error = Some(format!("failed to serialize metadata: {e}"));
                     Vec::new()
                 }
             }
@@ -132,17 +248,76 @@ impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
 
         let has_metadata = !metadata_section.is_empty();
 
+        // v0.5: Serialize external ID map if non-empty
+        let external_id_section = match index.external_ids {
+            None => Vec::new(),
+            Some(map) if map.is_empty() => Vec::new(),
+            Some(map) => match map.to_postcard() {
+                Ok(serialized) => {
+                    let crc = crc32fast::hash(&serialized);
+                    #[allow(clippy::cast_possible_truncation)]
+                    let section_header =
+                        ExternalIdSectionHeader::new_postcard(serialized.len() as u32, crc);
+
+                    let mut section =
+                        Vec::with_capacity(ExternalIdSectionHeader::SIZE + serialized.len());
+                    section.extend_from_slice(section_header.as_bytes());
+                    section.extend_from_slice(&serialized);
+                    section
+                }
+                // Same as metadata: dropping the section would lose every binding
+                Err(e) => {
+                    error = Some(format!("failed to serialize external IDs: {e}"));
+                    Vec::new()
+                }
+            },
+        };
+
+        // v0.5: Serialize BQ vectors so search_bq works right after reload
+        let bq_section = index.bq_storage.map(bq_section).unwrap_or_default();
+
+        // v0.5: Persist codebooks and codes so search_pq works without retraining
+        let pq_section = index.pq_storage.map(pq_section).unwrap_or_default();
+
+        // v0.5: Record the WAL position so recovery skips covered records
+        let wal_sequence = storage.wal_sequence();
+        let checkpoint_section = if wal_sequence > 0 {
+            CheckpointSection::new(wal_sequence).as_bytes().to_vec()
+        } else {
+            Vec::new()
+        };
+
         let mut header = FileHeader::new(dimensions);
         header.vector_count = vector_count;
         header.index_offset = index_offset;
         header.metadata_offset = tombstone_offset_start; // Points to tombstone bitvec start
         header.hnsw_m = index.config.m;
         header.hnsw_m0 = index.config.m0;
+        header.flags |= index.flags;
 
         // v0.4: Set HAS_METADATA flag if metadata section is present
         if has_metadata {
             header.flags |= Flags::HAS_METADATA;
         }
+        if !external_id_section.is_empty() {
+            header.flags |= Flags::HAS_EXTERNAL_IDS;
+        }
+        if !bq_section.is_empty() {
+            header.flags |= Flags::HAS_BQ;
+        }
+        if !quantizer_section.is_empty() {
+            header.flags |= if storage.config.is_dual() {
+                Flags::DUAL_STORAGE
+            } else {
+                Flags::QUANTIZED
+            };
+        }
+        if !pq_section.is_empty() {
+            header.flags |= Flags::HAS_PQ;
+        }
+        if !checkpoint_section.is_empty() {
+            header.flags |= Flags::HAS_CHECKPOINT;
+        }
 
         // v0.3: Persist deleted_count from index (W16.5)
         // SAFETY: deleted_count is usize, header field is u32.
@@ -153,6 +328,9 @@ impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
             header.deleted_count = index.deleted_count as u32;
         }
 
+        // v0.5: Persist the full config so metric and ef values survive reload
+        let config_section = ConfigSection::new(index.config, index.compaction_threshold);
+
         // TODO: RNG seed persistence if needed (index.rng is private or needs exposure?
         // For now we skip RNG state persistence as it is transient/reseeded).
 
@@ -160,7 +338,9 @@ impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
 
         ChunkIter {
             storage,
-            index,
+            vector_bytes,
+            nodes: index.nodes,
+            neighbors: index.neighbors,
             chunk_size,
             state: SerializationState::Header,
             buffer: Vec::with_capacity(chunk_size),
@@ -169,12 +349,138 @@ impl<'a> ChunkedWriter for (&'a VectorStorage, &'a HnswIndex) {
             node_index: 0,
             neighbor_offset: 0,
             tombstone_offset: 0,
+            config_section: *config_section.as_bytes(),
+            config_section_offset: 0,
             metadata_section,
             metadata_section_offset: 0,
+            external_id_section,
+            external_id_section_offset: 0,
+            bq_section,
+            bq_section_offset: 0,
+            quantizer_section,
+            quantizer_section_offset: 0,
+            pq_section,
+            pq_section_offset: 0,
+            checkpoint_section,
+            checkpoint_section_offset: 0,
+            error,
         }
     }
 }
 
+impl ChunkIter<'_> {
+    /// Error from serializing a section, if any.
+    ///
+    /// The failed section is left out of the stream, so callers must check
+    /// this before persisting the chunks.
+    pub(crate) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Kind and size of each section yielded after the header, in order.
+    ///
+    /// Optional sections that are absent are left out.
+    pub(crate) fn sections(&self) -> Vec<(SectionKind, usize)> {
+        let mut sections = vec![
+            (SectionKind::VECTORS, self.vector_bytes.len()),
+            (SectionKind::NODES, size_of_val(self.nodes)),
+            (SectionKind::NEIGHBORS, self.neighbors.len()),
+            (
+                SectionKind::TOMBSTONES,
+                (self.storage.deleted.len() + 7) / 8,
+            ),
+            (SectionKind::CONFIG, ConfigSection::SIZE),
+        ];
+        let optional = [
+            (SectionKind::METADATA, &self.metadata_section),
+            (SectionKind::EXTERNAL_IDS, &self.external_id_section),
+            (SectionKind::BQ, &self.bq_section),
+            (SectionKind::QUANTIZER, &self.quantizer_section),
+            (SectionKind::PQ, &self.pq_section),
+            (SectionKind::CHECKPOINT, &self.checkpoint_section),
+        ];
+        for (kind, section) in optional {
+            if !section.is_empty() {
+                sections.push((kind, section.len()));
+            }
+        }
+        sections
+    }
+}
+
+/// Builds the quantizer section: header, then the Postcard-serialized config.
+fn quantizer_section(q_config: QuantizerConfig) -> Vec<u8> {
+    // Serializing a plain struct of floats cannot fail
+    let serialized = postcard::to_allocvec(&q_config).expect("QuantizerConfig serializes");
+    let crc = crc32fast::hash(&serialized);
+    #[allow(clippy::cast_possible_truncation)]
+    let section_header = QuantizerSectionHeader::new_postcard(serialized.len() as u32, crc);
+
+    let mut section = Vec::with_capacity(QuantizerSectionHeader::SIZE + serialized.len());
+    section.extend_from_slice(section_header.as_bytes());
+    section.extend_from_slice(&serialized);
+    section
+}
+
+/// Builds the quantizer section for per-dimension SQ8 ranges (version 2).
+fn quantizer_ranges_section(ranges: &Sq8Ranges) -> Vec<u8> {
+    // Serializing vectors of floats cannot fail
+    let serialized = postcard::to_allocvec(ranges).expect("Sq8Ranges serializes");
+    let crc = crc32fast::hash(&serialized);
+    #[allow(clippy::cast_possible_truncation)]
+    let section_header = QuantizerSectionHeader::new_postcard_ranges(serialized.len() as u32, crc);
+
+    let mut section = Vec::with_capacity(QuantizerSectionHeader::SIZE + serialized.len());
+    section.extend_from_slice(section_header.as_bytes());
+    section.extend_from_slice(&serialized);
+    section
+}
+
+/// Builds the BQ section: header, packed vectors, then the tombstone bitvec.
+fn bq_section(bq_storage: &BinaryVectorStorage) -> Vec<u8> {
+    let mut deleted = bq_storage.deleted_bits().clone();
+    deleted.set_uninitialized(false);
+
+    let mut payload = Vec::with_capacity(bq_storage.memory_bytes() + deleted.as_raw_slice().len());
+    payload.extend_from_slice(bq_storage.raw_data());
+    payload.extend_from_slice(deleted.as_raw_slice());
+
+    let crc = crc32fast::hash(&payload);
+    #[allow(clippy::cast_possible_truncation)]
+    let section_header = BqSectionHeader::new(payload.len() as u32, crc);
+
+    let mut section = Vec::with_capacity(BqSectionHeader::SIZE + payload.len());
+    section.extend_from_slice(section_header.as_bytes());
+    section.extend_from_slice(&payload);
+    section
+}
+
+/// Builds the PQ section: header, shape, codebooks, then the codes.
+fn pq_section(pq_storage: &PqCodeStorage) -> Vec<u8> {
+    let quantizer = pq_storage.quantizer();
+    let codebooks = quantizer.codebooks();
+
+    let mut payload = Vec::with_capacity(8 + codebooks.len() * 4 + pq_storage.memory_bytes());
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        payload.extend_from_slice(&(quantizer.sub_vectors() as u32).to_le_bytes());
+        payload.extend_from_slice(&(quantizer.centroids() as u32).to_le_bytes());
+    }
+    for value in codebooks {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    payload.extend_from_slice(pq_storage.raw_codes());
+
+    let crc = crc32fast::hash(&payload);
+    #[allow(clippy::cast_possible_truncation)]
+    let section_header = PqSectionHeader::new(payload.len() as u32, crc);
+
+    let mut section = Vec::with_capacity(PqSectionHeader::SIZE + payload.len());
+    section.extend_from_slice(section_header.as_bytes());
+    section.extend_from_slice(&payload);
+    section
+}
+
 impl Iterator for ChunkIter<'_> {
     type Item = Vec<u8>;
 
@@ -209,36 +515,25 @@ impl Iterator for ChunkIter<'_> {
                     }
                 }
                 SerializationState::VectorData => {
-                    let data = self.storage.raw_data();
-                    let remaining_floats = data.len() - self.vector_data_offset;
+                    // F32 values (as little-endian bytes) or SQ8 codes
+                    let data = self.vector_bytes;
+                    let remaining_bytes = data.len() - self.vector_data_offset;
 
-                    if remaining_floats == 0 {
+                    if remaining_bytes == 0 {
                         self.state = SerializationState::IndexNodes;
                         continue;
                     }
 
-                    // How many floats can we fit?
-                    // space_left bytes / 4 bytes per float
-                    let floats_to_copy = min(remaining_floats, space_left / 4);
-
-                    if floats_to_copy > 0 {
-                        let end = self.vector_data_offset + floats_to_copy;
-                        let slice = &data[self.vector_data_offset..end];
-
-                        // Bulk copy as bytes
-                        let byte_slice = bytemuck::cast_slice(slice);
-                        self.buffer.extend_from_slice(byte_slice);
+                    let bytes_to_copy = min(remaining_bytes, space_left);
+                    let end = self.vector_data_offset + bytes_to_copy;
+                    self.buffer
+                        .extend_from_slice(&data[self.vector_data_offset..end]);
 
-                        self.vector_data_offset += floats_to_copy;
-                    }
+                    self.vector_data_offset += bytes_to_copy;
 
-                    // If we couldn't fit even one float but have space (e.g. 1-3 bytes),
-                    // we break to yield current chunk and resume next time with fresh buffer.
-                    // Or if we finished vectors, move state.
                     if self.vector_data_offset == data.len() {
                         self.state = SerializationState::IndexNodes;
-                    } else if floats_to_copy == 0 {
-                        // Buffer is full (modulo alignment)
+                    } else if bytes_to_copy == 0 {
                         break;
                     }
                 }
@@ -246,7 +541,7 @@ impl Iterator for ChunkIter<'_> {
                     // We need to serialize HnswNode structs.
                     // HnswNode is repr(C) but contains VectorId(u64) etc.
                     // Layout: 16 bytes.
-                    let nodes = &self.index.nodes;
+                    let nodes = self.nodes;
                     let remaining_nodes = nodes.len() - self.node_index;
 
                     if remaining_nodes == 0 {
@@ -284,7 +579,7 @@ impl Iterator for ChunkIter<'_> {
                     }
                 }
                 SerializationState::IndexNeighbors => {
-                    let neighbors = &self.index.neighbors.buffer;
+                    let neighbors = self.neighbors;
                     let remaining_bytes = neighbors.len() - self.neighbor_offset;
 
                     if remaining_bytes == 0 {
@@ -314,8 +609,8 @@ impl Iterator for ChunkIter<'_> {
                     let remaining_bytes = total_bytes - self.tombstone_offset;
 
                     if remaining_bytes == 0 {
-                        // After tombstones, write metadata section if present
-                        self.state = SerializationState::MetadataSection;
+                        // After tombstones, write the config section
+                        self.state = SerializationState::ConfigSection;
                         continue;
                     }
 
@@ -342,11 +637,30 @@ impl Iterator for ChunkIter<'_> {
                     }
 
                     if self.tombstone_offset == total_bytes {
-                        self.state = SerializationState::MetadataSection;
+                        self.state = SerializationState::ConfigSection;
                     } else if bytes_to_produce == 0 {
                         break;
                     }
                 }
+                SerializationState::ConfigSection => {
+                    // v0.5: Fixed-size config section, may straddle a chunk boundary
+                    let remaining_bytes = self.config_section.len() - self.config_section_offset;
+
+                    let bytes_to_copy = min(remaining_bytes, space_left);
+                    let start = self.config_section_offset;
+                    let end = start + bytes_to_copy;
+                    self.buffer
+                        .extend_from_slice(&self.config_section[start..end]);
+
+                    self.config_section_offset += bytes_to_copy;
+
+                    if self.config_section_offset == self.config_section.len() {
+                        // After config, write metadata section if present
+                        self.state = SerializationState::MetadataSection;
+                    } else if bytes_to_copy == 0 {
+                        break;
+                    }
+                }
                 SerializationState::MetadataSection => {
                     // v0.4: Write metadata section (header + serialized data)
                     // This section is pre-serialized in export_chunked()
@@ -354,7 +668,7 @@ impl Iterator for ChunkIter<'_> {
                         self.metadata_section.len() - self.metadata_section_offset;
 
                     if remaining_bytes == 0 {
-                        self.state = SerializationState::Done;
+                        self.state = SerializationState::ExternalIds;
                         continue;
                     }
 
@@ -367,6 +681,122 @@ impl Iterator for ChunkIter<'_> {
                     self.metadata_section_offset += bytes_to_copy;
 
                     if self.metadata_section_offset == self.metadata_section.len() {
+                        self.state = SerializationState::ExternalIds;
+                    } else if bytes_to_copy == 0 {
+                        break;
+                    }
+                }
+                SerializationState::ExternalIds => {
+                    // v0.5: Pre-serialized in export_chunked(), like metadata
+                    let remaining_bytes =
+                        self.external_id_section.len() - self.external_id_section_offset;
+
+                    if remaining_bytes == 0 {
+                        self.state = SerializationState::BqSection;
+                        continue;
+                    }
+
+                    let bytes_to_copy = min(remaining_bytes, space_left);
+                    let start = self.external_id_section_offset;
+                    let end = start + bytes_to_copy;
+                    self.buffer
+                        .extend_from_slice(&self.external_id_section[start..end]);
+
+                    self.external_id_section_offset += bytes_to_copy;
+
+                    if self.external_id_section_offset == self.external_id_section.len() {
+                        self.state = SerializationState::BqSection;
+                    } else if bytes_to_copy == 0 {
+                        break;
+                    }
+                }
+                SerializationState::BqSection => {
+                    // v0.5: Pre-serialized in export_chunked(), like external IDs
+                    let remaining_bytes = self.bq_section.len() - self.bq_section_offset;
+
+                    if remaining_bytes == 0 {
+                        self.state = SerializationState::Quantizer;
+                        continue;
+                    }
+
+                    let bytes_to_copy = min(remaining_bytes, space_left);
+                    let start = self.bq_section_offset;
+                    let end = start + bytes_to_copy;
+                    self.buffer.extend_from_slice(&self.bq_section[start..end]);
+
+                    self.bq_section_offset += bytes_to_copy;
+
+                    if self.bq_section_offset == self.bq_section.len() {
+                        self.state = SerializationState::Quantizer;
+                    } else if bytes_to_copy == 0 {
+                        break;
+                    }
+                }
+                SerializationState::Quantizer => {
+                    // v0.5: Pre-serialized in export_chunked(), like external IDs
+                    let remaining_bytes =
+                        self.quantizer_section.len() - self.quantizer_section_offset;
+
+                    if remaining_bytes == 0 {
+                        self.state = SerializationState::PqSection;
+                        continue;
+                    }
+
+                    let bytes_to_copy = min(remaining_bytes, space_left);
+                    let start = self.quantizer_section_offset;
+                    let end = start + bytes_to_copy;
+                    self.buffer
+                        .extend_from_slice(&self.quantizer_section[start..end]);
+
+                    self.quantizer_section_offset += bytes_to_copy;
+
+                    if self.quantizer_section_offset == self.quantizer_section.len() {
+                        self.state = SerializationState::PqSection;
+                    } else if bytes_to_copy == 0 {
+                        break;
+                    }
+                }
+                SerializationState::PqSection => {
+                    // v0.5: Pre-serialized in export_chunked(), like the BQ section
+                    let remaining_bytes = self.pq_section.len() - self.pq_section_offset;
+
+                    if remaining_bytes == 0 {
+                        self.state = SerializationState::Checkpoint;
+                        continue;
+                    }
+
+                    let bytes_to_copy = min(remaining_bytes, space_left);
+                    let start = self.pq_section_offset;
+                    let end = start + bytes_to_copy;
+                    self.buffer.extend_from_slice(&self.pq_section[start..end]);
+
+                    self.pq_section_offset += bytes_to_copy;
+
+                    if self.pq_section_offset == self.pq_section.len() {
+                        self.state = SerializationState::Checkpoint;
+                    } else if bytes_to_copy == 0 {
+                        break;
+                    }
+                }
+                SerializationState::Checkpoint => {
+                    // v0.5: Built in export_chunked(), may straddle a chunk boundary
+                    let remaining_bytes =
+                        self.checkpoint_section.len() - self.checkpoint_section_offset;
+
+                    if remaining_bytes == 0 {
+                        self.state = SerializationState::Done;
+                        continue;
+                    }
+
+                    let bytes_to_copy = min(remaining_bytes, space_left);
+                    let start = self.checkpoint_section_offset;
+                    let end = start + bytes_to_copy;
+                    self.buffer
+                        .extend_from_slice(&self.checkpoint_section[start..end]);
+
+                    self.checkpoint_section_offset += bytes_to_copy;
+
+                    if self.checkpoint_section_offset == self.checkpoint_section.len() {
                         self.state = SerializationState::Done;
                     } else if bytes_to_copy == 0 {
                         break;
@@ -437,9 +867,10 @@ mod tests {
             total_bytes += chunk.len();
         }
 
-        // Header (64) + 10 vectors * 4 dims * 4 bytes (160) + tombstones (2 bytes for 10 bits) = 226 bytes
+        // Header (64) + 10 vectors * 4 dims * 4 bytes (160) + tombstones (2 bytes for 10 bits)
+        // + config section (48) = 274 bytes
         // No metadata section since index has no metadata
-        let expected = 64 + 160 + 2; // Header + vector data + tombstones
+        let expected = 64 + 160 + 2 + 48; // Header + vector data + tombstones + config
         assert_eq!(total_bytes, expected);
     }
 
@@ -545,8 +976,9 @@ mod tests {
             "Expected multiple chunks, got {chunk_count}"
         );
 
-        // Header (64) + 5 vectors * 4 dims * 4 bytes (80) + tombstones (1 byte for 5 bits) = 145 bytes
-        let expected = 64 + 80 + 1;
+        // Header (64) + 5 vectors * 4 dims * 4 bytes (80) + tombstones (1 byte for 5 bits)
+        // + config section (48) = 193 bytes
+        let expected = 64 + 80 + 1 + 48;
         assert_eq!(total_bytes, expected);
     }
 
@@ -0,0 +1,194 @@
+//! Snapshot section compression.
+//!
+//! With [`Compression::Lz4`], [`write_snapshot_compressed`] stores each
+//! section other than the vectors and nodes as a [`CompressedSectionHeader`]
+//! section, flagged in its section table entry, and sets
+//! [`Flags::COMPRESSED`]. Sections that would not shrink are stored as-is.
+//! Vectors and nodes compress poorly and stay directly mappable.
+//! [`read_snapshot`] decompresses transparently.
+//!
+//! Snapshots written before section tables instead compress the neighbor
+//! pool and the whole tail (tombstones onwards) as one section each; those
+//! are still read.
+//!
+//! The data CRC in the file header covers the compressed bytes, and each
+//! compressed section carries its own CRC.
+//!
+//! The LZ4 codec is behind the opt-in `compression` cargo feature.
+//! Without it, writing or reading a compressed snapshot returns
+//! [`PersistenceError::Unsupported`].
+//!
+//! [`write_snapshot_compressed`]: crate::persistence::write_snapshot_compressed
+//! [`read_snapshot`]: crate::persistence::read_snapshot
+//! [`Flags::COMPRESSED`]: crate::persistence::Flags::COMPRESSED
+
+use crate::persistence::header::CompressedSectionHeader;
+use crate::persistence::PersistenceError;
+
+/// LZ4 never expands data by more than this factor when decompressing.
+const LZ4_MAX_RATIO: usize = 255;
+
+/// Compression applied to snapshot sections.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Store every section uncompressed.
+    #[default]
+    None,
+    /// LZ4 block compression (requires the `compression` feature).
+    Lz4,
+}
+
+impl Compression {
+    /// Returns true if this build can write and read snapshots using `self`.
+    #[must_use]
+    pub const fn is_available(self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Lz4 => cfg!(feature = "compression"),
+        }
+    }
+
+    /// Returns `Unsupported` if this build cannot use `self`.
+    pub(crate) fn ensure_available(self) -> Result<(), PersistenceError> {
+        if self.is_available() {
+            Ok(())
+        } else {
+            Err(unavailable())
+        }
+    }
+}
+
+fn unavailable() -> PersistenceError {
+    PersistenceError::Unsupported("snapshot compression requires the `compression` feature".into())
+}
+
+/// Compresses `raw` with LZ4, returning the section header followed by the
+/// compressed bytes.
+///
+/// # Errors
+///
+/// Returns `Unsupported` if the `compression` feature is disabled or the
+/// section exceeds 4 GiB.
+pub(crate) fn compress_section(raw: &[u8]) -> Result<Vec<u8>, PersistenceError> {
+    #[cfg(feature = "compression")]
+    {
+        let too_large = |len: usize| {
+            PersistenceError::Unsupported(format!(
+                "section of {len} bytes is too large to compress"
+            ))
+        };
+        let compressed = lz4_flex::block::compress(raw);
+        let raw_size = u32::try_from(raw.len()).map_err(|_| too_large(raw.len()))?;
+        let size = u32::try_from(compressed.len()).map_err(|_| too_large(compressed.len()))?;
+        let header = CompressedSectionHeader::new_lz4(raw_size, size, crc32fast::hash(&compressed));
+
+        let mut section = Vec::with_capacity(CompressedSectionHeader::SIZE + compressed.len());
+        section.extend_from_slice(header.as_bytes());
+        section.extend_from_slice(&compressed);
+        Ok(section)
+    }
+    #[cfg(not(feature = "compression"))]
+    {
+        let _ = raw;
+        Err(unavailable())
+    }
+}
+
+/// Validates and decompresses a section written by [`compress_section`].
+///
+/// `bytes` must hold exactly one section; `name` is used in error messages.
+///
+/// # Errors
+///
+/// Returns `Corrupted` if the header, length or CRC is invalid or the data
+/// does not decompress to the declared size, and `Unsupported` if the
+/// `compression` feature is disabled.
+pub(crate) fn decompress_section(bytes: &[u8], name: &str) -> Result<Vec<u8>, PersistenceError> {
+    let header = CompressedSectionHeader::from_bytes(bytes).map_err(|e| {
+        PersistenceError::Corrupted(format!("Invalid compressed {name} section: {e}"))
+    })?;
+
+    let data = &bytes[CompressedSectionHeader::SIZE..];
+    if data.len() != header.size as usize {
+        return Err(PersistenceError::Corrupted(format!(
+            "Compressed {name} section length mismatch: header says {}, got {}",
+            header.size,
+            data.len()
+        )));
+    }
+
+    let actual_crc = crc32fast::hash(data);
+    if actual_crc != header.crc {
+        return Err(PersistenceError::Corrupted(format!(
+            "Compressed {name} CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+            header.crc
+        )));
+    }
+
+    // Bound the allocation before trusting raw_size
+    let raw_size = header.raw_size as usize;
+    if raw_size > data.len().saturating_mul(LZ4_MAX_RATIO).saturating_add(16) {
+        return Err(PersistenceError::Corrupted(format!(
+            "Compressed {name} section claims {raw_size} bytes from {}",
+            data.len()
+        )));
+    }
+
+    #[cfg(feature = "compression")]
+    {
+        let raw = lz4_flex::block::decompress(data, raw_size)
+            .map_err(|e| PersistenceError::Corrupted(format!("Compressed {name} section: {e}")))?;
+        if raw.len() != raw_size {
+            return Err(PersistenceError::Corrupted(format!(
+                "Compressed {name} section decompressed to {} bytes, expected {raw_size}",
+                raw.len()
+            )));
+        }
+        Ok(raw)
+    }
+    #[cfg(not(feature = "compression"))]
+    Err(unavailable())
+}
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_roundtrip() {
+        let raw: Vec<u8> = (0..10_000u32).map(|i| (i % 7) as u8).collect();
+        let section = compress_section(&raw).unwrap();
+        assert!(section.len() < raw.len() / 10);
+        assert_eq!(decompress_section(&section, "test").unwrap(), raw);
+
+        let empty = compress_section(&[]).unwrap();
+        assert!(decompress_section(&empty, "test").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_section_is_rejected() {
+        let raw = vec![42u8; 1000];
+        let section = compress_section(&raw).unwrap();
+
+        let mut flipped = section.clone();
+        *flipped.last_mut().unwrap() ^= 0xFF;
+        assert!(matches!(
+            decompress_section(&flipped, "test"),
+            Err(PersistenceError::Corrupted(msg)) if msg.contains("CRC")
+        ));
+
+        // Truncated section
+        assert!(matches!(
+            decompress_section(&section[..section.len() - 1], "test"),
+            Err(PersistenceError::Corrupted(_))
+        ));
+
+        // Implausible decompressed size
+        let mut inflated = section;
+        inflated[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            decompress_section(&inflated, "test"),
+            Err(PersistenceError::Corrupted(msg)) if msg.contains("claims")
+        ));
+    }
+}
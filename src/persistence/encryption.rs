@@ -0,0 +1,581 @@
+//! Encryption at rest for snapshots and WAL logs.
+//!
+//! [`EncryptedBackend`] wraps any [`StorageBackend`] and encrypts everything
+//! written through it with XChaCha20-Poly1305. The key is derived with
+//! HKDF-SHA256 from a caller-supplied secret and a random per-store salt.
+//! The secret should be high-entropy key material (e.g. from a platform
+//! keystore); stretch passwords with a password KDF before passing them in.
+//!
+//! # Layout
+//!
+//! The stored bytes start with an [`EncryptionHeader`], followed by frames:
+//!
+//! | Size | Field      | Description                                 |
+//! |------|------------|---------------------------------------------|
+//! | 4    | len        | Ciphertext length, tag included (LE u32)    |
+//! | 24   | nonce      | Random XChaCha20 nonce                      |
+//! | len  | ciphertext | Sealed plaintext + 16-byte Poly1305 tag     |
+//!
+//! Every `append` seals its data as one frame, so WAL appends cost one
+//! frame rather than a rewrite. `atomic_write` seals the blob in frames of
+//! [`FRAME_SIZE`] bytes. Each frame is bound to its position in the store,
+//! so frames cannot be reordered or dropped from the middle.
+//!
+//! A key-check value in the header tells a wrong secret
+//! ([`PersistenceError::WrongKey`]) apart from modified data
+//! ([`PersistenceError::Tampered`]). A trailing frame cut short by a crash is
+//! dropped, like a torn WAL record.
+//!
+//! Snapshots written or read through the wrapper are buffered whole, since
+//! the plaintext must be authenticated before it is handed out.
+//!
+//! Only built with the opt-in `encryption` cargo feature.
+//!
+//! [`EncryptedBackend`]: crate::persistence::encryption::EncryptedBackend
+//! [`EncryptionHeader`]: crate::persistence::encryption::EncryptionHeader
+//! [`FRAME_SIZE`]: crate::persistence::encryption::FRAME_SIZE
+
+use crate::persistence::storage::{read_full, StorageBackend};
+use crate::persistence::PersistenceError;
+use bytemuck::{Pod, Zeroable};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use core::mem::size_of;
+use hkdf::Hkdf;
+use log::warn;
+use sha2::Sha256;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Magic number for encrypted stores: "EENC" = [0x45, 0x45, 0x4E, 0x43]
+pub const ENCRYPTION_MAGIC: [u8; 4] = *b"EENC";
+
+/// Current encrypted store version
+pub const ENCRYPTION_VERSION: u16 = 1;
+
+/// Key derivation identifier for HKDF-SHA256
+pub const KDF_HKDF_SHA256: u8 = 1;
+
+/// Cipher identifier for XChaCha20-Poly1305
+pub const CIPHER_XCHACHA20_POLY1305: u8 = 1;
+
+/// Plaintext bytes per frame when sealing a whole blob (1MB).
+pub const FRAME_SIZE: usize = 1024 * 1024;
+
+/// Frame prefix: ciphertext length + nonce
+const FRAME_PREFIX: usize = 4 + 24;
+
+/// Poly1305 tag length
+const TAG_SIZE: usize = 16;
+
+/// HKDF `info` strings, one per derived value
+const KEY_INFO: &[u8] = b"edgevec encryption key v1";
+const CHECK_INFO: &[u8] = b"edgevec key check v1";
+
+/// Header of an encrypted store (40 bytes).
+///
+/// # Layout
+///
+/// Total size: 40 bytes
+/// Alignment: 2 bytes
+///
+/// | Offset | Size | Field     | Description                          |
+/// |--------|------|-----------|--------------------------------------|
+/// | 0      | 4    | magic     | "EENC" = [0x45, 0x45, 0x4E, 0x43]    |
+/// | 4      | 2    | version   | Format version (1)                   |
+/// | 6      | 1    | kdf       | Key derivation (1=HKDF-SHA256)       |
+/// | 7      | 1    | cipher    | Cipher (1=XChaCha20-Poly1305)        |
+/// | 8      | 16   | salt      | Random HKDF salt                     |
+/// | 24     | 16   | key_check | HKDF output proving the key is right |
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct EncryptionHeader {
+    /// Magic number: "EENC" = [0x45, 0x45, 0x4E, 0x43]
+    pub magic: [u8; 4],
+
+    /// Format version (currently 1)
+    pub version: u16,
+
+    /// Key derivation function: 1=HKDF-SHA256
+    pub kdf: u8,
+
+    /// Cipher: 1=XChaCha20-Poly1305
+    pub cipher: u8,
+
+    /// Random HKDF salt, fixed for the life of the store
+    pub salt: [u8; 16],
+
+    /// Derived from the secret and salt; compared before decrypting
+    pub key_check: [u8; 16],
+}
+
+// Static assertion for EncryptionHeader size
+const _: () = assert!(size_of::<EncryptionHeader>() == 40);
+
+impl EncryptionHeader {
+    /// Size of the header in bytes.
+    pub const SIZE: usize = 40;
+
+    /// Parses an `EncryptionHeader` from (possibly unaligned) bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Corrupted` if the buffer is too short or the magic is
+    /// wrong, and `Unsupported` for an unknown version, KDF or cipher.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PersistenceError> {
+        if bytes.len() < Self::SIZE {
+            return Err(PersistenceError::Corrupted(format!(
+                "encryption header needs {} bytes, got {}",
+                Self::SIZE,
+                bytes.len()
+            )));
+        }
+
+        let header: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if header.magic != ENCRYPTION_MAGIC {
+            return Err(PersistenceError::Corrupted(
+                "not an encrypted store (bad magic)".into(),
+            ));
+        }
+        if header.version > ENCRYPTION_VERSION {
+            return Err(PersistenceError::Unsupported(format!(
+                "encryption format version {}",
+                header.version
+            )));
+        }
+        if header.kdf != KDF_HKDF_SHA256 || header.cipher != CIPHER_XCHACHA20_POLY1305 {
+            return Err(PersistenceError::Unsupported(format!(
+                "encryption kdf {} / cipher {}",
+                header.kdf, header.cipher
+            )));
+        }
+
+        Ok(header)
+    }
+
+    /// Returns the byte representation of the header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        bytemuck::bytes_of(self)
+    }
+}
+
+/// Keys derived from the secret for one salt.
+struct Keys {
+    header: EncryptionHeader,
+    cipher: XChaCha20Poly1305,
+}
+
+impl Keys {
+    fn derive(secret: &[u8], salt: [u8; 16]) -> Self {
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), secret);
+        let mut key = [0u8; 32];
+        let mut key_check = [0u8; 16];
+        // Output lengths are far below the HKDF limit (255 * 32 bytes)
+        hkdf.expand(KEY_INFO, &mut key)
+            .expect("valid HKDF output length");
+        hkdf.expand(CHECK_INFO, &mut key_check)
+            .expect("valid HKDF output length");
+
+        let cipher = XChaCha20Poly1305::new(&key.into());
+        key.fill(0);
+
+        Self {
+            header: EncryptionHeader {
+                magic: ENCRYPTION_MAGIC,
+                version: ENCRYPTION_VERSION,
+                kdf: KDF_HKDF_SHA256,
+                cipher: CIPHER_XCHACHA20_POLY1305,
+                salt,
+                key_check,
+            },
+            cipher,
+        }
+    }
+
+    /// Derives the keys for a stored header, checking the secret matches.
+    fn for_header(secret: &[u8], header: &EncryptionHeader) -> Result<Self, PersistenceError> {
+        let keys = Self::derive(secret, header.salt);
+        if keys.header.key_check != header.key_check {
+            return Err(PersistenceError::WrongKey);
+        }
+        Ok(keys)
+    }
+
+    /// Binds a frame to its store and position.
+    fn aad(&self, index: u64) -> [u8; 24] {
+        let mut aad = [0u8; 24];
+        aad[..16].copy_from_slice(&self.header.salt);
+        aad[16..].copy_from_slice(&index.to_le_bytes());
+        aad
+    }
+
+    /// Seals `plaintext` as frame number `index`.
+    fn seal(&self, index: u64, plaintext: &[u8]) -> Result<Vec<u8>, PersistenceError> {
+        let mut nonce = [0u8; 24];
+        getrandom::getrandom(&mut nonce)
+            .map_err(|e| PersistenceError::Io(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
+        let aad = self.aad(index);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| PersistenceError::Unsupported("frame too large to encrypt".into()))?;
+        let len = u32::try_from(ciphertext.len())
+            .map_err(|_| PersistenceError::Unsupported("frame too large to encrypt".into()))?;
+
+        let mut frame = Vec::with_capacity(FRAME_PREFIX + ciphertext.len());
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(&nonce);
+        frame.extend_from_slice(&ciphertext);
+        Ok(frame)
+    }
+
+    /// Seals `data` as a complete store: header, then frames of
+    /// [`FRAME_SIZE`] plaintext bytes. Returns the bytes and frame count.
+    fn seal_blob(&self, data: &[u8]) -> Result<(Vec<u8>, u64), PersistenceError> {
+        let mut out = Vec::with_capacity(
+            EncryptionHeader::SIZE
+                + data.len()
+                + (data.len() / FRAME_SIZE + 1) * (FRAME_PREFIX + TAG_SIZE),
+        );
+        out.extend_from_slice(self.header.as_bytes());
+        let mut frames = 0;
+        for chunk in data.chunks(FRAME_SIZE) {
+            out.extend_from_slice(&self.seal(frames, chunk)?);
+            frames += 1;
+        }
+        Ok((out, frames))
+    }
+}
+
+/// Frames found in the bytes after the header.
+struct FrameScan {
+    /// Frame boundaries: (offset of the prefix, ciphertext length)
+    frames: Vec<(usize, usize)>,
+    /// End of the last complete frame
+    valid_len: usize,
+}
+
+/// Locates the frames in a store without decrypting them.
+fn scan_frames(data: &[u8]) -> FrameScan {
+    let mut frames = Vec::new();
+    let mut offset = EncryptionHeader::SIZE;
+    while let Some(prefix) = data.get(offset..offset + FRAME_PREFIX) {
+        let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+        let end = offset + FRAME_PREFIX + len;
+        if len < TAG_SIZE || end > data.len() {
+            break;
+        }
+        frames.push((offset, len));
+        offset = end;
+    }
+    FrameScan {
+        frames,
+        valid_len: offset.min(data.len()),
+    }
+}
+
+/// A [`StorageBackend`] that encrypts everything stored in `B`.
+///
+/// See the [module documentation](self) for the format.
+///
+/// # Example
+///
+/// ```
+/// use edgevec::persistence::encryption::EncryptedBackend;
+/// use edgevec::persistence::{MemoryBackend, StorageBackend};
+///
+/// let inner = MemoryBackend::new();
+/// let mut backend = EncryptedBackend::new(inner.clone(), b"32 bytes of key material........").unwrap();
+/// backend.append(b"record").unwrap();
+/// assert_eq!(backend.read().unwrap(), b"record");
+/// assert_ne!(inner.read().unwrap(), b"record");
+/// ```
+pub struct EncryptedBackend<B> {
+    inner: B,
+    secret: Vec<u8>,
+    /// Keys for the store's salt (random if the store was empty)
+    keys: Keys,
+    /// Index of the next appended frame; `None` until the store is scanned
+    next_frame: Mutex<Option<u64>>,
+}
+
+impl<B: StorageBackend> EncryptedBackend<B> {
+    /// Wraps `inner`, deriving the key from `secret`.
+    ///
+    /// If `inner` already holds an encrypted store, its salt is reused and
+    /// `secret` is checked against it; otherwise a new salt is generated and
+    /// the header is written with the first append or atomic write.
+    ///
+    /// # Errors
+    ///
+    /// * `WrongKey` - `inner` was encrypted with a different secret
+    /// * `Corrupted` - `inner` holds data that is not an encrypted store
+    /// * `Unsupported` - `secret` is empty, or the store uses an unknown format
+    /// * `Io` - reading `inner` or generating the salt fails
+    pub fn new(inner: B, secret: &[u8]) -> Result<Self, PersistenceError> {
+        if secret.is_empty() {
+            return Err(PersistenceError::Unsupported(
+                "encryption secret must not be empty".into(),
+            ));
+        }
+
+        let mut bytes = [0u8; EncryptionHeader::SIZE];
+        let n = read_full(&mut inner.open_reader()?, &mut bytes)?;
+        let keys = if n == 0 {
+            let mut salt = [0u8; 16];
+            getrandom::getrandom(&mut salt).map_err(|e| {
+                PersistenceError::Io(std::io::Error::new(std::io::ErrorKind::Other, e))
+            })?;
+            Keys::derive(secret, salt)
+        } else {
+            Keys::for_header(secret, &EncryptionHeader::from_bytes(&bytes[..n])?)?
+        };
+
+        Ok(Self {
+            inner,
+            secret: secret.to_vec(),
+            keys,
+            next_frame: Mutex::new(None),
+        })
+    }
+
+    /// Returns the wrapped backend.
+    #[must_use]
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    fn next_frame(&self) -> Result<std::sync::MutexGuard<'_, Option<u64>>, PersistenceError> {
+        self.next_frame.lock().map_err(|_| {
+            PersistenceError::Io(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "lock poisoned",
+            ))
+        })
+    }
+
+    /// Counts the frames already stored, writing the header to an empty
+    /// store and cutting off a torn trailing frame.
+    fn prepare_append(&mut self) -> Result<u64, PersistenceError> {
+        let data = self.inner.read()?;
+        if data.is_empty() {
+            self.inner.append(self.keys.header.as_bytes())?;
+            return Ok(0);
+        }
+
+        let header = EncryptionHeader::from_bytes(&data)?;
+        if header.salt != self.keys.header.salt {
+            return Err(PersistenceError::Corrupted(
+                "encrypted store was replaced by another writer".into(),
+            ));
+        }
+        let scan = scan_frames(&data);
+        if scan.valid_len < data.len() {
+            warn!(
+                "Encrypted store: dropping {} bytes of torn trailing frame before append",
+                data.len() - scan.valid_len
+            );
+            self.inner.atomic_write("", &data[..scan.valid_len])?;
+        }
+        Ok(scan.frames.len() as u64)
+    }
+}
+
+impl<B: StorageBackend> StorageBackend for EncryptedBackend<B> {
+    fn append(&mut self, data: &[u8]) -> Result<(), PersistenceError> {
+        let cached = *self.next_frame()?;
+        let index = match cached {
+            Some(index) => index,
+            None => self.prepare_append()?,
+        };
+        let frame = self.keys.seal(index, data)?;
+        self.inner.append(&frame)?;
+        *self.next_frame()? = Some(index + 1);
+        Ok(())
+    }
+
+    fn read(&self) -> Result<Vec<u8>, PersistenceError> {
+        let data = self.inner.read()?;
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let header = EncryptionHeader::from_bytes(&data)?;
+        let replaced;
+        let keys = if header.salt == self.keys.header.salt {
+            &self.keys
+        } else {
+            replaced = Keys::for_header(&self.secret, &header)?;
+            &replaced
+        };
+        if header.key_check != keys.header.key_check {
+            return Err(PersistenceError::WrongKey);
+        }
+
+        let scan = scan_frames(&data);
+        if scan.valid_len < data.len() {
+            warn!(
+                "Encrypted store: ignoring {} bytes of torn trailing frame",
+                data.len() - scan.valid_len
+            );
+        }
+
+        let mut plaintext = Vec::with_capacity(data.len());
+        for (index, &(offset, len)) in scan.frames.iter().enumerate() {
+            let nonce = XNonce::from_slice(&data[offset + 4..offset + FRAME_PREFIX]);
+            let aad = keys.aad(index as u64);
+            let frame = keys
+                .cipher
+                .decrypt(
+                    nonce,
+                    Payload {
+                        msg: &data[offset + FRAME_PREFIX..offset + FRAME_PREFIX + len],
+                        aad: &aad,
+                    },
+                )
+                .map_err(|_| PersistenceError::Tampered { offset })?;
+            plaintext.extend_from_slice(&frame);
+        }
+        Ok(plaintext)
+    }
+
+    fn atomic_write(&self, key: &str, data: &[u8]) -> Result<(), PersistenceError> {
+        let (sealed, frames) = self.keys.seal_blob(data)?;
+        self.inner.atomic_write(key, &sealed)?;
+        // Only the default target is appended to
+        if key.is_empty() {
+            *self.next_frame()? = Some(frames);
+        }
+        Ok(())
+    }
+}
+
+impl<B> Drop for EncryptedBackend<B> {
+    fn drop(&mut self) {
+        self.secret.fill(0);
+    }
+}
+
+impl<B: fmt::Debug> fmt::Debug for EncryptedBackend<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the secret or keys
+        f.debug_struct("EncryptedBackend")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryBackend;
+
+    const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn test_header_layout() {
+        assert_eq!(size_of::<EncryptionHeader>(), EncryptionHeader::SIZE);
+        let keys = Keys::derive(SECRET, [7; 16]);
+        let parsed = EncryptionHeader::from_bytes(keys.header.as_bytes()).unwrap();
+        assert_eq!(parsed.salt, [7; 16]);
+        assert_eq!(parsed.key_check, keys.header.key_check);
+    }
+
+    #[test]
+    fn test_append_and_atomic_write_roundtrip() {
+        let inner = MemoryBackend::new();
+        let mut backend = EncryptedBackend::new(inner.clone(), SECRET).unwrap();
+        backend.append(b"first ").unwrap();
+        backend.append(b"second").unwrap();
+        assert_eq!(backend.read().unwrap(), b"first second");
+
+        let stored = inner.read().unwrap();
+        assert_eq!(
+            stored.len(),
+            EncryptionHeader::SIZE + 2 * (FRAME_PREFIX + TAG_SIZE) + 12
+        );
+        assert!(!stored.windows(6).any(|w| w == b"second"));
+
+        // A multi-frame blob, then appends continue after it
+        let blob: Vec<u8> = (0..FRAME_SIZE * 2 + 10)
+            .map(|i| i.to_le_bytes()[0])
+            .collect();
+        backend.atomic_write("", &blob).unwrap();
+        backend.append(b"tail").unwrap();
+        let mut expected = blob;
+        expected.extend_from_slice(b"tail");
+        assert_eq!(backend.read().unwrap(), expected);
+
+        // A second wrapper over the same store picks up where the first left
+        let mut reopened = EncryptedBackend::new(inner, SECRET).unwrap();
+        reopened.append(b"!").unwrap();
+        expected.push(b'!');
+        assert_eq!(reopened.read().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_wrong_key_and_tampering_are_distinct() {
+        let inner = MemoryBackend::new();
+        let mut backend = EncryptedBackend::new(inner.clone(), SECRET).unwrap();
+        backend.append(b"payload").unwrap();
+
+        assert!(matches!(
+            EncryptedBackend::new(inner.clone(), b"another secret"),
+            Err(PersistenceError::WrongKey)
+        ));
+
+        let mut stored = inner.read().unwrap();
+        let last = stored.len() - 1;
+        stored[last] ^= 0x01;
+        inner.atomic_write("", &stored).unwrap();
+        assert!(matches!(
+            backend.read(),
+            Err(PersistenceError::Tampered { offset }) if offset == EncryptionHeader::SIZE
+        ));
+    }
+
+    #[test]
+    fn test_reordered_frames_are_rejected() {
+        let inner = MemoryBackend::new();
+        let mut backend = EncryptedBackend::new(inner.clone(), SECRET).unwrap();
+        backend.append(b"aaaa").unwrap();
+        backend.append(b"bbbb").unwrap();
+
+        let stored = inner.read().unwrap();
+        let frame = FRAME_PREFIX + TAG_SIZE + 4;
+        let mut swapped = stored[..EncryptionHeader::SIZE].to_vec();
+        swapped.extend_from_slice(&stored[EncryptionHeader::SIZE + frame..]);
+        swapped.extend_from_slice(&stored[EncryptionHeader::SIZE..EncryptionHeader::SIZE + frame]);
+        inner.atomic_write("", &swapped).unwrap();
+
+        assert!(matches!(
+            backend.read(),
+            Err(PersistenceError::Tampered { .. })
+        ));
+    }
+
+    #[test]
+    fn test_torn_trailing_frame_is_dropped() {
+        let inner = MemoryBackend::new();
+        let mut backend = EncryptedBackend::new(inner.clone(), SECRET).unwrap();
+        backend.append(b"kept").unwrap();
+        backend.append(b"torn").unwrap();
+
+        let stored = inner.read().unwrap();
+        inner.atomic_write("", &stored[..stored.len() - 3]).unwrap();
+        assert_eq!(backend.read().unwrap(), b"kept");
+
+        // A new writer cuts the torn frame off before appending
+        let mut reopened = EncryptedBackend::new(inner, SECRET).unwrap();
+        reopened.append(b" again").unwrap();
+        assert_eq!(reopened.read().unwrap(), b"kept again");
+    }
+}
@@ -13,7 +13,7 @@ pub struct WalEntry {
     /// Sequence number (monotonically increasing)
     pub sequence: u64, // offset 0
 
-    /// Entry type (0=insert, 1=delete, 2=checkpoint)
+    /// Entry type, one of the `WalEntry::*` type constants
     pub entry_type: u8, // offset 8
 
     /// Padding
@@ -27,6 +27,29 @@ pub struct WalEntry {
 }
 
 impl WalEntry {
+    /// Vector insert: `[u64 id][f32 LE...]`.
+    pub const INSERT: u8 = 0;
+    /// SQ8 vector insert: `[u64 id][u8 codes...]`.
+    pub const INSERT_QUANTIZED: u8 = 1;
+    /// Checkpoint marker.
+    pub const CHECKPOINT: u8 = 2;
+    /// In-place vector update: `[u64 id][f32 LE...]`.
+    pub const UPDATE: u8 = 3;
+    /// Soft delete of a vector: `[u64 id]`.
+    pub const SOFT_DELETE: u8 = 4;
+    /// Metadata upsert: postcard-encoded `(u64 id, key, MetadataValue)`.
+    pub const METADATA_SET: u8 = 5;
+    /// Metadata key removal: postcard-encoded `(u64 id, key)`.
+    pub const METADATA_DELETE: u8 = 6;
+    /// Binary quantization enabled on the index (empty payload).
+    pub const ENABLE_BQ: u8 = 7;
+    /// Storage type conversion: postcard-encoded target `StorageType`.
+    pub const CONVERT_STORAGE: u8 = 8;
+    /// Hard delete of a vector (unlink and repair neighbors): `[u64 id]`.
+    pub const HARD_DELETE: u8 = 9;
+    /// External ID bound to a vector: postcard-encoded `(u64 id, ExternalId)`.
+    pub const EXTERNAL_ID_SET: u8 = 10;
+
     /// Creates a new `WalEntry` with the given sequence number and payload length.
     #[must_use]
     pub fn new(sequence: u64, entry_type: u8, payload_len: u32) -> Self {
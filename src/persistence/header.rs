@@ -1,3 +1,4 @@
+use crate::hnsw::HnswConfig;
 use bytemuck::{Pod, Zeroable};
 use core::mem::{align_of, size_of};
 use thiserror::Error;
@@ -8,8 +9,8 @@ pub const MAGIC: [u8; 4] = *b"EVEC";
 /// Current major version
 pub const VERSION_MAJOR: u8 = 0;
 
-/// Current minor version (bumped to 4 for metadata support per RFC-002)
-pub const VERSION_MINOR: u8 = 4;
+/// Current minor version (bumped to 5 for full `HnswConfig` persistence)
+pub const VERSION_MINOR: u8 = 5;
 
 /// Minimum supported minor version for migration
 pub const VERSION_MINOR_MIN: u8 = 1;
@@ -26,15 +27,40 @@ pub const FORMAT_POSTCARD: u8 = 1;
 /// Serialization format: JSON (text, debugging)
 pub const FORMAT_JSON: u8 = 2;
 
+/// Magic number for config section: "HCFG" = [0x48, 0x43, 0x46, 0x47]
+pub const CONFIG_MAGIC: [u8; 4] = *b"HCFG";
+
+/// Current config section version
+pub const CONFIG_VERSION: u16 = 1;
+
 /// File format flags
 #[allow(non_snake_case)]
 pub mod Flags {
-    /// Data is compressed
+    /// Some sections are stored compressed (v0.5+). With a section table,
+    /// each entry says whether its section is; without one, the neighbor
+    /// pool and the tail (tombstones onwards) are each one compressed section
     pub const COMPRESSED: u16 = 1 << 0;
-    /// Vectors are quantized
+    /// Vectors are SQ8-quantized: the vector block holds one `u8` per
+    /// dimension and a quantizer section is present (v0.5+)
     pub const QUANTIZED: u16 = 1 << 1;
     /// MetadataStore is present (v0.4+)
     pub const HAS_METADATA: u16 = 1 << 2;
+    /// External ID map is present (v0.5+)
+    pub const HAS_EXTERNAL_IDS: u16 = 1 << 3;
+    /// Snapshot holds a `FlatIndex`: no HNSW nodes or neighbors (v0.5+)
+    pub const FLAT_INDEX: u16 = 1 << 4;
+    /// Binary-quantized vectors are present (v0.5+)
+    pub const HAS_BQ: u16 = 1 << 5;
+    /// WAL checkpoint section is present (v0.5+)
+    pub const HAS_CHECKPOINT: u16 = 1 << 6;
+    /// A section table follows the header and locates every section;
+    /// `index_offset` and `metadata_offset` are informational (v0.5+)
+    pub const HAS_SECTION_TABLE: u16 = 1 << 7;
+    /// Product quantization codebooks and codes are present (v0.5+)
+    pub const HAS_PQ: u16 = 1 << 8;
+    /// Dual F32 + SQ8 storage: the vector block holds F32 vectors and a
+    /// quantizer section is present. Codes are re-encoded on load (v0.5+)
+    pub const DUAL_STORAGE: u16 = 1 << 9;
 }
 
 /// File header for .evec index files.
@@ -107,87 +133,1220 @@ const _: () = assert!(align_of::<FileHeader>() == 8);
 
 /// Metadata section header (16 bytes).
 ///
-/// Placed after tombstone bitvec when `Flags::HAS_METADATA` flag is set.
+/// Placed after tombstone bitvec when `Flags::HAS_METADATA` flag is set.
+///
+/// # Layout
+///
+/// Total size: 16 bytes
+/// Alignment: 4 bytes
+///
+/// | Offset | Size | Field    | Description                      |
+/// |--------|------|----------|----------------------------------|
+/// | 0      | 4    | magic    | "META" = [0x4D, 0x45, 0x54, 0x41]|
+/// | 4      | 2    | version  | Section format version (1)       |
+/// | 6      | 1    | format   | Serialization format (1=Postcard)|
+/// | 7      | 1    | reserved | Reserved for future use (0)      |
+/// | 8      | 4    | size     | Size of serialized metadata      |
+/// | 12     | 4    | crc      | CRC32 of serialized metadata     |
+///
+/// # Thread Safety
+///
+/// This type is `Send + Sync` as it is a POD struct.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct MetadataSectionHeader {
+    /// Magic number: "META" = [0x4D, 0x45, 0x54, 0x41]
+    pub magic: [u8; 4],
+
+    /// Section format version (currently 1)
+    pub version: u16,
+
+    /// Serialization format: 1=Postcard, 2=JSON
+    pub format: u8,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u8,
+
+    /// Size of serialized metadata in bytes
+    pub size: u32,
+
+    /// CRC32 of serialized metadata bytes
+    pub crc: u32,
+}
+
+// Static assertions for MetadataSectionHeader size and alignment
+const _: () = assert!(size_of::<MetadataSectionHeader>() == 16);
+const _: () = assert!(align_of::<MetadataSectionHeader>() == 4);
+
+impl MetadataSectionHeader {
+    /// The expected magic bytes "META".
+    pub const MAGIC: [u8; 4] = METADATA_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = METADATA_VERSION;
+
+    /// Postcard serialization format identifier.
+    pub const FORMAT_POSTCARD: u8 = FORMAT_POSTCARD;
+
+    /// JSON serialization format identifier.
+    pub const FORMAT_JSON: u8 = FORMAT_JSON;
+
+    /// Creates a new `MetadataSectionHeader` for Postcard-serialized metadata.
+    #[must_use]
+    pub fn new_postcard(size: u32, crc: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            format: Self::FORMAT_POSTCARD,
+            reserved: 0,
+            size,
+            crc,
+        }
+    }
+
+    /// Creates a new `MetadataSectionHeader` for JSON-serialized metadata.
+    #[must_use]
+    pub fn new_json(size: u32, crc: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            format: Self::FORMAT_JSON,
+            reserved: 0,
+            size,
+            crc,
+        }
+    }
+
+    /// Returns the byte representation of the header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses a `MetadataSectionHeader` from bytes.
+    ///
+    /// # Requirements
+    ///
+    /// - `bytes` must be at least 16 bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if:
+    /// - Buffer is less than 16 bytes
+    /// - Magic number is invalid
+    /// - Version is unsupported
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MetadataHeaderError> {
+        if bytes.len() < 16 {
+            return Err(MetadataHeaderError::BufferTooShort(bytes.len()));
+        }
+
+        // Copy to aligned buffer to avoid alignment issues
+        // This is necessary because the metadata section may not start at an aligned offset
+        let mut aligned_buf = [0u8; 16];
+        aligned_buf.copy_from_slice(&bytes[..16]);
+        let header = *bytemuck::from_bytes::<MetadataSectionHeader>(&aligned_buf);
+
+        header.validate_magic()?;
+        header.validate_version()?;
+        header.validate_format()?;
+
+        Ok(header)
+    }
+
+    /// Validates the magic bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataHeaderError::InvalidMagic` if magic bytes don't match "META".
+    pub fn validate_magic(&self) -> Result<(), MetadataHeaderError> {
+        if self.magic != Self::MAGIC {
+            return Err(MetadataHeaderError::InvalidMagic(self.magic));
+        }
+        Ok(())
+    }
+
+    /// Validates the version is supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataHeaderError::UnsupportedVersion` if version is newer than current.
+    pub fn validate_version(&self) -> Result<(), MetadataHeaderError> {
+        if self.version > Self::VERSION {
+            return Err(MetadataHeaderError::UnsupportedVersion(self.version));
+        }
+        Ok(())
+    }
+
+    /// Validates the serialization format is supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataHeaderError::UnsupportedFormat` if format is not 1 (Postcard) or 2 (JSON).
+    pub fn validate_format(&self) -> Result<(), MetadataHeaderError> {
+        if self.format != Self::FORMAT_POSTCARD && self.format != Self::FORMAT_JSON {
+            return Err(MetadataHeaderError::UnsupportedFormat(self.format));
+        }
+        Ok(())
+    }
+
+    /// Returns true if this header uses Postcard serialization.
+    #[must_use]
+    pub fn is_postcard(&self) -> bool {
+        self.format == Self::FORMAT_POSTCARD
+    }
+
+    /// Returns true if this header uses JSON serialization.
+    #[must_use]
+    pub fn is_json(&self) -> bool {
+        self.format == Self::FORMAT_JSON
+    }
+}
+
+/// Errors that can occur during metadata header parsing.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MetadataHeaderError {
+    /// Invalid magic number.
+    #[error("invalid metadata magic: expected 'META', got {0:?}")]
+    InvalidMagic([u8; 4]),
+
+    /// Unsupported version.
+    #[error("unsupported metadata version: {0}")]
+    UnsupportedVersion(u16),
+
+    /// Unsupported serialization format.
+    #[error("unsupported serialization format: {0}")]
+    UnsupportedFormat(u8),
+
+    /// Buffer too short.
+    #[error("buffer too short: expected 16 bytes, got {0}")]
+    BufferTooShort(usize),
+
+    /// Buffer is not 4-byte aligned.
+    #[error("buffer is not 4-byte aligned")]
+    UnalignedBuffer,
+
+    /// CRC mismatch.
+    #[error("CRC mismatch: expected {expected:#x}, got {actual:#x}")]
+    CrcMismatch {
+        /// Expected CRC (from header)
+        expected: u32,
+        /// Actual calculated CRC
+        actual: u32,
+    },
+}
+
+/// HNSW configuration section (48 bytes, v0.5+).
+///
+/// Placed directly after the tombstone bitvec (and before the optional
+/// metadata section). Stores the complete [`HnswConfig`] plus the index
+/// compaction threshold, so a snapshot reloads with the same metric and
+/// search parameters it was written with.
+///
+/// # Layout
+///
+/// Total size: 48 bytes
+/// Alignment: 8 bytes
+///
+/// | Offset | Size | Field                  | Description                        |
+/// |--------|------|------------------------|------------------------------------|
+/// | 0      | 4    | magic                  | "HCFG" = [0x48, 0x43, 0x46, 0x47]  |
+/// | 4      | 2    | version                | Section format version (1)         |
+/// | 6      | 2    | reserved               | Reserved for future use (0)        |
+/// | 8      | 4    | m                      | Max connections in layers > 0      |
+/// | 12     | 4    | m0                     | Max connections in layer 0         |
+/// | 16     | 4    | ef_construction        | Construction candidate list size   |
+/// | 20     | 4    | ef_search              | Search candidate list size         |
+/// | 24     | 4    | dimensions             | Vector dimensionality              |
+/// | 28     | 4    | metric                 | Metric code (0=L2, 1=Cosine, 2=Dot)|
+/// | 32     | 8    | compaction_threshold   | Tombstone ratio triggering compact |
+/// | 40     | 4    | crc                    | CRC32 of bytes 0..40               |
+/// | 44     | 4    | reserved2              | Reserved for future use (0)        |
+///
+/// # Thread Safety
+///
+/// This type is `Send + Sync` as it is a POD struct.
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct ConfigSection {
+    /// Magic number: "HCFG" = [0x48, 0x43, 0x46, 0x47]
+    pub magic: [u8; 4],
+
+    /// Section format version (currently 1)
+    pub version: u16,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u16,
+
+    /// HNSW M parameter
+    pub m: u32,
+
+    /// HNSW M0 parameter
+    pub m0: u32,
+
+    /// Construction-time candidate list size
+    pub ef_construction: u32,
+
+    /// Search-time candidate list size
+    pub ef_search: u32,
+
+    /// Vector dimensionality
+    pub dimensions: u32,
+
+    /// Distance metric code (see `HnswConfig::METRIC_*`)
+    pub metric: u32,
+
+    /// Tombstone ratio above which compaction is recommended
+    pub compaction_threshold: f64,
+
+    /// CRC32 of bytes 0..40
+    pub crc: u32,
+
+    /// Reserved for future use (must be 0)
+    pub reserved2: u32,
+}
+
+// Static assertions for ConfigSection size and alignment
+const _: () = assert!(size_of::<ConfigSection>() == 48);
+const _: () = assert!(align_of::<ConfigSection>() == 8);
+
+impl ConfigSection {
+    /// The expected magic bytes "HCFG".
+    pub const MAGIC: [u8; 4] = CONFIG_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = CONFIG_VERSION;
+
+    /// Size of the section in bytes.
+    pub const SIZE: usize = 48;
+
+    /// Offset of the `crc` field (the CRC covers all bytes before it).
+    const CRC_OFFSET: usize = 40;
+
+    /// Creates a config section from an `HnswConfig` and compaction threshold.
+    ///
+    /// The CRC is computed automatically.
+    #[must_use]
+    pub fn new(config: &HnswConfig, compaction_threshold: f64) -> Self {
+        let mut section = Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            reserved: 0,
+            m: config.m,
+            m0: config.m0,
+            ef_construction: config.ef_construction,
+            ef_search: config.ef_search,
+            dimensions: config.dimensions,
+            metric: config.metric,
+            compaction_threshold,
+            crc: 0,
+            reserved2: 0,
+        };
+        section.crc = section.compute_crc();
+        section
+    }
+
+    /// Returns the stored parameters as an `HnswConfig`.
+    #[must_use]
+    pub fn to_config(&self) -> HnswConfig {
+        HnswConfig {
+            m: self.m,
+            m0: self.m0,
+            ef_construction: self.ef_construction,
+            ef_search: self.ef_search,
+            dimensions: self.dimensions,
+            metric: self.metric,
+            _reserved: [0; 2],
+        }
+    }
+
+    /// Returns the byte representation of the section.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 48] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses a `ConfigSection` from bytes.
+    ///
+    /// The buffer does not need to be aligned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if:
+    /// - Buffer is less than 48 bytes
+    /// - Magic number is invalid
+    /// - Version is unsupported
+    /// - CRC does not match
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        // The section follows the tombstone bitvec, so it is rarely aligned.
+        let section: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if section.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: section.magic,
+            });
+        }
+        if section.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(section.version));
+        }
+
+        let actual = section.compute_crc();
+        if section.crc != actual {
+            return Err(SectionError::CrcMismatch {
+                expected: section.crc,
+                actual,
+            });
+        }
+
+        Ok(section)
+    }
+
+    fn compute_crc(&self) -> u32 {
+        crc32fast::hash(&self.as_bytes()[..Self::CRC_OFFSET])
+    }
+}
+
+/// Magic number for external ID section: "EXID" = [0x45, 0x58, 0x49, 0x44]
+pub const EXTERNAL_IDS_MAGIC: [u8; 4] = *b"EXID";
+
+/// Current external ID section version
+pub const EXTERNAL_IDS_VERSION: u16 = 1;
+
+/// External ID section header (16 bytes, v0.5+).
+///
+/// Placed after the metadata section (or after the config section if there
+/// is no metadata) when `Flags::HAS_EXTERNAL_IDS` is set. Followed by `size`
+/// bytes of Postcard-serialized `ExternalIdMap`.
+///
+/// # Layout
+///
+/// Total size: 16 bytes
+/// Alignment: 4 bytes
+///
+/// | Offset | Size | Field    | Description                      |
+/// |--------|------|----------|----------------------------------|
+/// | 0      | 4    | magic    | "EXID" = [0x45, 0x58, 0x49, 0x44]|
+/// | 4      | 2    | version  | Section format version (1)       |
+/// | 6      | 1    | format   | Serialization format (1=Postcard)|
+/// | 7      | 1    | reserved | Reserved for future use (0)      |
+/// | 8      | 4    | size     | Size of serialized map           |
+/// | 12     | 4    | crc      | CRC32 of serialized map          |
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct ExternalIdSectionHeader {
+    /// Magic number: "EXID" = [0x45, 0x58, 0x49, 0x44]
+    pub magic: [u8; 4],
+
+    /// Section format version (currently 1)
+    pub version: u16,
+
+    /// Serialization format: 1=Postcard
+    pub format: u8,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u8,
+
+    /// Size of serialized map in bytes
+    pub size: u32,
+
+    /// CRC32 of serialized map bytes
+    pub crc: u32,
+}
+
+// Static assertions for ExternalIdSectionHeader size and alignment
+const _: () = assert!(size_of::<ExternalIdSectionHeader>() == 16);
+const _: () = assert!(align_of::<ExternalIdSectionHeader>() == 4);
+
+impl ExternalIdSectionHeader {
+    /// The expected magic bytes "EXID".
+    pub const MAGIC: [u8; 4] = EXTERNAL_IDS_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = EXTERNAL_IDS_VERSION;
+
+    /// Size of the section header in bytes.
+    pub const SIZE: usize = 16;
+
+    /// Creates a header for a Postcard-serialized map.
+    #[must_use]
+    pub fn new_postcard(size: u32, crc: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            format: FORMAT_POSTCARD,
+            reserved: 0,
+            size,
+            crc,
+        }
+    }
+
+    /// Returns the byte representation of the header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses an `ExternalIdSectionHeader` from (possibly unaligned) bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the buffer is too short, or the magic, version or
+    /// format is not recognized.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let header: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if header.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: header.magic,
+            });
+        }
+        if header.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(header.version));
+        }
+        if header.format != FORMAT_POSTCARD {
+            return Err(SectionError::UnsupportedFormat(header.format));
+        }
+
+        Ok(header)
+    }
+}
+
+/// Magic number for binary quantization section: "BQVS" = [0x42, 0x51, 0x56, 0x53]
+pub const BQ_MAGIC: [u8; 4] = *b"BQVS";
+
+/// Current binary quantization section version
+pub const BQ_VERSION: u16 = 1;
+
+/// Binary quantization section header (16 bytes, v0.5+).
+///
+/// Placed after the external ID section (or the last section before it)
+/// when `Flags::HAS_BQ` is set. Followed by `size` bytes: the packed
+/// `BinaryVectorStorage` data (`vector_count * dimensions / 8` bytes, one
+/// entry per HNSW node) and its tombstone bitvec (`ceil(vector_count / 8)`
+/// bytes, Lsb0).
+///
+/// # Layout
+///
+/// Total size: 16 bytes
+/// Alignment: 4 bytes
+///
+/// | Offset | Size | Field    | Description                      |
+/// |--------|------|----------|----------------------------------|
+/// | 0      | 4    | magic    | "BQVS" = [0x42, 0x51, 0x56, 0x53]|
+/// | 4      | 2    | version  | Section format version (1)       |
+/// | 6      | 2    | reserved | Reserved for future use (0)      |
+/// | 8      | 4    | size     | Size of section payload          |
+/// | 12     | 4    | crc      | CRC32 of section payload         |
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct BqSectionHeader {
+    /// Magic number: "BQVS" = [0x42, 0x51, 0x56, 0x53]
+    pub magic: [u8; 4],
+
+    /// Section format version (currently 1)
+    pub version: u16,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u16,
+
+    /// Size of the payload in bytes
+    pub size: u32,
+
+    /// CRC32 of the payload bytes
+    pub crc: u32,
+}
+
+// Static assertions for BqSectionHeader size and alignment
+const _: () = assert!(size_of::<BqSectionHeader>() == 16);
+const _: () = assert!(align_of::<BqSectionHeader>() == 4);
+
+impl BqSectionHeader {
+    /// The expected magic bytes "BQVS".
+    pub const MAGIC: [u8; 4] = BQ_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = BQ_VERSION;
+
+    /// Size of the section header in bytes.
+    pub const SIZE: usize = 16;
+
+    /// Creates a header for a payload of `size` bytes with checksum `crc`.
+    #[must_use]
+    pub fn new(size: u32, crc: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            reserved: 0,
+            size,
+            crc,
+        }
+    }
+
+    /// Returns the byte representation of the header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses a `BqSectionHeader` from (possibly unaligned) bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the buffer is too short, or the magic or version is
+    /// not recognized.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let header: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if header.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: header.magic,
+            });
+        }
+        if header.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(header.version));
+        }
+
+        Ok(header)
+    }
+}
+
+/// Magic number for quantizer section: "SQ8Q" = [0x53, 0x51, 0x38, 0x51]
+pub const QUANTIZER_MAGIC: [u8; 4] = *b"SQ8Q";
+
+/// Current quantizer section version
+pub const QUANTIZER_VERSION: u16 = 2;
+
+/// Quantizer section header (16 bytes, v0.5+).
+///
+/// Placed after every other optional section when `Flags::QUANTIZED` is
+/// set. Followed by `size` bytes of Postcard-serialized quantizer
+/// parameters, needed to quantize new vectors and dequantize stored ones:
+///
+/// - version 1: a global `QuantizerConfig`
+/// - version 2: per-dimension `Sq8Ranges`
+///
+/// Global configs are still written as version 1, so readers that predate
+/// per-dimension ranges can load them.
+///
+/// # Layout
+///
+/// Total size: 16 bytes
+/// Alignment: 4 bytes
+///
+/// | Offset | Size | Field    | Description                      |
+/// |--------|------|----------|----------------------------------|
+/// | 0      | 4    | magic    | "SQ8Q" = [0x53, 0x51, 0x38, 0x51]|
+/// | 4      | 2    | version  | Section format version (1 or 2)  |
+/// | 6      | 1    | format   | Serialization format (1=Postcard)|
+/// | 7      | 1    | reserved | Reserved for future use (0)      |
+/// | 8      | 4    | size     | Size of serialized config        |
+/// | 12     | 4    | crc      | CRC32 of serialized config       |
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct QuantizerSectionHeader {
+    /// Magic number: "SQ8Q" = [0x53, 0x51, 0x38, 0x51]
+    pub magic: [u8; 4],
+
+    /// Section format version (1 = global config, 2 = per-dimension ranges)
+    pub version: u16,
+
+    /// Serialization format: 1=Postcard
+    pub format: u8,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u8,
+
+    /// Size of serialized config in bytes
+    pub size: u32,
+
+    /// CRC32 of serialized config bytes
+    pub crc: u32,
+}
+
+// Static assertions for QuantizerSectionHeader size and alignment
+const _: () = assert!(size_of::<QuantizerSectionHeader>() == 16);
+const _: () = assert!(align_of::<QuantizerSectionHeader>() == 4);
+
+impl QuantizerSectionHeader {
+    /// The expected magic bytes "SQ8Q".
+    pub const MAGIC: [u8; 4] = QUANTIZER_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = QUANTIZER_VERSION;
+
+    /// Size of the section header in bytes.
+    pub const SIZE: usize = 16;
+
+    /// Creates a header for a Postcard-serialized global `QuantizerConfig`.
+    #[must_use]
+    pub fn new_postcard(size: u32, crc: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: 1,
+            format: FORMAT_POSTCARD,
+            reserved: 0,
+            size,
+            crc,
+        }
+    }
+
+    /// Creates a header for Postcard-serialized per-dimension `Sq8Ranges`.
+    #[must_use]
+    pub fn new_postcard_ranges(size: u32, crc: u32) -> Self {
+        Self {
+            version: 2,
+            ..Self::new_postcard(size, crc)
+        }
+    }
+
+    /// Returns true if the payload holds per-dimension ranges.
+    #[must_use]
+    pub fn has_ranges(&self) -> bool {
+        self.version >= 2
+    }
+
+    /// Returns the byte representation of the header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses a `QuantizerSectionHeader` from (possibly unaligned) bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the buffer is too short, or the magic, version or
+    /// format is not recognized.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let header: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if header.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: header.magic,
+            });
+        }
+        if header.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(header.version));
+        }
+        if header.format != FORMAT_POSTCARD {
+            return Err(SectionError::UnsupportedFormat(header.format));
+        }
+
+        Ok(header)
+    }
+}
+
+/// Magic number for product quantization section: "PQCB" = [0x50, 0x51, 0x43, 0x42]
+pub const PQ_MAGIC: [u8; 4] = *b"PQCB";
+
+/// Current product quantization section version
+pub const PQ_VERSION: u16 = 1;
+
+/// Product quantization section header (16 bytes, v0.5+).
+///
+/// Placed after the quantizer section (or the last section before it)
+/// when `Flags::HAS_PQ` is set. Followed by `size` bytes:
+///
+/// | Size | Field | Description |
+/// |------|-------|-------------|
+/// | 4 | sub_vectors | Number of sub-vectors `m` (u32 LE) |
+/// | 4 | centroids | Centroids per sub-vector `k` (u32 LE) |
+/// | `m * k * (dimensions / m) * 4` | codebooks | `f32` LE, sub-vector major |
+/// | `vector_count * m` | codes | One code per HNSW node |
+///
+/// # Layout
+///
+/// Total size: 16 bytes
+/// Alignment: 4 bytes
+///
+/// | Offset | Size | Field    | Description                      |
+/// |--------|------|----------|----------------------------------|
+/// | 0      | 4    | magic    | "PQCB" = [0x50, 0x51, 0x43, 0x42]|
+/// | 4      | 2    | version  | Section format version (1)       |
+/// | 6      | 2    | reserved | Reserved for future use (0)      |
+/// | 8      | 4    | size     | Size of section payload          |
+/// | 12     | 4    | crc      | CRC32 of section payload         |
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct PqSectionHeader {
+    /// Magic number: "PQCB" = [0x50, 0x51, 0x43, 0x42]
+    pub magic: [u8; 4],
+
+    /// Section format version (currently 1)
+    pub version: u16,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u16,
+
+    /// Size of the payload in bytes
+    pub size: u32,
+
+    /// CRC32 of the payload bytes
+    pub crc: u32,
+}
+
+// Static assertions for PqSectionHeader size and alignment
+const _: () = assert!(size_of::<PqSectionHeader>() == 16);
+const _: () = assert!(align_of::<PqSectionHeader>() == 4);
+
+impl PqSectionHeader {
+    /// The expected magic bytes "PQCB".
+    pub const MAGIC: [u8; 4] = PQ_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = PQ_VERSION;
+
+    /// Size of the section header in bytes.
+    pub const SIZE: usize = 16;
+
+    /// Creates a header for a payload of `size` bytes with checksum `crc`.
+    #[must_use]
+    pub fn new(size: u32, crc: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            reserved: 0,
+            size,
+            crc,
+        }
+    }
+
+    /// Returns the byte representation of the header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses a `PqSectionHeader` from (possibly unaligned) bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the buffer is too short, or the magic or version is
+    /// not recognized.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let header: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if header.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: header.magic,
+            });
+        }
+        if header.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(header.version));
+        }
+
+        Ok(header)
+    }
+}
+
+/// Magic number for checkpoint section: "WALC" = [0x57, 0x41, 0x4C, 0x43]
+pub const CHECKPOINT_MAGIC: [u8; 4] = *b"WALC";
+
+/// Current checkpoint section version
+pub const CHECKPOINT_VERSION: u16 = 1;
+
+/// WAL checkpoint section (24 bytes, v0.5+).
+///
+/// Placed after every other section when `Flags::HAS_CHECKPOINT` is set.
+/// Records the WAL sequence number the snapshot was taken at: every WAL
+/// record with a lower sequence is already reflected in the snapshot, so
+/// recovery skips it and the log may be truncated up to it.
+///
+/// # Layout
+///
+/// Total size: 24 bytes
+/// Alignment: 8 bytes
+///
+/// | Offset | Size | Field        | Description                        |
+/// |--------|------|--------------|------------------------------------|
+/// | 0      | 4    | magic        | "WALC" = [0x57, 0x41, 0x4C, 0x43]  |
+/// | 4      | 2    | version      | Section format version (1)         |
+/// | 6      | 2    | reserved     | Reserved for future use (0)        |
+/// | 8      | 8    | wal_sequence | First WAL sequence not covered     |
+/// | 16     | 4    | crc          | CRC32 of bytes 0..16               |
+/// | 20     | 4    | reserved2    | Reserved for future use (0)        |
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct CheckpointSection {
+    /// Magic number: "WALC" = [0x57, 0x41, 0x4C, 0x43]
+    pub magic: [u8; 4],
+
+    /// Section format version (currently 1)
+    pub version: u16,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u16,
+
+    /// First WAL sequence number not covered by the snapshot
+    pub wal_sequence: u64,
+
+    /// CRC32 of bytes 0..16
+    pub crc: u32,
+
+    /// Reserved for future use (must be 0)
+    pub reserved2: u32,
+}
+
+// Static assertions for CheckpointSection size and alignment
+const _: () = assert!(size_of::<CheckpointSection>() == 24);
+const _: () = assert!(align_of::<CheckpointSection>() == 8);
+
+impl CheckpointSection {
+    /// The expected magic bytes "WALC".
+    pub const MAGIC: [u8; 4] = CHECKPOINT_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = CHECKPOINT_VERSION;
+
+    /// Size of the section in bytes.
+    pub const SIZE: usize = 24;
+
+    /// Offset of the `crc` field (the CRC covers all bytes before it).
+    const CRC_OFFSET: usize = 16;
+
+    /// Creates a checkpoint section. The CRC is computed automatically.
+    #[must_use]
+    pub fn new(wal_sequence: u64) -> Self {
+        let mut section = Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            reserved: 0,
+            wal_sequence,
+            crc: 0,
+            reserved2: 0,
+        };
+        section.crc = section.compute_crc();
+        section
+    }
+
+    /// Returns the byte representation of the section.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 24] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses a `CheckpointSection` from (possibly unaligned) bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the buffer is too short, the magic or version is not
+    /// recognized, or the CRC does not match.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let section: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if section.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: section.magic,
+            });
+        }
+        if section.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(section.version));
+        }
+
+        let actual = section.compute_crc();
+        if section.crc != actual {
+            return Err(SectionError::CrcMismatch {
+                expected: section.crc,
+                actual,
+            });
+        }
+
+        Ok(section)
+    }
+
+    fn compute_crc(&self) -> u32 {
+        crc32fast::hash(&self.as_bytes()[..Self::CRC_OFFSET])
+    }
+}
+
+/// Magic number for compressed sections: "CMPS" = [0x43, 0x4D, 0x50, 0x53]
+pub const COMPRESSED_MAGIC: [u8; 4] = *b"CMPS";
+
+/// Current compressed section version
+pub const COMPRESSED_VERSION: u16 = 1;
+
+/// Codec identifier for LZ4 block compression
+pub const CODEC_LZ4: u8 = 1;
+
+/// Compressed section header (20 bytes, v0.5+).
+///
+/// A compressed section is stored as this header followed by `size`
+/// compressed bytes. With a section table, any section whose entry has
+/// `SectionEntry::COMPRESSED` is stored this way. Older snapshots with
+/// `Flags::COMPRESSED` instead replace the neighbor pool and the tail
+/// (tombstones, config and every optional section) with one compressed
+/// section each; `metadata_offset` then points at the tail's header.
+///
+/// # Layout
+///
+/// Total size: 20 bytes
+/// Alignment: 4 bytes
+///
+/// | Offset | Size | Field    | Description                       |
+/// |--------|------|----------|-----------------------------------|
+/// | 0      | 4    | magic    | "CMPS" = [0x43, 0x4D, 0x50, 0x53] |
+/// | 4      | 2    | version  | Section format version (1)        |
+/// | 6      | 1    | codec    | Compression codec (1=LZ4)         |
+/// | 7      | 1    | reserved | Reserved for future use (0)       |
+/// | 8      | 4    | raw_size | Size of the data once decompressed|
+/// | 12     | 4    | size     | Size of the compressed data       |
+/// | 16     | 4    | crc      | CRC32 of the compressed data      |
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct CompressedSectionHeader {
+    /// Magic number: "CMPS" = [0x43, 0x4D, 0x50, 0x53]
+    pub magic: [u8; 4],
+
+    /// Section format version (currently 1)
+    pub version: u16,
+
+    /// Compression codec: 1=LZ4
+    pub codec: u8,
+
+    /// Reserved for future use (must be 0)
+    pub reserved: u8,
+
+    /// Size of the data once decompressed, in bytes
+    pub raw_size: u32,
+
+    /// Size of the compressed data in bytes
+    pub size: u32,
+
+    /// CRC32 of the compressed data
+    pub crc: u32,
+}
+
+// Static assertions for CompressedSectionHeader size and alignment
+const _: () = assert!(size_of::<CompressedSectionHeader>() == 20);
+const _: () = assert!(align_of::<CompressedSectionHeader>() == 4);
+
+impl CompressedSectionHeader {
+    /// The expected magic bytes "CMPS".
+    pub const MAGIC: [u8; 4] = COMPRESSED_MAGIC;
+
+    /// The current section version.
+    pub const VERSION: u16 = COMPRESSED_VERSION;
+
+    /// Size of the section header in bytes.
+    pub const SIZE: usize = 20;
+
+    /// Creates a header for `size` bytes of LZ4-compressed data.
+    #[must_use]
+    pub fn new_lz4(raw_size: u32, size: u32, crc: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            codec: CODEC_LZ4,
+            reserved: 0,
+            raw_size,
+            size,
+            crc,
+        }
+    }
+
+    /// Returns the byte representation of the header.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        bytemuck::cast_ref(self)
+    }
+
+    /// Parses a `CompressedSectionHeader` from (possibly unaligned) bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if the buffer is too short, or the magic, version or
+    /// codec is not recognized.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let header: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
+
+        if header.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: header.magic,
+            });
+        }
+        if header.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(header.version));
+        }
+        if header.codec != CODEC_LZ4 {
+            return Err(SectionError::UnsupportedFormat(header.codec));
+        }
+
+        Ok(header)
+    }
+}
+
+/// Magic number for the section table: "ETOC" = [0x45, 0x54, 0x4F, 0x43]
+pub const SECTION_TABLE_MAGIC: [u8; 4] = *b"ETOC";
+
+/// Current section table version
+pub const SECTION_TABLE_VERSION: u16 = 1;
+
+/// Type of a section listed in the section table.
+///
+/// Kinds a reader does not know are skipped, so new sections can be added
+/// without breaking older readers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SectionKind(pub u16);
+
+impl SectionKind {
+    /// Vector block: `f32` values, or one `u8` per dimension when quantized
+    pub const VECTORS: Self = Self(1);
+    /// HNSW node table (16 bytes per node)
+    pub const NODES: Self = Self(2);
+    /// HNSW neighbor pool
+    pub const NEIGHBORS: Self = Self(3);
+    /// Tombstone bitvec (one bit per vector, Lsb0)
+    pub const TOMBSTONES: Self = Self(4);
+    /// [`ConfigSection`]
+    pub const CONFIG: Self = Self(5);
+    /// [`MetadataSectionHeader`] followed by the metadata store
+    pub const METADATA: Self = Self(6);
+    /// [`ExternalIdSectionHeader`] followed by the external ID map
+    pub const EXTERNAL_IDS: Self = Self(7);
+    /// [`BqSectionHeader`] followed by the binary-quantized vectors
+    pub const BQ: Self = Self(8);
+    /// [`QuantizerSectionHeader`] followed by the SQ8 quantizer config
+    pub const QUANTIZER: Self = Self(9);
+    /// [`CheckpointSection`]
+    pub const CHECKPOINT: Self = Self(10);
+    /// [`PqSectionHeader`] followed by the PQ codebooks and codes
+    pub const PQ: Self = Self(11);
+
+    /// Human-readable name, used in error messages.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::VECTORS => "vectors",
+            Self::NODES => "nodes",
+            Self::NEIGHBORS => "neighbor pool",
+            Self::TOMBSTONES => "tombstones",
+            Self::CONFIG => "config",
+            Self::METADATA => "metadata",
+            Self::EXTERNAL_IDS => "external ID",
+            Self::BQ => "BQ",
+            Self::QUANTIZER => "quantizer",
+            Self::CHECKPOINT => "checkpoint",
+            Self::PQ => "PQ",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Section table header (16 bytes, v0.5+).
+///
+/// When `Flags::HAS_SECTION_TABLE` is set, this header follows the file
+/// header and is followed by `count` [`SectionEntry`] records. Sections are
+/// then laid out in table order, each starting on an 8-byte boundary.
 ///
 /// # Layout
 ///
 /// Total size: 16 bytes
 /// Alignment: 4 bytes
 ///
-/// | Offset | Size | Field    | Description                      |
-/// |--------|------|----------|----------------------------------|
-/// | 0      | 4    | magic    | "META" = [0x4D, 0x45, 0x54, 0x41]|
-/// | 4      | 2    | version  | Section format version (1)       |
-/// | 6      | 1    | format   | Serialization format (1=Postcard)|
-/// | 7      | 1    | reserved | Reserved for future use (0)      |
-/// | 8      | 4    | size     | Size of serialized metadata      |
-/// | 12     | 4    | crc      | CRC32 of serialized metadata     |
-///
-/// # Thread Safety
-///
-/// This type is `Send + Sync` as it is a POD struct.
+/// | Offset | Size | Field    | Description                       |
+/// |--------|------|----------|-----------------------------------|
+/// | 0      | 4    | magic    | "ETOC" = [0x45, 0x54, 0x4F, 0x43] |
+/// | 4      | 2    | version  | Table format version (1)          |
+/// | 6      | 2    | count    | Number of entries                 |
+/// | 8      | 4    | crc      | CRC32 of the entries              |
+/// | 12     | 4    | reserved | Reserved for future use (0)       |
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 #[repr(C)]
-pub struct MetadataSectionHeader {
-    /// Magic number: "META" = [0x4D, 0x45, 0x54, 0x41]
+pub struct SectionTableHeader {
+    /// Magic number: "ETOC" = [0x45, 0x54, 0x4F, 0x43]
     pub magic: [u8; 4],
 
-    /// Section format version (currently 1)
+    /// Table format version (currently 1)
     pub version: u16,
 
-    /// Serialization format: 1=Postcard, 2=JSON
-    pub format: u8,
-
-    /// Reserved for future use (must be 0)
-    pub reserved: u8,
-
-    /// Size of serialized metadata in bytes
-    pub size: u32,
+    /// Number of entries following the header
+    pub count: u16,
 
-    /// CRC32 of serialized metadata bytes
+    /// CRC32 of the entries
     pub crc: u32,
-}
 
-// Static assertions for MetadataSectionHeader size and alignment
-const _: () = assert!(size_of::<MetadataSectionHeader>() == 16);
-const _: () = assert!(align_of::<MetadataSectionHeader>() == 4);
+    /// Reserved for future use (must be 0)
+    pub reserved: u32,
+}
 
-impl MetadataSectionHeader {
-    /// The expected magic bytes "META".
-    pub const MAGIC: [u8; 4] = METADATA_MAGIC;
+// Static assertions for SectionTableHeader size and alignment
+const _: () = assert!(size_of::<SectionTableHeader>() == 16);
+const _: () = assert!(align_of::<SectionTableHeader>() == 4);
 
-    /// The current section version.
-    pub const VERSION: u16 = METADATA_VERSION;
+impl SectionTableHeader {
+    /// The expected magic bytes "ETOC".
+    pub const MAGIC: [u8; 4] = SECTION_TABLE_MAGIC;
 
-    /// Postcard serialization format identifier.
-    pub const FORMAT_POSTCARD: u8 = FORMAT_POSTCARD;
+    /// The current table version.
+    pub const VERSION: u16 = SECTION_TABLE_VERSION;
 
-    /// JSON serialization format identifier.
-    pub const FORMAT_JSON: u8 = FORMAT_JSON;
+    /// Size of the table header in bytes.
+    pub const SIZE: usize = 16;
 
-    /// Creates a new `MetadataSectionHeader` for Postcard-serialized metadata.
+    /// Creates a header for `count` entries whose bytes hash to `crc`.
     #[must_use]
-    pub fn new_postcard(size: u32, crc: u32) -> Self {
+    pub fn new(count: u16, crc: u32) -> Self {
         Self {
             magic: Self::MAGIC,
             version: Self::VERSION,
-            format: Self::FORMAT_POSTCARD,
-            reserved: 0,
-            size,
+            count,
             crc,
-        }
-    }
-
-    /// Creates a new `MetadataSectionHeader` for JSON-serialized metadata.
-    #[must_use]
-    pub fn new_json(size: u32, crc: u32) -> Self {
-        Self {
-            magic: Self::MAGIC,
-            version: Self::VERSION,
-            format: Self::FORMAT_JSON,
             reserved: 0,
-            size,
-            crc,
         }
     }
 
@@ -197,94 +1356,119 @@ impl MetadataSectionHeader {
         bytemuck::cast_ref(self)
     }
 
-    /// Parses a `MetadataSectionHeader` from bytes.
-    ///
-    /// # Requirements
-    ///
-    /// - `bytes` must be at least 16 bytes
+    /// Parses a `SectionTableHeader` from (possibly unaligned) bytes.
     ///
     /// # Errors
     ///
-    /// Returns `Err` if:
-    /// - Buffer is less than 16 bytes
-    /// - Magic number is invalid
-    /// - Version is unsupported
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MetadataHeaderError> {
-        if bytes.len() < 16 {
-            return Err(MetadataHeaderError::BufferTooShort(bytes.len()));
+    /// Returns `Err` if the buffer is too short, or the magic or version is
+    /// not recognized.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SectionError> {
+        if bytes.len() < Self::SIZE {
+            return Err(SectionError::BufferTooShort {
+                expected: Self::SIZE,
+                actual: bytes.len(),
+            });
         }
 
-        // Copy to aligned buffer to avoid alignment issues
-        // This is necessary because the metadata section may not start at an aligned offset
-        let mut aligned_buf = [0u8; 16];
-        aligned_buf.copy_from_slice(&bytes[..16]);
-        let header = *bytemuck::from_bytes::<MetadataSectionHeader>(&aligned_buf);
+        let header: Self = bytemuck::pod_read_unaligned(&bytes[..Self::SIZE]);
 
-        header.validate_magic()?;
-        header.validate_version()?;
-        header.validate_format()?;
+        if header.magic != Self::MAGIC {
+            return Err(SectionError::InvalidMagic {
+                expected: Self::MAGIC,
+                actual: header.magic,
+            });
+        }
+        if header.version > Self::VERSION {
+            return Err(SectionError::UnsupportedVersion(header.version));
+        }
 
         Ok(header)
     }
 
-    /// Validates the magic bytes.
-    ///
-    /// # Errors
-    ///
-    /// Returns `MetadataHeaderError::InvalidMagic` if magic bytes don't match "META".
-    pub fn validate_magic(&self) -> Result<(), MetadataHeaderError> {
-        if self.magic != Self::MAGIC {
-            return Err(MetadataHeaderError::InvalidMagic(self.magic));
-        }
-        Ok(())
+    /// Size of the whole table (header and entries) in bytes.
+    #[must_use]
+    pub fn table_size(&self) -> usize {
+        Self::SIZE + usize::from(self.count) * SectionEntry::SIZE
     }
+}
 
-    /// Validates the version is supported.
-    ///
-    /// # Errors
-    ///
-    /// Returns `MetadataHeaderError::UnsupportedVersion` if version is newer than current.
-    pub fn validate_version(&self) -> Result<(), MetadataHeaderError> {
-        if self.version > Self::VERSION {
-            return Err(MetadataHeaderError::UnsupportedVersion(self.version));
-        }
-        Ok(())
-    }
+/// One entry of the section table (24 bytes, v0.5+).
+///
+/// # Layout
+///
+/// Total size: 24 bytes
+/// Alignment: 8 bytes
+///
+/// | Offset | Size | Field  | Description                             |
+/// |--------|------|--------|-----------------------------------------|
+/// | 0      | 2    | kind   | [`SectionKind`]                         |
+/// | 2      | 2    | flags  | Bit 0: stored as a compressed section   |
+/// | 4      | 4    | crc    | CRC32 of the stored bytes               |
+/// | 8      | 8    | offset | Absolute file offset of the section     |
+/// | 16     | 8    | length | Stored length of the section in bytes   |
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Pod, Zeroable)]
+#[repr(C)]
+pub struct SectionEntry {
+    /// Section type, see [`SectionKind`]
+    pub kind: u16,
 
-    /// Validates the serialization format is supported.
-    ///
-    /// # Errors
-    ///
-    /// Returns `MetadataHeaderError::UnsupportedFormat` if format is not 1 (Postcard) or 2 (JSON).
-    pub fn validate_format(&self) -> Result<(), MetadataHeaderError> {
-        if self.format != Self::FORMAT_POSTCARD && self.format != Self::FORMAT_JSON {
-            return Err(MetadataHeaderError::UnsupportedFormat(self.format));
-        }
-        Ok(())
+    /// Entry flags (`SectionEntry::COMPRESSED`)
+    pub flags: u16,
+
+    /// CRC32 of the stored (possibly compressed) bytes
+    pub crc: u32,
+
+    /// Absolute file offset of the section
+    pub offset: u64,
+
+    /// Stored length of the section in bytes
+    pub length: u64,
+}
+
+// Static assertions for SectionEntry size and alignment
+const _: () = assert!(size_of::<SectionEntry>() == 24);
+const _: () = assert!(align_of::<SectionEntry>() == 8);
+
+impl SectionEntry {
+    /// Size of an entry in bytes.
+    pub const SIZE: usize = 24;
+
+    /// The section is stored as a [`CompressedSectionHeader`] section.
+    pub const COMPRESSED: u16 = 1 << 0;
+
+    /// Returns the section type.
+    #[must_use]
+    pub fn kind(&self) -> SectionKind {
+        SectionKind(self.kind)
     }
 
-    /// Returns true if this header uses Postcard serialization.
+    /// Returns true if the section is stored compressed.
     #[must_use]
-    pub fn is_postcard(&self) -> bool {
-        self.format == Self::FORMAT_POSTCARD
+    pub fn is_compressed(&self) -> bool {
+        self.flags & Self::COMPRESSED != 0
     }
 
-    /// Returns true if this header uses JSON serialization.
+    /// Returns the byte representation of the entry.
     #[must_use]
-    pub fn is_json(&self) -> bool {
-        self.format == Self::FORMAT_JSON
+    pub fn as_bytes(&self) -> &[u8; 24] {
+        bytemuck::cast_ref(self)
     }
 }
 
-/// Errors that can occur during metadata header parsing.
+/// Errors that can occur while parsing a snapshot section (v0.5+).
 #[derive(Debug, Error, PartialEq, Eq)]
-pub enum MetadataHeaderError {
+pub enum SectionError {
     /// Invalid magic number.
-    #[error("invalid metadata magic: expected 'META', got {0:?}")]
-    InvalidMagic([u8; 4]),
+    #[error("invalid section magic: expected {expected:?}, got {actual:?}")]
+    InvalidMagic {
+        /// Expected magic bytes
+        expected: [u8; 4],
+        /// Actual magic bytes
+        actual: [u8; 4],
+    },
 
     /// Unsupported version.
-    #[error("unsupported metadata version: {0}")]
+    #[error("unsupported section version: {0}")]
     UnsupportedVersion(u16),
 
     /// Unsupported serialization format.
@@ -292,23 +1476,28 @@ pub enum MetadataHeaderError {
     UnsupportedFormat(u8),
 
     /// Buffer too short.
-    #[error("buffer too short: expected 16 bytes, got {0}")]
-    BufferTooShort(usize),
-
-    /// Buffer is not 4-byte aligned.
-    #[error("buffer is not 4-byte aligned")]
-    UnalignedBuffer,
+    #[error("buffer too short: expected {expected} bytes, got {actual}")]
+    BufferTooShort {
+        /// Required size in bytes
+        expected: usize,
+        /// Actual size in bytes
+        actual: usize,
+    },
 
     /// CRC mismatch.
     #[error("CRC mismatch: expected {expected:#x}, got {actual:#x}")]
     CrcMismatch {
-        /// Expected CRC (from header)
+        /// Expected CRC (from section)
         expected: u32,
         /// Actual calculated CRC
         actual: u32,
     },
 }
 
+/// Former name of [`SectionError`], from when only the config section
+/// could fail to parse.
+pub type ConfigSectionError = SectionError;
+
 /// Errors that can occur during header parsing.
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum HeaderError {
@@ -473,11 +1662,71 @@ impl FileHeader {
         self.version_minor >= 4
     }
 
+    /// Returns true if this header is followed by a `ConfigSection` (v0.5+).
+    #[must_use]
+    pub fn supports_config(&self) -> bool {
+        self.version_minor >= 5
+    }
+
     /// Returns true if the HAS_METADATA flag is set.
     #[must_use]
     pub fn has_metadata(&self) -> bool {
         self.flags & Flags::HAS_METADATA != 0
     }
+
+    /// Returns true if the HAS_EXTERNAL_IDS flag is set.
+    #[must_use]
+    pub fn has_external_ids(&self) -> bool {
+        self.flags & Flags::HAS_EXTERNAL_IDS != 0
+    }
+
+    /// Returns true if the FLAT_INDEX flag is set.
+    #[must_use]
+    pub fn is_flat_index(&self) -> bool {
+        self.flags & Flags::FLAT_INDEX != 0
+    }
+
+    /// Returns true if the QUANTIZED flag is set.
+    #[must_use]
+    pub fn is_quantized(&self) -> bool {
+        self.flags & Flags::QUANTIZED != 0
+    }
+
+    /// Returns true if the DUAL_STORAGE flag is set.
+    #[must_use]
+    pub fn is_dual_storage(&self) -> bool {
+        self.flags & Flags::DUAL_STORAGE != 0
+    }
+
+    /// Returns true if the HAS_BQ flag is set.
+    #[must_use]
+    pub fn has_bq(&self) -> bool {
+        self.flags & Flags::HAS_BQ != 0
+    }
+
+    /// Returns true if the HAS_PQ flag is set.
+    #[must_use]
+    pub fn has_pq(&self) -> bool {
+        self.flags & Flags::HAS_PQ != 0
+    }
+
+    /// Returns true if the HAS_CHECKPOINT flag is set.
+    #[must_use]
+    pub fn has_checkpoint(&self) -> bool {
+        self.flags & Flags::HAS_CHECKPOINT != 0
+    }
+
+    /// Returns true if the COMPRESSED flag is set.
+    #[must_use]
+    pub fn is_compressed(&self) -> bool {
+        self.flags & Flags::COMPRESSED != 0
+    }
+
+    /// Returns true if the HAS_SECTION_TABLE flag is set.
+    #[must_use]
+    pub fn has_section_table(&self) -> bool {
+        self.flags & Flags::HAS_SECTION_TABLE != 0
+    }
 }
 
 #[cfg(test)]
@@ -638,11 +1887,225 @@ mod tests {
         ));
     }
 
+    // =========================================================================
+    // ConfigSection tests
+    // =========================================================================
+
+    #[test]
+    fn test_config_section_layout() {
+        assert_eq!(size_of::<ConfigSection>(), ConfigSection::SIZE);
+        assert_eq!(align_of::<ConfigSection>(), 8);
+    }
+
+    #[test]
+    fn test_config_section_roundtrip() {
+        let mut config = HnswConfig::new(96);
+        config.m = 20;
+        config.m0 = 40;
+        config.ef_construction = 321;
+        config.ef_search = 77;
+        config.metric = HnswConfig::METRIC_COSINE;
+
+        let section = ConfigSection::new(&config, 0.25);
+
+        // Parse from an unaligned offset, as happens in real snapshots
+        let mut buf = vec![0u8; 1];
+        buf.extend_from_slice(section.as_bytes());
+        let decoded = ConfigSection::from_bytes(&buf[1..]).unwrap();
+
+        assert_eq!(decoded.to_config(), config);
+        assert!((decoded.compaction_threshold - 0.25).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_config_section_crc_mismatch() {
+        let section = ConfigSection::new(&HnswConfig::new(8), 0.3);
+        let mut bytes = *section.as_bytes();
+        bytes[28] ^= 0x01; // Flip a bit in the metric field
+
+        let result = ConfigSection::from_bytes(&bytes);
+        assert!(matches!(result, Err(SectionError::CrcMismatch { .. })));
+    }
+
+    #[test]
+    fn test_config_section_invalid_magic() {
+        let mut section = ConfigSection::new(&HnswConfig::new(8), 0.3);
+        section.magic = *b"XXXX";
+
+        let result = ConfigSection::from_bytes(section.as_bytes());
+        assert!(matches!(result, Err(SectionError::InvalidMagic { .. })));
+    }
+
+    #[test]
+    fn test_config_section_buffer_too_short() {
+        let result = ConfigSection::from_bytes(&[0u8; 20]);
+        assert!(matches!(
+            result,
+            Err(SectionError::BufferTooShort {
+                expected: 48,
+                actual: 20
+            })
+        ));
+    }
+
+    #[test]
+    fn test_external_id_header_roundtrip() {
+        let header = ExternalIdSectionHeader::new_postcard(99, 0xABCD_0123);
+
+        let mut buf = vec![0u8; 3];
+        buf.extend_from_slice(header.as_bytes());
+        let decoded = ExternalIdSectionHeader::from_bytes(&buf[3..]).unwrap();
+
+        assert_eq!(decoded.magic, *b"EXID");
+        assert_eq!(decoded.size, 99);
+        assert_eq!(decoded.crc, 0xABCD_0123);
+    }
+
+    #[test]
+    fn test_external_id_header_rejects_metadata_magic() {
+        let meta = MetadataSectionHeader::new_postcard(0, 0);
+        let result = ExternalIdSectionHeader::from_bytes(meta.as_bytes());
+        assert!(matches!(result, Err(SectionError::InvalidMagic { .. })));
+    }
+
+    #[test]
+    fn test_bq_header_roundtrip() {
+        let header = BqSectionHeader::new(4096, 0x1234_5678);
+
+        let mut buf = vec![0u8; 5];
+        buf.extend_from_slice(header.as_bytes());
+        let decoded = BqSectionHeader::from_bytes(&buf[5..]).unwrap();
+
+        assert_eq!(decoded.magic, *b"BQVS");
+        assert_eq!(decoded.size, 4096);
+        assert_eq!(decoded.crc, 0x1234_5678);
+    }
+
+    #[test]
+    fn test_bq_header_rejects_external_id_magic() {
+        let exid = ExternalIdSectionHeader::new_postcard(0, 0);
+        let result = BqSectionHeader::from_bytes(exid.as_bytes());
+        assert!(matches!(result, Err(SectionError::InvalidMagic { .. })));
+    }
+
+    #[test]
+    fn test_pq_header_roundtrip() {
+        let header = PqSectionHeader::new(2048, 0x0BAD_F00D);
+
+        let mut buf = vec![0u8; 3];
+        buf.extend_from_slice(header.as_bytes());
+        let decoded = PqSectionHeader::from_bytes(&buf[3..]).unwrap();
+
+        assert_eq!(decoded.magic, *b"PQCB");
+        assert_eq!(decoded.size, 2048);
+        assert_eq!(decoded.crc, 0x0BAD_F00D);
+        assert!(matches!(
+            BqSectionHeader::from_bytes(header.as_bytes()),
+            Err(SectionError::InvalidMagic { .. })
+        ));
+    }
+
+    #[test]
+    fn test_quantizer_header_roundtrip() {
+        let header = QuantizerSectionHeader::new_postcard(8, 0xDEAD_BEEF);
+
+        let mut buf = vec![0u8; 1];
+        buf.extend_from_slice(header.as_bytes());
+        let decoded = QuantizerSectionHeader::from_bytes(&buf[1..]).unwrap();
+
+        assert_eq!(decoded.magic, *b"SQ8Q");
+        assert_eq!(decoded.version, 1);
+        assert!(!decoded.has_ranges());
+        assert_eq!(decoded.size, 8);
+        assert_eq!(decoded.crc, 0xDEAD_BEEF);
+
+        let ranges = QuantizerSectionHeader::new_postcard_ranges(64, 1);
+        let decoded = QuantizerSectionHeader::from_bytes(ranges.as_bytes()).unwrap();
+        assert_eq!(decoded.version, QUANTIZER_VERSION);
+        assert!(decoded.has_ranges());
+
+        let mut future = ranges;
+        future.version = QUANTIZER_VERSION + 1;
+        assert!(matches!(
+            QuantizerSectionHeader::from_bytes(future.as_bytes()),
+            Err(SectionError::UnsupportedVersion(_))
+        ));
+    }
+
+    #[test]
+    fn test_checkpoint_section_roundtrip() {
+        let section = CheckpointSection::new(0x0123_4567_89AB);
+
+        let mut buf = vec![0u8; 3];
+        buf.extend_from_slice(section.as_bytes());
+        let decoded = CheckpointSection::from_bytes(&buf[3..]).unwrap();
+        assert_eq!(decoded.wal_sequence, 0x0123_4567_89AB);
+
+        let mut bytes = *section.as_bytes();
+        bytes[8] ^= 0x01;
+        let result = CheckpointSection::from_bytes(&bytes);
+        assert!(matches!(result, Err(SectionError::CrcMismatch { .. })));
+    }
+
+    #[test]
+    fn test_compressed_section_header_roundtrip() {
+        let header = CompressedSectionHeader::new_lz4(4096, 512, 0xDEAD_BEEF);
+
+        let mut buf = vec![0u8; 1];
+        buf.extend_from_slice(header.as_bytes());
+        let decoded = CompressedSectionHeader::from_bytes(&buf[1..]).unwrap();
+        assert_eq!(decoded.raw_size, 4096);
+        assert_eq!(decoded.size, 512);
+        assert_eq!(decoded.crc, 0xDEAD_BEEF);
+
+        let mut bytes = *header.as_bytes();
+        bytes[6] = 9;
+        let result = CompressedSectionHeader::from_bytes(&bytes);
+        assert_eq!(result.unwrap_err(), SectionError::UnsupportedFormat(9));
+    }
+
+    #[test]
+    fn test_section_table_header_roundtrip() {
+        let header = SectionTableHeader::new(3, 0x1234_5678);
+        assert_eq!(header.table_size(), 16 + 3 * 24);
+
+        let mut buf = vec![0u8; 1];
+        buf.extend_from_slice(header.as_bytes());
+        let decoded = SectionTableHeader::from_bytes(&buf[1..]).unwrap();
+        assert_eq!(decoded.count, 3);
+        assert_eq!(decoded.crc, 0x1234_5678);
+
+        let mut bytes = *header.as_bytes();
+        bytes[4] = 2;
+        let result = SectionTableHeader::from_bytes(&bytes);
+        assert_eq!(result.unwrap_err(), SectionError::UnsupportedVersion(2));
+
+        let entry = SectionEntry {
+            kind: SectionKind::METADATA.0,
+            flags: SectionEntry::COMPRESSED,
+            crc: 7,
+            offset: 128,
+            length: 40,
+        };
+        let decoded: SectionEntry = bytemuck::pod_read_unaligned(entry.as_bytes());
+        assert_eq!(decoded, entry);
+        assert_eq!(decoded.kind(), SectionKind::METADATA);
+        assert!(decoded.is_compressed());
+        assert_eq!(SectionKind(99).name(), "unknown");
+    }
+
     #[test]
     fn test_flags_constants() {
         assert_eq!(Flags::COMPRESSED, 0b0001);
         assert_eq!(Flags::QUANTIZED, 0b0010);
         assert_eq!(Flags::HAS_METADATA, 0b0100);
+        assert_eq!(Flags::HAS_EXTERNAL_IDS, 0b1000);
+        assert_eq!(Flags::FLAT_INDEX, 0b1_0000);
+        assert_eq!(Flags::HAS_BQ, 0b10_0000);
+        assert_eq!(Flags::HAS_CHECKPOINT, 0b100_0000);
+        assert_eq!(Flags::HAS_SECTION_TABLE, 0b1000_0000);
+        assert_eq!(Flags::HAS_PQ, 0b1_0000_0000);
+        assert_eq!(Flags::DUAL_STORAGE, 0b10_0000_0000);
 
         // Flags should be combinable
         let combined = Flags::COMPRESSED | Flags::HAS_METADATA;
@@ -4,30 +4,56 @@
 
 /// Storage chunking logic.
 pub mod chunking;
+/// Snapshot section compression.
+pub mod compression;
+/// Encryption at rest.
+#[cfg(feature = "encryption")]
+pub mod encryption;
 /// WAL entry definitions.
 pub mod entry;
 /// File header definitions.
 pub mod header;
 /// Persistence reader.
 pub mod reader;
+/// Index-level WAL replay and checkpointing.
+pub mod replay;
+/// Snapshot section table.
+pub mod sections;
 /// Snapshot management.
 pub mod snapshot;
 /// Storage backend.
 pub mod storage;
+/// Offline snapshot verification.
+pub mod verify;
 /// Write-Ahead Log implementation.
 pub mod wal;
 /// Persistence writer.
 pub mod writer;
 
 pub use chunking::{ChunkedWriter, MIN_CHUNK_SIZE};
+pub use compression::Compression;
 pub use header::{
-    FileHeader, Flags, HeaderError, MetadataHeaderError, MetadataSectionHeader, FORMAT_JSON,
-    FORMAT_POSTCARD, MAGIC, METADATA_MAGIC, METADATA_VERSION, VERSION_MAJOR, VERSION_MINOR,
-    VERSION_MINOR_MIN,
+    BqSectionHeader, CheckpointSection, CompressedSectionHeader, ConfigSection, ConfigSectionError,
+    ExternalIdSectionHeader, FileHeader, Flags, HeaderError, MetadataHeaderError,
+    MetadataSectionHeader, PqSectionHeader, QuantizerSectionHeader, SectionEntry, SectionError,
+    SectionKind, SectionTableHeader, BQ_MAGIC, BQ_VERSION, CHECKPOINT_MAGIC, CHECKPOINT_VERSION,
+    CODEC_LZ4, COMPRESSED_MAGIC, COMPRESSED_VERSION, CONFIG_MAGIC, CONFIG_VERSION,
+    EXTERNAL_IDS_MAGIC, EXTERNAL_IDS_VERSION, FORMAT_JSON, FORMAT_POSTCARD, MAGIC, METADATA_MAGIC,
+    METADATA_VERSION, PQ_MAGIC, PQ_VERSION, QUANTIZER_MAGIC, QUANTIZER_VERSION,
+    SECTION_TABLE_MAGIC, SECTION_TABLE_VERSION, VERSION_MAJOR, VERSION_MINOR, VERSION_MINOR_MIN,
 };
 pub use reader::{read_file_header, read_index_header};
-pub use snapshot::{read_snapshot, write_snapshot};
-pub use storage::{MemoryBackend, StorageBackend};
+pub use replay::{
+    checkpoint, recover_flat_index, recover_index, recover_index_with_report, replay_flat_wal,
+    replay_wal,
+};
+pub use sections::{read_section, read_section_table, SectionTable};
+pub use snapshot::{
+    read_flat_snapshot, read_snapshot, read_snapshot_metadata, write_flat_snapshot,
+    write_flat_snapshot_compressed, write_snapshot, write_snapshot_compressed,
+};
+pub use storage::{MemoryBackend, SnapshotWriter, StorageBackend};
+pub use verify::{verify_snapshot, VerifyIssue, VerifyReport};
 pub use writer::write_empty_index;
 
 use thiserror::Error;
@@ -85,4 +111,15 @@ pub enum PersistenceError {
     /// Component not initialized.
     #[error("not initialized")]
     NotInitialized,
+
+    /// Encrypted data was written with a different secret.
+    #[error("wrong encryption key")]
+    WrongKey,
+
+    /// Encrypted data failed authentication (modified or corrupted).
+    #[error("encrypted data failed authentication at offset {offset}")]
+    Tampered {
+        /// Offset of the rejected frame in the stored bytes.
+        offset: usize,
+    },
 }
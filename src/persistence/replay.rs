@@ -0,0 +1,669 @@
+//! Index-level WAL replay and checkpointing.
+//!
+//! `VectorStorage::recover` only rebuilds raw vectors. The functions here
+//! replay every record type on top of a loaded snapshot, so the `HnswIndex`
+//! graph, its tombstones, its `MetadataStore` and its BQ storage come back
+//! consistent with the log. [`replay_flat_wal`] and [`recover_flat_index`]
+//! do the same for a [`FlatIndex`](crate::flat::FlatIndex).
+//!
+//! # Checkpoints
+//!
+//! A snapshot records the WAL sequence it was taken at (its checkpoint).
+//! Replay skips records below it, and [`checkpoint`] truncates them from
+//! the log once the snapshot is committed, so the WAL only grows with the
+//! changes made since the last snapshot.
+//!
+//! # Idempotence
+//!
+//! Snapshots without a checkpoint (written without a WAL, or by older
+//! versions) may already contain records replay sees. Each record is
+//! applied so that re-applying it is harmless:
+//!
+//! | Record | Already covered when |
+//! |--------|----------------------|
+//! | Insert | its ID is below the storage's next ID |
+//! | Soft delete | the vector is already deleted |
+//! | Hard delete | never: unlinking an unlinked node changes nothing |
+//! | Metadata set/delete, update | the vector is deleted (skipped) |
+//! | External ID binding | the vector is deleted, or the binding exists |
+//! | Enable BQ | BQ is already enabled |
+//! | Storage conversion | the storage already has the target type |
+//!
+//! IDs are positional, so a log spanning a `compact()` cannot be replayed.
+//! Save a snapshot and start a fresh log after compacting.
+
+use super::entry::WalEntry;
+use super::storage::StorageBackend;
+use super::wal::{RecoveryMode, RecoveryReport, RecoveryScan, WalAppender, WalError};
+use super::{read_flat_snapshot, read_snapshot, write_snapshot, PersistenceError};
+use crate::flat::FlatIndex;
+use crate::hnsw::{ExternalId, HnswIndex, VectorId};
+use crate::metadata::{MetadataValue, PostcardValue};
+use crate::storage::{decode_f32_payload, decode_storage_type_payload, VectorStorage};
+
+/// Encodes a `SOFT_DELETE` or `HARD_DELETE` payload.
+pub(crate) fn soft_delete_payload(vector_id: VectorId) -> [u8; 8] {
+    vector_id.0.to_le_bytes()
+}
+
+/// Encodes a `METADATA_SET` payload.
+pub(crate) fn metadata_set_payload(
+    vector_id: VectorId,
+    key: &str,
+    value: &MetadataValue,
+) -> Result<Vec<u8>, String> {
+    postcard::to_allocvec(&(vector_id.0, key, PostcardValue::from(value)))
+        .map_err(|e| e.to_string())
+}
+
+/// Encodes an `EXTERNAL_ID_SET` payload.
+pub(crate) fn external_id_payload(
+    vector_id: VectorId,
+    external_id: &ExternalId,
+) -> Result<Vec<u8>, String> {
+    postcard::to_allocvec(&(vector_id.0, external_id)).map_err(|e| e.to_string())
+}
+
+/// Encodes a `METADATA_DELETE` payload.
+pub(crate) fn metadata_delete_payload(vector_id: VectorId, key: &str) -> Result<Vec<u8>, String> {
+    postcard::to_allocvec(&(vector_id.0, key)).map_err(|e| e.to_string())
+}
+
+/// Replays a WAL on top of an index and its storage.
+///
+/// Records below the storage's checkpoint (restored from its snapshot) are
+/// skipped. The WAL appenders of the storage and the index (if any) are
+/// detached while replaying, so replayed records are not logged a second time.
+///
+/// An unreadable record ends replay at the valid prefix, as in
+/// `VectorStorage::recover_with_report`; `mode` decides whether damage
+/// followed by valid records is an error.
+///
+/// # Returns
+///
+/// A report of what was replayed and discarded. Its `next_sequence` is the
+/// sequence number to continue the log with (pass it to `WalAppender::new`).
+///
+/// # Errors
+///
+/// Returns `PersistenceError::Corrupted` if a record cannot be decoded or
+/// does not apply to the index (unknown vector, ID gap, unknown type), or
+/// in strict mode if the log is damaged before its last valid record.
+/// Returns `PersistenceError::Io` if the backend cannot be read.
+pub fn replay_wal(
+    index: &mut HnswIndex,
+    storage: &mut VectorStorage,
+    wal: &dyn StorageBackend,
+    mode: RecoveryMode,
+) -> Result<RecoveryReport, PersistenceError> {
+    let data = wal.read()?;
+
+    let appender = storage.wal.take();
+    let index_appender = index.wal.take();
+    let result = replay_entries(storage, &data, mode, |storage, entry, payload| {
+        apply_entry(index, storage, entry, payload)
+    });
+    storage.wal = appender;
+    index.wal = index_appender;
+    result
+}
+
+/// Replays a WAL on top of a [`FlatIndex`] and its storage.
+///
+/// Works like [`replay_wal`]. A flat index only logs inserts, soft deletes
+/// and metadata, so HNSW-only records (hard delete, external ID binding,
+/// enable BQ) are rejected.
+///
+/// # Errors
+///
+/// As [`replay_wal`].
+pub fn replay_flat_wal(
+    index: &mut FlatIndex,
+    storage: &mut VectorStorage,
+    wal: &dyn StorageBackend,
+    mode: RecoveryMode,
+) -> Result<RecoveryReport, PersistenceError> {
+    let data = wal.read()?;
+
+    let appender = storage.wal.take();
+    let result = replay_entries(storage, &data, mode, |storage, entry, payload| {
+        apply_flat_entry(index, storage, entry, payload)
+    });
+    storage.wal = appender;
+    result
+}
+
+/// Loads a snapshot and replays a WAL on top of it.
+///
+/// The returned storage and index share `wal` as their appender, continuing
+/// the log after the last replayed record, so later mutations keep being
+/// logged.
+/// Replay is lenient; use [`recover_index_with_report`] to inspect or
+/// reject a damaged log.
+///
+/// # Errors
+///
+/// Returns any error from [`read_snapshot`] or [`replay_wal`].
+///
+/// # Example
+///
+/// ```
+/// use edgevec::hnsw::{HnswConfig, HnswIndex};
+/// use edgevec::persistence::wal::WalAppender;
+/// use edgevec::persistence::{recover_index, write_snapshot, MemoryBackend};
+/// use edgevec::storage::VectorStorage;
+///
+/// let config = HnswConfig::new(4);
+/// let wal = MemoryBackend::new();
+/// let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+/// let mut index = HnswIndex::new(config, &storage).unwrap();
+///
+/// let mut snapshot = MemoryBackend::new();
+/// let a = index.insert(&[1.0, 0.0, 0.0, 0.0], &mut storage).unwrap();
+/// write_snapshot(&index, &storage, &mut snapshot).unwrap();
+///
+/// // Changes after the snapshot only live in the WAL
+/// index.insert(&[0.0, 1.0, 0.0, 0.0], &mut storage).unwrap();
+/// index.soft_delete_logged(a, &mut storage).unwrap();
+///
+/// let (recovered, _storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+/// assert_eq!(recovered.node_count(), 2);
+/// assert!(recovered.is_deleted(a).unwrap());
+/// ```
+pub fn recover_index(
+    snapshot: &dyn StorageBackend,
+    wal: Box<dyn StorageBackend>,
+) -> Result<(HnswIndex, VectorStorage), PersistenceError> {
+    recover_index_with_report(snapshot, wal, RecoveryMode::Lenient)
+        .map(|(index, storage, _report)| (index, storage))
+}
+
+/// Like [`recover_index`], but returns a [`RecoveryReport`] and lets the
+/// caller fail on a log damaged before its last valid record.
+///
+/// The discarded bytes are cut from the log before it is re-attached, so
+/// records appended afterwards are not hidden behind them. A log that
+/// strict mode rejects is left untouched for inspection.
+///
+/// # Errors
+///
+/// Returns any error from [`read_snapshot`] or [`replay_wal`].
+///
+/// # Example
+///
+/// ```
+/// use edgevec::hnsw::{HnswConfig, HnswIndex};
+/// use edgevec::persistence::wal::{RecoveryMode, WalAppender};
+/// use edgevec::persistence::{recover_index_with_report, write_snapshot, MemoryBackend};
+/// use edgevec::storage::VectorStorage;
+///
+/// let config = HnswConfig::new(4);
+/// let wal = MemoryBackend::new();
+/// let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+/// let mut index = HnswIndex::new(config, &storage).unwrap();
+///
+/// let mut snapshot = MemoryBackend::new();
+/// write_snapshot(&index, &storage, &mut snapshot).unwrap();
+/// index.insert(&[1.0, 0.0, 0.0, 0.0], &mut storage).unwrap();
+///
+/// let (_index, _storage, report) =
+///     recover_index_with_report(&snapshot, Box::new(wal), RecoveryMode::Strict).unwrap();
+/// assert!(report.is_clean());
+/// assert_eq!(report.entries_applied, 1);
+/// ```
+pub fn recover_index_with_report(
+    snapshot: &dyn StorageBackend,
+    wal: Box<dyn StorageBackend>,
+    mode: RecoveryMode,
+) -> Result<(HnswIndex, VectorStorage, RecoveryReport), PersistenceError> {
+    let (mut index, mut storage) = read_snapshot(snapshot)?;
+    let report = replay_wal(&mut index, &mut storage, wal.as_ref(), mode)?;
+    if let Some(offset) = report.first_bad_offset {
+        let data = wal.read()?;
+        wal.atomic_write("", &data[..offset])?;
+    }
+    storage.wal = Some(WalAppender::new(wal, report.next_sequence));
+    index.wal = storage.wal.clone();
+    Ok((index, storage, report))
+}
+
+/// Loads a [`FlatIndex`] snapshot and replays a WAL on top of it.
+///
+/// The flat counterpart of [`recover_index_with_report`]: the returned
+/// storage continues the log after the last replayed record.
+///
+/// # Errors
+///
+/// Returns any error from [`read_flat_snapshot`] or [`replay_flat_wal`].
+///
+/// # Example
+///
+/// ```
+/// use edgevec::flat::FlatIndex;
+/// use edgevec::hnsw::HnswConfig;
+/// use edgevec::persistence::wal::{RecoveryMode, WalAppender};
+/// use edgevec::persistence::{recover_flat_index, write_flat_snapshot, MemoryBackend};
+/// use edgevec::storage::VectorStorage;
+///
+/// let config = HnswConfig::new(2);
+/// let wal = MemoryBackend::new();
+/// let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+/// let mut index = FlatIndex::new(config, &storage).unwrap();
+///
+/// let mut snapshot = MemoryBackend::new();
+/// let a = index.insert(&[1.0, 0.0], &mut storage).unwrap();
+/// write_flat_snapshot(&index, &storage, &mut snapshot).unwrap();
+/// index.soft_delete(a, &mut storage).unwrap();
+///
+/// let (recovered, _storage, _report) =
+///     recover_flat_index(&snapshot, Box::new(wal), RecoveryMode::Strict).unwrap();
+/// assert_eq!(recovered.deleted_count(), 1);
+/// ```
+pub fn recover_flat_index(
+    snapshot: &dyn StorageBackend,
+    wal: Box<dyn StorageBackend>,
+    mode: RecoveryMode,
+) -> Result<(FlatIndex, VectorStorage, RecoveryReport), PersistenceError> {
+    let (mut index, mut storage) = read_flat_snapshot(snapshot)?;
+    let report = replay_flat_wal(&mut index, &mut storage, wal.as_ref(), mode)?;
+    if let Some(offset) = report.first_bad_offset {
+        let data = wal.read()?;
+        wal.atomic_write("", &data[..offset])?;
+    }
+    storage.wal = Some(WalAppender::new(wal, report.next_sequence));
+    Ok((index, storage, report))
+}
+
+/// Writes a snapshot and truncates the WAL records it covers.
+///
+/// The snapshot records the storage's current WAL sequence as its
+/// checkpoint. Only after it is committed are the covered records dropped
+/// from the log, so a crash at any point leaves a snapshot and log that
+/// recover to the same state.
+///
+/// # Returns
+///
+/// The checkpoint sequence recorded in the snapshot.
+///
+/// # Errors
+///
+/// Returns any error from [`write_snapshot`], or `PersistenceError::Io` if
+/// the WAL cannot be truncated (the snapshot is still valid).
+///
+/// # Example
+///
+/// ```
+/// use edgevec::hnsw::{HnswConfig, HnswIndex};
+/// use edgevec::persistence::wal::WalAppender;
+/// use edgevec::persistence::{checkpoint, MemoryBackend, StorageBackend};
+/// use edgevec::storage::VectorStorage;
+///
+/// let config = HnswConfig::new(4);
+/// let wal = MemoryBackend::new();
+/// let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+/// let mut index = HnswIndex::new(config, &storage).unwrap();
+/// index.insert(&[1.0, 0.0, 0.0, 0.0], &mut storage).unwrap();
+///
+/// let mut snapshot = MemoryBackend::new();
+/// assert_eq!(checkpoint(&index, &mut storage, &mut snapshot).unwrap(), 1);
+/// assert!(wal.read().unwrap().is_empty());
+/// ```
+pub fn checkpoint(
+    index: &HnswIndex,
+    storage: &mut VectorStorage,
+    snapshot: &mut dyn StorageBackend,
+) -> Result<u64, PersistenceError> {
+    write_snapshot(index, storage, snapshot)?;
+
+    let sequence = storage.wal_sequence();
+    storage.wal_checkpoint = sequence;
+    if let Some(wal) = &mut storage.wal {
+        wal.truncate_before(sequence).map_err(|e| match e {
+            WalError::Persistence(e) => e,
+            WalError::Io(e) => PersistenceError::Io(e),
+            other => PersistenceError::Io(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                other.to_string(),
+            )),
+        })?;
+    }
+    Ok(sequence)
+}
+
+/// Applies every valid record in `data` with `apply`.
+fn replay_entries<F>(
+    storage: &mut VectorStorage,
+    data: &[u8],
+    mode: RecoveryMode,
+    mut apply: F,
+) -> Result<RecoveryReport, PersistenceError>
+where
+    F: FnMut(&mut VectorStorage, &WalEntry, &[u8]) -> Result<(), String>,
+{
+    let checkpoint = storage.wal_checkpoint;
+    let mut scan = RecoveryScan::new(data, mode);
+    scan.report.next_sequence = checkpoint;
+
+    while let Some((entry, payload)) = scan
+        .next_entry()
+        .map_err(|e| PersistenceError::Corrupted(format!("WAL read failed: {e}")))?
+    {
+        if entry.sequence < checkpoint {
+            scan.report.entries_skipped += 1;
+            continue;
+        }
+
+        apply(storage, &entry, &payload).map_err(|e| {
+            PersistenceError::Corrupted(format!(
+                "WAL entry {} (type {}): {e}",
+                entry.sequence, entry.entry_type
+            ))
+        })?;
+        scan.report.entries_applied += 1;
+        scan.report.next_sequence = entry.sequence + 1;
+    }
+
+    Ok(scan.report)
+}
+
+/// Applies a single record. Errors are plain messages; the caller adds the
+/// record's sequence and type.
+fn apply_entry(
+    index: &mut HnswIndex,
+    storage: &mut VectorStorage,
+    entry: &WalEntry,
+    payload: &[u8],
+) -> Result<(), String> {
+    match entry.entry_type {
+        WalEntry::INSERT => {
+            let (id, vector) =
+                decode_f32_payload(payload, &index.config).map_err(|e| e.to_string())?;
+            replay_insert(index, storage, id, &vector)
+        }
+        WalEntry::INSERT_QUANTIZED => {
+            let id = decode_id(payload)?;
+            let Some(quantizer) = &storage.quantizer else {
+                return Err("quantized insert into F32 storage".into());
+            };
+            let vector = quantizer.dequantize(&payload[8..]);
+            replay_insert(index, storage, id, &vector)
+        }
+        WalEntry::CHECKPOINT => Ok(()),
+        WalEntry::UPDATE => {
+            let (id, vector) =
+                decode_f32_payload(payload, &index.config).map_err(|e| e.to_string())?;
+            let id = VectorId(id);
+            if is_deleted(index, id)? {
+                return Ok(());
+            }
+            index
+                .update(id, &vector, storage)
+                .map_err(|e| e.to_string())
+        }
+        WalEntry::SOFT_DELETE => {
+            let id = VectorId(decode_id(payload)?);
+            index.soft_delete(id).map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        WalEntry::HARD_DELETE => {
+            let id = VectorId(decode_id(payload)?);
+            index.hard_delete(id, storage).map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        WalEntry::METADATA_SET => {
+            let (id, key, value): (u64, String, PostcardValue) =
+                postcard::from_bytes(payload).map_err(|e| e.to_string())?;
+            if metadata_skipped(index, VectorId(id)) {
+                return Ok(());
+            }
+            // MetadataStore is keyed by u32; see insert_with_metadata
+            #[allow(clippy::cast_possible_truncation)]
+            let metadata_id = id as u32;
+            index
+                .metadata_mut()
+                .insert(metadata_id, &key, MetadataValue::from(value))
+                .map_err(|e| e.to_string())
+        }
+        WalEntry::METADATA_DELETE => {
+            let (id, key): (u64, String) =
+                postcard::from_bytes(payload).map_err(|e| e.to_string())?;
+            if metadata_skipped(index, VectorId(id)) {
+                return Ok(());
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            let metadata_id = id as u32;
+            index
+                .metadata_mut()
+                .delete(metadata_id, &key)
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        WalEntry::EXTERNAL_ID_SET => {
+            let (id, external_id): (u64, ExternalId) =
+                postcard::from_bytes(payload).map_err(|e| e.to_string())?;
+            let id = VectorId(id);
+            if is_deleted(index, id)? {
+                return Ok(());
+            }
+            // Already bound (snapshot or earlier replay) is fine; a clash is not
+            match index.external_ids.vector_id(&external_id) {
+                Some(bound) if bound == id => Ok(()),
+                Some(bound) => Err(format!(
+                    "external ID {external_id:?} is bound to vector {}",
+                    bound.0
+                )),
+                None => {
+                    index.external_ids.insert(external_id, id);
+                    Ok(())
+                }
+            }
+        }
+        WalEntry::ENABLE_BQ => {
+            if index.has_bq() {
+                return Ok(());
+            }
+            index.enable_bq(storage).map_err(|e| e.to_string())
+        }
+        WalEntry::CONVERT_STORAGE => {
+            let target = decode_storage_type_payload(payload).map_err(|e| e.to_string())?;
+            storage.convert_to(target).map_err(|e| e.to_string())
+        }
+        other => Err(format!("unknown entry type {other}")),
+    }
+}
+
+/// Applies a single record to a flat index.
+fn apply_flat_entry(
+    index: &mut FlatIndex,
+    storage: &mut VectorStorage,
+    entry: &WalEntry,
+    payload: &[u8],
+) -> Result<(), String> {
+    match entry.entry_type {
+        WalEntry::INSERT => {
+            let (id, vector) =
+                decode_f32_payload(payload, &index.config).map_err(|e| e.to_string())?;
+            if insert_pending(storage, id)? {
+                index.insert(&vector, storage).map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        }
+        WalEntry::INSERT_QUANTIZED => {
+            let id = decode_id(payload)?;
+            let Some(quantizer) = &storage.quantizer else {
+                return Err("quantized insert into F32 storage".into());
+            };
+            let vector = quantizer.dequantize(&payload[8..]);
+            if insert_pending(storage, id)? {
+                index.insert(&vector, storage).map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        }
+        WalEntry::CHECKPOINT => Ok(()),
+        WalEntry::UPDATE => {
+            let (id, vector) =
+                decode_f32_payload(payload, &index.config).map_err(|e| e.to_string())?;
+            let id = VectorId(id);
+            if storage.is_deleted(id) {
+                return Ok(());
+            }
+            storage.update(id, &vector).map_err(|e| e.to_string())
+        }
+        WalEntry::SOFT_DELETE => {
+            let id = VectorId(decode_id(payload)?);
+            index.soft_delete(id, storage).map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        WalEntry::METADATA_SET => {
+            let (id, key, value): (u64, String, PostcardValue) =
+                postcard::from_bytes(payload).map_err(|e| e.to_string())?;
+            if storage.is_deleted(VectorId(id)) {
+                return Ok(());
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            let metadata_id = id as u32;
+            index
+                .metadata
+                .insert(metadata_id, &key, MetadataValue::from(value))
+                .map_err(|e| e.to_string())
+        }
+        WalEntry::METADATA_DELETE => {
+            let (id, key): (u64, String) =
+                postcard::from_bytes(payload).map_err(|e| e.to_string())?;
+            if storage.is_deleted(VectorId(id)) {
+                return Ok(());
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            let metadata_id = id as u32;
+            index
+                .metadata
+                .delete(metadata_id, &key)
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+        WalEntry::CONVERT_STORAGE => {
+            let target = decode_storage_type_payload(payload).map_err(|e| e.to_string())?;
+            storage.convert_to(target).map_err(|e| e.to_string())
+        }
+        WalEntry::HARD_DELETE | WalEntry::EXTERNAL_ID_SET | WalEntry::ENABLE_BQ => {
+            Err("record is not supported by FlatIndex".into())
+        }
+        other => Err(format!("unknown entry type {other}")),
+    }
+}
+
+/// Inserts a logged vector unless the snapshot already holds it.
+fn replay_insert(
+    index: &mut HnswIndex,
+    storage: &mut VectorStorage,
+    id: u64,
+    vector: &[f32],
+) -> Result<(), String> {
+    if insert_pending(storage, id)? {
+        index.insert(vector, storage).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Returns whether a logged insert of `id` still has to be applied.
+fn insert_pending(storage: &VectorStorage, id: u64) -> Result<bool, String> {
+    if id > storage.next_id {
+        return Err(format!(
+            "insert of vector {id} leaves a gap (next ID is {})",
+            storage.next_id
+        ));
+    }
+    Ok(id == storage.next_id)
+}
+
+fn decode_id(payload: &[u8]) -> Result<u64, String> {
+    let bytes: [u8; 8] = payload
+        .get(..8)
+        .and_then(|b| b.try_into().ok())
+        .ok_or("payload too short")?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Metadata of deleted vectors is dropped. `metadata_mut()` accepts any ID,
+/// so metadata of IDs the index has no vector for is applied as logged.
+fn metadata_skipped(index: &HnswIndex, id: VectorId) -> bool {
+    index.is_deleted(id).unwrap_or(false)
+}
+
+fn is_deleted(index: &HnswIndex, id: VectorId) -> Result<bool, String> {
+    index
+        .is_deleted(id)
+        .map_err(|e| format!("vector {}: {e}", id.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hnsw::HnswConfig;
+    use crate::persistence::MemoryBackend;
+
+    fn logged(config: &HnswConfig) -> (HnswIndex, VectorStorage, MemoryBackend) {
+        let wal = MemoryBackend::new();
+        let storage = VectorStorage::new(config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+        let index = HnswIndex::new(config.clone(), &storage).unwrap();
+        (index, storage, wal)
+    }
+
+    #[test]
+    fn test_replay_from_empty_index() {
+        let config = HnswConfig::new(4);
+        let (mut index, mut storage, wal) = logged(&config);
+        let a = index.insert(&[1.0, 0.0, 0.0, 0.0], &mut storage).unwrap();
+        index.insert(&[0.0, 1.0, 0.0, 0.0], &mut storage).unwrap();
+        index
+            .set_metadata_logged(a, "tag", MetadataValue::Integer(7), &mut storage)
+            .unwrap();
+
+        let mut fresh_storage = VectorStorage::new(&config, None);
+        let mut fresh = HnswIndex::new(config, &fresh_storage).unwrap();
+        let report =
+            replay_wal(&mut fresh, &mut fresh_storage, &wal, RecoveryMode::Strict).unwrap();
+
+        assert_eq!(report.next_sequence, 3);
+        assert_eq!(report.entries_applied, 3);
+        assert_eq!(fresh.node_count(), 2);
+        assert_eq!(fresh_storage.get_vector(a)[..], [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(
+            fresh.metadata().get(1, "tag"),
+            Some(&MetadataValue::Integer(7))
+        );
+    }
+
+    #[test]
+    fn test_replay_is_idempotent() {
+        let config = HnswConfig::new(4);
+        let (mut index, mut storage, wal) = logged(&config);
+        let a = index.insert(&[1.0, 0.0, 0.0, 0.0], &mut storage).unwrap();
+        index
+            .set_metadata_logged(a, "tag", MetadataValue::Integer(7), &mut storage)
+            .unwrap();
+        index.soft_delete_logged(a, &mut storage).unwrap();
+
+        // Replaying the whole log over the live index changes nothing
+        replay_wal(&mut index, &mut storage, &wal, RecoveryMode::Lenient).unwrap();
+        assert_eq!(index.node_count(), 1);
+        assert_eq!(index.deleted_count(), 1);
+        assert!(index.metadata().is_empty());
+    }
+
+    #[test]
+    fn test_replay_rejects_id_gap() {
+        let config = HnswConfig::new(2);
+        let wal = MemoryBackend::new();
+        let mut appender = WalAppender::new(Box::new(wal.clone()), 0);
+        let mut payload = 5u64.to_le_bytes().to_vec();
+        payload.extend_from_slice(&1.0f32.to_le_bytes());
+        payload.extend_from_slice(&2.0f32.to_le_bytes());
+        appender.append(WalEntry::INSERT, &payload).unwrap();
+
+        let mut storage = VectorStorage::new(&config, None);
+        let mut index = HnswIndex::new(config, &storage).unwrap();
+        let err = replay_wal(&mut index, &mut storage, &wal, RecoveryMode::Lenient).unwrap_err();
+        assert!(matches!(err, PersistenceError::Corrupted(_)));
+    }
+}
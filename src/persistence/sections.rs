@@ -0,0 +1,506 @@
+//! Snapshot section table.
+//!
+//! Snapshots written with [`Flags::HAS_SECTION_TABLE`] place a
+//! [`SectionTableHeader`] and one [`SectionEntry`] per section right after
+//! the file header. Each entry carries the section's [`SectionKind`], file
+//! offset, stored length, a CRC of the stored bytes and whether the section
+//! is compressed. Sections follow in table order, each aligned to 8 bytes:
+//!
+//! ```text
+//! | header (64) | table | vectors | nodes | neighbors | tombstones | config | optional... |
+//! ```
+//!
+//! Readers skip kinds they do not know, so new sections can be added without
+//! breaking older readers. [`read_section`] loads a single section without
+//! touching the rest of the file, checking only that section's CRC.
+//!
+//! [`Flags::HAS_SECTION_TABLE`]: crate::persistence::Flags::HAS_SECTION_TABLE
+
+use crate::persistence::compression::{compress_section, decompress_section, Compression};
+use crate::persistence::header::{
+    FileHeader, Flags, SectionEntry, SectionKind, SectionTableHeader,
+};
+use crate::persistence::storage::{read_full, read_header};
+use crate::persistence::{PersistenceError, SnapshotWriter, StorageBackend};
+use crc32fast::Hasher;
+use std::borrow::Cow;
+use std::io::Read;
+
+/// Sections start on multiples of this many bytes, so vectors and nodes can
+/// be borrowed straight from a mapped file.
+const SECTION_ALIGN: u64 = 8;
+
+/// A parsed and validated section table.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SectionTable {
+    entries: Vec<SectionEntry>,
+}
+
+impl SectionTable {
+    /// Returns the entries in file order.
+    #[must_use]
+    pub fn entries(&self) -> &[SectionEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry for `kind`, if the snapshot has that section.
+    #[must_use]
+    pub fn get(&self, kind: SectionKind) -> Option<&SectionEntry> {
+        self.entries.iter().find(|e| e.kind() == kind)
+    }
+
+    /// Size of the table (header and entries) in bytes.
+    #[must_use]
+    pub fn size(&self) -> usize {
+        SectionTableHeader::SIZE + self.entries.len() * SectionEntry::SIZE
+    }
+
+    /// Reads the table that follows the file header.
+    ///
+    /// Checks the table CRC and that sections appear in file order, start
+    /// after the table and do not overlap.
+    pub(crate) fn read(reader: &mut dyn Read) -> Result<Self, PersistenceError> {
+        let mut header_bytes = [0u8; SectionTableHeader::SIZE];
+        let n = read_full(reader, &mut header_bytes)?;
+        let header = SectionTableHeader::from_bytes(&header_bytes[..n])
+            .map_err(|e| PersistenceError::Corrupted(format!("Invalid section table: {e}")))?;
+
+        let mut entry_bytes = vec![0u8; usize::from(header.count) * SectionEntry::SIZE];
+        if read_full(reader, &mut entry_bytes)? < entry_bytes.len() {
+            return Err(PersistenceError::Corrupted(
+                "Section table extends beyond file".into(),
+            ));
+        }
+
+        let actual_crc = crc32fast::hash(&entry_bytes);
+        if actual_crc != header.crc {
+            return Err(PersistenceError::Corrupted(format!(
+                "Section table CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+                header.crc
+            )));
+        }
+
+        let table = Self {
+            entries: entry_bytes
+                .chunks_exact(SectionEntry::SIZE)
+                .map(bytemuck::pod_read_unaligned)
+                .collect(),
+        };
+        table.validate()?;
+        Ok(table)
+    }
+
+    fn validate(&self) -> Result<(), PersistenceError> {
+        let mut end = (64 + self.size()) as u64;
+        for (i, entry) in self.entries.iter().enumerate() {
+            if entry.offset < end {
+                return Err(PersistenceError::Corrupted(format!(
+                    "Section kind {} at offset {} overlaps the previous section",
+                    entry.kind, entry.offset
+                )));
+            }
+            end = entry.offset.checked_add(entry.length).ok_or_else(|| {
+                PersistenceError::Corrupted(format!("Section kind {} length overflows", entry.kind))
+            })?;
+            if self.entries[..i].iter().any(|e| e.kind == entry.kind) {
+                return Err(PersistenceError::Corrupted(format!(
+                    "Section kind {} is listed twice",
+                    entry.kind
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error if any section extends beyond `file_len` bytes.
+    pub(crate) fn check_bounds(&self, file_len: usize) -> Result<(), PersistenceError> {
+        match self.entries.last() {
+            Some(last) if last.offset + last.length > file_len as u64 => {
+                Err(PersistenceError::Corrupted(format!(
+                    "Section {} extends beyond file: ends at {}, file is {file_len} bytes",
+                    last.kind().name(),
+                    last.offset + last.length
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Serializes the table header and entries.
+    fn to_bytes(&self) -> Vec<u8> {
+        let entries: Vec<u8> = self
+            .entries
+            .iter()
+            .flat_map(|e| e.as_bytes().iter().copied())
+            .collect();
+        // Writers emit one entry per known kind, far below u16::MAX
+        #[allow(clippy::cast_possible_truncation)]
+        let header = SectionTableHeader::new(self.entries.len() as u16, crc32fast::hash(&entries));
+
+        let mut bytes = header.as_bytes().to_vec();
+        bytes.extend_from_slice(&entries);
+        bytes
+    }
+}
+
+/// Returns the section as it was before compression.
+///
+/// `stored` must be the section's bytes as found in the file.
+pub(crate) fn expand<'a>(
+    entry: &SectionEntry,
+    stored: Cow<'a, [u8]>,
+) -> Result<Cow<'a, [u8]>, PersistenceError> {
+    if entry.is_compressed() {
+        decompress_section(&stored, entry.kind().name()).map(Cow::Owned)
+    } else {
+        Ok(stored)
+    }
+}
+
+/// Reads the section table of a snapshot.
+///
+/// # Errors
+///
+/// Returns `PersistenceError::Unsupported` if the snapshot predates section
+/// tables, `Corrupted` if the table is invalid, or any I/O error.
+pub fn read_section_table(backend: &dyn StorageBackend) -> Result<SectionTable, PersistenceError> {
+    let mut reader = backend.open_reader()?;
+    read_table(&mut reader).map(|(_, table)| table)
+}
+
+/// Loads a single section of a snapshot, decompressed.
+///
+/// Only the file header, the section table and the section itself are
+/// checked; the rest of the file is not verified. Returns `Ok(None)` if the
+/// snapshot has no section of that kind.
+///
+/// # Errors
+///
+/// Returns `PersistenceError::Unsupported` if the snapshot predates section
+/// tables, `Corrupted` if the table or the section fails its CRC, or any I/O
+/// error.
+pub fn read_section(
+    backend: &dyn StorageBackend,
+    kind: SectionKind,
+) -> Result<Option<Vec<u8>>, PersistenceError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(file) = backend.mapped() {
+        let mut reader = &file[..];
+        let (_, table) = read_table(&mut reader)?;
+        table.check_bounds(file.len())?;
+        return table
+            .get(kind)
+            .map(|entry| {
+                // SAFETY: check_bounds keeps every section within the mapping.
+                #[allow(clippy::cast_possible_truncation)]
+                let (offset, length) = (entry.offset as usize, entry.length as usize);
+                checked_section(entry, Cow::Borrowed(&file[offset..offset + length]))
+            })
+            .transpose();
+    }
+
+    let mut reader = backend.open_reader()?;
+    let (_, table) = read_table(&mut reader)?;
+    let Some(entry) = table.get(kind) else {
+        return Ok(None);
+    };
+
+    skip(&mut reader, entry.offset - (64 + table.size()) as u64)?;
+    let mut stored = Vec::new();
+    reader.take(entry.length).read_to_end(&mut stored)?;
+    if (stored.len() as u64) < entry.length {
+        return Err(PersistenceError::Corrupted(format!(
+            "Section {} extends beyond file",
+            kind.name()
+        )));
+    }
+    checked_section(entry, Cow::Owned(stored)).map(Some)
+}
+
+/// Reads the file header and the section table that follows it.
+fn read_table(reader: &mut dyn Read) -> Result<(FileHeader, SectionTable), PersistenceError> {
+    let header = read_header(reader)?;
+    if !header.has_section_table() {
+        return Err(PersistenceError::Unsupported(
+            "snapshot has no section table; re-save it to enable partial loading".into(),
+        ));
+    }
+    Ok((header, SectionTable::read(reader)?))
+}
+
+/// Verifies the CRC of a stored section, then expands it.
+fn checked_section(
+    entry: &SectionEntry,
+    stored: Cow<'_, [u8]>,
+) -> Result<Vec<u8>, PersistenceError> {
+    let actual_crc = crc32fast::hash(&stored);
+    if actual_crc != entry.crc {
+        return Err(PersistenceError::Corrupted(format!(
+            "Section {} CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+            entry.kind().name(),
+            entry.crc
+        )));
+    }
+    expand(entry, stored).map(Cow::into_owned)
+}
+
+/// Reads and discards `count` bytes.
+pub(crate) fn skip(reader: &mut dyn Read, count: u64) -> Result<(), PersistenceError> {
+    std::io::copy(&mut reader.take(count), &mut std::io::sink())?;
+    Ok(())
+}
+
+/// Lays a snapshot stream out as sections behind a section table.
+///
+/// The caller feeds the bytes that follow the file header, in the order and
+/// sizes given by the plan. Sections are streamed straight through unless
+/// they are compressed, in which case they are buffered one at a time.
+/// [`SectionWriter::finish`] fills in the table and the header.
+pub(crate) struct SectionWriter<'w> {
+    out: Box<dyn SnapshotWriter + 'w>,
+    /// Kind and uncompressed size of every section, in order
+    plan: Vec<(SectionKind, usize)>,
+    compression: Compression,
+    entries: Vec<SectionEntry>,
+    /// File offset of the next byte written
+    pos: u64,
+    /// File offset of the current section
+    start: u64,
+    /// Bytes still expected for the current section
+    remaining: usize,
+    /// CRC of everything after the section table
+    data_hasher: Hasher,
+    section_hasher: Hasher,
+    /// Raw bytes of the current section, if it is being compressed
+    buffer: Option<Vec<u8>>,
+}
+
+impl<'w> SectionWriter<'w> {
+    /// Reserves space for the file header and the table, then starts the
+    /// first section.
+    pub(crate) fn new(
+        mut out: Box<dyn SnapshotWriter + 'w>,
+        plan: Vec<(SectionKind, usize)>,
+        compression: Compression,
+    ) -> Result<Self, PersistenceError> {
+        let reserved = 64 + SectionTableHeader::SIZE + plan.len() * SectionEntry::SIZE;
+        out.write(&vec![0; reserved])?;
+
+        let mut writer = Self {
+            out,
+            plan,
+            compression,
+            entries: Vec::new(),
+            pos: reserved as u64,
+            start: 0,
+            remaining: 0,
+            data_hasher: Hasher::new(),
+            section_hasher: Hasher::new(),
+            buffer: None,
+        };
+        writer.start_section()?;
+        Ok(writer)
+    }
+
+    /// Writes the next bytes of the stream.
+    pub(crate) fn write(&mut self, mut data: &[u8]) -> Result<(), PersistenceError> {
+        while !data.is_empty() {
+            if self.entries.len() == self.plan.len() {
+                return Err(PersistenceError::Corrupted(
+                    "snapshot stream is longer than its section plan".into(),
+                ));
+            }
+
+            let (head, rest) = data.split_at(self.remaining.min(data.len()));
+            if let Some(buffer) = &mut self.buffer {
+                buffer.extend_from_slice(head);
+            } else {
+                self.section_hasher.update(head);
+                self.emit(head)?;
+            }
+            self.remaining -= head.len();
+            data = rest;
+
+            if self.remaining == 0 {
+                self.finish_section()?;
+                self.start_section()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the table and `header`, then commits.
+    ///
+    /// The header gains `Flags::HAS_SECTION_TABLE` (and `COMPRESSED` if any
+    /// section was compressed), its offsets are pointed at the nodes and
+    /// tombstones, and the data CRC is filled in.
+    pub(crate) fn finish(mut self, mut header: FileHeader) -> Result<(), PersistenceError> {
+        if self.entries.len() != self.plan.len() {
+            return Err(PersistenceError::Corrupted(
+                "snapshot stream ended before its section plan".into(),
+            ));
+        }
+
+        let table = SectionTable {
+            entries: self.entries,
+        };
+        let table_bytes = table.to_bytes();
+
+        // The table was reserved as zeros; hash its final bytes ahead of the rest
+        let mut hasher = Hasher::new();
+        hasher.update(&table_bytes);
+        hasher.combine(&self.data_hasher);
+
+        header.flags |= Flags::HAS_SECTION_TABLE;
+        if table.entries.iter().any(SectionEntry::is_compressed) {
+            header.flags |= Flags::COMPRESSED;
+        }
+        header.index_offset = table.get(SectionKind::NODES).map_or(0, |e| e.offset);
+        header.metadata_offset = table.get(SectionKind::TOMBSTONES).map_or(0, |e| e.offset);
+        header.data_crc = hasher.finalize();
+        header.update_checksum();
+
+        self.out.patch(0, header.as_bytes())?;
+        self.out.patch(64, &table_bytes)?;
+        self.out.commit()
+    }
+
+    /// Pads to the next section boundary and starts the next non-empty
+    /// section. Empty sections are recorded on the way.
+    fn start_section(&mut self) -> Result<(), PersistenceError> {
+        while let Some(&(kind, len)) = self.plan.get(self.entries.len()) {
+            // SAFETY: padding < SECTION_ALIGN
+            #[allow(clippy::cast_possible_truncation)]
+            let padding = ((SECTION_ALIGN - self.pos % SECTION_ALIGN) % SECTION_ALIGN) as usize;
+            self.emit(&[0; 8][..padding])?;
+            self.start = self.pos;
+
+            if len > 0 {
+                self.remaining = len;
+                // Vectors and nodes compress poorly and must stay mappable
+                let compress = self.compression != Compression::None
+                    && kind != SectionKind::VECTORS
+                    && kind != SectionKind::NODES;
+                self.buffer = compress.then(|| Vec::with_capacity(len));
+                return Ok(());
+            }
+
+            self.entries.push(SectionEntry {
+                kind: kind.0,
+                flags: 0,
+                crc: 0,
+                offset: self.start,
+                length: 0,
+            });
+        }
+        Ok(())
+    }
+
+    fn finish_section(&mut self) -> Result<(), PersistenceError> {
+        let (kind, len) = self.plan[self.entries.len()];
+        let entry = if let Some(raw) = self.buffer.take() {
+            // Keep the raw bytes if compression does not pay off
+            let compressed = compress_section(&raw)?;
+            let (stored, flags) = if compressed.len() < raw.len() {
+                (compressed, SectionEntry::COMPRESSED)
+            } else {
+                (raw, 0)
+            };
+            self.emit(&stored)?;
+            SectionEntry {
+                kind: kind.0,
+                flags,
+                crc: crc32fast::hash(&stored),
+                offset: self.start,
+                length: stored.len() as u64,
+            }
+        } else {
+            SectionEntry {
+                kind: kind.0,
+                flags: 0,
+                crc: std::mem::take(&mut self.section_hasher).finalize(),
+                offset: self.start,
+                length: len as u64,
+            }
+        };
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    fn emit(&mut self, data: &[u8]) -> Result<(), PersistenceError> {
+        self.data_hasher.update(data);
+        self.pos += data.len() as u64;
+        self.out.write(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: SectionKind, offset: u64, length: u64) -> SectionEntry {
+        SectionEntry {
+            kind: kind.0,
+            flags: 0,
+            crc: 0,
+            offset,
+            length,
+        }
+    }
+
+    fn parse(entries: Vec<SectionEntry>) -> Result<SectionTable, PersistenceError> {
+        let bytes = SectionTable { entries }.to_bytes();
+        SectionTable::read(&mut &bytes[..])
+    }
+
+    #[test]
+    fn test_table_roundtrip() {
+        // Header and two entries end at 64 + 16 + 48 = 128
+        let entries = vec![
+            entry(SectionKind::VECTORS, 128, 40),
+            entry(SectionKind(500), 168, 8),
+        ];
+        let table = parse(entries.clone()).unwrap();
+        assert_eq!(table.entries(), &entries[..]);
+        assert_eq!(table.size(), 64);
+        assert_eq!(table.get(SectionKind(500)).unwrap().offset, 168);
+        assert!(table.check_bounds(176).is_ok());
+        assert!(table.check_bounds(175).is_err());
+    }
+
+    #[test]
+    fn test_invalid_tables_are_rejected() {
+        let cases = [
+            // Starts inside the table
+            vec![entry(SectionKind::VECTORS, 64, 8)],
+            // Overlaps the previous section
+            vec![
+                entry(SectionKind::VECTORS, 128, 16),
+                entry(SectionKind::NODES, 136, 8),
+            ],
+            // Listed twice
+            vec![
+                entry(SectionKind::VECTORS, 128, 8),
+                entry(SectionKind::VECTORS, 136, 8),
+            ],
+            // Length overflows
+            vec![entry(SectionKind::VECTORS, 104, u64::MAX)],
+        ];
+        for entries in cases {
+            assert!(matches!(
+                parse(entries),
+                Err(PersistenceError::Corrupted(_))
+            ));
+        }
+
+        let mut bytes = SectionTable {
+            entries: vec![entry(SectionKind::VECTORS, 104, 8)],
+        }
+        .to_bytes();
+        bytes[20] ^= 1;
+        assert!(matches!(
+            SectionTable::read(&mut &bytes[..]),
+            Err(PersistenceError::Corrupted(msg)) if msg.contains("CRC mismatch")
+        ));
+    }
+}
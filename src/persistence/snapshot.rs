@@ -1,14 +1,30 @@
-use crate::hnsw::graph::{HnswIndex, HnswNode, NodeId};
+use crate::flat::FlatIndex;
+use crate::hnsw::graph::{HnswIndex, HnswNode};
+use crate::hnsw::ExternalIdMap;
 use crate::hnsw::HnswConfig;
 use crate::metadata::MetadataStore;
 use crate::persistence::chunking::ChunkedWriter;
-use crate::persistence::header::{FileHeader, Flags, HeaderError, MetadataSectionHeader};
-use crate::persistence::storage::load_snapshot;
+use crate::persistence::compression::{decompress_section, Compression};
+use crate::persistence::header::{
+    BqSectionHeader, CheckpointSection, ConfigSection, ExternalIdSectionHeader, FileHeader, Flags,
+    MetadataSectionHeader, PqSectionHeader, QuantizerSectionHeader, SectionEntry, SectionKind,
+};
+use crate::persistence::sections::{expand, read_section, skip, SectionTable, SectionWriter};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::persistence::storage::MappedFile;
+use crate::persistence::storage::{read_full, read_header, CrcReader};
 use crate::persistence::{PersistenceError, StorageBackend};
-use crate::storage::VectorStorage;
+use crate::quantization::product::ProductQuantizer;
+use crate::quantization::{QuantizerConfig, Sq8Ranges};
+use crate::storage::binary::BinaryVectorStorage;
+use crate::storage::mapped::CowSlice;
+use crate::storage::product::PqCodeStorage;
+use crate::storage::{StorageType, VectorStorage};
 use bitvec::prelude::*;
-use bytemuck::try_cast_slice;
+use bytemuck::Pod;
 use log::{debug, info, warn};
+use std::borrow::Cow;
+use std::io::Read;
 use std::mem::size_of;
 
 /// Standard chunk size for snapshot streaming (1MB).
@@ -33,30 +49,72 @@ pub fn write_snapshot(
     storage: &VectorStorage,
     backend: &mut dyn StorageBackend,
 ) -> Result<(), PersistenceError> {
-    let writer = (storage, index);
-    let mut buffer = Vec::new();
-    for chunk in writer.export_chunked(SNAPSHOT_CHUNK_SIZE) {
-        buffer.extend_from_slice(&chunk);
-    }
+    write_chunks(&(storage, index), backend, Compression::None)
+}
 
-    if buffer.len() < 64 {
-        return Err(PersistenceError::BufferTooSmall {
-            expected: 64,
-            actual: buffer.len(),
-        });
-    }
+/// Writes a snapshot like [`write_snapshot`], compressing every section but
+/// the vectors and nodes with `compression`.
+///
+/// [`read_snapshot`] decompresses transparently. See
+/// [`compression`](crate::persistence::compression) for the layout.
+///
+/// # Errors
+///
+/// Returns `PersistenceError::Unsupported` if `compression` is not
+/// available in this build, or any error [`write_snapshot`] can return.
+pub fn write_snapshot_compressed(
+    index: &HnswIndex,
+    storage: &VectorStorage,
+    backend: &mut dyn StorageBackend,
+    compression: Compression,
+) -> Result<(), PersistenceError> {
+    write_chunks(&(storage, index), backend, compression)
+}
 
-    let mut header_bytes = [0u8; 64];
-    header_bytes.copy_from_slice(&buffer[..64]);
-    let mut header = FileHeader::from_bytes(&header_bytes)?;
+/// Streams `writer` to the backend chunk by chunk, laying it out as
+/// sections behind a section table, then patches the table and header and
+/// commits.
+///
+/// Only one chunk is held at a time; CRCs are computed as chunks go out.
+/// With compression, each compressible section is buffered while it is
+/// compressed.
+fn write_chunks(
+    writer: &dyn ChunkedWriter,
+    backend: &mut dyn StorageBackend,
+    compression: Compression,
+) -> Result<(), PersistenceError> {
+    compression.ensure_available()?;
 
-    let mut hasher = crc32fast::Hasher::new();
-    hasher.update(&buffer[64..]);
-    header.data_crc = hasher.finalize();
-    header.update_checksum();
+    let chunks = writer.export_chunked(SNAPSHOT_CHUNK_SIZE);
+    if let Some(error) = chunks.error() {
+        return Err(PersistenceError::Corrupted(error.to_string()));
+    }
+    let mut out = SectionWriter::new(backend.atomic_writer("")?, chunks.sections(), compression)?;
+    let mut header = None;
 
-    buffer[..64].copy_from_slice(header.as_bytes());
-    backend.atomic_write("", &buffer)
+    for chunk in chunks {
+        // Chunks are at least MIN_CHUNK_SIZE, so the first holds the whole header
+        let data = if header.is_none() {
+            if chunk.len() < 64 {
+                return Err(PersistenceError::BufferTooSmall {
+                    expected: 64,
+                    actual: chunk.len(),
+                });
+            }
+            let parsed: FileHeader = bytemuck::pod_read_unaligned(&chunk[..64]);
+            header = Some(FileHeader::from_bytes(parsed.as_bytes())?);
+            &chunk[64..]
+        } else {
+            &chunk[..]
+        };
+        out.write(data)?;
+    }
+
+    let header = header.ok_or(PersistenceError::BufferTooSmall {
+        expected: 64,
+        actual: 0,
+    })?;
+    out.finish(header)
 }
 
 /// Reads a snapshot from the backend and reconstructs the index and storage.
@@ -81,310 +139,1221 @@ pub fn write_snapshot(
 pub fn read_snapshot(
     backend: &dyn StorageBackend,
 ) -> Result<(HnswIndex, VectorStorage), PersistenceError> {
-    // 1. Load Data (Verifies Checksum and Header)
-    let (header, data) = load_snapshot(backend)?;
+    let SnapshotParts {
+        header,
+        config,
+        compaction_threshold,
+        storage,
+        nodes,
+        neighbors_bytes,
+        metadata,
+        external_ids,
+        bq_storage,
+        pq_storage,
+    } = read_parts(backend)?;
+
+    if header.is_flat_index() {
+        return Err(PersistenceError::Unsupported(
+            "snapshot holds a FlatIndex; load it with read_flat_snapshot".into(),
+        ));
+    }
+
+    // Construct Index
+    let mut index =
+        HnswIndex::new(config, &storage).map_err(|e| PersistenceError::Corrupted(e.to_string()))?;
+
+    if let Some(threshold) = compaction_threshold {
+        index.set_compaction_threshold(threshold);
+    }
+
+    // Restore nodes and rebuild the VectorId -> NodeId lookup
+    index.nodes = nodes;
+    index
+        .rebuild_node_index()
+        .map_err(|_| PersistenceError::Corrupted("Duplicate VectorId in node table".into()))?;
+
+    // Restore neighbors
+    index.neighbors.buffer = neighbors_bytes;
+
+    // Restore max_layer and entry_point. Deleted nodes are skipped: a
+    // hard-deleted node has no links left to route through.
+    index.reassign_entry_point();
+
+    // v0.3: Restore deleted_count from header or recalculate for older formats
+    if header.supports_soft_delete() {
+        // Trust header value for v0.3+
+        index.deleted_count = header.deleted_count as usize;
+
+        // Verify consistency: count actual deleted nodes
+        let actual_deleted = index.nodes.iter().filter(|n| n.deleted != 0).count();
+        if actual_deleted != index.deleted_count {
+            // Mismatch detected — use actual count for safety
+            // This can happen if the snapshot was corrupted or manually edited
+            warn!(
+                "Snapshot deleted_count mismatch (header={}, actual={}). Using actual count.",
+                index.deleted_count, actual_deleted
+            );
+            index.deleted_count = actual_deleted;
+        }
+    } else if header.needs_migration() {
+        // Migration from v0.1/v0.2: node.pad was always 0, now interpreted as deleted=0
+        // All nodes are live in old format, deleted_count = 0
+        index.deleted_count = 0;
+
+        info!(
+            "Migrated snapshot from v0.{} to v0.3 format (soft delete enabled)",
+            header.version_minor
+        );
+    }
+
+    index.metadata = metadata;
+    index.external_ids = external_ids;
+    index.bq_storage = bq_storage;
+    index.pq_storage = pq_storage;
+
+    Ok((index, storage))
+}
+
+/// Writes a full snapshot of a [`FlatIndex`] and its storage to the backend.
+///
+/// Uses the same file format as [`write_snapshot`] with the
+/// [`Flags::FLAT_INDEX`] flag set and an empty node table. Soft deletes are
+/// carried by the storage tombstone bitvec.
+///
+/// # Errors
+///
+/// Returns `PersistenceError` if I/O fails or data cannot be serialized.
+pub fn write_flat_snapshot(
+    index: &FlatIndex,
+    storage: &VectorStorage,
+    backend: &mut dyn StorageBackend,
+) -> Result<(), PersistenceError> {
+    write_chunks(&(storage, index), backend, Compression::None)
+}
+
+/// Writes a [`FlatIndex`] snapshot like [`write_flat_snapshot`], compressing
+/// every section but the vectors with `compression`.
+///
+/// # Errors
+///
+/// Returns `PersistenceError::Unsupported` if `compression` is not
+/// available in this build, or any error [`write_flat_snapshot`] can return.
+pub fn write_flat_snapshot_compressed(
+    index: &FlatIndex,
+    storage: &VectorStorage,
+    backend: &mut dyn StorageBackend,
+    compression: Compression,
+) -> Result<(), PersistenceError> {
+    write_chunks(&(storage, index), backend, compression)
+}
+
+/// Reads a [`FlatIndex`] snapshot written by [`write_flat_snapshot`].
+///
+/// # Errors
+///
+/// Returns `PersistenceError` if data is corrupted, version mismatch, I/O
+/// error, or the snapshot holds an `HnswIndex` (use [`read_snapshot`]).
+pub fn read_flat_snapshot(
+    backend: &dyn StorageBackend,
+) -> Result<(FlatIndex, VectorStorage), PersistenceError> {
+    let parts = read_parts(backend)?;
+
+    if !parts.header.is_flat_index() {
+        return Err(PersistenceError::Unsupported(
+            "snapshot holds an HnswIndex; load it with read_snapshot".into(),
+        ));
+    }
+
+    let index = FlatIndex::from_parts(parts.config, &parts.storage, parts.metadata);
+    Ok((index, parts.storage))
+}
+
+/// Reads only the metadata store of a snapshot.
+///
+/// With a section table, only the metadata section is loaded and checked;
+/// older snapshots are read in full.
+///
+/// # Errors
+///
+/// Returns `PersistenceError` if the metadata section (or, for older
+/// snapshots, any part of the file) is corrupted, or on I/O error.
+pub fn read_snapshot_metadata(
+    backend: &dyn StorageBackend,
+) -> Result<MetadataStore, PersistenceError> {
+    let header = read_header(&mut backend.open_reader()?)?;
+    if !header.has_section_table() {
+        return read_parts(backend).map(|parts| parts.metadata);
+    }
+
+    match read_section(backend, SectionKind::METADATA)? {
+        Some(section) => read_metadata(&section, 0).map(|(metadata, _)| metadata),
+        None => Ok(MetadataStore::new()),
+    }
+}
+
+/// Everything decoded from a snapshot before an index is assembled.
+struct SnapshotParts {
+    header: FileHeader,
+    config: HnswConfig,
+    compaction_threshold: Option<f64>,
+    storage: VectorStorage,
+    nodes: CowSlice<HnswNode>,
+    neighbors_bytes: Vec<u8>,
+    metadata: MetadataStore,
+    external_ids: ExternalIdMap,
+    bq_storage: Option<BinaryVectorStorage>,
+    pq_storage: Option<PqCodeStorage>,
+}
+
+/// Loads and validates a snapshot, decoding every section shared by
+/// `HnswIndex` and `FlatIndex` snapshots.
+///
+/// Nothing is interpreted before the data CRC is verified, beyond the sizes
+/// in the (separately checksummed) header and section table.
+fn read_parts(backend: &dyn StorageBackend) -> Result<SnapshotParts, PersistenceError> {
+    // A mapped backend lends vectors and nodes straight from the file
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(file) = backend.mapped() {
+        let header = read_header(&mut &file[..])?;
+        let (layout, payload) = if header.has_section_table() {
+            map_sections(&header, &file)?
+        } else {
+            let layout = PayloadLayout::new(&header);
+            let payload = map_payload(&header, &layout, &file)?;
+            (layout, payload)
+        };
+        return decode_parts(header, &layout, payload);
+    }
+
+    // 1. Load Data (Verifies Header, then streams the payload)
+    let mut reader = backend.open_reader()?;
+    let header = read_header(&mut reader)?;
+    let (layout, payload) = if header.has_section_table() {
+        stream_sections(&header, reader)?
+    } else {
+        let layout = PayloadLayout::new(&header);
+        let payload = stream_payload(&header, &layout, reader)?;
+        (layout, payload)
+    };
+    decode_parts(header, &layout, payload)
+}
+
+/// Sizes of the payload blocks, as declared by the header.
+pub(crate) struct PayloadLayout {
+    pub(crate) vec_count: usize,
+    pub(crate) node_count: usize,
+    /// Bytes in the vector block (`index_offset` relative to the payload)
+    pub(crate) vector_bytes: usize,
+    /// Bytes in the neighbor pool, or `None` if it runs to the end of the
+    /// payload (no `metadata_offset`)
+    pub(crate) neighbors_bytes: Option<usize>,
+}
+
+impl PayloadLayout {
+    pub(crate) fn new(header: &FileHeader) -> Self {
+        // SAFETY: On 32-bit targets, vector counts > 2^32 would exceed memory anyway.
+        // This cast is intentional and documented.
+        #[allow(clippy::cast_possible_truncation)]
+        let vec_count = header.vector_count as usize;
+        // A FlatIndex snapshot has no nodes
+        let node_count = if header.is_flat_index() { 0 } else { vec_count };
+
+        // SAFETY: On 32-bit targets, offsets > 2^32 would exceed addressable memory.
+        #[allow(clippy::cast_possible_truncation)]
+        let vector_bytes = (header.index_offset as usize).saturating_sub(64);
+
+        // Note: metadata_offset indicates where neighbors end.
+        let neighbors_bytes = (header.metadata_offset > 0).then(|| {
+            // SAFETY: On 32-bit targets, offsets > 2^32 would exceed addressable memory.
+            #[allow(clippy::cast_possible_truncation)]
+            let metadata_offset = header.metadata_offset as usize;
+            metadata_offset
+                .saturating_sub(64)
+                .saturating_sub(vector_bytes + node_count * size_of::<HnswNode>())
+        });
+
+        Self {
+            vec_count,
+            node_count,
+            vector_bytes,
+            neighbors_bytes,
+        }
+    }
+
+    /// Layout of a snapshot whose blocks are located by `table`.
+    fn sectioned(header: &FileHeader, table: &SectionTable) -> Self {
+        // SAFETY: On 32-bit targets, sections > 2^32 would exceed addressable memory.
+        #[allow(clippy::cast_possible_truncation)]
+        let vector_bytes = table
+            .get(SectionKind::VECTORS)
+            .map_or(0, |e| e.length as usize);
+        Self {
+            vector_bytes,
+            neighbors_bytes: None,
+            ..Self::new(header)
+        }
+    }
+}
+
+/// The payload split into its blocks, not yet interpreted.
+///
+/// Blocks cut short by the end of the file are returned short; the length
+/// checks in [`decode_parts`] reject them.
+struct RawPayload<'a> {
+    vectors_f32: CowSlice<f32>,
+    vectors_u8: CowSlice<u8>,
+    nodes: CowSlice<HnswNode>,
+    neighbors: Vec<u8>,
+    /// The neighbor pool is stored as a compressed section
+    neighbors_compressed: bool,
+    tail: Tail<'a>,
+}
+
+/// Tombstones, config and optional sections, as stored.
+enum Tail<'a> {
+    /// Back to back after the neighbor pool; one compressed section if
+    /// `Flags::COMPRESSED` is set
+    Packed(Cow<'a, [u8]>),
+    /// Located through the section table; unknown kinds are left out
+    Sections(Vec<(SectionEntry, Cow<'a, [u8]>)>),
+}
+
+/// Returns true for the sections that follow the neighbor pool.
+fn is_tail_section(kind: SectionKind) -> bool {
+    matches!(
+        kind,
+        SectionKind::TOMBSTONES
+            | SectionKind::CONFIG
+            | SectionKind::METADATA
+            | SectionKind::EXTERNAL_IDS
+            | SectionKind::BQ
+            | SectionKind::QUANTIZER
+            | SectionKind::PQ
+            | SectionKind::CHECKPOINT
+    )
+}
+
+/// Rejects compressed vector or node sections, which must stay mappable.
+fn ensure_uncompressed(entry: &SectionEntry) -> Result<(), PersistenceError> {
+    if entry.is_compressed() {
+        return Err(PersistenceError::Unsupported(format!(
+            "compressed {} section",
+            entry.kind().name()
+        )));
+    }
+    Ok(())
+}
+
+/// Streams the payload, reading vectors, nodes and neighbors straight into
+/// the buffers the index keeps. Only the tail is buffered separately.
+fn stream_payload(
+    header: &FileHeader,
+    layout: &PayloadLayout,
+    reader: Box<dyn Read + '_>,
+) -> Result<RawPayload<'static>, PersistenceError> {
+    let mut reader = CrcReader::new(reader);
+
+    let (vectors_f32, vectors_u8) = read_vector_block(&mut reader, header, layout.vector_bytes)?;
+    let nodes = read_pod_vec::<HnswNode>(&mut reader, layout.node_count)?;
+
+    // Without a metadata offset, the neighbors run to the end of the payload
+    let (neighbors, tail) = if let Some(neighbors_bytes) = layout.neighbors_bytes {
+        let neighbors = read_pod_vec::<u8>(&mut reader, neighbors_bytes)?;
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail)?;
+        (neighbors, tail)
+    } else {
+        let mut neighbors = Vec::new();
+        reader.read_to_end(&mut neighbors)?;
+        (neighbors, Vec::new())
+    };
+
+    check_data_crc(header, reader.finalize())?;
+
+    Ok(RawPayload {
+        vectors_f32: vectors_f32.into(),
+        vectors_u8: vectors_u8.into(),
+        nodes: nodes.into(),
+        neighbors,
+        neighbors_compressed: header.is_compressed(),
+        tail: Tail::Packed(Cow::Owned(tail)),
+    })
+}
+
+/// Streams a snapshot with a section table, reading each known section in
+/// file order. Padding and unknown sections are read past but not kept.
+fn stream_sections(
+    header: &FileHeader,
+    reader: Box<dyn Read + '_>,
+) -> Result<(PayloadLayout, RawPayload<'static>), PersistenceError> {
+    let mut reader = CrcReader::new(reader);
+    let table = SectionTable::read(&mut reader)?;
+    let layout = PayloadLayout::sectioned(header, &table);
+
+    let mut payload = RawPayload {
+        vectors_f32: CowSlice::default(),
+        vectors_u8: CowSlice::default(),
+        nodes: CowSlice::default(),
+        neighbors: Vec::new(),
+        neighbors_compressed: false,
+        tail: Tail::Sections(Vec::new()),
+    };
+    let mut tail = Vec::new();
+
+    let mut pos = (64 + table.size()) as u64;
+    for entry in table.entries() {
+        skip(&mut reader, entry.offset - pos)?;
+        pos = entry.offset + entry.length;
+
+        // SAFETY: On 32-bit targets, sections > 2^32 would exceed addressable memory.
+        #[allow(clippy::cast_possible_truncation)]
+        let length = entry.length as usize;
+        match entry.kind() {
+            SectionKind::VECTORS => {
+                ensure_uncompressed(entry)?;
+                let (vectors_f32, vectors_u8) = read_vector_block(&mut reader, header, length)?;
+                payload.vectors_f32 = vectors_f32.into();
+                payload.vectors_u8 = vectors_u8.into();
+            }
+            SectionKind::NODES => {
+                ensure_uncompressed(entry)?;
+                let node_size = size_of::<HnswNode>();
+                payload.nodes = read_pod_vec::<HnswNode>(&mut reader, length / node_size)?.into();
+                read_pod_vec::<u8>(&mut reader, length % node_size)?;
+            }
+            SectionKind::NEIGHBORS => {
+                payload.neighbors = read_pod_vec::<u8>(&mut reader, length)?;
+                payload.neighbors_compressed = entry.is_compressed();
+            }
+            kind if is_tail_section(kind) => {
+                tail.push((*entry, Cow::Owned(read_pod_vec::<u8>(&mut reader, length)?)));
+            }
+            _ => skip(&mut reader, entry.length)?,
+        }
+    }
+    skip(&mut reader, u64::MAX)?;
+
+    check_data_crc(header, reader.finalize())?;
+
+    payload.tail = Tail::Sections(tail);
+    Ok((layout, payload))
+}
+
+/// Reads a vector block of `bytes` bytes as its in-memory type.
+fn read_vector_block(
+    reader: &mut dyn Read,
+    header: &FileHeader,
+    bytes: usize,
+) -> Result<(Vec<f32>, Vec<u8>), PersistenceError> {
+    if header.is_quantized() {
+        Ok((Vec::new(), read_pod_vec::<u8>(reader, bytes)?))
+    } else {
+        let floats = read_pod_vec::<f32>(reader, bytes / 4)?;
+        // Keep the stream in step with the header if the block is not whole floats
+        read_pod_vec::<u8>(reader, bytes % 4)?;
+        Ok((floats, Vec::new()))
+    }
+}
+
+fn check_data_crc(header: &FileHeader, calculated_crc: u32) -> Result<(), PersistenceError> {
+    if calculated_crc != header.data_crc {
+        return Err(PersistenceError::ChecksumMismatch {
+            expected: header.data_crc,
+            actual: calculated_crc,
+        });
+    }
+    Ok(())
+}
+
+/// Splits a mapped file into its blocks, borrowing vectors and nodes from
+/// the mapping. Blocks that are not aligned for their type are copied.
+#[cfg(not(target_arch = "wasm32"))]
+fn map_payload<'a>(
+    header: &FileHeader,
+    layout: &PayloadLayout,
+    file: &'a MappedFile,
+) -> Result<RawPayload<'a>, PersistenceError> {
+    let payload = &file[64..];
+    check_data_crc(header, crc32fast::hash(payload))?;
+
+    // Clamps a payload range to the file, returning it as a file offset
+    let block = |start: usize, len: usize| {
+        let start = start.min(payload.len());
+        let end = start.saturating_add(len).min(payload.len());
+        (64 + start, end - start)
+    };
+
+    let (offset, bytes) = block(0, layout.vector_bytes);
+    let (vectors_f32, vectors_u8) = map_vector_block(header, file, offset, bytes);
+
+    let nodes_bytes = layout.node_count * size_of::<HnswNode>();
+    let (offset, bytes) = block(layout.vector_bytes, nodes_bytes);
+    let nodes = borrow_or_copy(file, offset, bytes);
+
+    let neighbors_start = layout.vector_bytes + nodes_bytes;
+    let (offset, bytes) = block(
+        neighbors_start,
+        layout.neighbors_bytes.unwrap_or(usize::MAX),
+    );
+    let neighbors = file[offset..offset + bytes].to_vec();
+    let tail = if layout.neighbors_bytes.is_some() {
+        &file[offset + bytes..]
+    } else {
+        &[]
+    };
+
+    Ok(RawPayload {
+        vectors_f32,
+        vectors_u8,
+        nodes,
+        neighbors,
+        neighbors_compressed: header.is_compressed(),
+        tail: Tail::Packed(Cow::Borrowed(tail)),
+    })
+}
+
+/// Splits a mapped file with a section table into its sections, borrowing
+/// vectors, nodes and the tail sections from the mapping.
+#[cfg(not(target_arch = "wasm32"))]
+fn map_sections<'a>(
+    header: &FileHeader,
+    file: &'a MappedFile,
+) -> Result<(PayloadLayout, RawPayload<'a>), PersistenceError> {
+    check_data_crc(header, crc32fast::hash(&file[64..]))?;
+
+    let table = SectionTable::read(&mut &file[64..])?;
+    table.check_bounds(file.len())?;
+    let layout = PayloadLayout::sectioned(header, &table);
+
+    let mut payload = RawPayload {
+        vectors_f32: CowSlice::default(),
+        vectors_u8: CowSlice::default(),
+        nodes: CowSlice::default(),
+        neighbors: Vec::new(),
+        neighbors_compressed: false,
+        tail: Tail::Sections(Vec::new()),
+    };
+    let mut tail = Vec::new();
+
+    for entry in table.entries() {
+        // SAFETY: check_bounds keeps every section within the mapping.
+        #[allow(clippy::cast_possible_truncation)]
+        let (offset, length) = (entry.offset as usize, entry.length as usize);
+        match entry.kind() {
+            SectionKind::VECTORS => {
+                ensure_uncompressed(entry)?;
+                (payload.vectors_f32, payload.vectors_u8) =
+                    map_vector_block(header, file, offset, length);
+            }
+            SectionKind::NODES => {
+                ensure_uncompressed(entry)?;
+                payload.nodes = borrow_or_copy(file, offset, length);
+            }
+            SectionKind::NEIGHBORS => {
+                payload.neighbors = file[offset..offset + length].to_vec();
+                payload.neighbors_compressed = entry.is_compressed();
+            }
+            kind if is_tail_section(kind) => {
+                tail.push((*entry, Cow::Borrowed(&file[offset..offset + length])));
+            }
+            _ => {}
+        }
+    }
+
+    payload.tail = Tail::Sections(tail);
+    Ok((layout, payload))
+}
+
+/// Borrows a vector block of `bytes` bytes at `offset` as its in-memory type.
+#[cfg(not(target_arch = "wasm32"))]
+fn map_vector_block(
+    header: &FileHeader,
+    file: &MappedFile,
+    offset: usize,
+    bytes: usize,
+) -> (CowSlice<f32>, CowSlice<u8>) {
+    if header.is_quantized() {
+        (CowSlice::default(), borrow_or_copy(file, offset, bytes))
+    } else {
+        (borrow_or_copy(file, offset, bytes), CowSlice::default())
+    }
+}
+
+/// Borrows the whole values of `T` in `bytes` bytes at `offset` of `file`,
+/// or copies them if the range is not aligned for `T`.
+#[cfg(not(target_arch = "wasm32"))]
+fn borrow_or_copy<T: Pod>(file: &MappedFile, offset: usize, bytes: usize) -> CowSlice<T> {
+    let count = bytes / size_of::<T>();
+    CowSlice::mapped(file, offset, count).unwrap_or_else(|| {
+        let mut src = &file[offset..offset + count * size_of::<T>()];
+        read_pod_vec(&mut src, count)
+            .expect("in-memory read cannot fail")
+            .into()
+    })
+}
+
+/// Locates the tombstones, config and optional sections of a payload.
+enum TailSections<'a> {
+    /// Legacy layout: tombstones at the start, then the config, then the
+    /// optional sections in order. `offset` is where the next optional
+    /// section starts.
+    Packed {
+        data: Cow<'a, [u8]>,
+        tombstone_bytes: usize,
+        offset: usize,
+    },
+    /// Decompressed sections from the section table
+    Sections(Vec<(SectionKind, Cow<'a, [u8]>)>),
+}
+
+impl TailSections<'_> {
+    /// Returns the bytes holding section `kind` and the offset it starts
+    /// at. A missing section yields an empty slice.
+    fn locate(&self, kind: SectionKind) -> (&[u8], usize) {
+        match self {
+            Self::Packed {
+                data,
+                tombstone_bytes,
+                offset,
+            } => {
+                let start = match kind {
+                    SectionKind::TOMBSTONES => 0,
+                    SectionKind::CONFIG => *tombstone_bytes,
+                    _ => *offset,
+                };
+                (data, start)
+            }
+            Self::Sections(sections) => {
+                let bytes = sections
+                    .iter()
+                    .find(|(k, _)| *k == kind)
+                    .map_or(&[][..], |(_, bytes)| bytes);
+                (bytes, 0)
+            }
+        }
+    }
+
+    /// Records that the optional section just read ends at `end`.
+    fn advance(&mut self, end: usize) {
+        if let Self::Packed { offset, .. } = self {
+            *offset = end;
+        }
+    }
+}
+
+/// Decodes the blocks of a CRC-verified payload.
+fn decode_parts(
+    header: FileHeader,
+    layout: &PayloadLayout,
+    payload: RawPayload<'_>,
+) -> Result<SnapshotParts, PersistenceError> {
+    let RawPayload {
+        vectors_f32,
+        vectors_u8,
+        nodes,
+        neighbors: mut neighbors_bytes,
+        neighbors_compressed,
+        tail,
+    } = payload;
+    let dim = header.dimensions;
+    let vec_count = layout.vec_count;
+    let node_count = layout.node_count;
+    // SQ8 snapshots store one u8 code per dimension instead of an f32
+    let bytes_per_value = if header.is_quantized() { 1 } else { 4 };
+    let vec_data_len = vec_count * (dim as usize) * bytes_per_value;
+    let index_offset_local = layout.vector_bytes;
+
+    if layout
+        .neighbors_bytes
+        .is_some_and(|len| neighbors_bytes.len() < len)
+    {
+        // Offset beyond data length -> Corruption or Truncation.
+        // Fallback to all active.
+        warn!(
+            "Metadata offset {} exceeds data length. Treating all vectors as active.",
+            header.metadata_offset
+        );
+    }
 
     // Verify Flags (C3)
     // v0.4: We now support HAS_METADATA flag (bit 2)
-    // Other flags (COMPRESSED, QUANTIZED) are still unsupported.
-    let supported_flags = Flags::HAS_METADATA;
+    // COMPRESSED (bit 0) marks compressed sections (v0.5+).
+    // v0.5: HAS_EXTERNAL_IDS (bit 3) is also supported.
+    // FLAT_INDEX (bit 4) marks a snapshot without HNSW nodes.
+    // HAS_BQ (bit 5) adds a binary quantization section.
+    // QUANTIZED (bit 1) stores SQ8 codes plus a quantizer section.
+    // HAS_CHECKPOINT (bit 6) records the WAL sequence the snapshot covers.
+    // HAS_SECTION_TABLE (bit 7) locates sections through a table.
+    // HAS_PQ (bit 8) adds product quantization codebooks and codes.
+    // DUAL_STORAGE (bit 9) stores F32 vectors plus a quantizer section.
+    let supported_flags = Flags::COMPRESSED
+        | Flags::QUANTIZED
+        | Flags::HAS_METADATA
+        | Flags::HAS_EXTERNAL_IDS
+        | Flags::FLAT_INDEX
+        | Flags::HAS_BQ
+        | Flags::HAS_CHECKPOINT
+        | Flags::HAS_SECTION_TABLE
+        | Flags::HAS_PQ
+        | Flags::DUAL_STORAGE;
     let unsupported = header.flags & !supported_flags;
     if unsupported != 0 {
         return Err(PersistenceError::Corrupted(format!(
-            "Unsupported flags: 0x{:x}. Supported: 0x{:x} (HAS_METADATA).",
+            "Unsupported flags: 0x{:x}. Supported: 0x{:x} (COMPRESSED, QUANTIZED, HAS_METADATA, HAS_EXTERNAL_IDS, FLAT_INDEX, HAS_BQ, HAS_CHECKPOINT, HAS_SECTION_TABLE, HAS_PQ, DUAL_STORAGE).",
             header.flags, supported_flags
         )));
     }
 
-    // 2. Reconstruct VectorStorage
-    // Calculate sizes
-    let dim = header.dimensions;
-    // SAFETY: On 32-bit targets, vector counts > 2^32 would exceed memory anyway.
-    // This cast is intentional and documented.
-    #[allow(clippy::cast_possible_truncation)]
-    let vec_count = header.vector_count as usize;
-    let vec_data_len = vec_count * (dim as usize) * 4;
-
-    // Validate offsets
-    // SAFETY: On 32-bit targets, offsets > 2^32 would exceed addressable memory.
-    #[allow(clippy::cast_possible_truncation)]
-    let index_offset_local = (header.index_offset as usize).saturating_sub(64);
-    if index_offset_local > data.len() {
-        return Err(PersistenceError::Header(HeaderError::BufferTooShort(
-            index_offset_local,
-        )));
+    // The CRC above covered the compressed bytes; expand them before decoding
+    if neighbors_compressed {
+        neighbors_bytes = decompress_section(&neighbors_bytes, "neighbor pool")?;
     }
+    let mut tail = match tail {
+        Tail::Packed(data) => TailSections::Packed {
+            data: if header.is_compressed() {
+                Cow::Owned(decompress_section(&data, "tail")?)
+            } else {
+                data
+            },
+            tombstone_bytes: (vec_count + 7) / 8,
+            // Optional sections follow the tombstones and the config (v0.5+)
+            offset: (vec_count + 7) / 8
+                + if header.supports_config() {
+                    ConfigSection::SIZE
+                } else {
+                    0
+                },
+        },
+        Tail::Sections(sections) => TailSections::Sections(
+            sections
+                .into_iter()
+                .map(|(entry, stored)| Ok((entry.kind(), expand(&entry, stored)?)))
+                .collect::<Result<_, PersistenceError>>()?,
+        ),
+    };
 
-    // Slice vector data
-    let vector_bytes = &data[0..index_offset_local];
-    if vector_bytes.len() != vec_data_len {
+    // 2. Reconstruct VectorStorage
+    if index_offset_local != vec_data_len {
         return Err(PersistenceError::Corrupted(format!(
-            "Vector data length mismatch: expected {}, got {}",
-            vec_data_len,
-            vector_bytes.len()
+            "Vector data length mismatch: expected {vec_data_len}, got {index_offset_local}"
         )));
     }
 
+    // Restore the full config (v0.5+) or migrate from header-only params
+    let (data, offset) = tail.locate(SectionKind::CONFIG);
+    let (config, compaction_threshold) = read_config(&header, data, offset)?;
+
     // Create storage
-    let config = HnswConfig::new(dim);
     // Initialize empty storage (no WAL attached yet)
     let mut storage = VectorStorage::new(&config, None);
 
-    // Bulk load vector data
-    // Assuming F32 (flags checked above)
-    // Note: Use try_cast_slice to handle potential alignment issues gracefully.
-    // If the slice is misaligned, fall back to copying byte-by-byte.
-    if !vector_bytes.is_empty() {
-        match bytemuck::try_cast_slice::<u8, f32>(vector_bytes) {
-            Ok(floats) => {
-                storage.data_f32.extend_from_slice(floats);
-            }
-            Err(_) => {
-                // Alignment issue - copy manually via LittleEndian reads
-                // This is slower but handles arbitrary alignment
-                for chunk in vector_bytes.chunks_exact(4) {
-                    let bytes: [u8; 4] = chunk.try_into().expect("chunks_exact guarantees 4 bytes");
-                    storage.data_f32.push(f32::from_le_bytes(bytes));
-                }
-            }
-        }
-    }
+    // The vector block moves in as-is.
+    // SQ8 codes are kept; the quantizer is restored from its section below.
+    storage.data_f32 = vectors_f32;
+    storage.quantized_data = vectors_u8;
 
-    // Reconstruct 'deleted' bitvec
-    if header.metadata_offset > 0 {
-        // SAFETY: On 32-bit targets, offsets > 2^32 would exceed addressable memory.
-        #[allow(clippy::cast_possible_truncation)]
-        let meta_offset = (header.metadata_offset as usize).saturating_sub(64);
-
-        // Robustness: Allow meta_offset to equal data.len() (implies 0 bytes of metadata found).
-        // If vec_count > 0, we expect bytes, but if they are missing, we treat as "all active"
-        // by defaulting to the loop below if slice is empty.
-        if meta_offset <= data.len() {
-            let deleted_bytes = &data[meta_offset..];
-            let deleted_bits = BitVec::<u8, Lsb0>::from_slice(deleted_bytes);
-
-            storage.deleted.clear();
-            for i in 0..vec_count {
-                if i < deleted_bits.len() {
-                    storage.deleted.push(deleted_bits[i]);
-                } else {
-                    storage.deleted.push(false);
-                }
-            }
-        } else {
-            // Offset beyond data length -> Corruption or Truncation.
-            // Fallback to all active.
-            warn!(
-                "Metadata offset {} exceeds data length {}. Treating all vectors as active.",
-                meta_offset,
-                data.len()
-            );
-            for _ in 0..vec_count {
-                storage.deleted.push(false);
-            }
-        }
-    } else {
-        // Legacy/Fallback: assume all active
-        for _ in 0..vec_count {
-            storage.deleted.push(false);
-        }
+    // Reconstruct 'deleted' bitvec from the tombstones.
+    // Robustness: Missing tombstones (no metadata offset, or data missing)
+    // are treated as "all active".
+    let (tombstones, _) = tail.locate(SectionKind::TOMBSTONES);
+    let deleted_bits = BitVec::<u8, Lsb0>::from_slice(tombstones);
+    storage.deleted.clear();
+    for i in 0..vec_count {
+        storage
+            .deleted
+            .push(i < deleted_bits.len() && deleted_bits[i]);
     }
 
     storage.next_id = (vec_count as u64) + 1;
 
     // 3. Reconstruct HnswIndex
-    // Index data starts at index_offset_local
-    let index_bytes = &data[index_offset_local..];
+    // Verify we got the expected number of nodes
+    if nodes.len() != node_count {
+        return Err(PersistenceError::Corrupted(format!(
+            "Node count mismatch: expected {}, got {}",
+            node_count,
+            nodes.len()
+        )));
+    }
 
-    // Calculate node size
-    let nodes_len_bytes = vec_count * size_of::<HnswNode>();
+    // v0.4: Load metadata section if HAS_METADATA flag is set
+    let metadata = if header.has_metadata() {
+        let (data, offset) = tail.locate(SectionKind::METADATA);
+        let (metadata, end) = read_metadata(data, offset)?;
+        tail.advance(end);
+        metadata
+    } else {
+        // No metadata section (v0.3 or v0.4 without metadata)
+        MetadataStore::new()
+    };
 
-    // Note: metadata_offset indicates where neighbors end.
-    // If metadata_offset > index_offset, we can use it to bound the index data.
-    let neighbors_end = if header.metadata_offset > 0 {
-        // SAFETY: On 32-bit targets, offsets > 2^32 would exceed addressable memory.
-        #[allow(clippy::cast_possible_truncation)]
-        let meta_offset_usize = header.metadata_offset as usize;
-        meta_offset_usize.saturating_sub(64) - index_offset_local
+    // v0.5: Load external ID section if HAS_EXTERNAL_IDS flag is set
+    let external_ids = if header.has_external_ids() {
+        let (data, offset) = tail.locate(SectionKind::EXTERNAL_IDS);
+        let (map, end) = read_external_ids(data, offset)?;
+        tail.advance(end);
+        map
+    } else {
+        ExternalIdMap::new()
+    };
+
+    // v0.5: Load BQ vectors if HAS_BQ flag is set (one entry per node)
+    let bq_storage = if header.has_bq() {
+        let (data, offset) = tail.locate(SectionKind::BQ);
+        let (bq_storage, end) = read_bq(data, offset, dim as usize, node_count)?;
+        tail.advance(end);
+        Some(bq_storage)
+    } else {
+        None
+    };
+
+    // v0.5: Restore the SQ8 quantizer if QUANTIZED or DUAL_STORAGE is set
+    if header.is_quantized() || header.is_dual_storage() {
+        let (data, offset) = tail.locate(SectionKind::QUANTIZER);
+        let (storage_type, end) = read_quantizer(data, offset, dim as usize)?;
+        tail.advance(end);
+        if header.is_dual_storage() {
+            storage.set_storage_type(storage_type.into_dual());
+            storage.rebuild_codes();
+        } else {
+            storage.set_storage_type(storage_type);
+        }
+    }
+
+    // v0.5: Load PQ codebooks and codes if HAS_PQ flag is set (one code per node)
+    let pq_storage = if header.has_pq() {
+        let (data, offset) = tail.locate(SectionKind::PQ);
+        let (pq_storage, end) = read_pq(data, offset, dim as usize, node_count, config.metric)?;
+        tail.advance(end);
+        Some(pq_storage)
     } else {
-        index_bytes.len()
+        None
     };
 
-    if nodes_len_bytes > index_bytes.len() {
+    // v0.5: Restore the WAL checkpoint if HAS_CHECKPOINT flag is set
+    if header.has_checkpoint() {
+        let (data, offset) = tail.locate(SectionKind::CHECKPOINT);
+        let section = data
+            .get(offset..)
+            .ok_or_else(|| {
+                PersistenceError::Corrupted("Checkpoint section extends beyond file".into())
+            })
+            .and_then(|bytes| {
+                CheckpointSection::from_bytes(bytes).map_err(|e| {
+                    PersistenceError::Corrupted(format!("Invalid checkpoint section: {e}"))
+                })
+            })?;
+        storage.wal_checkpoint = section.wal_sequence;
+    }
+
+    Ok(SnapshotParts {
+        header,
+        config,
+        compaction_threshold,
+        storage,
+        nodes,
+        neighbors_bytes,
+        metadata,
+        external_ids,
+        bq_storage,
+        pq_storage,
+    })
+}
+
+/// Parses the metadata section starting at `offset` within the snapshot tail.
+///
+/// Returns the store and the offset just past the section.
+pub(crate) fn read_metadata(
+    data: &[u8],
+    offset: usize,
+) -> Result<(MetadataStore, usize), PersistenceError> {
+    if offset + 16 > data.len() {
         return Err(PersistenceError::Corrupted(
-            "Index data too short for nodes".into(),
+            "Metadata section header extends beyond file".into(),
         ));
     }
 
-    let (nodes_bytes, neighbors_rest) = index_bytes.split_at(nodes_len_bytes);
-
-    // neighbors are from split point to neighbors_end
-    // neighbors_rest starts at 0 relative to split.
-    // We want up to neighbors_end - nodes_len_bytes.
-    let neighbors_len = neighbors_end.saturating_sub(nodes_len_bytes);
-    let neighbors_bytes = &neighbors_rest[..std::cmp::min(neighbors_len, neighbors_rest.len())];
-
-    // Parse Nodes using bytemuck for alignment-safe casting.
-    //
-    // This replaces the previous unsafe pointer cast that had undefined behavior
-    // when alignment was not guaranteed. bytemuck::try_cast_slice verifies
-    // alignment at runtime and returns an error if misaligned.
-    //
-    // See: docs/audits/unsafe_audit_persistence.md for the original issue.
-    // Fixed in: W13.2 (bytemuck integration)
-    // Updated in: W26.5 - handle alignment fallback by copying to aligned Vec
-    let nodes: Vec<HnswNode> = if nodes_bytes.is_empty() {
-        Vec::new()
-    } else if let Ok(nodes) = try_cast_slice::<u8, HnswNode>(nodes_bytes) {
-        nodes.to_vec()
+    // Read MetadataSectionHeader (16 bytes)
+    let meta_header = MetadataSectionHeader::from_bytes(&data[offset..])
+        .map_err(|e| PersistenceError::Corrupted(format!("Invalid metadata header: {e}")))?;
+
+    // Validate CRC before deserializing
+    let meta_data_start = offset + 16;
+    let meta_data_end = meta_data_start + meta_header.size as usize;
+
+    if meta_data_end > data.len() {
+        return Err(PersistenceError::Corrupted(format!(
+            "Metadata section data extends beyond file: need {} bytes, have {}",
+            meta_data_end,
+            data.len()
+        )));
+    }
+
+    let meta_data = &data[meta_data_start..meta_data_end];
+    let actual_crc = crc32fast::hash(meta_data);
+    if actual_crc != meta_header.crc {
+        return Err(PersistenceError::Corrupted(format!(
+            "Metadata CRC mismatch: expected {:#x}, got {:#x}",
+            meta_header.crc, actual_crc
+        )));
+    }
+
+    // Deserialize based on format
+    let store = if meta_header.is_postcard() {
+        MetadataStore::from_postcard(meta_data).map_err(|e| {
+            PersistenceError::Corrupted(format!("Metadata postcard decode failed: {e}"))
+        })?
+    } else if meta_header.is_json() {
+        MetadataStore::from_json(meta_data)
+            .map_err(|e| PersistenceError::Corrupted(format!("Metadata JSON decode failed: {e}")))?
     } else {
-        // Alignment issue - copy to aligned Vec<u8> first, then cast
-        // This is slower but handles arbitrary alignment
-        let mut aligned: Vec<u8> = Vec::with_capacity(nodes_bytes.len());
-        aligned.extend_from_slice(nodes_bytes);
-        // Now aligned should be properly aligned for HnswNode
-        try_cast_slice::<u8, HnswNode>(&aligned)
-            .map_err(|e| {
-                PersistenceError::Corrupted(format!(
-                    "HnswNode alignment error after copy: {e:?}. Data may be corrupted."
-                ))
-            })?
-            .to_vec()
+        return Err(PersistenceError::Corrupted(format!(
+            "Unknown metadata format: {}",
+            meta_header.format
+        )));
     };
 
-    // Verify we got the expected number of nodes
-    if nodes.len() != vec_count {
+    debug!(
+        "Loaded metadata section: {} vectors, {} total keys",
+        store.vector_count(),
+        store.total_key_count()
+    );
+
+    Ok((store, meta_data_end))
+}
+
+/// Reads up to `count` values of `T` from `reader`, one chunk at a time.
+///
+/// Capacity is reserved once, so the result never holds more than its
+/// final size; a short stream yields a short vector (the caller's CRC check
+/// rejects it).
+fn read_pod_vec<T: Pod>(reader: &mut dyn Read, count: usize) -> Result<Vec<T>, PersistenceError> {
+    let mut out: Vec<T> = Vec::new();
+    out.try_reserve_exact(count).map_err(|e| {
+        PersistenceError::Corrupted(format!("snapshot section of {count} values: {e}"))
+    })?;
+
+    let per_chunk = (SNAPSHOT_CHUNK_SIZE / size_of::<T>()).max(1);
+    while out.len() < count {
+        let start = out.len();
+        let n = per_chunk.min(count - start);
+        out.resize(start + n, T::zeroed());
+        let filled = read_full(reader, bytemuck::cast_slice_mut(&mut out[start..]))?;
+        if filled < n * size_of::<T>() {
+            out.truncate(start + filled / size_of::<T>());
+            break;
+        }
+    }
+    Ok(out)
+}
+
+/// Parses the external ID section starting at `offset` within the snapshot tail.
+///
+/// Returns the map and the offset just past the section.
+fn read_external_ids(
+    data: &[u8],
+    offset: usize,
+) -> Result<(ExternalIdMap, usize), PersistenceError> {
+    let section_header = data
+        .get(offset..)
+        .ok_or_else(|| {
+            PersistenceError::Corrupted("External ID section extends beyond file".into())
+        })
+        .and_then(|bytes| {
+            ExternalIdSectionHeader::from_bytes(bytes).map_err(|e| {
+                PersistenceError::Corrupted(format!("Invalid external ID header: {e}"))
+            })
+        })?;
+
+    let start = offset + ExternalIdSectionHeader::SIZE;
+    let end = start + section_header.size as usize;
+    if end > data.len() {
         return Err(PersistenceError::Corrupted(format!(
-            "Node count mismatch: expected {}, got {}",
-            vec_count,
-            nodes.len()
+            "External ID section data extends beyond file: need {} bytes, have {}",
+            end,
+            data.len()
         )));
     }
 
-    // Construct Index
-    let mut index =
-        HnswIndex::new(config, &storage).map_err(|e| PersistenceError::Corrupted(e.to_string()))?;
+    let payload = &data[start..end];
+    let actual_crc = crc32fast::hash(payload);
+    if actual_crc != section_header.crc {
+        return Err(PersistenceError::Corrupted(format!(
+            "External ID CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+            section_header.crc
+        )));
+    }
 
-    // Restore nodes
-    index.nodes = nodes;
+    let map = ExternalIdMap::from_postcard(payload).map_err(|e| {
+        PersistenceError::Corrupted(format!("External ID postcard decode failed: {e}"))
+    })?;
 
-    // Restore neighbors
-    index.neighbors.buffer.extend_from_slice(neighbors_bytes);
+    debug!("Loaded external ID section: {} entries", map.len());
+    Ok((map, end))
+}
 
-    // Restore max_layer and entry_point
-    let mut max_layer = 0;
-    let mut entry_point = None;
+/// Parses the BQ section starting at `offset` within the snapshot tail.
+///
+/// The section must hold exactly `count` vectors of `dimensions` bits.
+/// Returns the storage and the offset just past the section.
+fn read_bq(
+    data: &[u8],
+    offset: usize,
+    dimensions: usize,
+    count: usize,
+) -> Result<(BinaryVectorStorage, usize), PersistenceError> {
+    let section_header = data
+        .get(offset..)
+        .ok_or_else(|| PersistenceError::Corrupted("BQ section extends beyond file".into()))
+        .and_then(|bytes| {
+            BqSectionHeader::from_bytes(bytes)
+                .map_err(|e| PersistenceError::Corrupted(format!("Invalid BQ header: {e}")))
+        })?;
 
-    for (i, node) in index.nodes.iter().enumerate() {
-        if node.max_layer > max_layer {
-            max_layer = node.max_layer;
-        }
-        if node.max_layer == max_layer {
-            // SAFETY: Node index fits in u32 (max ~4B nodes, well within limits).
-            #[allow(clippy::cast_possible_truncation)]
-            let node_id = i as u32;
-            entry_point = Some(NodeId(node_id));
-        }
+    let start = offset + BqSectionHeader::SIZE;
+    let end = start + section_header.size as usize;
+    if end > data.len() {
+        return Err(PersistenceError::Corrupted(format!(
+            "BQ section data extends beyond file: need {} bytes, have {}",
+            end,
+            data.len()
+        )));
     }
 
-    index.max_layer = max_layer;
-    index.entry_point = entry_point;
+    let payload = &data[start..end];
+    let actual_crc = crc32fast::hash(payload);
+    if actual_crc != section_header.crc {
+        return Err(PersistenceError::Corrupted(format!(
+            "BQ CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+            section_header.crc
+        )));
+    }
 
-    // v0.3: Restore deleted_count from header or recalculate for older formats
-    if header.supports_soft_delete() {
-        // Trust header value for v0.3+
-        index.deleted_count = header.deleted_count as usize;
+    let vectors_len = count * (dimensions / 8);
+    let expected = vectors_len + (count + 7) / 8;
+    if payload.len() != expected {
+        return Err(PersistenceError::Corrupted(format!(
+            "BQ section size mismatch: expected {expected} bytes for {count} vectors, got {}",
+            payload.len()
+        )));
+    }
 
-        // Verify consistency: count actual deleted nodes
-        let actual_deleted = index.nodes.iter().filter(|n| n.deleted != 0).count();
-        if actual_deleted != index.deleted_count {
-            // Mismatch detected — use actual count for safety
-            // This can happen if the snapshot was corrupted or manually edited
-            warn!(
-                "Snapshot deleted_count mismatch (header={}, actual={}). Using actual count.",
-                index.deleted_count, actual_deleted
-            );
-            index.deleted_count = actual_deleted;
-        }
-    } else if header.needs_migration() {
-        // Migration from v0.1/v0.2: node.pad was always 0, now interpreted as deleted=0
-        // All nodes are live in old format, deleted_count = 0
-        index.deleted_count = 0;
+    let (vectors, tombstones) = payload.split_at(vectors_len);
+    let mut deleted = BitVec::<u8, Lsb0>::from_slice(tombstones);
+    deleted.truncate(count);
 
-        info!(
-            "Migrated snapshot from v0.{} to v0.3 format (soft delete enabled)",
-            header.version_minor
-        );
+    let bq_storage = BinaryVectorStorage::from_raw_parts(dimensions, vectors.to_vec(), deleted)
+        .map_err(|e| PersistenceError::Corrupted(format!("Invalid BQ section: {e}")))?;
+
+    debug!("Loaded BQ section: {} vectors", bq_storage.len());
+    Ok((bq_storage, end))
+}
+
+/// Parses the PQ section starting at `offset` within the snapshot tail.
+///
+/// Returns the code storage and the offset just past the section.
+fn read_pq(
+    data: &[u8],
+    offset: usize,
+    dimensions: usize,
+    count: usize,
+    metric: u32,
+) -> Result<(PqCodeStorage, usize), PersistenceError> {
+    let section_header = data
+        .get(offset..)
+        .ok_or_else(|| PersistenceError::Corrupted("PQ section extends beyond file".into()))
+        .and_then(|bytes| {
+            PqSectionHeader::from_bytes(bytes)
+                .map_err(|e| PersistenceError::Corrupted(format!("Invalid PQ header: {e}")))
+        })?;
+
+    let start = offset + PqSectionHeader::SIZE;
+    let end = start + section_header.size as usize;
+    if end > data.len() {
+        return Err(PersistenceError::Corrupted(format!(
+            "PQ section data extends beyond file: need {} bytes, have {}",
+            end,
+            data.len()
+        )));
     }
 
-    // v0.4: Load metadata section if HAS_METADATA flag is set
-    if header.has_metadata() {
-        // Calculate metadata section offset
-        // It comes after: vector data + hnsw nodes + neighbors + tombstone bitvec
-        let tombstone_bytes = (vec_count + 7) / 8;
-        // SAFETY: On 32-bit targets, offsets > 2^32 would exceed addressable memory.
-        #[allow(clippy::cast_possible_truncation)]
-        let tombstone_offset = (header.metadata_offset as usize).saturating_sub(64);
-        let metadata_section_offset = tombstone_offset + tombstone_bytes;
+    let payload = &data[start..end];
+    let actual_crc = crc32fast::hash(payload);
+    if actual_crc != section_header.crc {
+        return Err(PersistenceError::Corrupted(format!(
+            "PQ CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+            section_header.crc
+        )));
+    }
 
-        if metadata_section_offset + 16 > data.len() {
-            return Err(PersistenceError::Corrupted(
-                "Metadata section header extends beyond file".into(),
-            ));
-        }
+    if payload.len() < 8 {
+        return Err(PersistenceError::Corrupted("PQ section too short".into()));
+    }
+    let (shape, rest) = payload.split_at(8);
+    let sub_vectors = u32::from_le_bytes([shape[0], shape[1], shape[2], shape[3]]) as usize;
+    let centroids = u32::from_le_bytes([shape[4], shape[5], shape[6], shape[7]]) as usize;
 
-        // Read MetadataSectionHeader (16 bytes)
-        let meta_header = MetadataSectionHeader::from_bytes(&data[metadata_section_offset..])
-            .map_err(|e| PersistenceError::Corrupted(format!("Invalid metadata header: {e}")))?;
+    // Sizes come from the file; a damaged shape must not overflow
+    let codebooks_len = centroids
+        .checked_mul(dimensions * size_of::<f32>())
+        .ok_or_else(|| PersistenceError::Corrupted("PQ centroid count out of range".into()))?;
+    let expected = count
+        .checked_mul(sub_vectors)
+        .and_then(|codes| codes.checked_add(codebooks_len));
+    if expected != Some(rest.len()) {
+        let expected = expected.unwrap_or(usize::MAX);
+        return Err(PersistenceError::Corrupted(format!(
+            "PQ section size mismatch: expected {expected} bytes for {count} codes, got {}",
+            rest.len()
+        )));
+    }
 
-        // Validate CRC before deserializing
-        let meta_data_start = metadata_section_offset + 16;
-        let meta_data_end = meta_data_start + meta_header.size as usize;
+    let (codebooks, codes) = rest.split_at(codebooks_len);
+    let codebooks = codebooks
+        .chunks_exact(size_of::<f32>())
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    let quantizer = ProductQuantizer::from_codebooks(dimensions, sub_vectors, centroids, codebooks)
+        .map_err(|e| PersistenceError::Corrupted(format!("Invalid PQ section: {e}")))?;
+    let pq_storage = PqCodeStorage::from_raw_parts(quantizer, metric, codes.to_vec())
+        .map_err(|e| PersistenceError::Corrupted(format!("Invalid PQ section: {e}")))?;
 
-        if meta_data_end > data.len() {
-            return Err(PersistenceError::Corrupted(format!(
-                "Metadata section data extends beyond file: need {} bytes, have {}",
-                meta_data_end,
-                data.len()
-            )));
-        }
+    debug!("Loaded PQ section: {} codes", pq_storage.len());
+    Ok((pq_storage, end))
+}
 
-        let meta_data = &data[meta_data_start..meta_data_end];
-        let actual_crc = crc32fast::hash(meta_data);
-        if actual_crc != meta_header.crc {
+/// Parses the quantizer section starting at `offset` within the snapshot tail.
+///
+/// Returns the SQ8 storage type and the offset just past the section.
+/// Per-dimension ranges (version 2) must cover `dimensions`.
+fn read_quantizer(
+    data: &[u8],
+    offset: usize,
+    dimensions: usize,
+) -> Result<(StorageType, usize), PersistenceError> {
+    let section_header = data
+        .get(offset..)
+        .ok_or_else(|| PersistenceError::Corrupted("Quantizer section extends beyond file".into()))
+        .and_then(|bytes| {
+            QuantizerSectionHeader::from_bytes(bytes)
+                .map_err(|e| PersistenceError::Corrupted(format!("Invalid quantizer header: {e}")))
+        })?;
+
+    let start = offset + QuantizerSectionHeader::SIZE;
+    let end = start + section_header.size as usize;
+    if end > data.len() {
+        return Err(PersistenceError::Corrupted(format!(
+            "Quantizer section data extends beyond file: need {} bytes, have {}",
+            end,
+            data.len()
+        )));
+    }
+
+    let payload = &data[start..end];
+    let actual_crc = crc32fast::hash(payload);
+    if actual_crc != section_header.crc {
+        return Err(PersistenceError::Corrupted(format!(
+            "Quantizer CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+            section_header.crc
+        )));
+    }
+
+    let decode_error = |e: postcard::Error| {
+        PersistenceError::Corrupted(format!("Quantizer postcard decode failed: {e}"))
+    };
+
+    if section_header.has_ranges() {
+        let ranges: Sq8Ranges = postcard::from_bytes(payload).map_err(decode_error)?;
+        if ranges.min.len() != dimensions || ranges.max.len() != dimensions {
             return Err(PersistenceError::Corrupted(format!(
-                "Metadata CRC mismatch: expected {:#x}, got {:#x}",
-                meta_header.crc, actual_crc
+                "Quantizer ranges cover {}/{} dimensions, expected {dimensions}",
+                ranges.min.len(),
+                ranges.max.len()
             )));
         }
+        debug!(
+            "Loaded quantizer section: {:?} ranges for {dimensions} dimensions",
+            ranges.mode
+        );
+        return Ok((StorageType::QuantizedU8Ranges(ranges), end));
+    }
 
-        // Deserialize based on format
-        let loaded_metadata = if meta_header.is_postcard() {
-            MetadataStore::from_postcard(meta_data).map_err(|e| {
-                PersistenceError::Corrupted(format!("Metadata postcard decode failed: {e}"))
-            })?
-        } else if meta_header.is_json() {
-            MetadataStore::from_json(meta_data).map_err(|e| {
-                PersistenceError::Corrupted(format!("Metadata JSON decode failed: {e}"))
-            })?
-        } else {
-            return Err(PersistenceError::Corrupted(format!(
-                "Unknown metadata format: {}",
-                meta_header.format
-            )));
-        };
+    let q_config: QuantizerConfig = postcard::from_bytes(payload).map_err(decode_error)?;
 
-        debug!(
-            "Loaded metadata section: {} vectors, {} total keys",
-            loaded_metadata.vector_count(),
-            loaded_metadata.total_key_count()
+    debug!(
+        "Loaded quantizer section: min={}, max={}",
+        q_config.min, q_config.max
+    );
+    Ok((StorageType::QuantizedU8(q_config), end))
+}
+
+/// Restores the `HnswConfig` and compaction threshold for a snapshot.
+///
+/// v0.5+ snapshots carry a [`ConfigSection`] after the tombstone bitvec,
+/// starting at `offset` within `data`.
+/// Older snapshots only stored `m`/`m0` in the header; for those the remaining
+/// parameters fall back to `HnswConfig::new` defaults and a warning is logged,
+/// since the metric may differ from the one the index was built with.
+fn read_config(
+    header: &FileHeader,
+    data: &[u8],
+    offset: usize,
+) -> Result<(HnswConfig, Option<f64>), PersistenceError> {
+    if !header.supports_config() {
+        let mut config = HnswConfig::new(header.dimensions);
+        config.m = header.hnsw_m;
+        config.m0 = header.hnsw_m0;
+
+        warn!(
+            "Snapshot v0.{} does not store the full HNSW config; metric, ef_construction \
+             and ef_search reset to defaults (L2, {}, {}). Re-save to upgrade to v0.{}.",
+            header.version_minor,
+            config.ef_construction,
+            config.ef_search,
+            FileHeader::VERSION_MINOR
         );
+        return Ok((config, None));
+    }
 
-        index.metadata = loaded_metadata;
-    } else {
-        // No metadata section (v0.3 or v0.4 without metadata)
-        index.metadata = MetadataStore::new();
+    if header.metadata_offset == 0 || offset + ConfigSection::SIZE > data.len() {
+        return Err(PersistenceError::Corrupted(
+            "Config section extends beyond file".into(),
+        ));
     }
 
-    Ok((index, storage))
+    let section = ConfigSection::from_bytes(&data[offset..])
+        .map_err(|e| PersistenceError::Corrupted(format!("Invalid config section: {e}")))?;
+
+    if section.dimensions != header.dimensions
+        || section.m != header.hnsw_m
+        || section.m0 != header.hnsw_m0
+    {
+        return Err(PersistenceError::Corrupted(format!(
+            "Config section disagrees with header: dim/m/m0 = {}/{}/{}, header = {}/{}/{}",
+            section.dimensions,
+            section.m,
+            section.m0,
+            header.dimensions,
+            header.hnsw_m,
+            header.hnsw_m0
+        )));
+    }
+
+    // Reject unknown metrics here rather than failing on the first search
+    if !matches!(
+        section.metric,
+        HnswConfig::METRIC_L2_SQUARED | HnswConfig::METRIC_COSINE | HnswConfig::METRIC_DOT_PRODUCT
+    ) {
+        return Err(PersistenceError::Corrupted(format!(
+            "Config section has unknown metric code {}",
+            section.metric
+        )));
+    }
+
+    debug!(
+        "Loaded config section: metric={}, ef_construction={}, ef_search={}",
+        section.metric, section.ef_construction, section.ef_search
+    );
+
+    Ok((section.to_config(), Some(section.compaction_threshold)))
 }
@@ -1,7 +1,35 @@
 use super::{header::FileHeader, PersistenceError};
 use crc32fast::Hasher;
+use std::io::{self, Cursor, Read};
 use std::sync::{Arc, RwLock};
 
+/// A blob being streamed to a backend, replacing its content atomically.
+///
+/// Nothing is visible until [`SnapshotWriter::commit`]; dropping the writer
+/// first leaves the previous content in place.
+pub trait SnapshotWriter {
+    /// Appends `data` to the blob.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if writing fails.
+    fn write(&mut self, data: &[u8]) -> Result<(), PersistenceError>;
+
+    /// Overwrites bytes already written, starting at `offset`.
+    ///
+    /// Used to fill in the snapshot header once the data CRC is known.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if writing fails, or
+    /// `PersistenceError::Corrupted` if the range was not written yet.
+    fn patch(&mut self, offset: usize, data: &[u8]) -> Result<(), PersistenceError>;
+
+    /// Commits the written bytes as the new content.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if flushing or renaming fails.
+    fn commit(self: Box<Self>) -> Result<(), PersistenceError>;
+}
+
 /// Abstraction for persistent storage backend.
 ///
 /// Enables swappable backends for File (native) and IndexedDB (WASM).
@@ -33,6 +61,58 @@ pub trait StorageBackend: Send + Sync {
     /// # Errors
     /// Returns `PersistenceError::Io` if writing or renaming fails.
     fn atomic_write(&self, key: &str, data: &[u8]) -> Result<(), PersistenceError>;
+
+    /// Discards the first `len` bytes, keeping the rest.
+    ///
+    /// Used to drop WAL records covered by a snapshot checkpoint. The default
+    /// implementation rewrites the remainder with `atomic_write`, so a crash
+    /// leaves either the old or the truncated content.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if reading or writing fails.
+    fn truncate_front(&mut self, len: usize) -> Result<(), PersistenceError> {
+        let data = self.read()?;
+        self.atomic_write("", data.get(len..).unwrap_or_default())
+    }
+
+    /// Starts a streamed atomic write identified by `key`.
+    ///
+    /// The default collects the bytes in memory and hands them to
+    /// `atomic_write` on commit. Backends that can write incrementally
+    /// should override it so snapshots are not buffered whole.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if the write cannot be started.
+    fn atomic_writer<'a>(
+        &'a self,
+        key: &str,
+    ) -> Result<Box<dyn SnapshotWriter + 'a>, PersistenceError> {
+        Ok(Box::new(BufferedWriter {
+            backend: self,
+            key: key.to_string(),
+            buffer: Vec::new(),
+        }))
+    }
+
+    /// Opens the storage content for sequential reading.
+    ///
+    /// The default reads everything with `read`. Backends that can stream
+    /// should override it.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if reading fails.
+    fn open_reader(&self) -> Result<Box<dyn Read + '_>, PersistenceError> {
+        Ok(Box::new(Cursor::new(self.read()?)))
+    }
+
+    /// Returns the read-only memory mapping of the content, if the backend
+    /// has one.
+    ///
+    /// `read_snapshot` borrows vectors and nodes from the mapping instead of
+    /// copying them. The default returns `None`.
+    fn mapped(&self) -> Option<MappedFile> {
+        None
+    }
 }
 
 /// Abstraction for persistent storage backend.
@@ -66,11 +146,176 @@ pub trait StorageBackend {
     /// # Errors
     /// Returns `PersistenceError::Io` if writing fails.
     fn atomic_write(&self, key: &str, data: &[u8]) -> Result<(), PersistenceError>;
+
+    /// Discards the first `len` bytes, keeping the rest.
+    ///
+    /// Used to drop WAL records covered by a snapshot checkpoint. The default
+    /// implementation rewrites the remainder with `atomic_write`, so a crash
+    /// leaves either the old or the truncated content.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if reading or writing fails.
+    fn truncate_front(&mut self, len: usize) -> Result<(), PersistenceError> {
+        let data = self.read()?;
+        self.atomic_write("", data.get(len..).unwrap_or_default())
+    }
+
+    /// Starts a streamed atomic write identified by `key`.
+    ///
+    /// The default collects the bytes in memory and hands them to
+    /// `atomic_write` on commit. Backends that can write incrementally
+    /// should override it so snapshots are not buffered whole.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if the write cannot be started.
+    fn atomic_writer<'a>(
+        &'a self,
+        key: &str,
+    ) -> Result<Box<dyn SnapshotWriter + 'a>, PersistenceError> {
+        Ok(Box::new(BufferedWriter {
+            backend: self,
+            key: key.to_string(),
+            buffer: Vec::new(),
+        }))
+    }
+
+    /// Opens the storage content for sequential reading.
+    ///
+    /// The default reads everything with `read`. Backends that can stream
+    /// should override it.
+    ///
+    /// # Errors
+    /// Returns `PersistenceError::Io` if reading fails.
+    fn open_reader(&self) -> Result<Box<dyn Read + '_>, PersistenceError> {
+        Ok(Box::new(Cursor::new(self.read()?)))
+    }
+}
+
+/// A read-only memory mapping of a snapshot file, shared by everything
+/// borrowed from it.
+///
+/// Created by [`file::FileBackend::open_mmap`]. Cloning is cheap; the
+/// mapping is released when the last clone (including any index or storage
+/// borrowing from it) is dropped.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub struct MappedFile {
+    map: Arc<memmap2::Mmap>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::ops::Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for MappedFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedFile")
+            .field("len", &self.map.len())
+            .finish()
+    }
+}
+
+/// Default [`SnapshotWriter`]: buffers the blob and commits it with
+/// `atomic_write`.
+struct BufferedWriter<'a, B: ?Sized> {
+    backend: &'a B,
+    key: String,
+    buffer: Vec<u8>,
+}
+
+impl<B: StorageBackend + ?Sized> SnapshotWriter for BufferedWriter<'_, B> {
+    fn write(&mut self, data: &[u8]) -> Result<(), PersistenceError> {
+        self.buffer.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn patch(&mut self, offset: usize, data: &[u8]) -> Result<(), PersistenceError> {
+        let target = self
+            .buffer
+            .get_mut(offset..offset + data.len())
+            .ok_or_else(|| PersistenceError::Corrupted("patch beyond written data".into()))?;
+        target.copy_from_slice(data);
+        Ok(())
+    }
+
+    fn commit(self: Box<Self>) -> Result<(), PersistenceError> {
+        self.backend.atomic_write(&self.key, &self.buffer)
+    }
+}
+
+/// Wraps a reader, computing the CRC32 of everything read through it.
+pub(crate) struct CrcReader<R> {
+    inner: R,
+    hasher: Hasher,
+}
+
+impl<R: Read> CrcReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Hasher::new(),
+        }
+    }
+
+    /// Returns the CRC32 of the bytes read so far.
+    pub(crate) fn finalize(self) -> u32 {
+        self.hasher.finalize()
+    }
+}
+
+impl<R: Read> Read for CrcReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+/// Fills `buf` from `reader`, stopping early only at end of input.
+///
+/// Returns the number of bytes read.
+pub(crate) fn read_full(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Reads and validates the 64-byte file header at the start of `reader`.
+///
+/// # Errors
+/// Returns `PersistenceError::BufferTooSmall` if fewer than 64 bytes are
+/// available, or the header validation error.
+pub(crate) fn read_header(reader: &mut dyn Read) -> Result<FileHeader, PersistenceError> {
+    // Read into a FileHeader so the bytes are aligned for validation
+    let mut header = <FileHeader as bytemuck::Zeroable>::zeroed();
+    let n = read_full(reader, bytemuck::bytes_of_mut(&mut header))?;
+    if n < 64 {
+        return Err(PersistenceError::BufferTooSmall {
+            expected: 64,
+            actual: n,
+        });
+    }
+    Ok(FileHeader::from_bytes(header.as_bytes())?)
 }
 
 /// Loads a snapshot from storage with full integrity verification.
 ///
-/// This replaces the legacy `StorageBackend::load` static method.
+/// This replaces the legacy `StorageBackend::load` static method. The
+/// payload is read through [`StorageBackend::open_reader`] with the CRC
+/// computed as it streams in.
 ///
 /// # Errors
 /// Returns `PersistenceError` if:
@@ -81,25 +326,18 @@ pub trait StorageBackend {
 pub fn load_snapshot(
     backend: &dyn StorageBackend,
 ) -> Result<(FileHeader, Vec<u8>), PersistenceError> {
-    let data = backend.read()?;
+    let mut reader = backend.open_reader()?;
 
-    // 1. Check Buffer Size (Header)
-    if data.len() < 64 {
-        return Err(PersistenceError::BufferTooSmall {
-            expected: 64,
-            actual: data.len(),
-        });
-    }
+    // 1. Parse and Validate Header
+    let header = read_header(&mut reader)?;
 
-    // 2. Parse and Validate Header
-    let header = FileHeader::from_bytes(&data[0..64])?;
+    // 2. Read the payload, computing its CRC
+    let mut reader = CrcReader::new(reader);
+    let mut payload = Vec::new();
+    reader.read_to_end(&mut payload)?;
 
     // 3. Verify Data CRC
-    let payload = &data[64..];
-    let mut hasher = Hasher::new();
-    hasher.update(payload);
-    let calculated_crc = hasher.finalize();
-
+    let calculated_crc = reader.finalize();
     if calculated_crc != header.data_crc {
         return Err(PersistenceError::ChecksumMismatch {
             expected: header.data_crc,
@@ -107,7 +345,7 @@ pub fn load_snapshot(
         });
     }
 
-    Ok((header, payload.to_vec()))
+    Ok((header, payload))
 }
 
 /// In-memory storage backend for testing and WASM.
@@ -164,13 +402,13 @@ impl StorageBackend for MemoryBackend {
 #[cfg(not(target_arch = "wasm32"))]
 /// File-based storage implementation.
 pub mod file {
-    use super::{PersistenceError, StorageBackend}; // Minimal imports
-                                                   // Re-import things needed from std as they are not in super in a way that * imports cleanly without clippy complaining
-                                                   // Actually clippy suggests explicit imports.
+    use super::{MappedFile, PersistenceError, SnapshotWriter, StorageBackend}; // Minimal imports
+                                                                               // Re-import things needed from std as they are not in super in a way that * imports cleanly without clippy complaining
+                                                                               // Actually clippy suggests explicit imports.
     use std::fs::{File, OpenOptions};
-    use std::io::{Read, Write};
+    use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
     use std::path::{Path, PathBuf};
-    use std::sync::Mutex;
+    use std::sync::{Arc, Mutex};
 
     /// File-based storage backend.
     #[derive(Debug)]
@@ -178,6 +416,8 @@ pub mod file {
         path: PathBuf,
         /// Mutex protected file handle to allow interior mutability in atomic_write
         file: Mutex<Option<File>>,
+        /// Read-only mapping, set by `open_mmap`
+        mapped: Option<MappedFile>,
     }
 
     impl FileBackend {
@@ -186,7 +426,47 @@ pub mod file {
             Self {
                 path: path.into(),
                 file: Mutex::new(None),
+                mapped: None,
+            }
+        }
+
+        /// Opens an existing file read-only and memory-maps it.
+        ///
+        /// `read_snapshot` on this backend borrows vector data and nodes
+        /// straight from the mapping, so loading costs page faults rather
+        /// than a copy of the file. The first mutation of the loaded index or
+        /// storage copies the affected buffer to the heap. Writes through
+        /// this backend fail with `PersistenceError::Unsupported`.
+        ///
+        /// Snapshots are replaced by renaming a new file over the old one,
+        /// which leaves an existing mapping intact. The file must not be
+        /// truncated or modified in place while mapped.
+        ///
+        /// # Errors
+        ///
+        /// Returns `PersistenceError::Io` if the file cannot be opened or
+        /// mapped.
+        pub fn open_mmap(path: impl Into<PathBuf>) -> Result<Self, PersistenceError> {
+            let path = path.into();
+            let file = File::open(&path).map_err(PersistenceError::Io)?;
+            // SAFETY: The mapping is read-only. Snapshot writers replace the
+            // file by rename, so the mapped inode is never modified; in-place
+            // modification by other processes is documented as unsupported.
+            let map = unsafe { memmap2::Mmap::map(&file) }.map_err(PersistenceError::Io)?;
+            Ok(Self {
+                path,
+                file: Mutex::new(None),
+                mapped: Some(MappedFile { map: Arc::new(map) }),
+            })
+        }
+
+        fn check_writable(&self) -> Result<(), PersistenceError> {
+            if self.mapped.is_some() {
+                return Err(PersistenceError::Unsupported(
+                    "FileBackend opened read-only with open_mmap".into(),
+                ));
             }
+            Ok(())
         }
 
         fn open_append(&self) -> Result<File, PersistenceError> {
@@ -209,10 +489,83 @@ pub mod file {
             let file = guard.as_ref().ok_or(PersistenceError::NotInitialized)?;
             file.try_clone().map_err(PersistenceError::Io)
         }
+
+        /// Drops the cached append handle after the file was replaced.
+        fn invalidate_handle(&self) -> Result<(), PersistenceError> {
+            let mut guard = self.file.lock().map_err(|_| {
+                PersistenceError::Io(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "lock poisoned",
+                ))
+            })?;
+            *guard = None;
+            Ok(())
+        }
+    }
+
+    /// Streams a blob to a temporary file and renames it over the target on
+    /// commit.
+    struct FileSnapshotWriter<'a> {
+        backend: &'a FileBackend,
+        file: Option<BufWriter<File>>,
+        tmp_path: PathBuf,
+        target: PathBuf,
+    }
+
+    impl FileSnapshotWriter<'_> {
+        fn file(&mut self) -> Result<&mut BufWriter<File>, PersistenceError> {
+            self.file.as_mut().ok_or(PersistenceError::NotInitialized)
+        }
+    }
+
+    impl SnapshotWriter for FileSnapshotWriter<'_> {
+        fn write(&mut self, data: &[u8]) -> Result<(), PersistenceError> {
+            self.file()?.write_all(data).map_err(PersistenceError::Io)
+        }
+
+        fn patch(&mut self, offset: usize, data: &[u8]) -> Result<(), PersistenceError> {
+            let file = self.file()?;
+            let end = file.seek(SeekFrom::End(0)).map_err(PersistenceError::Io)?;
+            if (offset + data.len()) as u64 > end {
+                return Err(PersistenceError::Corrupted(
+                    "patch beyond written data".into(),
+                ));
+            }
+            file.seek(SeekFrom::Start(offset as u64))
+                .map_err(PersistenceError::Io)?;
+            file.write_all(data).map_err(PersistenceError::Io)?;
+            file.seek(SeekFrom::End(0)).map_err(PersistenceError::Io)?;
+            Ok(())
+        }
+
+        fn commit(mut self: Box<Self>) -> Result<(), PersistenceError> {
+            let file = self
+                .file
+                .take()
+                .ok_or(PersistenceError::NotInitialized)?
+                .into_inner()
+                .map_err(|e| PersistenceError::Io(e.into_error()))?;
+            file.sync_all().map_err(PersistenceError::Io)?;
+            drop(file);
+
+            std::fs::rename(&self.tmp_path, &self.target).map_err(PersistenceError::Io)?;
+            sync_parent_dir(&self.target)?;
+            self.backend.invalidate_handle()
+        }
+    }
+
+    impl Drop for FileSnapshotWriter<'_> {
+        fn drop(&mut self) {
+            // Not committed: discard the partial temporary file
+            if self.file.take().is_some() {
+                let _ = std::fs::remove_file(&self.tmp_path);
+            }
+        }
     }
 
     impl StorageBackend for FileBackend {
         fn append(&mut self, data: &[u8]) -> Result<(), PersistenceError> {
+            self.check_writable()?;
             let mut file = self.open_append()?;
             file.write_all(data).map_err(PersistenceError::Io)?;
             file.sync_all().map_err(PersistenceError::Io)?;
@@ -220,6 +573,9 @@ pub mod file {
         }
 
         fn read(&self) -> Result<Vec<u8>, PersistenceError> {
+            if let Some(mapped) = &self.mapped {
+                return Ok(mapped.to_vec());
+            }
             if !self.path.exists() {
                 return Ok(Vec::new());
             }
@@ -231,26 +587,40 @@ pub mod file {
         }
 
         fn atomic_write(&self, key: &str, data: &[u8]) -> Result<(), PersistenceError> {
+            let mut writer = self.atomic_writer(key)?;
+            writer.write(data)?;
+            writer.commit()
+        }
+
+        fn atomic_writer<'a>(
+            &'a self,
+            key: &str,
+        ) -> Result<Box<dyn SnapshotWriter + 'a>, PersistenceError> {
+            self.check_writable()?;
             let target = resolve_target_path(&self.path, key);
             let tmp_path = target.with_extension("tmp");
-            {
-                let mut file = File::create(&tmp_path).map_err(PersistenceError::Io)?;
-                file.write_all(data).map_err(PersistenceError::Io)?;
-                file.sync_all().map_err(PersistenceError::Io)?;
-            }
-            std::fs::rename(&tmp_path, &target).map_err(PersistenceError::Io)?;
-            sync_parent_dir(&target)?;
+            let file = File::create(&tmp_path).map_err(PersistenceError::Io)?;
+            Ok(Box::new(FileSnapshotWriter {
+                backend: self,
+                file: Some(BufWriter::new(file)),
+                tmp_path,
+                target,
+            }))
+        }
 
-            // Invalidate cache
-            let mut guard = self.file.lock().map_err(|_| {
-                PersistenceError::Io(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    "lock poisoned",
-                ))
-            })?;
-            *guard = None;
+        fn open_reader(&self) -> Result<Box<dyn Read + '_>, PersistenceError> {
+            if let Some(mapped) = &self.mapped {
+                return Ok(Box::new(&mapped[..]));
+            }
+            if !self.path.exists() {
+                return Ok(Box::new(std::io::empty()));
+            }
+            let file = File::open(&self.path).map_err(PersistenceError::Io)?;
+            Ok(Box::new(BufReader::new(file)))
+        }
 
-            Ok(())
+        fn mapped(&self) -> Option<MappedFile> {
+            self.mapped.clone()
         }
     }
 
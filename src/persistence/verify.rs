@@ -0,0 +1,516 @@
+//! Offline snapshot verification.
+//!
+//! [`verify_snapshot`] checks a snapshot without building an index from it.
+//! Unlike [`read_snapshot`](crate::persistence::read_snapshot), which stops at the first
+//! problem, it keeps going and reports everything it finds, including
+//! damage the loader does not look for, such as neighbor lists that would
+//! only fail at search time.
+
+use crate::hnsw::graph::{HnswNode, NodeId};
+use crate::persistence::compression::decompress_section;
+use crate::persistence::header::{ConfigSection, FileHeader, SectionKind};
+use crate::persistence::sections::{expand, SectionTable};
+use crate::persistence::snapshot::{read_metadata, PayloadLayout};
+use crate::persistence::storage::read_header;
+use crate::persistence::StorageBackend;
+use bytemuck::Pod;
+use std::borrow::Cow;
+use std::mem::size_of;
+use thiserror::Error;
+
+/// Stop collecting issues after this many; a badly damaged neighbor pool
+/// would otherwise report one per node.
+const MAX_ISSUES: usize = 1000;
+
+/// A problem found by [`verify_snapshot`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum VerifyIssue {
+    /// The snapshot could not be read, or its header is invalid (bad magic,
+    /// version or header CRC). Nothing else was checked.
+    #[error("unreadable snapshot: {0}")]
+    Unreadable(String),
+
+    /// The CRC over the payload does not match the header.
+    #[error("data CRC mismatch: expected {expected:#x}, got {actual:#x}")]
+    DataCrc {
+        /// CRC stored in the header.
+        expected: u32,
+        /// CRC of the payload as found.
+        actual: u32,
+    },
+
+    /// The section table, or one of the sections it lists, is invalid.
+    #[error("{0}")]
+    Section(String),
+
+    /// The node table does not hold one node per vector.
+    #[error("node count mismatch: expected {expected}, got {actual}")]
+    NodeCount {
+        /// Nodes implied by the header's `vector_count`.
+        expected: u64,
+        /// Nodes found in the file.
+        actual: u64,
+    },
+
+    /// A node's neighbor blob lies outside the neighbor pool.
+    #[error(
+        "node {node}: neighbor range {offset}+{len} exceeds pool of {pool_len} bytes",
+        node = node.0
+    )]
+    NeighborRange {
+        /// The node.
+        node: NodeId,
+        /// `neighbor_offset` of the node.
+        offset: u32,
+        /// `neighbor_len` of the node.
+        len: u16,
+        /// Size of the neighbor pool.
+        pool_len: usize,
+    },
+
+    /// A node's neighbor list is not valid VByte, or runs past its blob.
+    #[error("node {node}: invalid neighbor encoding on layer {layer}", node = node.0)]
+    NeighborEncoding {
+        /// The node.
+        node: NodeId,
+        /// Layer of the damaged list.
+        layer: u8,
+    },
+
+    /// A node links to a node that does not exist.
+    #[error(
+        "node {node}: neighbor {neighbor} on layer {layer} is out of range",
+        node = node.0
+    )]
+    NeighborOutOfRange {
+        /// The node.
+        node: NodeId,
+        /// Layer of the list.
+        layer: u8,
+        /// The decoded neighbor ID.
+        neighbor: u32,
+    },
+
+    /// The tombstones disagree with the header's `deleted_count`.
+    #[error("deleted count mismatch: header says {expected}, tombstones mark {actual}")]
+    DeletedCount {
+        /// `deleted_count` stored in the header.
+        expected: u32,
+        /// Entries marked deleted in the file.
+        actual: u64,
+    },
+
+    /// The metadata section is missing, fails its CRC or cannot be decoded.
+    #[error("{0}")]
+    Metadata(String),
+}
+
+/// Outcome of [`verify_snapshot`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Problems found, in the order they were detected.
+    pub issues: Vec<VerifyIssue>,
+    /// True if more problems were found than `issues` holds.
+    pub truncated: bool,
+    /// Vectors declared by the header.
+    pub vector_count: u64,
+    /// HNSW nodes whose neighbor lists were checked.
+    pub nodes_checked: u64,
+    /// Neighbor links decoded across all nodes and layers.
+    pub links_checked: u64,
+}
+
+impl VerifyReport {
+    /// Returns true if no problems were found.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(&mut self, issue: VerifyIssue) {
+        if self.issues.len() < MAX_ISSUES {
+            self.issues.push(issue);
+        } else {
+            self.truncated = true;
+        }
+    }
+}
+
+/// Checks a snapshot for corruption without loading it.
+///
+/// Verifies the header CRC and the data CRC, then walks the payload:
+/// - the node table holds one node per vector,
+/// - every node's neighbor range lies inside the neighbor pool and decodes
+///   as valid VByte, with neighbor IDs in range,
+/// - the tombstones match the header's `deleted_count`,
+/// - the metadata section passes its CRC and decodes.
+///
+/// Snapshots with a section table also have each section's CRC checked.
+/// Problems are collected rather than returned as errors; an unreadable
+/// backend is reported as [`VerifyIssue::Unreadable`]. The whole file is
+/// read into memory unless the backend is memory-mapped.
+#[must_use]
+pub fn verify_snapshot(backend: &dyn StorageBackend) -> VerifyReport {
+    let mut report = VerifyReport::default();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(file) = backend.mapped() {
+        verify_bytes(&file, &mut report);
+        return report;
+    }
+
+    match backend.read() {
+        Ok(bytes) => verify_bytes(&bytes, &mut report),
+        Err(e) => report.push(VerifyIssue::Unreadable(e.to_string())),
+    }
+    report
+}
+
+/// The blocks the structural checks look at, expanded if compressed.
+struct Blocks<'a> {
+    nodes: Cow<'a, [u8]>,
+    neighbors: Cow<'a, [u8]>,
+    tombstones: Cow<'a, [u8]>,
+    /// The metadata section and its offset within the slice
+    metadata: Option<(Cow<'a, [u8]>, usize)>,
+}
+
+fn verify_bytes(bytes: &[u8], report: &mut VerifyReport) {
+    let header = match read_header(&mut &bytes[..]) {
+        Ok(header) => header,
+        Err(e) => return report.push(VerifyIssue::Unreadable(e.to_string())),
+    };
+    report.vector_count = header.vector_count;
+
+    let actual = crc32fast::hash(&bytes[64..]);
+    if actual != header.data_crc {
+        report.push(VerifyIssue::DataCrc {
+            expected: header.data_crc,
+            actual,
+        });
+    }
+
+    let blocks = if header.has_section_table() {
+        sectioned_blocks(bytes, report)
+    } else {
+        packed_blocks(&header, bytes, report)
+    };
+    let Some(blocks) = blocks else {
+        return;
+    };
+
+    let layout = PayloadLayout::new(&header);
+    let nodes = check_nodes(&layout, &blocks.nodes, report);
+    check_neighbors(&nodes, &blocks.neighbors, report);
+    check_deleted_count(&header, &layout, &nodes, &blocks.tombstones, report);
+
+    if header.has_metadata() {
+        match blocks.metadata {
+            Some((data, offset)) => {
+                if let Err(e) = read_metadata(&data, offset) {
+                    report.push(VerifyIssue::Metadata(e.to_string()));
+                }
+            }
+            None => report.push(VerifyIssue::Metadata("Metadata section is missing".into())),
+        }
+    }
+}
+
+/// Locates the blocks through the section table, checking each section's
+/// CRC on the way.
+fn sectioned_blocks<'a>(bytes: &'a [u8], report: &mut VerifyReport) -> Option<Blocks<'a>> {
+    let table = SectionTable::read(&mut &bytes[64..])
+        .and_then(|table| table.check_bounds(bytes.len()).map(|()| table));
+    let table = match table {
+        Ok(table) => table,
+        Err(e) => {
+            report.push(VerifyIssue::Section(e.to_string()));
+            return None;
+        }
+    };
+
+    let mut section = |kind: SectionKind| -> Cow<'a, [u8]> {
+        let Some(entry) = table.get(kind) else {
+            return Cow::Borrowed(&[]);
+        };
+        // SAFETY: check_bounds above keeps every section inside `bytes`.
+        #[allow(clippy::cast_possible_truncation)]
+        let stored = &bytes[entry.offset as usize..(entry.offset + entry.length) as usize];
+        let actual_crc = crc32fast::hash(stored);
+        if actual_crc != entry.crc {
+            report.push(VerifyIssue::Section(format!(
+                "Section {} CRC mismatch: expected {:#x}, got {actual_crc:#x}",
+                kind.name(),
+                entry.crc
+            )));
+        }
+        expand(entry, Cow::Borrowed(stored)).unwrap_or_else(|e| {
+            report.push(VerifyIssue::Section(e.to_string()));
+            Cow::Borrowed(&[])
+        })
+    };
+
+    let nodes = section(SectionKind::NODES);
+    let neighbors = section(SectionKind::NEIGHBORS);
+    let tombstones = section(SectionKind::TOMBSTONES);
+    let metadata = table
+        .get(SectionKind::METADATA)
+        .is_some()
+        .then(|| (section(SectionKind::METADATA), 0));
+
+    // Sections the structural checks skip still get their CRC checked
+    for entry in table.entries() {
+        if ![
+            SectionKind::NODES,
+            SectionKind::NEIGHBORS,
+            SectionKind::TOMBSTONES,
+            SectionKind::METADATA,
+        ]
+        .contains(&entry.kind())
+        {
+            section(entry.kind());
+        }
+    }
+
+    Some(Blocks {
+        nodes,
+        neighbors,
+        tombstones,
+        metadata,
+    })
+}
+
+/// Splits a snapshot without a section table at the offsets in its header.
+fn packed_blocks<'a>(
+    header: &FileHeader,
+    bytes: &'a [u8],
+    report: &mut VerifyReport,
+) -> Option<Blocks<'a>> {
+    let layout = PayloadLayout::new(header);
+    let payload = &bytes[64..];
+    let block = |start: usize, len: usize| {
+        let start = start.min(payload.len());
+        &payload[start..start.saturating_add(len).min(payload.len())]
+    };
+
+    let nodes_bytes = layout.node_count * size_of::<HnswNode>();
+    let nodes = block(layout.vector_bytes, nodes_bytes);
+    let neighbors_start = layout.vector_bytes + nodes_bytes;
+    let neighbors = block(
+        neighbors_start,
+        layout.neighbors_bytes.unwrap_or(usize::MAX),
+    );
+    let tail = block(neighbors_start + neighbors.len(), usize::MAX);
+
+    let (neighbors, tail) = if header.is_compressed() {
+        let expanded = decompress_section(neighbors, "neighbor pool")
+            .and_then(|n| Ok((n, decompress_section(tail, "tail")?)));
+        match expanded {
+            Ok((neighbors, tail)) => (Cow::Owned(neighbors), Cow::Owned(tail)),
+            Err(e) => {
+                report.push(VerifyIssue::Section(e.to_string()));
+                return None;
+            }
+        }
+    } else {
+        (Cow::Borrowed(neighbors), Cow::Borrowed(tail))
+    };
+
+    let tombstone_bytes = ((layout.vec_count + 7) / 8).min(tail.len());
+    // Metadata is the first optional section after the config (v0.5+)
+    let metadata_offset = tombstone_bytes
+        + if header.supports_config() {
+            ConfigSection::SIZE
+        } else {
+            0
+        };
+    let tombstones = match &tail {
+        Cow::Borrowed(tail) => Cow::Borrowed(&tail[..tombstone_bytes]),
+        Cow::Owned(tail) => Cow::Owned(tail[..tombstone_bytes].to_vec()),
+    };
+
+    Some(Blocks {
+        nodes: Cow::Borrowed(nodes),
+        neighbors,
+        tombstones,
+        metadata: Some((tail, metadata_offset)),
+    })
+}
+
+/// Checks that the node table holds one node per vector and decodes it.
+fn check_nodes(layout: &PayloadLayout, bytes: &[u8], report: &mut VerifyReport) -> Vec<HnswNode> {
+    let node_size = size_of::<HnswNode>();
+    let actual = bytes.len() / node_size;
+    if actual != layout.node_count || bytes.len() % node_size != 0 {
+        report.push(VerifyIssue::NodeCount {
+            expected: layout.node_count as u64,
+            actual: actual as u64,
+        });
+    }
+    read_pods(&bytes[..actual * node_size])
+}
+
+/// Checks every node's neighbor lists against the pool and the node count.
+fn check_neighbors(nodes: &[HnswNode], pool: &[u8], report: &mut VerifyReport) {
+    for (i, node) in nodes.iter().enumerate() {
+        // SAFETY: node tables are indexed by u32 NodeIds.
+        #[allow(clippy::cast_possible_truncation)]
+        let id = NodeId(i as u32);
+        report.nodes_checked += 1;
+        if node.neighbor_len == 0 {
+            continue;
+        }
+
+        let start = node.neighbor_offset as usize;
+        let end = start + usize::from(node.neighbor_len);
+        let Some(blob) = pool.get(start..end) else {
+            report.push(VerifyIssue::NeighborRange {
+                node: id,
+                offset: node.neighbor_offset,
+                len: node.neighbor_len,
+                pool_len: pool.len(),
+            });
+            continue;
+        };
+
+        // Lists for layers 0..=max_layer, back to back; a blob that ends
+        // early leaves the remaining layers empty
+        let mut cursor = 0;
+        for layer in 0..=node.max_layer {
+            if cursor >= blob.len() {
+                break;
+            }
+            let Some(links) = check_list(blob, &mut cursor) else {
+                report.push(VerifyIssue::NeighborEncoding { node: id, layer });
+                break;
+            };
+            for neighbor in links {
+                report.links_checked += 1;
+                if neighbor as usize >= nodes.len() {
+                    report.push(VerifyIssue::NeighborOutOfRange {
+                        node: id,
+                        layer,
+                        neighbor,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Decodes one count-prefixed, delta-encoded list starting at `cursor`.
+///
+/// Returns `None` if a value is malformed, the list runs past the blob, or
+/// the deltas overflow a `u32`.
+fn check_list(blob: &[u8], cursor: &mut usize) -> Option<Vec<u32>> {
+    let count = vbyte(blob, cursor)?;
+    // Every delta takes at least one byte
+    if count as usize > blob.len() - *cursor {
+        return None;
+    }
+    let mut id = 0u32;
+    (0..count)
+        .map(|_| {
+            id = id.checked_add(vbyte(blob, cursor)?)?;
+            Some(id)
+        })
+        .collect()
+}
+
+/// Decodes a VByte value, rejecting truncated or over-long encodings.
+fn vbyte(blob: &[u8], cursor: &mut usize) -> Option<u32> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *blob.get(*cursor)?;
+        *cursor += 1;
+        // The fifth byte only has room for the top 4 bits of a u32
+        if shift == 28 && byte > 0x0F {
+            return None;
+        }
+        value |= u32::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Compares the header's `deleted_count` with the deleted entries in the
+/// file: node flags for HNSW snapshots, the tombstone bitvec for flat ones.
+fn check_deleted_count(
+    header: &FileHeader,
+    layout: &PayloadLayout,
+    nodes: &[HnswNode],
+    tombstones: &[u8],
+    report: &mut VerifyReport,
+) {
+    // v0.1/v0.2 had a reserved field in its place
+    if !header.supports_soft_delete() {
+        return;
+    }
+    let actual = if header.is_flat_index() {
+        (0..layout.vec_count)
+            .filter(|&i| {
+                tombstones
+                    .get(i / 8)
+                    .is_some_and(|b| b & (1 << (i % 8)) != 0)
+            })
+            .count()
+    } else {
+        nodes.iter().filter(|n| n.deleted != 0).count()
+    };
+    if actual != header.deleted_count as usize {
+        report.push(VerifyIssue::DeletedCount {
+            expected: header.deleted_count,
+            actual: actual as u64,
+        });
+    }
+}
+
+/// Copies `bytes` into a vector of `T`, whatever their alignment.
+fn read_pods<T: Pod>(bytes: &[u8]) -> Vec<T> {
+    bytes
+        .chunks_exact(size_of::<T>())
+        .map(bytemuck::pod_read_unaligned)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vbyte_rejects_malformed_values() {
+        let mut cursor = 0;
+        assert_eq!(
+            vbyte(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F], &mut cursor),
+            Some(u32::MAX)
+        );
+        assert_eq!(cursor, 5);
+
+        // Truncated, over-long and too-large encodings
+        for bad in [
+            &[0x80][..],
+            &[0x80, 0x80, 0x80, 0x80, 0x80],
+            &[0xFF, 0xFF, 0xFF, 0xFF, 0x1F],
+        ] {
+            assert_eq!(vbyte(bad, &mut 0), None, "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_check_list_decodes_deltas() {
+        // [count 3][2][3][200]: IDs 2, 5, 205
+        let blob = [3, 2, 3, 0xC8, 0x01, 0, 0];
+        let mut cursor = 0;
+        assert_eq!(check_list(&blob, &mut cursor), Some(vec![2, 5, 205]));
+        assert_eq!(cursor, 5);
+
+        // Count larger than the blob, and deltas that overflow
+        assert_eq!(check_list(&[9, 1, 1], &mut 0), None);
+        assert_eq!(
+            check_list(&[2, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 1], &mut 0),
+            None
+        );
+    }
+}
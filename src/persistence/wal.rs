@@ -2,7 +2,9 @@ use crate::persistence::entry::WalEntry;
 use crate::persistence::storage::StorageBackend;
 use crate::persistence::PersistenceError;
 use crc32fast::Hasher;
+use std::fmt;
 use std::io::{self, Read};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use thiserror::Error;
 
 /// Header size in bytes: sequence(8) + type(1) + pad(3) + len(4)
@@ -51,6 +53,152 @@ pub enum WalError {
         /// The maximum allowed size.
         max: usize,
     },
+
+    /// An unreadable record is followed by valid ones (strict recovery only).
+    #[error("corrupted record at offset {offset} is followed by valid records")]
+    Corrupted {
+        /// Byte offset of the unreadable record.
+        offset: usize,
+    },
+}
+
+/// How recovery treats an unreadable record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecoveryMode {
+    /// Stop at the first unreadable record and keep the valid prefix.
+    #[default]
+    Lenient,
+    /// Fail with `WalError::Corrupted` if valid records follow an unreadable
+    /// one. A torn final record, which any crash during an append can leave
+    /// behind, is still dropped.
+    Strict,
+}
+
+/// Outcome of recovering from a WAL.
+///
+/// A damaged final record (a torn write) and damage in the middle of the log
+/// both end recovery at the last valid record, but only the latter loses
+/// records that were fully written. `valid_records_after` tells them apart.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecoveryReport {
+    /// Records applied to the recovered state.
+    pub entries_applied: usize,
+    /// Valid records not applied: covered by the snapshot's checkpoint, or
+    /// of a type the recovery path does not handle.
+    pub entries_skipped: usize,
+    /// Bytes from the first unreadable record to the end of the log.
+    pub bytes_discarded: usize,
+    /// Byte offset of the first unreadable record, if any.
+    pub first_bad_offset: Option<usize>,
+    /// Whether a valid record was found after the first unreadable one.
+    pub valid_records_after: bool,
+    /// Sequence number to continue the log with.
+    pub next_sequence: u64,
+}
+
+impl RecoveryReport {
+    /// Returns true if every byte of the log was read.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.first_bad_offset.is_none()
+    }
+
+    /// Returns true if only a partially written final record was discarded.
+    #[must_use]
+    pub fn is_torn_tail(&self) -> bool {
+        self.first_bad_offset.is_some() && !self.valid_records_after
+    }
+}
+
+/// Reads the valid records of a log for recovery, filling a `RecoveryReport`.
+///
+/// Callers count applied and skipped records in `report` themselves.
+pub(crate) struct RecoveryScan<'a> {
+    data: &'a [u8],
+    offset: usize,
+    mode: RecoveryMode,
+    done: bool,
+    pub(crate) report: RecoveryReport,
+}
+
+impl<'a> RecoveryScan<'a> {
+    pub(crate) fn new(data: &'a [u8], mode: RecoveryMode) -> Self {
+        Self {
+            data,
+            offset: 0,
+            mode,
+            done: false,
+            report: RecoveryReport::default(),
+        }
+    }
+
+    /// Returns the next valid record, or `None` at the end of the valid prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WalError::Corrupted` in strict mode when valid records follow
+    /// an unreadable one, and `WalError::Io` if reading fails.
+    pub(crate) fn next_entry(&mut self) -> Result<Option<(WalEntry, Vec<u8>)>, WalError> {
+        if self.done {
+            return Ok(None);
+        }
+        match WalIterator::new(&self.data[self.offset..]).next() {
+            None => {
+                self.done = true;
+                Ok(None)
+            }
+            Some(Ok((entry, payload))) => {
+                self.offset += WAL_HEADER_SIZE + payload.len() + CRC_SIZE;
+                Ok(Some((entry, payload)))
+            }
+            Some(Err(
+                e @ (WalError::Truncated { .. }
+                | WalError::ChecksumMismatch { .. }
+                | WalError::PayloadTooLarge { .. }),
+            )) => {
+                self.done = true;
+                let offset = self.offset;
+                let valid_after = find_valid_record(self.data, offset + 1);
+                self.report.first_bad_offset = Some(offset);
+                self.report.bytes_discarded = self.data.len() - offset;
+                self.report.valid_records_after = valid_after;
+
+                if valid_after {
+                    if self.mode == RecoveryMode::Strict {
+                        return Err(WalError::Corrupted { offset });
+                    }
+                    log::warn!(
+                        "WAL corrupted at offset {offset} ({e}); discarding {} bytes including valid records",
+                        self.report.bytes_discarded
+                    );
+                } else {
+                    log::warn!(
+                        "WAL ends with a torn record at offset {offset} ({e}); discarding {} bytes",
+                        self.report.bytes_discarded
+                    );
+                }
+                Ok(None)
+            }
+            Some(Err(e)) => Err(e),
+        }
+    }
+}
+
+/// Returns true if a complete record with a valid CRC starts at or after `from`.
+fn find_valid_record(data: &[u8], from: usize) -> bool {
+    (from..data.len()).any(|start| {
+        let rest = &data[start..];
+        if rest.len() < WAL_HEADER_SIZE + CRC_SIZE || rest[9..12] != [0, 0, 0] {
+            return false;
+        }
+        let payload_len = u32::from_le_bytes(rest[12..16].try_into().expect("4 bytes")) as usize;
+        let end = WAL_HEADER_SIZE + payload_len;
+        if payload_len > MAX_PAYLOAD_SIZE || rest.len() < end + CRC_SIZE {
+            return false;
+        }
+        let stored = u32::from_le_bytes(rest[end..end + CRC_SIZE].try_into().expect("4 bytes"));
+        crc32fast::hash(&rest[..end]) == stored
+    })
 }
 
 /// Iterator over entries in a Write-Ahead Log.
@@ -179,21 +327,51 @@ impl<R: Read> Iterator for WalIterator<R> {
 }
 
 /// Appends entries to the Write-Ahead Log.
+///
+/// Clones share the log and its sequence counter, so an `HnswIndex` and
+/// the `VectorStorage` it was created with append to the same WAL.
+#[derive(Clone)]
 pub struct WalAppender {
+    state: Arc<Mutex<AppenderState>>,
+}
+
+struct AppenderState {
     backend: Box<dyn StorageBackend>,
     next_sequence: u64,
 }
 
+impl fmt::Debug for WalAppender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WalAppender")
+            .field("next_sequence", &self.next_sequence())
+            .finish_non_exhaustive()
+    }
+}
+
 impl WalAppender {
     /// Creates a new `WalAppender` starting at the given sequence number.
     #[must_use]
     pub fn new(backend: Box<dyn StorageBackend>, next_sequence: u64) -> Self {
         Self {
-            backend,
-            next_sequence,
+            state: Arc::new(Mutex::new(AppenderState {
+                backend,
+                next_sequence,
+            })),
         }
     }
 
+    /// Locks the shared state. A panic mid-append leaves nothing half-updated
+    /// in memory (the backend write is the last step), so poisoning is ignored.
+    fn lock(&self) -> MutexGuard<'_, AppenderState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the sequence number the next appended entry will get.
+    #[must_use]
+    pub fn next_sequence(&self) -> u64 {
+        self.lock().next_sequence
+    }
+
     /// Appends a new entry to the WAL.
     ///
     /// # Arguments
@@ -217,8 +395,9 @@ impl WalAppender {
         #[allow(clippy::cast_possible_truncation)]
         let payload_len_u32 = payload_len as u32;
 
-        let entry_sequence = self.next_sequence;
-        self.next_sequence += 1;
+        let mut state = self.lock();
+        let entry_sequence = state.next_sequence;
+        state.next_sequence += 1;
 
         // Serialize Header (Manual Little-Endian)
         let mut header_bytes = [0u8; WAL_HEADER_SIZE];
@@ -239,11 +418,46 @@ impl WalAppender {
         buffer.extend_from_slice(payload);
         buffer.extend_from_slice(&crc.to_le_bytes());
 
-        self.backend.append(&buffer)?;
+        state.backend.append(&buffer)?;
 
         Ok(())
     }
 
+    /// Discards records with a sequence below `sequence` from the log.
+    ///
+    /// Call once a snapshot with `sequence` as its checkpoint has been
+    /// written: those records are already reflected in it. Records at or
+    /// above `sequence`, and any unreadable tail, are kept.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `WalError::Persistence` if the backend cannot be read or
+    /// rewritten.
+    pub fn truncate_before(&mut self, sequence: u64) -> Result<usize, WalError> {
+        let mut state = self.lock();
+        let data = state.backend.read()?;
+
+        let mut covered = 0;
+        for result in WalIterator::new(io::Cursor::new(&data)) {
+            let Ok((entry, payload)) = result else {
+                break;
+            };
+            if entry.sequence >= sequence {
+                break;
+            }
+            covered += WAL_HEADER_SIZE + payload.len() + CRC_SIZE;
+        }
+
+        if covered > 0 {
+            state.backend.truncate_front(covered)?;
+        }
+        Ok(covered)
+    }
+
     /// Flushes the underlying writer to ensure durability.
     ///
     /// This is now a no-op as `StorageBackend::append` implies durability.
@@ -315,4 +529,109 @@ mod tests {
 
         assert_eq!(count, 100);
     }
+
+    #[test]
+    fn test_truncate_before_keeps_uncovered_records() {
+        use crate::persistence::storage::{MemoryBackend, StorageBackend};
+        use std::io::Cursor;
+
+        let memory = MemoryBackend::new();
+        let mut appender = WalAppender::new(Box::new(memory.clone()), 0);
+        for i in 0..10_u8 {
+            appender.append(0, &[i]).expect("append failed");
+        }
+
+        let discarded = appender.truncate_before(6).expect("truncate failed");
+        assert_eq!(discarded, 6 * (WAL_HEADER_SIZE + 1 + CRC_SIZE));
+
+        // Appending continues the sequence after truncation
+        appender.append(0, &[10]).expect("append failed");
+
+        let data = memory.read().expect("read failed");
+        let sequences: Vec<u64> = WalIterator::new(Cursor::new(data))
+            .map(|r| r.expect("replay failed").0.sequence)
+            .collect();
+        assert_eq!(sequences, vec![6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_cloned_appenders_share_sequence() {
+        use crate::persistence::storage::{MemoryBackend, StorageBackend};
+        use std::io::Cursor;
+
+        let memory = MemoryBackend::new();
+        let mut first = WalAppender::new(Box::new(memory.clone()), 3);
+        let mut second = first.clone();
+
+        first.append(0, &[0]).expect("append failed");
+        second.append(0, &[1]).expect("append failed");
+        first.append(0, &[2]).expect("append failed");
+        assert_eq!(second.next_sequence(), 6);
+
+        let data = memory.read().expect("read failed");
+        let sequences: Vec<u64> = WalIterator::new(Cursor::new(data))
+            .map(|r| r.expect("replay failed").0.sequence)
+            .collect();
+        assert_eq!(sequences, vec![3, 4, 5]);
+    }
+
+    fn ten_records() -> Vec<u8> {
+        use crate::persistence::storage::{MemoryBackend, StorageBackend};
+
+        let memory = MemoryBackend::new();
+        let mut appender = WalAppender::new(Box::new(memory.clone()), 0);
+        for i in 0..10_u8 {
+            appender.append(0, &[i; 4]).expect("append failed");
+        }
+        memory.read().expect("read failed")
+    }
+
+    fn scan_all(data: &[u8], mode: RecoveryMode) -> Result<RecoveryReport, WalError> {
+        let mut scan = RecoveryScan::new(data, mode);
+        while scan.next_entry()?.is_some() {
+            scan.report.entries_applied += 1;
+        }
+        Ok(scan.report)
+    }
+
+    const RECORD: usize = WAL_HEADER_SIZE + 4 + CRC_SIZE;
+
+    #[test]
+    fn test_recovery_scan_torn_tail() {
+        let mut data = ten_records();
+        data.truncate(data.len() - 2);
+
+        let report = scan_all(&data, RecoveryMode::Strict).expect("torn tail is not an error");
+        assert_eq!(report.entries_applied, 9);
+        assert_eq!(report.first_bad_offset, Some(9 * RECORD));
+        assert_eq!(report.bytes_discarded, RECORD - 2);
+        assert!(report.is_torn_tail());
+    }
+
+    #[test]
+    fn test_recovery_scan_mid_log_corruption() {
+        let mut data = ten_records();
+        data[3 * RECORD + WAL_HEADER_SIZE] ^= 0xFF;
+
+        let report = scan_all(&data, RecoveryMode::Lenient).expect("lenient scan failed");
+        assert_eq!(report.entries_applied, 3);
+        assert_eq!(report.first_bad_offset, Some(3 * RECORD));
+        assert_eq!(report.bytes_discarded, 7 * RECORD);
+        assert!(report.valid_records_after);
+        assert!(!report.is_torn_tail());
+
+        let err = scan_all(&data, RecoveryMode::Strict).unwrap_err();
+        assert!(matches!(err, WalError::Corrupted { offset } if offset == 3 * RECORD));
+    }
+
+    #[test]
+    fn test_recovery_scan_corrupted_length() {
+        // A damaged length field must not hide the records after it
+        let mut data = ten_records();
+        data[5 * RECORD + 15] = 0xFF;
+
+        let report = scan_all(&data, RecoveryMode::Lenient).expect("lenient scan failed");
+        assert_eq!(report.entries_applied, 5);
+        assert!(report.valid_records_after);
+    }
 }
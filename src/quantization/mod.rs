@@ -8,6 +8,8 @@
 //! - [`ScalarQuantizer`]: SQ8 quantization (f32 -> u8), 4x compression
 //! - [`BinaryQuantizer`]: Binary quantization (f32 -> bit), 32x compression (fixed 768D)
 //! - `BinaryVector`: Variable-dimension binary quantization (any dimension divisible by 8)
+//! - [`ProductQuantizer`](crate::quantization::ProductQuantizer): Product quantization (f32 -> one byte per sub-vector),
+//!   `4 * d / M` compression with ADC distance tables
 //!
 //! # Example
 //!
@@ -37,6 +39,9 @@
 /// Binary quantization (sign-based) implementation.
 pub mod binary;
 
+/// Product quantization (PQ) with asymmetric distance tables.
+pub mod product;
+
 /// Scalar quantization (SQ8) implementation.
 pub mod scalar;
 
@@ -66,5 +71,6 @@ pub mod variable;
 pub use binary::{
     BinaryQuantizer, QuantizedVector, BINARY_QUANTIZATION_DIM, QUANTIZED_VECTOR_SIZE,
 };
-pub use scalar::{QuantizerConfig, ScalarQuantizer};
+pub use product::{DistanceTable, PqConfig, PqError, ProductQuantizer};
+pub use scalar::{QuantizerConfig, ScalarQuantizer, Sq8Mode, Sq8Ranges, Sq8TrainConfig};
 pub use variable::{BinaryVector, QuantizationError};
@@ -1,3 +1,4 @@
+use crate::hnsw::HnswConfig;
 use serde::{Deserialize, Serialize};
 use std::vec::Vec;
 
@@ -20,20 +21,164 @@ pub struct QuantizerConfig {
     pub max: f32, // offset 4
 }
 
-/// Scalar Quantizer implementation using global min/max normalization.
+/// Code layout for SQ8 ranges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sq8Mode {
+    /// Min-max codes in `[0, 255]`.
+    #[default]
+    Asymmetric,
+    /// Zero-centered `i8` codes in `[-127, 127]`, stored as bytes.
+    ///
+    /// Each range is `[-absmax, absmax]`, so 0.0 maps exactly to code 0 and
+    /// dot products need no offset correction. Suited to dot-product models.
+    Symmetric,
+}
+
+/// Training options for [`ScalarQuantizer::train_with`].
 ///
-/// Maps f32 values to u8 in the range [0, 255].
-/// Values outside [min, max] are clamped.
+/// The default reproduces [`ScalarQuantizer::train`]: one global min/max
+/// range, no clipping.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sq8TrainConfig {
+    /// Code layout.
+    pub mode: Sq8Mode,
+    /// Train one range per dimension instead of one global range.
+    pub per_dimension: bool,
+    /// Central fraction of values kept when picking ranges, e.g. `0.999`.
+    ///
+    /// Values outside the clipped range saturate to the end codes, so a few
+    /// outliers no longer stretch the step for everything else. `None`, or a
+    /// value outside `(0.0, 1.0)`, keeps the full observed range.
+    pub percentile: Option<f32>,
+}
+
+/// Trained per-dimension SQ8 ranges.
+///
+/// Dimension `i` covers `min[i]..=max[i]`. In [`Sq8Mode::Symmetric`] every
+/// range is `[-absmax, absmax]`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sq8Ranges {
+    /// Code layout.
+    pub mode: Sq8Mode,
+    /// Lower bound per dimension.
+    pub min: Vec<f32>,
+    /// Upper bound per dimension.
+    pub max: Vec<f32>,
+}
+
+impl Sq8Ranges {
+    /// Number of dimensions covered.
+    #[must_use]
+    pub fn dimensions(&self) -> usize {
+        self.min.len()
+    }
+}
+
+/// Per-dimension lookup tables derived from [`Sq8Ranges`].
+#[derive(Clone, Debug)]
+struct DimensionTables {
+    ranges: Sq8Ranges,
+    /// Value of one code step per dimension (0.0 for empty ranges).
+    steps: Vec<f32>,
+    /// Codes per unit value (inverse of `steps`, 0.0 for empty ranges).
+    scales: Vec<f32>,
+    /// Squared steps: distance kernel weights.
+    weights: Vec<f32>,
+    /// `min * step`: weights of the linear terms of asymmetric dot products.
+    offsets: Vec<f32>,
+    /// `sum(min^2)`: constant term of asymmetric dot products.
+    offset_norm: f32,
+    /// All-ones codes, used to sum weighted codes with the dot kernel.
+    ones: Vec<u8>,
+}
+
+impl DimensionTables {
+    fn new(ranges: Sq8Ranges) -> Self {
+        let levels = match ranges.mode {
+            Sq8Mode::Asymmetric => 255.0,
+            Sq8Mode::Symmetric => 127.0,
+        };
+        let steps: Vec<f32> = ranges
+            .min
+            .iter()
+            .zip(&ranges.max)
+            .map(|(&min, &max)| {
+                let range = max - min;
+                if range.abs() < f32::EPSILON {
+                    0.0
+                } else {
+                    match ranges.mode {
+                        Sq8Mode::Asymmetric => range / levels,
+                        Sq8Mode::Symmetric => range / 2.0 / levels,
+                    }
+                }
+            })
+            .collect();
+        let scales = steps
+            .iter()
+            .map(|&step| if step == 0.0 { 0.0 } else { 1.0 / step })
+            .collect();
+        let weights = steps.iter().map(|&step| step * step).collect();
+        let offsets = ranges.min.iter().zip(&steps).map(|(m, s)| m * s).collect();
+        let offset_norm = ranges.min.iter().map(|m| m * m).sum();
+        let ones = vec![1; ranges.dimensions()];
+        Self {
+            ranges,
+            steps,
+            scales,
+            weights,
+            offsets,
+            offset_norm,
+            ones,
+        }
+    }
+}
+
+/// Scalar Quantizer implementation (SQ8).
+///
+/// Maps f32 values to one byte per dimension. By default a single global
+/// min/max range maps values to `[0, 255]`; [`ScalarQuantizer::train_with`]
+/// adds per-dimension and percentile-clipped ranges and a symmetric `i8`
+/// layout. Values outside the range are clamped.
 #[derive(Clone, Debug)]
 pub struct ScalarQuantizer {
     config: QuantizerConfig,
+    /// Per-dimension tables; `None` for a global range.
+    dims: Option<Box<DimensionTables>>,
 }
 
 impl ScalarQuantizer {
     /// Create a new quantizer with specific config.
     #[must_use]
     pub fn new(config: QuantizerConfig) -> Self {
-        Self { config }
+        Self { config, dims: None }
+    }
+
+    /// Create a quantizer from trained per-dimension ranges.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ranges.min` and `ranges.max` have different lengths.
+    #[must_use]
+    pub fn from_ranges(ranges: Sq8Ranges) -> Self {
+        assert_eq!(
+            ranges.min.len(),
+            ranges.max.len(),
+            "SQ8 ranges need one min and one max per dimension"
+        );
+        let config = QuantizerConfig {
+            min: ranges.min.iter().copied().fold(f32::INFINITY, f32::min),
+            max: ranges.max.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        };
+        let config = if config.min > config.max {
+            QuantizerConfig { min: 0.0, max: 1.0 }
+        } else {
+            config
+        };
+        Self {
+            config,
+            dims: Some(Box::new(DimensionTables::new(ranges))),
+        }
     }
 
     /// Train the quantizer on a batch of vectors to find global min/max.
@@ -52,36 +197,69 @@ impl ScalarQuantizer {
     /// - If all values are equal (min == max), returns that value as both min and max.
     #[must_use]
     pub fn train(vectors: &[&[f32]]) -> Self {
-        if vectors.is_empty() {
-            return Self {
-                config: QuantizerConfig { min: 0.0, max: 1.0 },
-            };
-        }
+        Self::train_with(vectors, &Sq8TrainConfig::default())
+    }
 
-        let mut min = f32::MAX;
-        let mut max = f32::MIN;
+    /// Train the quantizer with per-dimension, clipped or symmetric ranges.
+    ///
+    /// A global asymmetric config yields a plain [`QuantizerConfig`]
+    /// quantizer; every other config yields per-dimension [`Sq8Ranges`]
+    /// (a global symmetric range is repeated for each dimension).
+    ///
+    /// # Edge Cases
+    ///
+    /// - If `vectors` is empty or holds no finite values, returns the
+    ///   default 0.0..1.0 global range.
+    /// - A dimension with no finite values gets the range 0.0..0.0.
+    #[must_use]
+    pub fn train_with(vectors: &[&[f32]], config: &Sq8TrainConfig) -> Self {
+        let default = Self::new(QuantizerConfig { min: 0.0, max: 1.0 });
+        let Some(dimensions) = vectors.first().map(|v| v.len()) else {
+            return default;
+        };
+        let keep = config
+            .percentile
+            .filter(|p| *p > 0.0 && *p < 1.0)
+            .unwrap_or(1.0);
 
-        for vec in vectors {
-            for &val in *vec {
-                if val < min {
-                    min = val;
-                }
-                if val > max {
-                    max = val;
-                }
+        if !config.per_dimension {
+            let values = vectors.iter().flat_map(|v| v.iter().copied());
+            let Some((min, max)) = value_range(values, config.mode, keep) else {
+                return default;
+            };
+            if config.mode == Sq8Mode::Asymmetric {
+                return Self::new(QuantizerConfig { min, max });
             }
+            return Self::from_ranges(Sq8Ranges {
+                mode: config.mode,
+                min: vec![min; dimensions],
+                max: vec![max; dimensions],
+            });
         }
 
-        // Handle case where no valid values were seen (e.g. all NaNs or empty slices)
-        if min > max {
-            return Self {
-                config: QuantizerConfig { min: 0.0, max: 1.0 },
+        let mut min = Vec::with_capacity(dimensions);
+        let mut max = Vec::with_capacity(dimensions);
+        let mut any = false;
+        for d in 0..dimensions {
+            let values = vectors.iter().filter_map(|v| v.get(d).copied());
+            let (lo, hi) = match value_range(values, config.mode, keep) {
+                Some(range) => {
+                    any = true;
+                    range
+                }
+                None => (0.0, 0.0),
             };
+            min.push(lo);
+            max.push(hi);
         }
-
-        Self {
-            config: QuantizerConfig { min, max },
+        if !any {
+            return default;
         }
+        Self::from_ranges(Sq8Ranges {
+            mode: config.mode,
+            min,
+            max,
+        })
     }
 
     /// Quantize a vector from f32 to u8.
@@ -89,12 +267,37 @@ impl ScalarQuantizer {
     /// # Formula
     /// `u8 = (val - min) / (max - min) * 255`
     ///
+    /// Per-dimension ranges use each dimension's own `min` and `max`.
+    /// Symmetric codes are `round(val / absmax * 127)` as two's-complement
+    /// `i8` bytes.
+    ///
     /// # Behavior
     /// - Outliers (val < min or val > max) are clamped.
     /// - NaN values are treated as min (0).
     #[must_use]
     #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
     pub fn quantize(&self, vector: &[f32]) -> Vec<u8> {
+        if let Some(dims) = &self.dims {
+            return match dims.ranges.mode {
+                Sq8Mode::Asymmetric => vector
+                    .iter()
+                    .zip(&dims.ranges.min)
+                    .zip(&dims.scales)
+                    .map(|((&val, &min), &scale)| {
+                        ((val - min) * scale).round().clamp(0.0, 255.0) as u8
+                    })
+                    .collect(),
+                Sq8Mode::Symmetric => vector
+                    .iter()
+                    .zip(&dims.scales)
+                    .map(|(&val, &scale)| {
+                        // NaN saturates to 0, which is the symmetric midpoint.
+                        ((val * scale).round().clamp(-127.0, 127.0) as i8) as u8
+                    })
+                    .collect(),
+            };
+        }
+
         let range = self.config.max - self.config.min;
 
         if range.abs() < f32::EPSILON {
@@ -118,8 +321,24 @@ impl ScalarQuantizer {
     /// # Formula
     /// `f32 = min + (u8 / 255) * (max - min)`
     #[must_use]
-    #[allow(clippy::cast_lossless)]
+    #[allow(clippy::cast_lossless, clippy::cast_possible_wrap)]
     pub fn dequantize(&self, quantized: &[u8]) -> Vec<f32> {
+        if let Some(dims) = &self.dims {
+            return match dims.ranges.mode {
+                Sq8Mode::Asymmetric => quantized
+                    .iter()
+                    .zip(&dims.ranges.min)
+                    .zip(&dims.steps)
+                    .map(|((&q, &min), &step)| min + f32::from(q) * step)
+                    .collect(),
+                Sq8Mode::Symmetric => quantized
+                    .iter()
+                    .zip(&dims.steps)
+                    .map(|(&q, &step)| f32::from(q as i8) * step)
+                    .collect(),
+            };
+        }
+
         let range = self.config.max - self.config.min;
         let min = self.config.min;
         let scale = range / 255.0;
@@ -132,14 +351,169 @@ impl ScalarQuantizer {
         out
     }
 
+    /// L2 Squared distance between two codes from this quantizer.
+    ///
+    /// A global range returns the distance in code units, exactly as
+    /// [`l2_squared_u8`](crate::metric::simd::l2_squared_u8). Per-dimension
+    /// ranges weight each dimension by its squared step, which yields the
+    /// distance between the dequantized vectors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the codes have different lengths, or if per-dimension codes
+    /// do not match the trained dimensions.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn l2_squared(&self, a: &[u8], b: &[u8]) -> f32 {
+        use crate::metric::simd;
+
+        match &self.dims {
+            None => simd::l2_squared_u8(a, b) as f32,
+            Some(dims) => match dims.ranges.mode {
+                Sq8Mode::Asymmetric => simd::l2_squared_u8_scaled(a, b, &dims.weights),
+                Sq8Mode::Symmetric => simd::l2_squared_i8_scaled(a, b, &dims.weights),
+            },
+        }
+    }
+
+    /// Dot product of the dequantized vectors of two codes.
+    ///
+    /// Symmetric codes need a single weighted kernel pass; asymmetric codes
+    /// add the offset terms from each range's `min`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the codes have different lengths, or if per-dimension codes
+    /// do not match the trained dimensions.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn dot_product(&self, a: &[u8], b: &[u8]) -> f32 {
+        use crate::metric::simd;
+
+        let Some(dims) = &self.dims else {
+            // (min + s*a)·(min + s*b) = s²·a·b + min·s·(Σa + Σb) + n·min²
+            let min = self.config.min;
+            let step = (self.config.max - min) / 255.0;
+            let sum = |v: &[u8]| v.iter().map(|&x| u32::from(x)).sum::<u32>() as f32;
+            return step * step * simd::dot_product_u8(a, b) as f32
+                + min * step * (sum(a) + sum(b))
+                + a.len() as f32 * min * min;
+        };
+        match dims.ranges.mode {
+            Sq8Mode::Symmetric => simd::dot_product_i8_scaled(a, b, &dims.weights),
+            Sq8Mode::Asymmetric => {
+                simd::dot_product_u8_scaled(a, b, &dims.weights)
+                    + simd::dot_product_u8_scaled(a, &dims.ones, &dims.offsets)
+                    + simd::dot_product_u8_scaled(b, &dims.ones, &dims.offsets)
+                    + dims.offset_norm
+            }
+        }
+    }
+
+    /// Cosine distance (`1 - cosine similarity`) of the dequantized vectors
+    /// of two codes.
+    ///
+    /// Matches [`Cosine`](crate::metric::Cosine): a zero vector has distance
+    /// `1.0`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the codes have different lengths, or if per-dimension codes
+    /// do not match the trained dimensions.
+    #[must_use]
+    pub fn cosine_distance(&self, a: &[u8], b: &[u8]) -> f32 {
+        let norms = self.dot_product(a, a) * self.dot_product(b, b);
+        if norms <= 0.0 {
+            return 1.0;
+        }
+        let similarity = (self.dot_product(a, b) / norms.sqrt()).clamp(-1.0, 1.0);
+        1.0 - similarity
+    }
+
+    /// Distance between two codes under an HNSW metric code.
+    ///
+    /// Orders candidates the same way as the metric's F32 distance:
+    /// [`l2_squared`](Self::l2_squared) for L2, the negated
+    /// [`dot_product`](Self::dot_product) for dot product (smaller is more
+    /// similar, as with `NegativeDotProduct`) and
+    /// [`cosine_distance`](Self::cosine_distance) for cosine. Unknown codes
+    /// fall back to L2.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the codes have different lengths, or if per-dimension codes
+    /// do not match the trained dimensions.
+    #[must_use]
+    pub fn distance(&self, metric: u32, a: &[u8], b: &[u8]) -> f32 {
+        match metric {
+            HnswConfig::METRIC_DOT_PRODUCT => -self.dot_product(a, b),
+            HnswConfig::METRIC_COSINE => self.cosine_distance(a, b),
+            _ => self.l2_squared(a, b),
+        }
+    }
+
     /// Get the configuration.
+    ///
+    /// For per-dimension quantizers this is the envelope of all ranges.
     #[must_use]
     pub fn config(&self) -> QuantizerConfig {
         self.config
     }
+
+    /// Returns the per-dimension ranges, or `None` for a global range.
+    #[must_use]
+    pub fn ranges(&self) -> Option<&Sq8Ranges> {
+        self.dims.as_ref().map(|dims| &dims.ranges)
+    }
+}
+
+/// Range of the finite `values` after clipping to the central `keep` fraction.
+///
+/// Symmetric ranges clip on absolute values. Returns `None` if there are
+/// no finite values. Only clipping needs to buffer the values.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn value_range(values: impl Iterator<Item = f32>, mode: Sq8Mode, keep: f32) -> Option<(f32, f32)> {
+    let finite = values.filter(|x| x.is_finite());
+    let (min, max) = if keep >= 1.0 {
+        finite.fold(None, |range, x| match range {
+            None => Some((x, x)),
+            Some((lo, hi)) => Some((f32::min(lo, x), f32::max(hi, x))),
+        })?
+    } else {
+        let mut buffer: Vec<f32> = match mode {
+            Sq8Mode::Asymmetric => finite.collect(),
+            Sq8Mode::Symmetric => finite.map(f32::abs).collect(),
+        };
+        let last = buffer.len().checked_sub(1)?;
+        // SAFETY: indices are rounded fractions of `last`, so they fit.
+        let mut quantile = |q: f64| -> f32 {
+            let idx = ((q * last as f64).round() as usize).min(last);
+            *buffer.select_nth_unstable_by(idx, f32::total_cmp).1
+        };
+        let keep = f64::from(keep);
+        match mode {
+            Sq8Mode::Asymmetric => {
+                let tail = (1.0 - keep) / 2.0;
+                (quantile(tail), quantile(1.0 - tail))
+            }
+            Sq8Mode::Symmetric => (0.0, quantile(keep)),
+        }
+    };
+    match mode {
+        Sq8Mode::Asymmetric => Some((min, max)),
+        Sq8Mode::Symmetric => {
+            let absmax = max.abs().max(min.abs());
+            Some((-absmax, absmax))
+        }
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::cast_precision_loss)]
 mod tests {
     use super::*;
 
@@ -208,4 +582,210 @@ mod tests {
         assert!((decoded[0] - 5.0).abs() < f32::EPSILON);
         assert!((decoded[1] - 5.0).abs() < f32::EPSILON);
     }
+
+    fn dequantized_l2(q: &ScalarQuantizer, a: &[u8], b: &[u8]) -> f32 {
+        let (a, b) = (q.dequantize(a), q.dequantize(b));
+        a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum()
+    }
+
+    fn dequantized_dot(q: &ScalarQuantizer, a: &[u8], b: &[u8]) -> f32 {
+        let (a, b) = (q.dequantize(a), q.dequantize(b));
+        a.iter().zip(&b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_train_per_dimension() {
+        let v1 = vec![1.0, 100.0, -2.0];
+        let v2 = vec![3.0, 300.0, -1.0];
+        let config = Sq8TrainConfig {
+            per_dimension: true,
+            ..Sq8TrainConfig::default()
+        };
+        let q = ScalarQuantizer::train_with(&[&v1, &v2], &config);
+
+        let ranges = q.ranges().unwrap();
+        assert_eq!(ranges.mode, Sq8Mode::Asymmetric);
+        assert_eq!(ranges.min, vec![1.0, 100.0, -2.0]);
+        assert_eq!(ranges.max, vec![3.0, 300.0, -1.0]);
+        assert_eq!(q.config().min, -2.0);
+        assert_eq!(q.config().max, 300.0);
+
+        // Each dimension uses its full code range
+        assert_eq!(q.quantize(&v1), vec![0, 0, 0]);
+        assert_eq!(q.quantize(&v2), vec![255, 255, 255]);
+        let mid = [2.0, 200.0, -1.5];
+        let decoded = q.dequantize(&q.quantize(&mid));
+        for ((orig, dec), step) in mid.iter().zip(&decoded).zip([2.0, 200.0, 1.0]) {
+            assert!((orig - dec).abs() <= step / 510.0 + 1e-6, "{orig} vs {dec}");
+        }
+    }
+
+    #[test]
+    fn test_train_with_default_matches_train() {
+        let v1 = vec![1.0, 5.0, -2.0];
+        let v2 = vec![0.0, 10.0, f32::NAN];
+        let vectors = vec![v1.as_slice(), v2.as_slice()];
+
+        let q = ScalarQuantizer::train_with(&vectors, &Sq8TrainConfig::default());
+        assert!(q.ranges().is_none());
+        assert_eq!(q.config(), ScalarQuantizer::train(&vectors).config());
+        assert_eq!(
+            q.config(),
+            QuantizerConfig {
+                min: -2.0,
+                max: 10.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_percentile_clips_outliers() {
+        let mut vectors: Vec<Vec<f32>> = (0..1000).map(|i| vec![i as f32 / 1000.0]).collect();
+        vectors.push(vec![1.0e6]);
+        let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+
+        let full = ScalarQuantizer::train(&refs);
+        assert!((full.config().max - 1.0e6).abs() < 1.0);
+
+        let config = Sq8TrainConfig {
+            percentile: Some(0.99),
+            ..Sq8TrainConfig::default()
+        };
+        let clipped = ScalarQuantizer::train_with(&refs, &config);
+        assert!(clipped.config().min < 0.01);
+        assert!(clipped.config().max < 1.0);
+
+        // The outlier saturates; ordinary values keep their resolution
+        assert_eq!(clipped.quantize(&[1.0e6]), vec![255]);
+        let decoded = clipped.dequantize(&clipped.quantize(&[0.5]));
+        assert!((decoded[0] - 0.5).abs() < 0.005);
+    }
+
+    #[test]
+    fn test_symmetric_codes() {
+        let v1 = vec![-4.0, 0.5];
+        let v2 = vec![2.0, -1.0];
+        let config = Sq8TrainConfig {
+            mode: Sq8Mode::Symmetric,
+            per_dimension: true,
+            percentile: None,
+        };
+        let q = ScalarQuantizer::train_with(&[&v1, &v2], &config);
+
+        let ranges = q.ranges().unwrap();
+        assert_eq!(ranges.min, vec![-4.0, -1.0]);
+        assert_eq!(ranges.max, vec![4.0, 1.0]);
+
+        // Zero is exact, the ends map to -127 and 127 as i8 bytes
+        assert_eq!(q.quantize(&[0.0, 0.0]), vec![0, 0]);
+        assert_eq!(q.quantize(&[4.0, -1.0]), vec![127, 0x81]);
+        assert_eq!(q.quantize(&[-9.0, 9.0]), vec![0x81, 127]);
+
+        let decoded = q.dequantize(&q.quantize(&v1));
+        for (orig, dec) in v1.iter().zip(&decoded) {
+            assert!((orig - dec).abs() < 0.02, "{orig} vs {dec}");
+        }
+    }
+
+    #[test]
+    fn test_global_symmetric_repeats_range() {
+        let config = Sq8TrainConfig {
+            mode: Sq8Mode::Symmetric,
+            ..Sq8TrainConfig::default()
+        };
+        let q = ScalarQuantizer::train_with(&[&[0.5, -3.0, 1.0][..]], &config);
+        let ranges = q.ranges().unwrap();
+        assert_eq!(ranges.min, vec![-3.0; 3]);
+        assert_eq!(ranges.max, vec![3.0; 3]);
+    }
+
+    #[test]
+    fn test_distances_match_dequantized_vectors() {
+        let a = [0.3, -12.0, 150.0, 0.0, 7.5, -0.25, 42.0, 3.0, 1.0];
+        let b = [-0.1, 8.0, 20.0, 0.5, 7.0, 0.25, -42.0, 2.0, -1.0];
+        let refs = [&a[..], &b[..]];
+
+        let per_dim = Sq8TrainConfig {
+            per_dimension: true,
+            ..Sq8TrainConfig::default()
+        };
+        let symmetric = Sq8TrainConfig {
+            mode: Sq8Mode::Symmetric,
+            ..per_dim
+        };
+        let quantizers = [
+            ScalarQuantizer::train(&refs),
+            ScalarQuantizer::train_with(&refs, &per_dim),
+            ScalarQuantizer::train_with(&refs, &symmetric),
+        ];
+
+        for q in &quantizers {
+            let (qa, qb) = (q.quantize(&a), q.quantize(&b));
+            let dot = dequantized_dot(q, &qa, &qb);
+            assert!((q.dot_product(&qa, &qb) - dot).abs() < 1e-3 * dot.abs().max(1.0));
+        }
+        for q in &quantizers[1..] {
+            let (qa, qb) = (q.quantize(&a), q.quantize(&b));
+            let l2 = dequantized_l2(q, &qa, &qb);
+            assert!((q.l2_squared(&qa, &qb) - l2).abs() < 1e-3 * l2);
+        }
+
+        // A global range keeps the code-unit distance
+        let q = &quantizers[0];
+        let (qa, qb) = (q.quantize(&a), q.quantize(&b));
+        let expected = crate::metric::scalar::l2_squared_u8(&qa, &qb) as f32;
+        assert!((q.l2_squared(&qa, &qb) - expected).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_metric_distances_match_f32_metrics() {
+        use crate::metric::{Cosine, L2Squared, Metric, NegativeDotProduct};
+
+        let a = [0.3, -0.8, 0.1, 0.0, 0.5, -0.25, 0.9, 0.05, -1.0];
+        let b = [-0.1, 0.6, 0.2, 0.5, 0.4, 0.25, -0.7, 0.3, 1.0];
+        let refs = [&a[..], &b[..]];
+        let symmetric = Sq8TrainConfig {
+            mode: Sq8Mode::Symmetric,
+            per_dimension: true,
+            percentile: None,
+        };
+
+        for q in [
+            ScalarQuantizer::train(&refs),
+            ScalarQuantizer::train_with(&refs, &symmetric),
+        ] {
+            let (qa, qb) = (q.quantize(&a), q.quantize(&b));
+            let (da, db) = (q.dequantize(&qa), q.dequantize(&qb));
+
+            let cosine = q.distance(HnswConfig::METRIC_COSINE, &qa, &qb);
+            assert!((cosine - Cosine::distance(&da, &db)).abs() < 1e-4);
+            let dot = q.distance(HnswConfig::METRIC_DOT_PRODUCT, &qa, &qb);
+            assert!((dot - NegativeDotProduct::distance(&da, &db)).abs() < 1e-4);
+            assert!(q.cosine_distance(&qa, &qa).abs() < 1e-4);
+        }
+
+        let q = ScalarQuantizer::train_with(&refs, &symmetric);
+        let (qa, qb) = (q.quantize(&a), q.quantize(&b));
+        let (da, db) = (q.dequantize(&qa), q.dequantize(&qb));
+        let l2 = q.distance(HnswConfig::METRIC_L2_SQUARED, &qa, &qb);
+        assert!((l2 - L2Squared::distance(&da, &db)).abs() < 1e-4);
+
+        // A zero vector has cosine distance 1, as in `Cosine`
+        let zero = q.quantize(&[0.0; 9]);
+        assert!((q.cosine_distance(&zero, &qa) - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_from_ranges_constant_dimension() {
+        let q = ScalarQuantizer::from_ranges(Sq8Ranges {
+            mode: Sq8Mode::Asymmetric,
+            min: vec![2.0, 0.0],
+            max: vec![2.0, 1.0],
+        });
+        assert_eq!(q.quantize(&[5.0, 1.0]), vec![0, 255]);
+        let decoded = q.dequantize(&[0, 255]);
+        assert!((decoded[0] - 2.0).abs() < f32::EPSILON);
+        assert!((decoded[1] - 1.0).abs() < f32::EPSILON);
+    }
 }
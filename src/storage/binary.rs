@@ -302,6 +302,44 @@ impl BinaryVectorStorage {
         Ok(id)
     }
 
+    /// Overwrites the data of an existing live vector.
+    ///
+    /// # Errors
+    ///
+    /// - `BinaryStorageError::DimensionMismatch` if vector dimension doesn't match.
+    /// - `BinaryStorageError::NotFound` if ID is out of bounds or deleted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::storage::binary::BinaryVectorStorage;
+    /// use edgevec::quantization::variable::BinaryVector;
+    ///
+    /// let mut storage = BinaryVectorStorage::new(128).unwrap();
+    /// let id = storage.insert(&BinaryVector::quantize(&vec![1.0f32; 128]).unwrap()).unwrap();
+    ///
+    /// storage.update(id, &BinaryVector::quantize(&vec![-1.0f32; 128]).unwrap()).unwrap();
+    /// assert_eq!(storage.get_raw(id).unwrap(), &[0u8; 16]);
+    /// ```
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn update(&mut self, id: u64, vector: &BinaryVector) -> Result<(), BinaryStorageError> {
+        if vector.dimension() != self.dimension {
+            return Err(BinaryStorageError::DimensionMismatch {
+                expected: self.dimension,
+                actual: vector.dimension(),
+            });
+        }
+
+        let idx = id as usize;
+        if idx >= self.count || self.deleted[idx] {
+            return Err(BinaryStorageError::NotFound { id });
+        }
+
+        let start = idx * self.bytes_per_vector;
+        self.data[start..start + self.bytes_per_vector].copy_from_slice(vector.data());
+        Ok(())
+    }
+
     /// Retrieves a vector by ID.
     ///
     /// Returns `None` if ID is out of bounds or vector is deleted.
@@ -515,6 +553,48 @@ impl BinaryVectorStorage {
         })
     }
 
+    /// Returns the packed data of every vector (including deleted).
+    #[must_use]
+    pub(crate) fn raw_data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the tombstone bitvec (one bit per vector).
+    #[must_use]
+    pub(crate) fn deleted_bits(&self) -> &BitVec<u8, Lsb0> {
+        &self.deleted
+    }
+
+    /// Rebuilds a storage from packed data and tombstones, as written by a
+    /// snapshot.
+    ///
+    /// # Errors
+    ///
+    /// - `BinaryStorageError::InvalidDimension` if dimension % 8 != 0 or is 0.
+    /// - `BinaryStorageError::DimensionMismatch` if `data` is not a whole
+    ///   number of vectors or `deleted` has a different vector count.
+    pub(crate) fn from_raw_parts(
+        dimension: usize,
+        data: Vec<u8>,
+        deleted: BitVec<u8, Lsb0>,
+    ) -> Result<Self, BinaryStorageError> {
+        let mut storage = Self::new(dimension)?;
+        let bytes_per_vector = storage.bytes_per_vector;
+
+        if data.len() % bytes_per_vector != 0 || data.len() / bytes_per_vector != deleted.len() {
+            return Err(BinaryStorageError::DimensionMismatch {
+                expected: dimension,
+                actual: data.len() * 8 / deleted.len().max(1),
+            });
+        }
+
+        storage.count = deleted.len();
+        storage.next_id = storage.count as u64;
+        storage.data = data;
+        storage.deleted = deleted;
+        Ok(storage)
+    }
+
     /// Compacts internal buffers to minimize memory usage.
     pub fn shrink_to_fit(&mut self) {
         self.data.shrink_to_fit();
@@ -904,4 +984,37 @@ mod tests {
         assert_eq!(cloned.dimension(), storage.dimension());
         assert_eq!(cloned.get_raw(0), storage.get_raw(0));
     }
+
+    #[test]
+    fn test_from_raw_parts_roundtrip() {
+        let mut storage = BinaryVectorStorage::new(16).unwrap();
+        storage.insert_raw(&[0xAB, 0xCD]).unwrap();
+        storage.insert_raw(&[0x12, 0x34]).unwrap();
+        storage.delete(0).unwrap();
+
+        let rebuilt = BinaryVectorStorage::from_raw_parts(
+            16,
+            storage.raw_data().to_vec(),
+            storage.deleted_bits().clone(),
+        )
+        .unwrap();
+
+        assert_eq!(rebuilt.len(), 2);
+        assert!(rebuilt.is_deleted(0));
+        assert_eq!(rebuilt.get_raw(1), Some(&[0x12, 0x34][..]));
+
+        // New inserts continue after the restored vectors
+        let mut rebuilt = rebuilt;
+        assert_eq!(rebuilt.insert_raw(&[0, 0]).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_from_raw_parts_rejects_length_mismatch() {
+        let result =
+            BinaryVectorStorage::from_raw_parts(16, vec![0u8; 3], BitVec::repeat(false, 2));
+        assert!(matches!(
+            result,
+            Err(BinaryStorageError::DimensionMismatch { .. })
+        ));
+    }
 }
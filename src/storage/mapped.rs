@@ -0,0 +1,181 @@
+//! Buffers that may borrow from a memory-mapped snapshot.
+//!
+//! `read_snapshot` over a mapped backend hands out [`CowSlice`]s pointing
+//! into the file instead of copying vectors and nodes into `Vec`s. The
+//! first mutation copies the data into an owned `Vec` (copy-on-write), so
+//! a mapped index behaves exactly like a loaded one.
+
+use bytemuck::Pod;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::mem::size_of;
+use std::ops::Deref;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::persistence::storage::MappedFile;
+
+/// A slice of `T` that is either owned or borrowed from a [`MappedFile`].
+#[derive(Clone)]
+pub(crate) enum CowSlice<T> {
+    /// Heap-allocated data.
+    Owned(Vec<T>),
+    /// `len` values starting at byte `offset` of a mapped file.
+    #[cfg(not(target_arch = "wasm32"))]
+    Mapped {
+        file: MappedFile,
+        offset: usize,
+        len: usize,
+    },
+}
+
+impl<T: Pod> CowSlice<T> {
+    /// Borrows `len` values at byte `offset` of `file`.
+    ///
+    /// Returns `None` if the range is out of bounds or not aligned for `T`;
+    /// callers fall back to copying.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn mapped(file: &MappedFile, offset: usize, len: usize) -> Option<Self> {
+        let bytes = file.get(offset..offset.checked_add(len.checked_mul(size_of::<T>())?)?)?;
+        bytemuck::try_cast_slice::<u8, T>(bytes).ok()?;
+        Some(Self::Mapped {
+            file: file.clone(),
+            offset,
+            len,
+        })
+    }
+
+    /// Returns true if the data is borrowed from a mapped file.
+    pub(crate) fn is_mapped(&self) -> bool {
+        !matches!(self, Self::Owned(_))
+    }
+
+    /// Returns the owned data, copying it out of the mapping first if needed.
+    pub(crate) fn to_mut(&mut self) -> &mut Vec<T> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.is_mapped() {
+            *self = Self::Owned(self.to_vec());
+        }
+        match self {
+            Self::Owned(vec) => vec,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Mapped { .. } => unreachable!("copied above"),
+        }
+    }
+
+    /// Returns the number of heap-allocated values (0 while mapped).
+    pub(crate) fn capacity(&self) -> usize {
+        match self {
+            Self::Owned(vec) => vec.capacity(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Mapped { .. } => 0,
+        }
+    }
+
+    /// Shrinks owned data to fit; mapped data holds no spare capacity.
+    pub(crate) fn shrink_to_fit(&mut self) {
+        if let Self::Owned(vec) = self {
+            vec.shrink_to_fit();
+        }
+    }
+}
+
+impl<T: Pod> Deref for CowSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Owned(vec) => vec,
+            // Bounds and alignment were checked in `mapped`
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Mapped { file, offset, len } => {
+                bytemuck::cast_slice(&file[*offset..*offset + *len * size_of::<T>()])
+            }
+        }
+    }
+}
+
+impl<'a, T: Pod> IntoIterator for &'a CowSlice<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> Default for CowSlice<T> {
+    fn default() -> Self {
+        Self::Owned(Vec::new())
+    }
+}
+
+impl<T> From<Vec<T>> for CowSlice<T> {
+    fn from(vec: Vec<T>) -> Self {
+        Self::Owned(vec)
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for CowSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: Pod + Serialize> Serialize for CowSlice<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (**self).serialize(serializer)
+    }
+}
+
+impl<'de, T: Pod + Deserialize<'de>> Deserialize<'de> for CowSlice<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::deserialize(deserializer).map(Self::Owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owned_slice_derefs_and_mutates() {
+        let mut slice = CowSlice::from(vec![1.0f32, 2.0]);
+        assert!(!slice.is_mapped());
+        slice.to_mut().push(3.0);
+        assert_eq!(&*slice, &[1.0, 2.0, 3.0]);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_mapped_slice_copies_on_write() {
+        use crate::persistence::storage::file::FileBackend;
+        use crate::persistence::StorageBackend;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("floats.bin");
+        let floats = [1.0f32, 2.0, 3.0, 4.0];
+        FileBackend::new(&path)
+            .atomic_write("", bytemuck::cast_slice(&floats))
+            .unwrap();
+
+        let backend = FileBackend::open_mmap(&path).unwrap();
+        let file = backend.mapped().unwrap();
+        let mut slice = CowSlice::<f32>::mapped(&file, 4, 3).unwrap();
+        assert!(slice.is_mapped());
+        assert_eq!(&*slice, &[2.0, 3.0, 4.0]);
+        assert_eq!(slice.capacity(), 0);
+
+        // Out of bounds and misaligned ranges are rejected
+        assert!(CowSlice::<f32>::mapped(&file, 8, 3).is_none());
+        assert!(CowSlice::<f32>::mapped(&file, 2, 1).is_none());
+
+        slice.to_mut()[0] = 9.0;
+        assert!(!slice.is_mapped());
+        assert_eq!(&*slice, &[9.0, 3.0, 4.0]);
+        // The file is untouched
+        assert_eq!(
+            std::fs::read(&path).unwrap(),
+            bytemuck::cast_slice::<f32, u8>(&floats)
+        );
+    }
+}
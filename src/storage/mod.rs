@@ -10,17 +10,23 @@
 
 use crate::hnsw::graph::VectorProvider;
 use crate::hnsw::{HnswConfig, VectorId};
+use crate::persistence::entry::WalEntry;
 use crate::persistence::storage::StorageBackend;
-use crate::persistence::wal::{WalAppender, WalError, WalIterator};
-use crate::quantization::{QuantizerConfig, ScalarQuantizer};
+use crate::persistence::wal::{RecoveryMode, RecoveryReport, RecoveryScan, WalAppender, WalError};
+use crate::quantization::{QuantizerConfig, ScalarQuantizer, Sq8Ranges, Sq8TrainConfig};
 use bitvec::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::io::Cursor;
 use thiserror::Error;
 
 /// Binary vector storage for quantized vectors.
 pub mod binary;
+/// Buffers borrowed from memory-mapped snapshots.
+pub(crate) mod mapped;
+/// Product-quantized code storage.
+pub mod product;
+
+use mapped::CowSlice;
 
 /// Errors that can occur during storage operations.
 #[derive(Debug, Error)]
@@ -45,6 +51,10 @@ pub enum StorageError {
     /// Invalid data encountered during recovery.
     #[error("corrupted data: {0}")]
     Corrupted(String),
+
+    /// The vector ID does not refer to a live vector.
+    #[error("vector not found: {0}")]
+    NotFound(u64),
 }
 
 /// Configuration for vector storage.
@@ -55,20 +65,87 @@ pub enum StorageType {
     Float32,
     /// Store 8-bit quantized vectors.
     QuantizedU8(QuantizerConfig),
+    /// Store 8-bit quantized vectors with per-dimension ranges.
+    QuantizedU8Ranges(Sq8Ranges),
+    /// Store full precision f32 vectors alongside 8-bit quantized codes.
+    ///
+    /// Search traverses the graph with the codes; exact vectors are kept for
+    /// rescoring (see `HnswIndex::search_sq8_rescored`).
+    DualU8(QuantizerConfig),
+    /// `DualU8` with per-dimension ranges.
+    DualU8Ranges(Sq8Ranges),
+}
+
+impl StorageType {
+    /// Creates the SQ8 storage type matching a trained quantizer.
+    #[must_use]
+    pub fn from_quantizer(quantizer: &ScalarQuantizer) -> Self {
+        match quantizer.ranges() {
+            Some(ranges) => Self::QuantizedU8Ranges(ranges.clone()),
+            None => Self::QuantizedU8(quantizer.config()),
+        }
+    }
+
+    /// Creates the dual F32 + SQ8 storage type matching a trained quantizer.
+    #[must_use]
+    pub fn dual_from_quantizer(quantizer: &ScalarQuantizer) -> Self {
+        Self::from_quantizer(quantizer).into_dual()
+    }
+
+    /// Returns true if vectors are stored as SQ8 codes.
+    #[must_use]
+    pub fn is_quantized(&self) -> bool {
+        !matches!(self, Self::Float32)
+    }
+
+    /// Returns true if full precision f32 vectors are stored.
+    #[must_use]
+    pub fn keeps_f32(&self) -> bool {
+        matches!(
+            self,
+            Self::Float32 | Self::DualU8(_) | Self::DualU8Ranges(_)
+        )
+    }
+
+    /// Returns true if both f32 vectors and SQ8 codes are stored.
+    #[must_use]
+    pub fn is_dual(&self) -> bool {
+        matches!(self, Self::DualU8(_) | Self::DualU8Ranges(_))
+    }
+
+    /// Turns an SQ8 storage type into its dual counterpart.
+    pub(crate) fn into_dual(self) -> Self {
+        match self {
+            Self::QuantizedU8(config) => Self::DualU8(config),
+            Self::QuantizedU8Ranges(ranges) => Self::DualU8Ranges(ranges),
+            other => other,
+        }
+    }
+
+    /// Builds the quantizer for SQ8 storage types.
+    pub(crate) fn quantizer(&self) -> Option<ScalarQuantizer> {
+        match self {
+            Self::Float32 => None,
+            Self::QuantizedU8(config) | Self::DualU8(config) => Some(ScalarQuantizer::new(*config)),
+            Self::QuantizedU8Ranges(ranges) | Self::DualU8Ranges(ranges) => {
+                Some(ScalarQuantizer::from_ranges(ranges.clone()))
+            }
+        }
+    }
 }
 
 /// Contiguous vector storage with WAL persistence.
 #[derive(Serialize, Deserialize)]
 pub struct VectorStorage {
     /// Full precision vector data (layout: [`v0_d0`, ..., `v1_d0`, ...]).
-    /// Populated if `storage_type` is `Float32` (or in dual mode).
+    /// Populated if `storage_type` is `Float32`, `DualU8` or `DualU8Ranges`.
     #[serde(default)]
-    pub(crate) data_f32: Vec<f32>,
+    pub(crate) data_f32: CowSlice<f32>,
 
     /// Quantized vector data (layout: [`v0_d0`, ..., `v1_d0`, ...]).
-    /// Populated if `storage_type` is `QuantizedU8`.
+    /// Populated for every storage type except `Float32`.
     #[serde(default)]
-    pub(crate) quantized_data: Vec<u8>,
+    pub(crate) quantized_data: CowSlice<u8>,
 
     /// Storage configuration.
     #[serde(default)]
@@ -86,6 +163,10 @@ pub struct VectorStorage {
     /// Skipped during serialization - must be re-attached after deserialization if needed.
     #[serde(skip)]
     pub(crate) wal: Option<WalAppender>,
+    /// WAL sequence covered by the snapshot this storage was loaded from
+    /// (0 if none). Records below it are skipped on replay.
+    #[serde(skip)]
+    pub(crate) wal_checkpoint: u64,
     /// Next available ID.
     pub(crate) next_id: u64,
 }
@@ -100,13 +181,14 @@ impl VectorStorage {
     #[must_use]
     pub fn new(config: &HnswConfig, wal: Option<WalAppender>) -> Self {
         Self {
-            data_f32: Vec::new(),
-            quantized_data: Vec::new(),
+            data_f32: CowSlice::default(),
+            quantized_data: CowSlice::default(),
             config: StorageType::Float32,
             quantizer: None,
             deleted: BitVec::new(),
             dimensions: config.dimensions,
             wal,
+            wal_checkpoint: 0,
             next_id: 1, // Start at 1 because 0 is reserved sentinel
         }
     }
@@ -114,13 +196,168 @@ impl VectorStorage {
     /// Set the storage type (e.g. to enable quantization).
     ///
     /// Note: This does not convert existing data. It only affects future inserts.
+    /// Use [`convert_to`](Self::convert_to) on a populated storage.
     pub fn set_storage_type(&mut self, config: StorageType) {
-        if let StorageType::QuantizedU8(q_config) = &config {
-            self.quantizer = Some(ScalarQuantizer::new(*q_config));
+        if config.is_quantized() {
+            self.quantizer = config.quantizer();
         }
         self.config = config;
     }
 
+    /// Trains an SQ8 quantizer on the live vectors in storage.
+    ///
+    /// Pass the result through [`StorageType::from_quantizer`] or
+    /// [`StorageType::dual_from_quantizer`] to [`convert_to`](Self::convert_to).
+    /// Quantized storage trains on its dequantized codes.
+    #[must_use]
+    pub fn train_quantizer(&self, config: &Sq8TrainConfig) -> ScalarQuantizer {
+        let live: Vec<Cow<'_, [f32]>> = (1..=self.len() as u64)
+            .map(VectorId)
+            .filter(|id| !self.is_deleted(*id))
+            .map(|id| self.get_vector(id))
+            .collect();
+        let refs: Vec<&[f32]> = live.iter().map(AsRef::as_ref).collect();
+        ScalarQuantizer::train_with(&refs, config)
+    }
+
+    /// Converts every stored vector to another storage type.
+    ///
+    /// Unlike [`set_storage_type`](Self::set_storage_type), existing vectors
+    /// are re-encoded. Each one is read from the F32 copy when the storage
+    /// keeps one, and from its dequantized codes otherwise:
+    ///
+    /// - To `QuantizedU8*`: vectors are quantized and the F32 copy is dropped.
+    /// - To `DualU8*`: the F32 copy is kept (or created) next to the codes.
+    /// - To `Float32`: codes are dequantized and dropped.
+    ///
+    /// Deleted slots are converted too, because HNSW keeps routing through
+    /// tombstoned nodes. `VectorId`s do not change, so indexes built on
+    /// this storage stay valid.
+    ///
+    /// # Durability
+    ///
+    /// If a WAL is configured, the target type is logged (entry type 8)
+    /// *before* memory is modified, and replay repeats the conversion at the
+    /// same point of the log. Converting to the current type is a no-op, so
+    /// an interrupted migration can simply be run again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::DimensionMismatch` if the target's per-dimension
+    /// ranges do not match the storage dimensions, or `StorageError::Wal`
+    /// if the WAL write fails. Storage is unchanged on error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use edgevec::hnsw::HnswConfig;
+    /// use edgevec::quantization::Sq8TrainConfig;
+    /// use edgevec::storage::{StorageType, VectorStorage};
+    ///
+    /// let config = HnswConfig::new(4);
+    /// let mut storage = VectorStorage::new(&config, None);
+    /// let id = storage.insert(&[0.0, 0.25, 0.5, 1.0]).unwrap();
+    ///
+    /// let quantizer = storage.train_quantizer(&Sq8TrainConfig::default());
+    /// storage.convert_to(StorageType::from_quantizer(&quantizer)).unwrap();
+    /// assert_eq!(storage.get_quantized_vector(id), &[0, 64, 128, 255]);
+    ///
+    /// storage.convert_to(StorageType::Float32).unwrap();
+    /// assert!((storage.get_vector(id)[1] - 0.25).abs() < 0.01);
+    /// ```
+    pub fn convert_to(&mut self, target: StorageType) -> Result<(), StorageError> {
+        if target == self.config {
+            return Ok(());
+        }
+        if let StorageType::QuantizedU8Ranges(ranges) | StorageType::DualU8Ranges(ranges) = &target
+        {
+            let len = u32::try_from(ranges.dimensions()).unwrap_or(u32::MAX);
+            if len != self.dimensions {
+                return Err(StorageError::DimensionMismatch {
+                    expected: self.dimensions,
+                    actual: len,
+                });
+            }
+        }
+
+        if let Some(wal) = &mut self.wal {
+            let payload = postcard::to_allocvec(&target)
+                .map_err(|e| StorageError::Corrupted(e.to_string()))?;
+            wal.append(WalEntry::CONVERT_STORAGE, &payload)?;
+        }
+
+        // F32 copy of every slot: the stored one or the dequantized codes
+        let dim = self.dimensions as usize;
+        let vectors = if self.config.keeps_f32() {
+            std::mem::take(&mut self.data_f32)
+        } else {
+            let quantizer = self.sq8_quantizer().clone();
+            let mut vectors = Vec::with_capacity(self.quantized_data.len());
+            if dim > 0 {
+                for codes in self.quantized_data.chunks_exact(dim) {
+                    vectors.extend_from_slice(&quantizer.dequantize(codes));
+                }
+            }
+            CowSlice::from(vectors)
+        };
+
+        self.quantizer = target.quantizer();
+        self.quantized_data = match &self.quantizer {
+            Some(quantizer) => quantize_all(quantizer, &vectors, dim).into(),
+            None => CowSlice::default(),
+        };
+        self.data_f32 = if target.keeps_f32() {
+            vectors
+        } else {
+            CowSlice::default()
+        };
+        self.config = target;
+        Ok(())
+    }
+
+    /// Returns the storage type (F32 or SQ8 with its quantizer parameters).
+    #[must_use]
+    pub fn storage_type(&self) -> &StorageType {
+        &self.config
+    }
+
+    /// Returns true if the vector data is borrowed from a memory-mapped
+    /// snapshot (see `FileBackend::open_mmap`).
+    ///
+    /// The first insert or update copies it to the heap.
+    #[must_use]
+    pub fn is_mapped(&self) -> bool {
+        self.data_f32.is_mapped() || self.quantized_data.is_mapped()
+    }
+
+    /// Returns true if a WAL appender is attached.
+    #[must_use]
+    pub fn has_wal(&self) -> bool {
+        self.wal.is_some()
+    }
+
+    /// Returns the WAL sequence a snapshot of this storage covers.
+    ///
+    /// This is the next sequence of the attached WAL, or the checkpoint the
+    /// storage was loaded with when no WAL is attached. 0 means no WAL
+    /// record is covered.
+    #[must_use]
+    pub fn wal_sequence(&self) -> u64 {
+        self.wal
+            .as_ref()
+            .map_or(self.wal_checkpoint, WalAppender::next_sequence)
+    }
+
+    /// Appends an index-level record (delete, metadata, BQ) to the WAL.
+    ///
+    /// No-op when no WAL is attached.
+    pub(crate) fn log_entry(&mut self, entry_type: u8, payload: &[u8]) -> Result<(), StorageError> {
+        if let Some(wal) = &mut self.wal {
+            wal.append(entry_type, payload)?;
+        }
+        Ok(())
+    }
+
     /// Inserts a vector into storage.
     ///
     /// # Durability
@@ -183,27 +420,17 @@ impl VectorStorage {
 
             // Step 3: Append and Sync
             // Entry Type 0 = Insert (F32)
-            wal.append(0, &payload)?;
+            wal.append(WalEntry::INSERT, &payload)?;
             // wal.sync() is implied by append
         }
 
-        // Step 4: Update Memory
-        match &self.config {
-            StorageType::Float32 => {
-                self.data_f32.extend_from_slice(vector);
-            }
-            StorageType::QuantizedU8(config) => {
-                // Ensure quantizer is initialized
-                if self.quantizer.is_none() {
-                    self.quantizer = Some(ScalarQuantizer::new(*config));
-                }
-                let q = self
-                    .quantizer
-                    .as_ref()
-                    .expect("quantizer initialized above");
-                let quantized = q.quantize(vector);
-                self.quantized_data.extend_from_slice(&quantized);
-            }
+        // Step 4: Update Memory (dual storage fills both buffers)
+        if self.config.keeps_f32() {
+            self.data_f32.to_mut().extend_from_slice(vector);
+        }
+        if self.config.is_quantized() {
+            let quantized = self.sq8_quantizer().quantize(vector);
+            self.quantized_data.to_mut().extend_from_slice(&quantized);
         }
 
         self.deleted.push(false);
@@ -244,7 +471,7 @@ impl VectorStorage {
         }
 
         // Ensure we are in Quantized mode
-        if !matches!(self.config, StorageType::QuantizedU8(_)) {
+        if !self.config.is_quantized() {
             // Alternatively, we could auto-switch, but strict is better.
             // For now, if we are in Float32, we cannot store quantized data reliably without dequantizing (which we can't do without config).
             // We'll return an error or panic. Let's return error.
@@ -261,19 +488,108 @@ impl VectorStorage {
             payload.extend_from_slice(data);
 
             // Entry Type 1 = Insert Quantized
-            wal.append(1, &payload)?;
+            wal.append(WalEntry::INSERT_QUANTIZED, &payload)?;
             // wal.sync() implied
         }
 
-        self.quantized_data.extend_from_slice(data);
+        // Dual storage keeps the F32 buffer aligned with the codes
+        if self.config.keeps_f32() {
+            let vector = self.sq8_quantizer().dequantize(data);
+            self.data_f32.to_mut().extend_from_slice(&vector);
+        }
+        self.quantized_data.to_mut().extend_from_slice(data);
         self.deleted.push(false);
         self.next_id += 1;
 
         Ok(VectorId(id))
     }
 
+    /// Overwrites the vector stored under an existing ID.
+    ///
+    /// # Durability
+    ///
+    /// If a WAL is configured, the new vector is logged (entry type 3) *before*
+    /// memory is modified, matching `insert`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::DimensionMismatch` if the vector length is wrong,
+    /// `StorageError::NotFound` if `id` is not a live vector, or
+    /// `StorageError::Wal` if the WAL write fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if quantizer is `None` in `QuantizedU8` storage mode (logic error).
+    pub fn update(&mut self, id: VectorId, vector: &[f32]) -> Result<(), StorageError> {
+        let len = u32::try_from(vector.len()).unwrap_or(u32::MAX);
+        if len != self.dimensions {
+            return Err(StorageError::DimensionMismatch {
+                expected: self.dimensions,
+                actual: len,
+            });
+        }
+
+        if id == VectorId::INVALID || id.0 > self.deleted.len() as u64 || self.is_deleted(id) {
+            return Err(StorageError::NotFound(id.0));
+        }
+
+        if let Some(wal) = &mut self.wal {
+            let mut payload = Vec::with_capacity(8 + vector.len() * 4);
+            payload.extend_from_slice(&id.0.to_le_bytes());
+            for val in vector {
+                payload.extend_from_slice(&val.to_le_bytes());
+            }
+
+            // Entry Type 3 = Update (F32)
+            wal.append(WalEntry::UPDATE, &payload)?;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let start = (id.0 as usize - 1) * vector.len();
+        let end = start + vector.len();
+
+        if self.config.keeps_f32() {
+            self.data_f32.to_mut()[start..end].copy_from_slice(vector);
+        }
+        if self.config.is_quantized() {
+            let quantized = self.sq8_quantizer().quantize(vector);
+            self.quantized_data.to_mut()[start..end].copy_from_slice(&quantized);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the SQ8 quantizer, building it from the storage type if needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the storage type is `Float32`.
+    fn sq8_quantizer(&mut self) -> &ScalarQuantizer {
+        let config = &self.config;
+        self.quantizer
+            .get_or_insert_with(|| config.quantizer().expect("SQ8 storage type"))
+    }
+
+    /// Re-encodes every stored F32 vector into SQ8 codes.
+    ///
+    /// Used when loading dual storage, whose snapshots only hold F32 data.
+    pub(crate) fn rebuild_codes(&mut self) {
+        let dim = self.dimensions as usize;
+        let quantizer = self.sq8_quantizer().clone();
+        self.quantized_data = quantize_all(&quantizer, &self.data_f32, dim).into();
+    }
+
     /// Recovers storage state from a WAL backend.
     ///
+    /// Only vector records (inserts, updates and storage conversions) are
+    /// applied. Index-level records such as soft deletes and metadata
+    /// changes are skipped; use [`crate::persistence::recover_index`] to
+    /// rebuild a full `HnswIndex`.
+    ///
+    /// An unreadable record ends recovery at the valid prefix. Use
+    /// [`VectorStorage::recover_with_report`] to find out what was discarded,
+    /// or to fail on corruption in the middle of the log.
+    ///
     /// # Arguments
     ///
     /// * `backend` - The storage backend to read from.
@@ -295,6 +611,31 @@ impl VectorStorage {
         backend: Box<dyn StorageBackend>,
         config: &HnswConfig,
     ) -> Result<Self, StorageError> {
+        Self::recover_with_report(backend, config, RecoveryMode::Lenient)
+            .map(|(storage, _report)| storage)
+    }
+
+    /// Recovers storage state from a WAL backend and reports what was read.
+    ///
+    /// The report tells a torn final record (expected after a crash) apart
+    /// from damage in the middle of the log. With `RecoveryMode::Strict`,
+    /// the latter fails instead of silently dropping the records after it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `StorageError::Wal(WalError::Corrupted { .. })` in strict mode
+    /// when valid records follow an unreadable one, and any error
+    /// [`VectorStorage::recover`] returns.
+    ///
+    /// # Panics
+    ///
+    /// Panics if internal byte conversions fail (guaranteed safe by length checks).
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn recover_with_report(
+        backend: Box<dyn StorageBackend>,
+        config: &HnswConfig,
+        mode: RecoveryMode,
+    ) -> Result<(Self, RecoveryReport), StorageError> {
         let mut storage = Self::new(config, None);
 
         // Read all data from backend
@@ -304,123 +645,49 @@ impl VectorStorage {
                 e.to_string(),
             ))
         })?;
-        if data.is_empty() {
-            return Ok(storage);
-        }
-
-        let reader = Cursor::new(data);
-        let iterator = WalIterator::new(reader);
 
-        let mut max_id = 0;
-
-        for result in iterator {
-            let (entry, payload) = match result {
-                Ok(val) => val,
-                Err(WalError::Truncated { expected, actual }) => {
-                    // [C1] Handle truncation gracefully (partial write at end of log)
-                    eprintln!(
-                        "WAL truncated (expected {expected} bytes, got {actual}). Stopping recovery at valid prefix."
-                    );
-                    break;
-                }
-                Err(WalError::ChecksumMismatch { expected, actual }) => {
-                    // [C1] Handle checksum mismatch.
-                    // Assumption: Mismatch implies torn write at the end of the log.
-                    // We treat this as a truncation and preserve the valid prefix.
-                    // NOTE: If this happens in the middle of a file, we still stop here,
-                    // effectively truncating the potentially corrupted tail.
-                    eprintln!(
-                        "WAL checksum mismatch (expected {expected:#x}, got {actual:#x}). Stopping recovery at valid prefix."
-                    );
-                    break;
-                }
-                Err(e) => return Err(StorageError::Wal(e)),
-            };
+        let mut scan = RecoveryScan::new(&data, mode);
 
-            if entry.entry_type == 0 {
+        // Records go through insert/update, so they land in whichever
+        // buffers the storage type set by earlier CONVERT_STORAGE records
+        // uses. There is no WAL attached, so nothing is logged again.
+        while let Some((entry, payload)) = scan.next_entry()? {
+            scan.report.next_sequence = entry.sequence + 1;
+            if entry.entry_type == WalEntry::INSERT {
                 // Insert (Float32)
-                if payload.len() < 8 {
-                    return Err(StorageError::Corrupted("Insert payload too short".into()));
-                }
-                // SAFETY: payload length is checked to be at least 8 in the block ending at line 210.
-                let id_bytes: [u8; 8] = payload[0..8].try_into().expect("payload length checked");
-                let id = u64::from_le_bytes(id_bytes);
-
-                // Validate ID continuity (simple check for now)
-                if id != storage.next_id {
-                    // In a strict replay, IDs should match sequence if we only append.
-                    // But for now, we trust the WAL ID.
-                }
-
-                let vec_bytes = &payload[8..];
-                if vec_bytes.len() % 4 != 0 {
-                    return Err(StorageError::Corrupted(
-                        "Vector bytes alignment error".into(),
-                    ));
-                }
-
-                let vec_len = vec_bytes.len() / 4;
-                if let Ok(len) = u32::try_from(vec_len) {
-                    if len != config.dimensions {
-                        return Err(StorageError::DimensionMismatch {
-                            expected: config.dimensions,
-                            actual: len,
-                        });
-                    }
-                } else {
-                    return Err(StorageError::DimensionMismatch {
-                        expected: config.dimensions,
-                        actual: u32::MAX,
-                    });
-                }
-
-                // Convert bytes back to f32
-                let vector: Vec<f32> = vec_bytes
-                    .chunks_exact(4)
-                    .map(|chunk| {
-                        // SAFETY: chunks_exact(4) guarantees each chunk is exactly 4 bytes.
-                        let b: [u8; 4] = chunk
-                            .try_into()
-                            .expect("chunks_exact returns exact size slices");
-                        f32::from_le_bytes(b)
-                    })
-                    .collect();
-
-                // Apply to memory - defaulting to data_f32 because that's what we have from entry_type 0
-                storage.data_f32.extend_from_slice(&vector);
-                storage.deleted.push(false);
-                storage.next_id = id + 1;
-                max_id = max_id.max(id);
-            } else if entry.entry_type == 1 {
+                let (id, vector) = decode_f32_payload(&payload, config)?;
+                storage.next_id = id;
+                storage.insert(&vector)?;
+            } else if entry.entry_type == WalEntry::INSERT_QUANTIZED {
                 // Insert Quantized
                 if payload.len() < 8 {
                     return Err(StorageError::Corrupted("Insert payload too short".into()));
                 }
                 let id_bytes: [u8; 8] = payload[0..8].try_into().expect("checked");
-                let id = u64::from_le_bytes(id_bytes);
-
-                let vec_bytes = &payload[8..];
-                // Check dimensions
-                if let Ok(len) = u32::try_from(vec_bytes.len()) {
-                    if len != config.dimensions {
-                        return Err(StorageError::DimensionMismatch {
-                            expected: config.dimensions,
-                            actual: len,
-                        });
+                storage.next_id = u64::from_le_bytes(id_bytes);
+                storage.insert_quantized(&payload[8..])?;
+            } else if entry.entry_type == WalEntry::UPDATE {
+                // Update (Float32): overwrite an existing slot in place
+                let (id, vector) = decode_f32_payload(&payload, config)?;
+                storage.update(VectorId(id), &vector).map_err(|e| match e {
+                    StorageError::NotFound(id) => {
+                        StorageError::Corrupted(format!("Update references unknown vector {id}"))
                     }
-                }
-
-                storage.quantized_data.extend_from_slice(vec_bytes);
-                storage.deleted.push(false);
-                storage.next_id = id + 1;
-                max_id = max_id.max(id);
+                    other => other,
+                })?;
+            } else if entry.entry_type == WalEntry::CONVERT_STORAGE {
+                storage.convert_to(decode_storage_type_payload(&payload)?)?;
+            } else {
+                scan.report.entries_skipped += 1;
+                continue;
             }
+            scan.report.entries_applied += 1;
         }
 
         // Truncation repair removed as it's not supported by StorageBackend trait directly.
         // We rely on append-only semantics.
 
-        Ok(storage)
+        Ok((storage, scan.report))
     }
 
     /// Returns the number of vectors stored.
@@ -460,42 +727,39 @@ impl VectorStorage {
         let start = idx * dim;
         let end = start + dim;
 
-        match &self.config {
-            StorageType::Float32 => {
-                assert!(
-                    !self.data_f32.is_empty(),
-                    "get_vector called on storage without f32 data"
-                );
-                assert!(
-                    end <= self.data_f32.len(),
-                    "get_vector: VectorId {} out of bounds (idx={}, end={}, data_len={})",
-                    id.0,
-                    idx,
-                    end,
-                    self.data_f32.len()
-                );
-                Cow::Borrowed(&self.data_f32[start..end])
-            }
-            StorageType::QuantizedU8(_) => {
-                assert!(
-                    !self.quantized_data.is_empty(),
-                    "get_vector called on storage without quantized data"
-                );
-                assert!(
-                    end <= self.quantized_data.len(),
-                    "get_vector: VectorId {} out of bounds (idx={}, end={}, data_len={})",
-                    id.0,
-                    idx,
-                    end,
-                    self.quantized_data.len()
-                );
-                let q_data = &self.quantized_data[start..end];
-                let q = self
-                    .quantizer
-                    .as_ref()
-                    .expect("quantizer not initialized in QuantizedU8 mode");
-                Cow::Owned(q.dequantize(q_data))
-            }
+        if self.config.keeps_f32() {
+            assert!(
+                !self.data_f32.is_empty(),
+                "get_vector called on storage without f32 data"
+            );
+            assert!(
+                end <= self.data_f32.len(),
+                "get_vector: VectorId {} out of bounds (idx={}, end={}, data_len={})",
+                id.0,
+                idx,
+                end,
+                self.data_f32.len()
+            );
+            Cow::Borrowed(&self.data_f32[start..end])
+        } else {
+            assert!(
+                !self.quantized_data.is_empty(),
+                "get_vector called on storage without quantized data"
+            );
+            assert!(
+                end <= self.quantized_data.len(),
+                "get_vector: VectorId {} out of bounds (idx={}, end={}, data_len={})",
+                id.0,
+                idx,
+                end,
+                self.quantized_data.len()
+            );
+            let q_data = &self.quantized_data[start..end];
+            let q = self
+                .quantizer
+                .as_ref()
+                .expect("quantizer not initialized in QuantizedU8 mode");
+            Cow::Owned(q.dequantize(q_data))
         }
     }
 
@@ -579,6 +843,71 @@ impl VectorStorage {
     }
 }
 
+/// Quantizes a flat buffer of `dim`-sized vectors into one flat code buffer.
+fn quantize_all(quantizer: &ScalarQuantizer, vectors: &[f32], dim: usize) -> Vec<u8> {
+    let mut codes = Vec::with_capacity(vectors.len());
+    if dim > 0 {
+        for vector in vectors.chunks_exact(dim) {
+            codes.extend_from_slice(&quantizer.quantize(vector));
+        }
+    }
+    codes
+}
+
+/// Decodes a postcard-encoded `StorageType` WAL payload (entry type 8).
+pub(crate) fn decode_storage_type_payload(payload: &[u8]) -> Result<StorageType, StorageError> {
+    postcard::from_bytes(payload)
+        .map_err(|e| StorageError::Corrupted(format!("Invalid storage type: {e}")))
+}
+
+/// Decodes an `[u64 ID] + [f32...]` WAL payload (entry types 0 and 3).
+pub(crate) fn decode_f32_payload(
+    payload: &[u8],
+    config: &HnswConfig,
+) -> Result<(u64, Vec<f32>), StorageError> {
+    if payload.len() < 8 {
+        return Err(StorageError::Corrupted("Insert payload too short".into()));
+    }
+    let id_bytes: [u8; 8] = payload[0..8].try_into().expect("payload length checked");
+    let id = u64::from_le_bytes(id_bytes);
+
+    let vec_bytes = &payload[8..];
+    if vec_bytes.len() % 4 != 0 {
+        return Err(StorageError::Corrupted(
+            "Vector bytes alignment error".into(),
+        ));
+    }
+
+    let vec_len = vec_bytes.len() / 4;
+    if let Ok(len) = u32::try_from(vec_len) {
+        if len != config.dimensions {
+            return Err(StorageError::DimensionMismatch {
+                expected: config.dimensions,
+                actual: len,
+            });
+        }
+    } else {
+        return Err(StorageError::DimensionMismatch {
+            expected: config.dimensions,
+            actual: u32::MAX,
+        });
+    }
+
+    // Convert bytes back to f32
+    let vector = vec_bytes
+        .chunks_exact(4)
+        .map(|chunk| {
+            // SAFETY: chunks_exact(4) guarantees each chunk is exactly 4 bytes.
+            let b: [u8; 4] = chunk
+                .try_into()
+                .expect("chunks_exact returns exact size slices");
+            f32::from_le_bytes(b)
+        })
+        .collect();
+
+    Ok((id, vector))
+}
+
 impl VectorProvider for VectorStorage {
     fn get_vector(&self, id: VectorId) -> Cow<'_, [f32]> {
         self.get_vector(id)
@@ -589,24 +918,28 @@ impl VectorProvider for VectorStorage {
     }
 
     fn get_quantized_vector(&self, id: VectorId) -> Option<&[u8]> {
-        match self.config {
-            StorageType::QuantizedU8(_) => Some(self.get_quantized_vector(id)),
-            StorageType::Float32 => None,
+        if self.config.is_quantized() {
+            Some(self.get_quantized_vector(id))
+        } else {
+            None
         }
     }
 
     fn quantize_query<'a>(&self, query: &[f32], output: &'a mut Vec<u8>) -> Option<&'a [u8]> {
-        match &self.config {
-            StorageType::QuantizedU8(_) => {
-                if let Some(q) = &self.quantizer {
-                    *output = q.quantize(query);
-                    Some(output)
-                } else {
-                    None
-                }
-            }
-            StorageType::Float32 => None,
+        if !self.config.is_quantized() {
+            return None;
         }
+        let q = self.quantizer.as_ref()?;
+        *output = q.quantize(query);
+        Some(output)
+    }
+
+    fn quantized_distance(&self, metric: u32, a: &[u8], b: &[u8]) -> f32 {
+        // Codes are only handed out in SQ8 mode, which always has a quantizer
+        self.quantizer
+            .as_ref()
+            .expect("quantizer not initialized in QuantizedU8 mode")
+            .distance(metric, a, b)
     }
 }
 
@@ -667,6 +1000,236 @@ mod tests {
         assert!((slice[1] - 10.0).abs() < 1e-5);
     }
 
+    #[test]
+    fn test_dual_storage_keeps_f32_and_codes() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let q_config = QuantizerConfig {
+            min: 0.0,
+            max: 10.0,
+        };
+        storage.set_storage_type(StorageType::DualU8(q_config));
+        assert!(storage.storage_type().is_quantized());
+        assert!(storage.storage_type().keeps_f32());
+
+        let id = storage.insert(&[0.3, 10.0]).unwrap();
+        assert_eq!(storage.get_quantized_vector(id), &[8, 255]);
+        // Exact vectors are borrowed, not dequantized
+        let exact = storage.get_vector(id);
+        assert!(matches!(exact, Cow::Borrowed(_)));
+        assert_eq!(&exact[..], &[0.3, 10.0]);
+
+        storage.update(id, &[5.0, 0.1]).unwrap();
+        assert_eq!(&storage.get_vector(id)[..], &[5.0, 0.1]);
+        assert_eq!(storage.get_quantized_vector(id), &[128, 3]);
+
+        // Pre-quantized inserts keep both buffers aligned
+        let id2 = storage.insert_quantized(&[0, 255]).unwrap();
+        assert_eq!(&storage.get_vector(id2)[..], &[0.0, 10.0]);
+        assert_eq!(storage.get_quantized_vector(id2), &[0, 255]);
+
+        storage.quantized_data = CowSlice::default();
+        storage.rebuild_codes();
+        assert_eq!(storage.get_quantized_vector(id), &[128, 3]);
+        assert_eq!(storage.get_quantized_vector(id2), &[0, 255]);
+    }
+
+    #[test]
+    fn test_convert_to_reencodes_existing_vectors() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let a = storage.insert(&[0.0, 10.0]).unwrap();
+        let b = storage.insert(&[5.0, 2.5]).unwrap();
+        storage.mark_deleted(b);
+
+        let q_config = QuantizerConfig {
+            min: 0.0,
+            max: 10.0,
+        };
+        storage
+            .convert_to(StorageType::QuantizedU8(q_config))
+            .unwrap();
+        assert!(storage.data_f32.is_empty());
+        assert_eq!(storage.get_quantized_vector(a), &[0, 255]);
+        // Tombstoned slots keep routing data
+        assert_eq!(storage.get_quantized_vector(b), &[128, 64]);
+
+        storage.convert_to(StorageType::DualU8(q_config)).unwrap();
+        assert!(matches!(storage.get_vector(a), Cow::Borrowed(_)));
+        assert_eq!(storage.get_quantized_vector(b), &[128, 64]);
+
+        storage.convert_to(StorageType::Float32).unwrap();
+        assert!(storage.quantized_data.is_empty());
+        assert!(storage.quantizer.is_none());
+        let restored = storage.get_vector(b);
+        assert!((restored[0] - 5.0).abs() < 0.03);
+        assert!((restored[1] - 2.5).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_convert_to_rejects_mismatched_ranges() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let id = storage.insert(&[1.0, 2.0]).unwrap();
+
+        let ranges = Sq8Ranges {
+            mode: crate::quantization::Sq8Mode::Asymmetric,
+            min: vec![0.0; 3],
+            max: vec![1.0; 3],
+        };
+        assert!(matches!(
+            storage.convert_to(StorageType::QuantizedU8Ranges(ranges)),
+            Err(StorageError::DimensionMismatch {
+                expected: 2,
+                actual: 3
+            })
+        ));
+        assert_eq!(storage.storage_type(), &StorageType::Float32);
+        assert_eq!(&storage.get_vector(id)[..], &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_conversion_replayed_from_wal() {
+        use crate::persistence::storage::MemoryBackend;
+
+        let config = HnswConfig::new(2);
+        let backend = MemoryBackend::new();
+        let wal = WalAppender::new(Box::new(backend.clone()), 0);
+        let mut storage = VectorStorage::new(&config, Some(wal));
+
+        let a = storage.insert(&[1.0, 9.0]).unwrap();
+        let target = StorageType::QuantizedU8(QuantizerConfig {
+            min: 0.0,
+            max: 10.0,
+        });
+        storage.convert_to(target.clone()).unwrap();
+        // Codes logged after the conversion use the new quantizer
+        let b = storage.insert_quantized(&[255, 0]).unwrap();
+
+        let recovered = VectorStorage::recover(Box::new(backend), &config).unwrap();
+        assert_eq!(recovered.storage_type(), &target);
+        assert_eq!(
+            recovered.get_quantized_vector(a),
+            storage.get_quantized_vector(a)
+        );
+        assert_eq!(&recovered.get_vector(b)[..], &[10.0, 0.0]);
+    }
+
+    #[test]
+    fn test_inserts_and_updates_after_conversion_replayed_from_wal() {
+        use crate::persistence::storage::MemoryBackend;
+        use crate::quantization::Sq8Mode;
+
+        let config = HnswConfig::new(2);
+        let q_config = QuantizerConfig {
+            min: 0.0,
+            max: 10.0,
+        };
+        let ranges = Sq8Ranges {
+            mode: Sq8Mode::Asymmetric,
+            min: vec![0.0, -5.0],
+            max: vec![10.0, 5.0],
+        };
+        let targets = [
+            StorageType::Float32,
+            StorageType::QuantizedU8(q_config),
+            StorageType::QuantizedU8Ranges(ranges.clone()),
+            StorageType::DualU8(q_config),
+            StorageType::DualU8Ranges(ranges),
+        ];
+
+        for target in targets {
+            let backend = MemoryBackend::new();
+            let wal = WalAppender::new(Box::new(backend.clone()), 0);
+            let mut storage = VectorStorage::new(&config, Some(wal));
+
+            let a = storage.insert(&[1.0, 2.0]).unwrap();
+            if target == StorageType::Float32 {
+                // Start from codes so the conversion back is not a no-op
+                storage
+                    .convert_to(StorageType::QuantizedU8(q_config))
+                    .unwrap();
+            }
+            storage.convert_to(target.clone()).unwrap();
+            let b = storage.insert(&[3.0, 4.0]).unwrap();
+            storage.update(a, &[5.0, 1.0]).unwrap();
+            storage.update(b, &[7.0, 3.0]).unwrap();
+
+            let recovered = VectorStorage::recover(Box::new(backend), &config).unwrap();
+            assert_eq!(recovered.storage_type(), &target);
+            assert_eq!(recovered.len(), 2);
+            for id in [a, b] {
+                assert_eq!(
+                    recovered.get_vector(id),
+                    storage.get_vector(id),
+                    "{target:?}"
+                );
+                if target.is_quantized() {
+                    assert_eq!(
+                        recovered.get_quantized_vector(id),
+                        storage.get_quantized_vector(id),
+                        "{target:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_update_overwrites_slot() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let id1 = storage.insert(&[1.0, 2.0]).unwrap();
+        let id2 = storage.insert(&[3.0, 4.0]).unwrap();
+
+        storage.update(id1, &[5.0, 6.0]).unwrap();
+
+        assert_eq!(&storage.get_vector(id1)[..], &[5.0, 6.0]);
+        assert_eq!(&storage.get_vector(id2)[..], &[3.0, 4.0]);
+        assert_eq!(storage.len(), 2);
+    }
+
+    #[test]
+    fn test_update_rejects_missing_or_deleted() {
+        let config = HnswConfig::new(2);
+        let mut storage = VectorStorage::new(&config, None);
+        let id = storage.insert(&[1.0, 2.0]).unwrap();
+
+        assert!(matches!(
+            storage.update(VectorId(2), &[0.0, 0.0]),
+            Err(StorageError::NotFound(2))
+        ));
+        assert!(matches!(
+            storage.update(id, &[0.0]),
+            Err(StorageError::DimensionMismatch { .. })
+        ));
+
+        storage.mark_deleted(id);
+        assert!(matches!(
+            storage.update(id, &[0.0, 0.0]),
+            Err(StorageError::NotFound(1))
+        ));
+    }
+
+    #[test]
+    fn test_update_replayed_from_wal() {
+        use crate::persistence::storage::MemoryBackend;
+
+        let config = HnswConfig::new(2);
+        let backend = MemoryBackend::new();
+        let wal = WalAppender::new(Box::new(backend.clone()), 0);
+        let mut storage = VectorStorage::new(&config, Some(wal));
+
+        let id1 = storage.insert(&[1.0, 2.0]).unwrap();
+        let id2 = storage.insert(&[3.0, 4.0]).unwrap();
+        storage.update(id2, &[7.0, 8.0]).unwrap();
+
+        let recovered = VectorStorage::recover(Box::new(backend), &config).unwrap();
+        assert_eq!(recovered.len(), 2);
+        assert_eq!(&recovered.get_vector(id1)[..], &[1.0, 2.0]);
+        assert_eq!(&recovered.get_vector(id2)[..], &[7.0, 8.0]);
+    }
+
     #[test]
     fn test_insert_quantized() {
         let config = HnswConfig::new(2);
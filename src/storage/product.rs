@@ -0,0 +1,201 @@
+//! Product-quantized code storage.
+//!
+//! Holds a trained [`ProductQuantizer`](crate::quantization::ProductQuantizer) and one code (`sub_vectors` bytes)
+//! per HNSW node, stored contiguously in insertion order so the code of
+//! node `n` starts at `n * sub_vectors`.
+//!
+//! Cosine indexes encode unit-length vectors: `PqCodeStorage` normalizes
+//! vectors before encoding when its metric is `METRIC_COSINE`, matching the
+//! normalized query used by
+//! [`ProductQuantizer::distance_table`](crate::quantization::ProductQuantizer::distance_table).
+//!
+//! # Example
+//!
+//! ```
+//! use edgevec::hnsw::HnswConfig;
+//! use edgevec::quantization::product::{PqConfig, ProductQuantizer};
+//! use edgevec::storage::product::PqCodeStorage;
+//!
+//! let vectors: Vec<Vec<f32>> = (0..50).map(|i| vec![i as f32; 8]).collect();
+//! let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+//! let pq = ProductQuantizer::train(&refs, &PqConfig::new(2)).unwrap();
+//!
+//! let mut codes = PqCodeStorage::new(pq, HnswConfig::METRIC_L2_SQUARED);
+//! let id = codes.insert(&vectors[7]).unwrap();
+//! assert_eq!(codes.get(id).unwrap().len(), 2);
+//! ```
+
+use crate::hnsw::HnswConfig;
+use crate::quantization::product::{
+    normalized, DistanceTable, PqConfig, PqError, ProductQuantizer,
+};
+use std::borrow::Cow;
+
+/// PQ codes for every node of an index, with the quantizer that made them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PqCodeStorage {
+    quantizer: ProductQuantizer,
+    /// Index metric (`HnswConfig::METRIC_*`)
+    metric: u32,
+    /// `sub_vectors` bytes per node, in node order
+    codes: Vec<u8>,
+}
+
+impl PqCodeStorage {
+    /// Creates empty storage for codes made by `quantizer`, for an index
+    /// using `metric`.
+    #[must_use]
+    pub fn new(quantizer: ProductQuantizer, metric: u32) -> Self {
+        Self {
+            quantizer,
+            metric,
+            codes: Vec::new(),
+        }
+    }
+
+    /// Trains a quantizer on `vectors` and returns empty storage for it.
+    ///
+    /// For cosine the vectors are normalized before training, as they are
+    /// before encoding.
+    ///
+    /// # Errors
+    ///
+    /// See [`ProductQuantizer::train`].
+    pub fn train(vectors: &[&[f32]], config: &PqConfig, metric: u32) -> Result<Self, PqError> {
+        let quantizer = if metric == HnswConfig::METRIC_COSINE {
+            let unit: Vec<Vec<f32>> = vectors.iter().map(|v| normalized(v)).collect();
+            let refs: Vec<&[f32]> = unit.iter().map(Vec::as_slice).collect();
+            ProductQuantizer::train(&refs, config)?
+        } else {
+            ProductQuantizer::train(vectors, config)?
+        };
+        Ok(Self::new(quantizer, metric))
+    }
+
+    /// Rebuilds storage from its parts, e.g. as read from a snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CodeLengthMismatch` if `codes` is not a whole number of
+    /// codes.
+    pub fn from_raw_parts(
+        quantizer: ProductQuantizer,
+        metric: u32,
+        codes: Vec<u8>,
+    ) -> Result<Self, PqError> {
+        let code_size = quantizer.sub_vectors();
+        if codes.len() % code_size != 0 {
+            return Err(PqError::CodeLengthMismatch {
+                expected: code_size,
+                actual: codes.len() % code_size,
+            });
+        }
+        Ok(Self {
+            quantizer,
+            metric,
+            codes,
+        })
+    }
+
+    /// Returns empty storage sharing this storage's quantizer and metric.
+    #[must_use]
+    pub fn emptied(&self) -> Self {
+        Self::new(self.quantizer.clone(), self.metric)
+    }
+
+    /// The quantizer the codes were made with.
+    #[must_use]
+    pub fn quantizer(&self) -> &ProductQuantizer {
+        &self.quantizer
+    }
+
+    /// Number of stored codes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.codes.len() / self.quantizer.sub_vectors()
+    }
+
+    /// Returns true if no codes are stored.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Bytes used by the codes (the codebooks not included).
+    #[must_use]
+    pub fn memory_bytes(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// All codes, concatenated in node order.
+    #[must_use]
+    pub fn raw_codes(&self) -> &[u8] {
+        &self.codes
+    }
+
+    /// Encodes `vector` and appends its code, returning its ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DimensionMismatch` if `vector` has the wrong length.
+    pub fn insert(&mut self, vector: &[f32]) -> Result<u64, PqError> {
+        let id = self.len() as u64;
+        let vector = self.prepare(vector);
+        self.quantizer.encode_into(&vector, &mut self.codes)?;
+        Ok(id)
+    }
+
+    /// Appends an all-zero code, keeping IDs aligned with node indices
+    /// for nodes that have no vector to encode.
+    pub fn insert_placeholder(&mut self) -> u64 {
+        let id = self.len() as u64;
+        self.codes
+            .resize(self.codes.len() + self.quantizer.sub_vectors(), 0);
+        id
+    }
+
+    /// Re-encodes the code stored under `id`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if `id` is out of range.
+    /// - `DimensionMismatch` if `vector` has the wrong length.
+    pub fn update(&mut self, id: u64, vector: &[f32]) -> Result<(), PqError> {
+        if id >= self.len() as u64 {
+            return Err(PqError::NotFound { id });
+        }
+        let code = self.quantizer.encode(&self.prepare(vector))?;
+        // SAFETY: id < len, which fits in usize.
+        #[allow(clippy::cast_possible_truncation)]
+        let start = id as usize * code.len();
+        self.codes[start..start + code.len()].copy_from_slice(&code);
+        Ok(())
+    }
+
+    /// The code stored under `id`.
+    #[must_use]
+    pub fn get(&self, id: u64) -> Option<&[u8]> {
+        let code_size = self.quantizer.sub_vectors();
+        let start = usize::try_from(id).ok()?.checked_mul(code_size)?;
+        self.codes.get(start..start + code_size)
+    }
+
+    /// Builds the ADC table for `query` under this storage's metric.
+    ///
+    /// # Errors
+    ///
+    /// - `DimensionMismatch` if `query` has the wrong length.
+    /// - `InvalidConfig` if the metric is not a known metric code.
+    pub fn distance_table(&self, query: &[f32]) -> Result<DistanceTable, PqError> {
+        self.quantizer.distance_table(query, self.metric)
+    }
+
+    /// Returns the vector as it is encoded: normalized for cosine.
+    fn prepare<'v>(&self, vector: &'v [f32]) -> Cow<'v, [f32]> {
+        if self.metric == HnswConfig::METRIC_COSINE {
+            Cow::Owned(normalized(vector))
+        } else {
+            Cow::Borrowed(vector)
+        }
+    }
+}
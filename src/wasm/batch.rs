@@ -14,7 +14,6 @@
 //! - `InternalError` → `INTERNAL_ERROR`
 
 use super::memory::track_batch_insert;
-use crate::batch::BatchInsertable;
 use crate::error::BatchError;
 use js_sys::{Array, Float32Array};
 use wasm_bindgen::prelude::*;
@@ -153,7 +152,7 @@ fn convert_js_vectors(vectors: &Array, start_id: u64) -> Result<Vec<(u64, Vec<f3
 /// Implements the WASM `insertBatch` method for `EdgeVec`.
 ///
 /// This function is the FFI bridge between JavaScript and the Rust
-/// `BatchInsertable::batch_insert` method.
+/// batch insertion logic behind `BatchInsertable::batch_insert`.
 ///
 /// # Arguments
 ///
@@ -188,11 +187,12 @@ pub fn insert_batch_impl(
     // Convert JS vectors to Rust format
     let rust_vectors = convert_js_vectors(&vectors, start_id)?;
 
-    // Call the BatchInsertable trait method
-    let ids = edge_vec.inner.batch_insert(
+    // The generated IDs are placeholders, so don't record them as external IDs
+    let ids = edge_vec.inner.batch_insert_impl(
         rust_vectors,
         &mut edge_vec.storage,
         None::<fn(usize, usize)>,
+        false,
     )?;
 
     // Track memory allocation for memory pressure monitoring
@@ -655,16 +655,22 @@ impl EdgeVec {
     ///
     /// A `PersistenceIterator` that yields `Uint8Array` chunks.
     ///
+    /// # Errors
+    ///
+    /// Returns an error if a section of the index cannot be serialized.
+    ///
     /// # Safety
     ///
     /// The returned iterator holds a reference to this `EdgeVec` instance.
     /// You MUST ensure `EdgeVec` is not garbage collected or freed while using the iterator.
     #[wasm_bindgen]
-    #[must_use]
-    pub fn save_stream(&self, chunk_size: Option<usize>) -> PersistenceIterator {
+    pub fn save_stream(&self, chunk_size: Option<usize>) -> Result<PersistenceIterator, JsValue> {
         let size = chunk_size.unwrap_or(10 * 1024 * 1024); // 10MB default
         let writer = (&self.storage, &self.inner);
         let iter = writer.export_chunked(size);
+        if let Some(error) = iter.error() {
+            return Err(EdgeVecError::from(PersistenceError::Corrupted(error.to_string())).into());
+        }
 
         // SAFETY: We transmute the lifetime to 'static to allow returning the iterator to JS.
         // JS garbage collection manages the lifetime of EdgeVec.
@@ -672,10 +678,10 @@ impl EdgeVec {
         // This is a common pattern in wasm-bindgen for iterators.
         let static_iter = unsafe { std::mem::transmute::<ChunkIter<'_>, ChunkIter<'static>>(iter) };
 
-        PersistenceIterator {
+        Ok(PersistenceIterator {
             iter: static_iter,
             liveness: self.liveness.clone(),
-        }
+        })
     }
 
     /// Saves the database to IndexedDB.
@@ -1848,16 +1854,19 @@ impl EdgeVec {
 
                 // Rescore filtered candidates with F32 if we have enough
                 if !filtered.is_empty() {
-                    use super::hnsw::rescore::rescore_top_k;
-                    let rescored = rescore_top_k(
+                    use super::hnsw::rescore::{rescore_top_k_with_metric, similarity};
+                    let metric = self.inner.config.metric;
+                    let rescored = rescore_top_k_with_metric(
                         &filtered,
                         &query_vec,
                         &self.storage,
+                        metric,
                         opts.k.min(filtered.len()),
-                    );
+                    )
+                    .map_err(EdgeVecError::from)?;
                     filtered = rescored
                         .into_iter()
-                        .map(|(id, dist)| (id, 1.0 / (1.0 + dist)))
+                        .map(|(id, dist)| (id, similarity(metric, dist)))
                         .collect();
                 }
 
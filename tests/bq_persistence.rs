@@ -2,13 +2,16 @@
 //!
 //! Tests that persistence works correctly when BQ is enabled:
 //! - Index can be saved/loaded with BQ enabled
-//! - F32 vectors are preserved (BQ vectors regenerated on load)
+//! - F32 and BQ vectors are preserved (BQ is restored from its own section)
 //! - Metadata is preserved across save/load
 //! - Search works after reload
 
 use edgevec::hnsw::{HnswConfig, HnswIndex};
 use edgevec::metadata::MetadataValue;
-use edgevec::persistence::{read_snapshot, write_snapshot, MemoryBackend};
+use edgevec::persistence::{
+    read_snapshot, write_snapshot, FileHeader, Flags, MemoryBackend, PersistenceError,
+    StorageBackend,
+};
 use edgevec::storage::VectorStorage;
 use rand::rngs::StdRng;
 use rand::{Rng, SeedableRng};
@@ -189,7 +192,7 @@ mod bq_persistence {
         );
     }
 
-    /// Test roundtrip with BQ index - verify BQ needs to be re-enabled after load.
+    /// Test roundtrip with BQ index - BQ is restored without calling `enable_bq`.
     #[test]
     fn test_bq_index_bq_state_after_load() {
         const DIM: u32 = 64;
@@ -213,16 +216,107 @@ mod bq_persistence {
         // Save and load
         let mut backend = MemoryBackend::new();
         write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
-        let (loaded_index, _) = read_snapshot(&backend).expect("Failed to read snapshot");
+        let (loaded_index, loaded_storage) =
+            read_snapshot(&backend).expect("Failed to read snapshot");
 
-        // Note: BQ storage is NOT persisted, so after load, has_bq() returns false
-        // This is expected behavior - BQ can be regenerated from F32 vectors
-        assert!(
-            !loaded_index.has_bq(),
-            "Loaded index should not have BQ (BQ not persisted)"
+        assert!(loaded_index.has_bq(), "Loaded index should have BQ");
+        let original_bq = index.bq_storage().unwrap();
+        let loaded_bq = loaded_index.bq_storage().unwrap();
+        assert_eq!(loaded_bq.len(), original_bq.len());
+        for ((id_a, data_a, del_a), (id_b, data_b, del_b)) in
+            original_bq.iter_all().zip(loaded_bq.iter_all())
+        {
+            assert_eq!((id_a, data_a, del_a), (id_b, data_b, del_b));
+        }
+
+        // search_bq works immediately and scores like the original index
+        // (equal-score ties may come back in a different order)
+        let query: Vec<f32> = (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect();
+        let scores = |results: Vec<(edgevec::hnsw::VectorId, f32)>| -> Vec<f32> {
+            results.into_iter().map(|(_, score)| score).collect()
+        };
+        let loaded_results = loaded_index
+            .search_bq(&query, 10, &loaded_storage)
+            .expect("BQ search after load failed");
+        assert_eq!(loaded_results.len(), 10);
+        assert_eq!(
+            scores(loaded_results),
+            scores(
+                index
+                    .search_bq(&query, 10, &storage)
+                    .expect("BQ search failed")
+            )
         );
+    }
+
+    /// Test that soft deletes stay hidden from BQ search after reload.
+    #[test]
+    fn test_bq_index_soft_delete_after_load() {
+        const DIM: u32 = 32;
+
+        let config = HnswConfig::new(DIM);
+        let mut storage = VectorStorage::new(&config, None);
+        let mut index = HnswIndex::with_bq(config, &storage).expect("BQ index creation failed");
+
+        let mut rng = StdRng::seed_from_u64(4242);
+        let mut ids = Vec::new();
+        for _ in 0..40 {
+            let v: Vec<f32> = (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            ids.push(index.insert_bq(&v, &mut storage).expect("Insert failed"));
+        }
+        let target = ids[7];
+        index.soft_delete(target).expect("Delete failed");
+        let query = storage.get_vector(target).into_owned();
+
+        let mut backend = MemoryBackend::new();
+        write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+        let (loaded_index, loaded_storage) =
+            read_snapshot(&backend).expect("Failed to read snapshot");
 
-        println!("BQ state after load: has_bq() = {}", loaded_index.has_bq());
+        let results = loaded_index
+            .search_bq_rescored(&query, 10, 4, &loaded_storage)
+            .expect("BQ search after load failed");
+        assert!(results.iter().all(|(id, _)| *id != target));
+    }
+
+    /// Test that a corrupted BQ section is rejected by its own CRC.
+    #[test]
+    fn test_bq_section_crc_mismatch_rejected() {
+        const DIM: u32 = 16;
+
+        let config = HnswConfig::new(DIM);
+        let mut storage = VectorStorage::new(&config, None);
+        let mut index = HnswIndex::with_bq(config, &storage).expect("BQ index creation failed");
+        for i in 0..10 {
+            #[allow(clippy::cast_precision_loss)]
+            let v = vec![i as f32 - 5.0; DIM as usize];
+            index.insert_bq(&v, &mut storage).expect("Insert failed");
+        }
+
+        let mut backend = MemoryBackend::new();
+        write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+        let mut data = backend.read().expect("Failed to read backend");
+
+        let mut header = FileHeader::from_bytes(&data[..64]).expect("Invalid header");
+        assert!(header.flags & Flags::HAS_BQ != 0);
+
+        // The BQ section is last: flip a vector byte, then fix the outer data
+        // CRC so only the section CRC can catch it.
+        let len = data.len();
+        data[len - 3] ^= 0xFF;
+        header.data_crc = crc32fast::hash(&data[64..]);
+        header.update_checksum();
+        data[..64].copy_from_slice(header.as_bytes());
+
+        let corrupted = MemoryBackend::new();
+        corrupted.atomic_write("", &data).expect("Failed to write");
+
+        let result = read_snapshot(&corrupted);
+        assert!(
+            matches!(result, Err(PersistenceError::Corrupted(ref msg)) if msg.contains("BQ CRC")),
+            "Expected BQ CRC error, got {:?}",
+            result.err()
+        );
     }
 
     /// Test that we can insert more vectors after loading a BQ index.
@@ -291,6 +385,10 @@ mod bq_persistence {
 
         assert_eq!(loaded_index.len(), 0);
         assert_eq!(loaded_storage.len(), 0);
+        assert!(
+            loaded_index.has_bq(),
+            "Empty BQ index should keep BQ enabled"
+        );
 
         println!("Empty BQ index roundtrip: success");
     }
@@ -0,0 +1,111 @@
+//! Integration tests for metric-aware BQ rescoring.
+//!
+//! `search_bq_rescored` must rank candidates by the index metric, not L2,
+//! and report scores consistent with that metric.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::metric::simd::cosine_similarity;
+use edgevec::storage::VectorStorage;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+const DIM: u32 = 32;
+const COUNT: usize = 60;
+
+/// Random vectors with very different magnitudes, so L2 and angular
+/// rankings disagree.
+fn random_vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let scale = rng.gen_range(0.1..20.0);
+            (0..DIM).map(|_| rng.gen_range(-1.0..1.0) * scale).collect()
+        })
+        .collect()
+}
+
+fn build(metric: u32) -> (HnswIndex, VectorStorage, Vec<Vec<f32>>) {
+    let mut config = HnswConfig::new(DIM);
+    config.metric = metric;
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::with_bq(config, &storage).unwrap();
+
+    let vectors = random_vectors(COUNT, 1);
+    for v in &vectors {
+        index.insert_bq(v, &mut storage).unwrap();
+    }
+    (index, storage, vectors)
+}
+
+/// Exact top-k by a "higher is better" score.
+fn exact_top_k(vectors: &[Vec<f32>], k: usize, score: impl Fn(&[f32]) -> f32) -> Vec<VectorId> {
+    let mut all: Vec<(VectorId, f32)> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (VectorId(i as u64 + 1), score(v)))
+        .collect();
+    all.sort_by(|a, b| b.1.total_cmp(&a.1));
+    all.into_iter().take(k).map(|(id, _)| id).collect()
+}
+
+#[test]
+fn test_bq_rescore_cosine_ranks_by_angle() {
+    let (index, storage, vectors) = build(HnswConfig::METRIC_COSINE);
+    let query = random_vectors(1, 2).remove(0);
+
+    // Overfetch covers the whole index, so rescoring alone decides the order
+    let results = index
+        .search_bq_rescored(&query, 5, COUNT, &storage)
+        .unwrap();
+    let ids: Vec<VectorId> = results.iter().map(|(id, _)| *id).collect();
+    assert_eq!(
+        ids,
+        exact_top_k(&vectors, 5, |v| cosine_similarity(&query, v))
+    );
+
+    for (id, score) in &results {
+        let v = storage.get_vector(*id);
+        assert!((score - cosine_similarity(&query, &v)).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn test_bq_rescore_dot_product_ranks_by_dot() {
+    let (index, storage, vectors) = build(HnswConfig::METRIC_DOT_PRODUCT);
+    let query = random_vectors(1, 3).remove(0);
+    let dot = |v: &[f32]| v.iter().zip(&query).map(|(a, b)| a * b).sum::<f32>();
+
+    let results = index
+        .search_bq_rescored(&query, 5, COUNT, &storage)
+        .unwrap();
+    let ids: Vec<VectorId> = results.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, exact_top_k(&vectors, 5, dot));
+
+    for (id, score) in &results {
+        let v = storage.get_vector(*id);
+        assert!((score - dot(&v)).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn test_bq_rescore_l2_unchanged() {
+    let (index, storage, vectors) = build(HnswConfig::METRIC_L2_SQUARED);
+    let query = random_vectors(1, 4).remove(0);
+    let l2 = |v: &[f32]| {
+        v.iter()
+            .zip(&query)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f32>()
+    };
+
+    let results = index
+        .search_bq_rescored(&query, 5, COUNT, &storage)
+        .unwrap();
+    let ids: Vec<VectorId> = results.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, exact_top_k(&vectors, 5, |v| -l2(v)));
+
+    for (id, score) in &results {
+        let v = storage.get_vector(*id);
+        assert!((score - 1.0 / (1.0 + l2(&v))).abs() < 1e-5);
+    }
+}
@@ -4,9 +4,9 @@
 //! while preserving vector DATA and search quality.
 //!
 //! Note: Due to storage design constraints, vector IDs are REMAPPED during
-//! compaction. The tests verify that vector content is preserved, not IDs.
+//! compaction. `CompactionResult::id_map` reports the old → new mapping.
 
-use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::hnsw::{ExternalId, GraphError, HnswConfig, HnswIndex, VectorId};
 use edgevec::storage::VectorStorage;
 
 fn create_index_with_vectors(count: usize, dim: u32) -> (HnswIndex, VectorStorage) {
@@ -143,7 +143,7 @@ fn test_compact_no_tombstones_noop() {
 
     assert_eq!(result.tombstones_removed, 0);
     assert_eq!(result.new_size, 10);
-    assert_eq!(result.duration_ms, 0);
+    // duration_ms reports the rebuild, which happens even without tombstones
     assert_eq!(new_index.node_count(), 10);
 }
 
@@ -204,8 +204,9 @@ fn test_insert_with_id_validates_and_inserts() {
     let mut storage = VectorStorage::new(&config, None);
     let mut index = HnswIndex::new(config, &storage).unwrap();
 
-    // Note: insert_with_id validates the ID but assigns sequential IDs due to
-    // storage constraints. The returned ID will be 1 (first insert), not 42.
+    // Note: insert_with_id assigns sequential VectorIds due to storage
+    // constraints. The returned ID will be 1 (first insert), not 42; the
+    // requested ID is recorded as the vector's external ID.
     let requested_id = VectorId(42);
     let vector = vec![1.0, 2.0, 3.0, 4.0];
 
@@ -219,6 +220,12 @@ fn test_insert_with_id_validates_and_inserts() {
 
     // The assigned ID exists
     assert!(!index.is_deleted(assigned_id).unwrap());
+
+    // The requested ID resolves to the assigned one
+    assert_eq!(
+        index.resolve_external_id(&ExternalId::Num(42)),
+        Some(assigned_id)
+    );
 }
 
 #[test]
@@ -227,17 +234,19 @@ fn test_insert_with_id_rejects_existing_id() {
     let mut storage = VectorStorage::new(&config, None);
     let mut index = HnswIndex::new(config, &storage).unwrap();
 
-    // Insert first vector (gets ID 1)
+    // Insert first vector under caller ID 1
     let vector = vec![1.0, 2.0, 3.0, 4.0];
-    let first_id = index.insert(&vector, &mut storage).unwrap();
-    assert_eq!(first_id, VectorId(1));
+    index
+        .insert_with_id(VectorId(1), &vector, &mut storage)
+        .unwrap();
 
     // Try to insert with the same ID that already exists
     let result = index.insert_with_id(VectorId(1), &vector, &mut storage);
     assert!(
-        result.is_err(),
+        matches!(result, Err(GraphError::DuplicateExternalId(_))),
         "Should reject ID that already exists in index"
     );
+    assert_eq!(index.node_count(), 1);
 }
 
 #[test]
@@ -434,3 +443,61 @@ fn test_compaction_warning_custom_threshold() {
     );
     assert!(warning.unwrap().contains("10.0%"));
 }
+
+#[test]
+fn test_compact_returns_id_map() {
+    let (mut index, storage) = create_index_with_vectors(20, 4);
+    for i in (1..=20).step_by(2) {
+        index.soft_delete(VectorId(i)).unwrap();
+    }
+
+    let (new_index, new_storage, result) = index.compact(&storage).unwrap();
+
+    assert_eq!(result.id_map.len(), 10);
+    for old in (2..=20).step_by(2).map(VectorId) {
+        let new = result.id_map[&old];
+        assert_eq!(
+            storage.get_vector(old),
+            new_storage.get_vector(new),
+            "id_map must point at the same vector data"
+        );
+        assert!(!new_index.is_deleted(new).unwrap());
+    }
+    for old in (1..=20).step_by(2).map(VectorId) {
+        assert!(!result.id_map.contains_key(&old));
+    }
+}
+
+#[test]
+fn test_compact_preserves_bq() {
+    let config = HnswConfig::new(16);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::with_bq(config, &storage).unwrap();
+
+    for i in 0..30 {
+        let vec: Vec<f32> = (0..16)
+            .map(|j| if (i + j) % 3 == 0 { 1.0 } else { -1.0 })
+            .collect();
+        index.insert(&vec, &mut storage).unwrap();
+    }
+    for i in 1..=10 {
+        index.soft_delete(VectorId(i)).unwrap();
+    }
+
+    let (new_index, new_storage, result) = index.compact(&storage).unwrap();
+
+    let bq = new_index.bq_storage().expect("BQ must survive compaction");
+    assert_eq!(bq.len(), 20);
+
+    let old_bq = index.bq_storage().unwrap();
+    for (old, new) in &result.id_map {
+        // BQ slots are indexed by node position (VectorId - 1)
+        assert_eq!(old_bq.get_raw(old.0 - 1), bq.get_raw(new.0 - 1));
+    }
+
+    let query = new_storage.get_vector(VectorId(1)).into_owned();
+    assert!(!new_index
+        .search_bq(&query, 5, &new_storage)
+        .unwrap()
+        .is_empty());
+}
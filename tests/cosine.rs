@@ -0,0 +1,97 @@
+//! Integration tests for the cosine metric.
+//!
+//! `METRIC_COSINE` must rank by angle regardless of vector magnitude, report
+//! `1 - cosine_similarity` as the distance, and survive snapshots.
+
+use edgevec::flat::FlatIndex;
+use edgevec::hnsw::{HnswConfig, HnswIndex};
+use edgevec::metric::simd::cosine_similarity;
+use edgevec::persistence::{read_snapshot, write_snapshot, MemoryBackend};
+use edgevec::storage::VectorStorage;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+fn cosine_config(dim: u32) -> HnswConfig {
+    let mut config = HnswConfig::new(dim);
+    config.metric = HnswConfig::METRIC_COSINE;
+    config
+}
+
+/// Random vectors with wildly different magnitudes.
+fn random_vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let scale = rng.gen_range(0.01..100.0);
+            (0..dim).map(|_| rng.gen_range(-1.0..1.0) * scale).collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_cosine_ignores_magnitude() {
+    let config = cosine_config(3);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+
+    // Same direction as the query but far away in L2
+    let aligned = index.insert(&[100.0, 0.0, 0.0], &mut storage).unwrap();
+    // Close in L2 but at 45 degrees
+    index.insert(&[1.0, 1.0, 0.0], &mut storage).unwrap();
+    index.insert(&[0.0, -1.0, 0.0], &mut storage).unwrap();
+
+    let results = index.search(&[1.0, 0.0, 0.0], 3, &storage).unwrap();
+    assert_eq!(results[0].vector_id, aligned);
+    assert!(results[0].distance.abs() < 1e-6);
+    assert!((results[1].distance - (1.0 - std::f32::consts::FRAC_1_SQRT_2)).abs() < 1e-5);
+    assert!((results[2].distance - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_cosine_hnsw_matches_exact_search() {
+    let config = cosine_config(16);
+    let vectors = random_vectors(400, 16, 1);
+
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config.clone(), &storage).unwrap();
+    for v in &vectors {
+        index.insert(v, &mut storage).unwrap();
+    }
+    let flat = FlatIndex::new(config, &storage).unwrap();
+
+    let mut agree = 0;
+    let queries = random_vectors(50, 16, 2);
+    for query in &queries {
+        let approx = index.search(query, 1, &storage).unwrap();
+        let exact = flat.search(query, 1, &storage).unwrap();
+        if approx[0].vector_id == exact[0].vector_id {
+            agree += 1;
+        }
+
+        let v = storage.get_vector(exact[0].vector_id);
+        let expected = 1.0 - cosine_similarity(query, &v);
+        assert!((exact[0].distance - expected).abs() < 1e-5);
+    }
+    assert!(agree >= 48, "cosine top-1 recall too low: {agree}/50");
+}
+
+#[test]
+fn test_cosine_snapshot_roundtrip() {
+    let config = cosine_config(8);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for v in random_vectors(100, 8, 3) {
+        index.insert(&v, &mut storage).unwrap();
+    }
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded.config.metric, HnswConfig::METRIC_COSINE);
+
+    let query = vec![0.5; 8];
+    assert_eq!(
+        loaded.search(&query, 10, &loaded_storage).unwrap(),
+        index.search(&query, 10, &storage).unwrap()
+    );
+}
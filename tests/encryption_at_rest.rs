@@ -0,0 +1,129 @@
+//! Integration tests for `EncryptedBackend`.
+//!
+//! Snapshots and WAL logs written through the wrapper must never hit disk in
+//! plaintext, and must recover exactly like unencrypted ones.
+#![cfg(feature = "encryption")]
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::encryption::{EncryptedBackend, EncryptionHeader};
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::wal::WalAppender;
+use edgevec::persistence::{
+    checkpoint, read_snapshot, recover_index, write_snapshot, write_snapshot_compressed,
+    Compression, PersistenceError, StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+use std::path::Path;
+
+const DIM: u32 = 8;
+const SECRET: &[u8] = b"device-bound key material, 32 by";
+
+fn vector(seed: u32) -> Vec<f32> {
+    (0..DIM)
+        .map(|d| ((seed * 31 + d * 7) % 17) as f32 + 0.25)
+        .collect()
+}
+
+fn encrypted(path: &Path) -> EncryptedBackend<FileBackend> {
+    EncryptedBackend::new(FileBackend::new(path), SECRET).unwrap()
+}
+
+/// True if the raw bytes of `v` appear anywhere in `data`.
+fn contains_vector(data: &[u8], v: &[f32]) -> bool {
+    let needle: &[u8] = bytemuck::cast_slice(v);
+    data.windows(needle.len()).any(|w| w == needle)
+}
+
+#[test]
+fn test_encrypted_snapshot_and_wal_recover() {
+    let dir = tempfile::tempdir().unwrap();
+    let snapshot_path = dir.path().join("index.evec");
+    let wal_path = dir.path().join("index.wal");
+
+    let config = HnswConfig::new(DIM);
+    let wal = WalAppender::new(Box::new(encrypted(&wal_path)), 0);
+    let mut storage = VectorStorage::new(&config, Some(wal));
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..20 {
+        index.insert(&vector(i), &mut storage).unwrap();
+    }
+    let mut snapshot = encrypted(&snapshot_path);
+    checkpoint(&index, &mut storage, &mut snapshot).unwrap();
+
+    // Only in the WAL
+    let late = index.insert(&vector(100), &mut storage).unwrap();
+    index.soft_delete_logged(VectorId(3), &mut storage).unwrap();
+    drop((index, storage));
+
+    let raw_snapshot = std::fs::read(&snapshot_path).unwrap();
+    let raw_wal = std::fs::read(&wal_path).unwrap();
+    assert!(!contains_vector(&raw_snapshot, &vector(5)));
+    assert!(!contains_vector(&raw_wal, &vector(100)));
+    assert!(!raw_snapshot.starts_with(b"EVEC"));
+
+    let (recovered, recovered_storage) =
+        recover_index(&encrypted(&snapshot_path), Box::new(encrypted(&wal_path))).unwrap();
+    assert_eq!(recovered.node_count(), 21);
+    assert_eq!(recovered_storage.get_vector(late)[..], vector(100)[..]);
+    assert!(recovered.is_deleted(VectorId(3)).unwrap());
+}
+
+#[test]
+fn test_compressed_snapshot_through_encryption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..50 {
+        index.insert(&vector(i), &mut storage).unwrap();
+    }
+    write_snapshot_compressed(&index, &storage, &mut encrypted(&path), Compression::Lz4).unwrap();
+
+    let (loaded, loaded_storage) = read_snapshot(&encrypted(&path)).unwrap();
+    let query = vector(7);
+    assert_eq!(
+        loaded.search(&query, 5, &loaded_storage).unwrap(),
+        index.search(&query, 5, &storage).unwrap()
+    );
+}
+
+#[test]
+fn test_wrong_key_and_tampering_surface_distinctly() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..10 {
+        index.insert(&vector(i), &mut storage).unwrap();
+    }
+    write_snapshot(&index, &storage, &mut encrypted(&path)).unwrap();
+
+    let wrong = EncryptedBackend::new(FileBackend::new(&path), b"some other secret");
+    assert!(matches!(wrong, Err(PersistenceError::WrongKey)));
+
+    // Flip one ciphertext byte: the key is right, but the frame fails to
+    // authenticate
+    let mut raw = std::fs::read(&path).unwrap();
+    raw[EncryptionHeader::SIZE + 40] ^= 0x80;
+    std::fs::write(&path, &raw).unwrap();
+    let backend = encrypted(&path);
+    let result = read_snapshot(&backend);
+    assert!(matches!(
+        result,
+        Err(PersistenceError::Tampered { offset }) if offset == EncryptionHeader::SIZE
+    ));
+
+    // An unencrypted file is not mistaken for either
+    let plain_path = dir.path().join("plain.evec");
+    write_snapshot(&index, &storage, &mut FileBackend::new(&plain_path)).unwrap();
+    let plain = EncryptedBackend::new(FileBackend::new(&plain_path), SECRET);
+    assert!(matches!(plain, Err(PersistenceError::Corrupted(_))));
+    assert!(FileBackend::new(&plain_path)
+        .read()
+        .unwrap()
+        .starts_with(b"EVEC"));
+}
@@ -0,0 +1,234 @@
+//! Integration tests for caller-supplied external IDs.
+//!
+//! External IDs must be usable for insert, batch insert, search, delete and
+//! fetch, and must survive `compact()` and snapshot round-trips even though
+//! internal `VectorId`s are renumbered.
+
+use edgevec::batch::BatchInsertable;
+use edgevec::hnsw::{ExternalId, GraphError, HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::{
+    read_snapshot, write_snapshot, ExternalIdSectionHeader, FileHeader, Flags, MemoryBackend,
+    PersistenceError, StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+
+fn create_env(dim: u32) -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(dim);
+    let storage = VectorStorage::new(&config, None);
+    let index = HnswIndex::new(config, &storage).expect("Failed to create index");
+    (index, storage)
+}
+
+fn unit(dim: usize, axis: usize) -> Vec<f32> {
+    let mut v = vec![0.0; dim];
+    v[axis] = 1.0;
+    v
+}
+
+#[test]
+fn test_insert_search_fetch_by_external_id() {
+    let (mut index, mut storage) = create_env(4);
+
+    index
+        .insert_with_external_id(1000u64, &unit(4, 0), &mut storage)
+        .unwrap();
+    index
+        .insert_with_external_id("beta", &unit(4, 1), &mut storage)
+        .unwrap();
+
+    let results = index.search_external(&unit(4, 1), 1, &storage).unwrap();
+    assert_eq!(results[0].external_id, Some(ExternalId::from("beta")));
+
+    let fetched = index
+        .get_external(&ExternalId::Num(1000), &storage)
+        .expect("vector should exist");
+    assert_eq!(&fetched[..], &unit(4, 0)[..]);
+}
+
+#[test]
+fn test_duplicate_external_id_rejected() {
+    let (mut index, mut storage) = create_env(4);
+
+    index
+        .insert_with_external_id("a", &unit(4, 0), &mut storage)
+        .unwrap();
+    let result = index.insert_with_external_id("a", &unit(4, 1), &mut storage);
+
+    assert!(matches!(result, Err(GraphError::DuplicateExternalId(_))));
+    assert_eq!(index.len(), 1);
+    assert_eq!(storage.len(), 1, "storage must not grow on rejection");
+}
+
+#[test]
+fn test_delete_external_releases_key() {
+    let (mut index, mut storage) = create_env(4);
+
+    let first = index
+        .insert_with_external_id(7u64, &unit(4, 0), &mut storage)
+        .unwrap();
+    assert!(index.delete_external(7u64).unwrap());
+    assert!(index.is_deleted(first).unwrap());
+    assert!(index.get_external(&ExternalId::Num(7), &storage).is_none());
+
+    // Key can be reused after delete
+    let second = index
+        .insert_with_external_id(7u64, &unit(4, 2), &mut storage)
+        .unwrap();
+    assert_ne!(first, second);
+    assert_eq!(index.resolve_external_id(&ExternalId::Num(7)), Some(second));
+
+    // Unknown key
+    assert!(matches!(
+        index.delete_external(99u64),
+        Err(GraphError::ExternalIdNotFound(ExternalId::Num(99)))
+    ));
+}
+
+#[test]
+fn test_batch_insert_records_caller_ids() {
+    let (mut index, mut storage) = create_env(4);
+
+    let vectors = vec![(500u64, unit(4, 0)), (600, unit(4, 1)), (700, unit(4, 2))];
+    let ids = index
+        .batch_insert(vectors, &mut storage, None::<fn(usize, usize)>)
+        .unwrap();
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    let results = index.search_external(&unit(4, 1), 1, &storage).unwrap();
+    assert_eq!(results[0].external_id, Some(ExternalId::Num(600)));
+
+    // Caller IDs, not internal IDs, are used for duplicate detection
+    let again = index
+        .batch_insert(
+            vec![(1u64, unit(4, 3)), (600, unit(4, 3))],
+            &mut storage,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+    assert_eq!(again.len(), 1, "only caller ID 600 is a duplicate");
+}
+
+#[test]
+fn test_external_ids_survive_compaction() {
+    let (mut index, mut storage) = create_env(4);
+
+    for i in 0..20u64 {
+        #[allow(clippy::cast_precision_loss)]
+        let v = vec![i as f32, 1.0, 0.0, 0.0];
+        index
+            .insert_with_external_id(format!("doc-{i}"), &v, &mut storage)
+            .unwrap();
+    }
+    for i in (0..20u64).step_by(2) {
+        index.delete_external(format!("doc-{i}")).unwrap();
+    }
+
+    let (new_index, new_storage, result) = index.compact(&storage).unwrap();
+    assert_eq!(result.new_size, 10);
+    assert_eq!(new_index.external_ids().len(), 10);
+
+    for i in (1..20u64).step_by(2) {
+        let key = ExternalId::from(format!("doc-{i}"));
+        let v = new_index
+            .get_external(&key, &new_storage)
+            .expect("live key survives compaction");
+        #[allow(clippy::cast_precision_loss)]
+        let expected = i as f32;
+        assert!((v[0] - expected).abs() < f32::EPSILON);
+    }
+    assert!(!new_index.contains_external_id(&ExternalId::from("doc-0")));
+}
+
+#[test]
+fn test_external_ids_survive_snapshot() {
+    let (mut index, mut storage) = create_env(4);
+
+    index
+        .insert_with_external_id(u64::MAX, &unit(4, 0), &mut storage)
+        .unwrap();
+    index.insert(&unit(4, 1), &mut storage).unwrap(); // no external ID
+    index
+        .insert_with_external_id("gamma", &unit(4, 2), &mut storage)
+        .unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+
+    let data = backend.read().unwrap();
+    let flags = u16::from_le_bytes([data[6], data[7]]);
+    assert_ne!(flags & Flags::HAS_EXTERNAL_IDS, 0);
+
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded.external_ids(), index.external_ids());
+    assert_eq!(
+        loaded.resolve_external_id(&ExternalId::from("gamma")),
+        Some(VectorId(3))
+    );
+
+    let results = loaded
+        .search_external(&unit(4, 1), 1, &loaded_storage)
+        .unwrap();
+    assert_eq!(results[0].vector_id, VectorId(2));
+    assert_eq!(results[0].external_id, None);
+}
+
+#[test]
+fn test_snapshot_without_external_ids_has_no_flag() {
+    let (mut index, mut storage) = create_env(4);
+    index.insert(&unit(4, 0), &mut storage).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+
+    let data = backend.read().unwrap();
+    let flags = u16::from_le_bytes([data[6], data[7]]);
+    assert_eq!(flags & Flags::HAS_EXTERNAL_IDS, 0);
+
+    let (loaded, _) = read_snapshot(&backend).unwrap();
+    assert!(loaded.external_ids().is_empty());
+}
+
+#[test]
+fn test_snapshot_with_duplicate_external_ids_rejected() {
+    let (mut index, mut storage) = create_env(4);
+    index
+        .insert_with_external_id("dup-a", &unit(4, 0), &mut storage)
+        .unwrap();
+    index
+        .insert_with_external_id("dup-b", &unit(4, 1), &mut storage)
+        .unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let mut data = backend.read().unwrap();
+
+    // Bind "dup-a" to both vectors, then re-seal every CRC so only the
+    // duplicate itself can be caught
+    let section = data.windows(4).position(|w| w == b"EXID").unwrap();
+    let start = section + ExternalIdSectionHeader::SIZE;
+    let mut section_header: ExternalIdSectionHeader =
+        bytemuck::pod_read_unaligned(&data[section..start]);
+    let end = start + section_header.size as usize;
+    let key = start
+        + data[start..end]
+            .windows(5)
+            .position(|w| w == b"dup-b")
+            .unwrap();
+    data[key + 4] = b'a';
+    section_header.crc = crc32fast::hash(&data[start..end]);
+    data[section..start].copy_from_slice(bytemuck::bytes_of(&section_header));
+
+    let mut header: FileHeader = bytemuck::pod_read_unaligned(&data[..64]);
+    header.data_crc = crc32fast::hash(&data[64..]);
+    header.update_checksum();
+    data[..64].copy_from_slice(header.as_bytes());
+
+    let tampered = MemoryBackend::new();
+    tampered.atomic_write("", &data).unwrap();
+    let result = read_snapshot(&tampered);
+    assert!(
+        matches!(result, Err(PersistenceError::Corrupted(ref msg)) if msg.contains("bound twice")),
+        "Expected duplicate binding error, got {:?}",
+        result.err()
+    );
+}
@@ -0,0 +1,166 @@
+//! Integration tests for the exact brute-force `FlatIndex`.
+//!
+//! The flat index must return the true nearest neighbors, honour metadata
+//! filters and soft deletes, round-trip through snapshots and promote to an
+//! `HnswIndex` without renumbering vectors.
+
+use edgevec::flat::FlatIndex;
+use edgevec::hnsw::{HnswConfig, VectorId};
+use edgevec::metadata::MetadataValue;
+use edgevec::persistence::wal::WalAppender;
+use edgevec::persistence::{
+    read_flat_snapshot, read_snapshot, write_flat_snapshot, write_snapshot, MemoryBackend,
+    PersistenceError, StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+fn random_vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect()
+}
+
+fn build(count: usize, dim: usize, seed: u64) -> (FlatIndex, VectorStorage) {
+    let config = HnswConfig::new(dim as u32);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = FlatIndex::new(config, &storage).unwrap();
+
+    for (i, v) in random_vectors(count, dim, seed).iter().enumerate() {
+        let mut meta = HashMap::new();
+        #[allow(clippy::cast_possible_wrap)]
+        meta.insert("bucket".to_string(), MetadataValue::Integer((i % 4) as i64));
+        index.insert_with_metadata(&mut storage, v, meta).unwrap();
+    }
+    (index, storage)
+}
+
+fn brute_force(storage: &VectorStorage, query: &[f32], k: usize) -> Vec<VectorId> {
+    let mut all: Vec<(VectorId, f32)> = (1..=storage.len() as u64)
+        .map(VectorId)
+        .filter(|&id| !storage.is_deleted(id))
+        .map(|id| {
+            let v = storage.get_vector(id);
+            let d: f32 = v.iter().zip(query).map(|(a, b)| (a - b) * (a - b)).sum();
+            (id, d)
+        })
+        .collect();
+    all.sort_by(|a, b| a.1.total_cmp(&b.1));
+    all.into_iter().take(k).map(|(id, _)| id).collect()
+}
+
+#[test]
+fn test_flat_search_matches_brute_force() {
+    let (index, storage) = build(500, 16, 1);
+
+    for query in random_vectors(20, 16, 2) {
+        let results = index.search(&query, 10, &storage).unwrap();
+        let ids: Vec<VectorId> = results.iter().map(|r| r.vector_id).collect();
+        assert_eq!(ids, brute_force(&storage, &query, 10));
+    }
+}
+
+#[test]
+fn test_flat_filtered_search_is_exact() {
+    let (index, storage) = build(200, 8, 3);
+    let query = vec![0.0; 8];
+
+    let results = index
+        .search_filtered(&storage, &query, "bucket = 2", 10)
+        .unwrap();
+    assert_eq!(results.len(), 10);
+
+    // Every match is in bucket 2 and no closer bucket-2 vector was skipped
+    let expected: Vec<VectorId> = brute_force(&storage, &query, 200)
+        .into_iter()
+        .filter(|id| (id.0 - 1) % 4 == 2)
+        .take(10)
+        .collect();
+    let ids: Vec<VectorId> = results.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, expected);
+}
+
+#[test]
+fn test_flat_snapshot_roundtrip() {
+    let (mut index, mut storage) = build(50, 4, 4);
+    index.soft_delete(VectorId(5), &mut storage).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_flat_snapshot(&index, &storage, &mut backend).unwrap();
+    let (loaded, loaded_storage) = read_flat_snapshot(&backend).unwrap();
+
+    assert_eq!(loaded.len(), 49);
+    assert_eq!(loaded.deleted_count(), 1);
+    assert_eq!(loaded.metadata(), index.metadata());
+
+    let query = vec![0.1; 4];
+    assert_eq!(
+        loaded.search(&query, 5, &loaded_storage).unwrap(),
+        index.search(&query, 5, &storage).unwrap()
+    );
+}
+
+#[test]
+fn test_snapshot_kinds_are_not_interchangeable() {
+    let (index, storage) = build(5, 4, 5);
+    let mut flat_backend = MemoryBackend::new();
+    write_flat_snapshot(&index, &storage, &mut flat_backend).unwrap();
+    assert!(matches!(
+        read_snapshot(&flat_backend),
+        Err(PersistenceError::Unsupported(_))
+    ));
+
+    let hnsw = index.into_hnsw(&storage).unwrap();
+    let mut hnsw_backend = MemoryBackend::new();
+    write_snapshot(&hnsw, &storage, &mut hnsw_backend).unwrap();
+    assert!(matches!(
+        read_flat_snapshot(&hnsw_backend),
+        Err(PersistenceError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn test_promote_to_hnsw_keeps_ids_metadata_and_deletes() {
+    let (mut index, mut storage) = build(300, 8, 6);
+    index.soft_delete(VectorId(10), &mut storage).unwrap();
+
+    let hnsw = index.into_hnsw(&storage).unwrap();
+    assert_eq!(hnsw.node_count(), 300);
+    assert_eq!(hnsw.live_count(), 299);
+    assert!(hnsw.is_deleted(VectorId(10)).unwrap());
+    assert!(hnsw.metadata().has_key(11, "bucket"));
+    assert!(!hnsw.metadata().has_key(10, "bucket"));
+
+    // Every live vector finds itself
+    for id in (1..=300).map(VectorId).filter(|&id| id != VectorId(10)) {
+        let query = storage.get_vector(id).into_owned();
+        let results = hnsw.search(&query, 1, &storage).unwrap();
+        assert_eq!(results[0].vector_id, id);
+    }
+
+    // The promoted index persists like any other HNSW index
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&hnsw, &storage, &mut backend).unwrap();
+    let (loaded, _) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded.deleted_count(), 1);
+}
+
+#[test]
+fn test_promote_to_hnsw_does_not_relog_deletes() {
+    let config = HnswConfig::new(4);
+    let wal = MemoryBackend::new();
+    let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+    let mut index = FlatIndex::new(config, &storage).unwrap();
+    for v in random_vectors(5, 4, 7) {
+        index.insert(&v, &mut storage).unwrap();
+    }
+    index.soft_delete(VectorId(2), &mut storage).unwrap();
+    let logged = wal.read().unwrap().len();
+
+    let hnsw = index.into_hnsw(&storage).unwrap();
+    assert!(hnsw.is_deleted(VectorId(2)).unwrap());
+    assert_eq!(wal.read().unwrap().len(), logged);
+}
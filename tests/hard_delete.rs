@@ -0,0 +1,188 @@
+//! Integration tests for hard delete with neighbor repair.
+//!
+//! After `hard_delete`, no neighbor list may reference the removed node and
+//! recall on the remaining vectors must stay close to a fresh build.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, NodeId, VectorId};
+use edgevec::persistence::wal::WalAppender;
+use edgevec::persistence::{read_snapshot, recover_index, write_snapshot, MemoryBackend};
+use edgevec::storage::VectorStorage;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+fn build_index(count: usize, dim: usize, seed: u64) -> (HnswIndex, VectorStorage, Vec<VectorId>) {
+    let config = HnswConfig::new(dim as u32);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).expect("Failed to create index");
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let ids = (0..count)
+        .map(|_| {
+            let v: Vec<f32> = (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            index.insert(&v, &mut storage).unwrap()
+        })
+        .collect();
+    (index, storage, ids)
+}
+
+fn referenced_nodes(index: &HnswIndex) -> HashSet<NodeId> {
+    let mut out = HashSet::new();
+    for i in 0..index.node_count() {
+        #[allow(clippy::cast_possible_truncation)]
+        let node = index.get_node(NodeId(i as u32)).unwrap();
+        for lc in 0..=node.max_layer {
+            out.extend(index.get_neighbors_layer(node, lc).unwrap());
+        }
+    }
+    out
+}
+
+#[test]
+fn test_hard_delete_leaves_no_dangling_links() {
+    let (mut index, mut storage, ids) = build_index(300, 16, 1);
+
+    for &id in ids.iter().step_by(3) {
+        assert!(index.hard_delete(id, &mut storage).unwrap());
+    }
+
+    let referenced = referenced_nodes(&index);
+    for (i, _) in ids.iter().enumerate().step_by(3) {
+        #[allow(clippy::cast_possible_truncation)]
+        let node_id = NodeId(i as u32);
+        assert!(!referenced.contains(&node_id), "node {i} still linked");
+        assert_eq!(index.get_node(node_id).unwrap().neighbor_len, 0);
+    }
+    assert_eq!(index.deleted_count(), 100);
+    assert_eq!(index.live_count(), 200);
+}
+
+#[test]
+fn test_hard_delete_preserves_recall() {
+    let (mut index, mut storage, ids) = build_index(500, 16, 2);
+
+    for &id in ids.iter().step_by(2) {
+        index.hard_delete(id, &mut storage).unwrap();
+    }
+
+    // Every surviving vector must still find itself
+    let survivors: Vec<VectorId> = ids.iter().skip(1).step_by(2).copied().collect();
+    let found = survivors
+        .iter()
+        .filter(|&&id| {
+            let query = storage.get_vector(id).into_owned();
+            let results = index.search(&query, 1, &storage).unwrap();
+            results.first().map(|r| r.vector_id) == Some(id)
+        })
+        .count();
+
+    assert!(
+        found * 100 >= survivors.len() * 98,
+        "self-recall after hard delete too low: {found}/{}",
+        survivors.len()
+    );
+}
+
+#[test]
+fn test_hard_delete_never_returned_by_search() {
+    let (mut index, mut storage, ids) = build_index(100, 8, 3);
+    let target = ids[42];
+    let query = storage.get_vector(target).into_owned();
+
+    index.hard_delete(target, &mut storage).unwrap();
+
+    let results = index.search(&query, 10, &storage).unwrap();
+    assert_eq!(results.len(), 10);
+    assert!(results.iter().all(|r| r.vector_id != target));
+}
+
+#[test]
+fn test_hard_delete_all_then_insert() {
+    let (mut index, mut storage, ids) = build_index(20, 4, 4);
+    for &id in &ids {
+        index.hard_delete(id, &mut storage).unwrap();
+    }
+    assert!(index.entry_point().is_none());
+
+    let new_id = index.insert(&[0.5; 4], &mut storage).unwrap();
+    let results = index.search(&[0.5; 4], 1, &storage).unwrap();
+    assert_eq!(results[0].vector_id, new_id);
+}
+
+#[test]
+fn test_hard_delete_then_compact_and_snapshot() {
+    let (mut index, mut storage, ids) = build_index(60, 8, 5);
+    for &id in ids.iter().take(20) {
+        index.hard_delete(id, &mut storage).unwrap();
+    }
+
+    let (compacted, compacted_storage, result) = index.compact(&storage).unwrap();
+    assert_eq!(result.tombstones_removed, 20);
+    assert_eq!(compacted.node_count(), 40);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded.deleted_count(), 20);
+
+    let query = compacted_storage.get_vector(VectorId(1)).into_owned();
+    assert_eq!(
+        loaded.search(&query, 5, &loaded_storage).unwrap(),
+        index.search(&query, 5, &storage).unwrap()
+    );
+}
+
+#[test]
+fn test_hard_delete_entry_point_survives_reload() {
+    let (mut index, mut storage, _) = build_index(300, 16, 6);
+    let ep = index.entry_point().unwrap();
+    let ep_vid = index.get_node(ep).unwrap().vector_id;
+    let query = storage.get_vector(ep_vid).into_owned();
+
+    index.hard_delete(ep_vid, &mut storage).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+
+    // The reloaded index must not start searches at the unlinked node
+    assert_eq!(loaded.entry_point(), index.entry_point());
+    assert_eq!(loaded.max_layer(), index.max_layer());
+    let results = loaded.search(&query, 10, &loaded_storage).unwrap();
+    assert_eq!(results.len(), 10);
+    assert_eq!(results, index.search(&query, 10, &storage).unwrap());
+}
+
+#[test]
+fn test_hard_delete_recovered_from_wal() {
+    let config = HnswConfig::new(8);
+    let wal = MemoryBackend::new();
+    let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let ids: Vec<VectorId> = (0..80)
+        .map(|_| {
+            let v: Vec<f32> = (0..8).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            index.insert(&v, &mut storage).unwrap()
+        })
+        .collect();
+
+    let mut snapshot = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+
+    // Only in the WAL
+    for &id in ids.iter().step_by(4) {
+        index.hard_delete(id, &mut storage).unwrap();
+    }
+    drop(index);
+
+    let (recovered, _) = recover_index(&snapshot, Box::new(wal)).unwrap();
+    assert_eq!(recovered.deleted_count(), 20);
+    let referenced = referenced_nodes(&recovered);
+    for (i, _) in ids.iter().enumerate().step_by(4) {
+        #[allow(clippy::cast_possible_truncation)]
+        let node_id = NodeId(i as u32);
+        assert!(!referenced.contains(&node_id), "node {i} still linked");
+        assert_eq!(recovered.get_node(node_id).unwrap().neighbor_len, 0);
+    }
+}
@@ -90,3 +90,31 @@ fn test_delete_non_existent() {
     // });
     // assert!(res.is_err());
 }
+
+/// VectorId lookups must keep working on an index loaded from a snapshot
+#[test]
+fn test_soft_delete_after_snapshot_load() {
+    use edgevec::persistence::{read_snapshot, write_snapshot, MemoryBackend};
+
+    let config = HnswConfig::new(2);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+
+    let ids: Vec<_> = (0..50)
+        .map(|i| {
+            #[allow(clippy::cast_precision_loss)]
+            let v = [i as f32, 0.0];
+            index.insert(&v, &mut storage).unwrap()
+        })
+        .collect();
+    index.soft_delete(ids[3]).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let (mut loaded, _) = read_snapshot(&backend).unwrap();
+
+    assert!(loaded.is_deleted(ids[3]).unwrap());
+    assert!(loaded.contains_id(ids[49].0));
+    assert!(loaded.soft_delete(ids[10]).unwrap());
+    assert_eq!(loaded.deleted_count(), 2);
+}
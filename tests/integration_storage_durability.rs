@@ -1,7 +1,7 @@
 use edgevec::hnsw::{HnswConfig, VectorId};
 use edgevec::persistence::storage::file::FileBackend;
-use edgevec::persistence::wal::WalAppender;
-use edgevec::storage::VectorStorage;
+use edgevec::persistence::wal::{RecoveryMode, WalAppender, WalError};
+use edgevec::storage::{StorageError, VectorStorage};
 use std::fs::OpenOptions;
 use std::io::{Seek, SeekFrom, Write};
 
@@ -113,3 +113,77 @@ fn test_recovery_checksum_fail_tail() {
     // 4. Verify we lost the last entry due to checksum mismatch
     assert_eq!(recovered.len(), 99, "Should recover 99 entries");
 }
+
+// INT-DUR-004: Mid-log corruption is reported, and rejected in strict mode
+#[test]
+fn test_recovery_checksum_fail_mid_log() {
+    let dir = tempfile::tempdir().unwrap();
+    let wal_path = dir.path().join("wal_mid.log");
+    let config = HnswConfig::new(4);
+
+    {
+        let backend = Box::new(FileBackend::new(&wal_path));
+        let wal = WalAppender::new(backend, 0);
+        let mut storage = VectorStorage::new(&config, Some(wal));
+        for i in 0..100 {
+            storage.insert(&[i as f32; 4]).unwrap();
+        }
+    }
+
+    // Corrupt a payload byte of entry 50 (each entry is 44 bytes)
+    let offset = 50 * 44;
+    {
+        let mut file = OpenOptions::new().write(true).open(&wal_path).unwrap();
+        file.seek(SeekFrom::Start(offset + 20)).unwrap();
+        file.write_all(&[0xFF]).unwrap();
+    }
+
+    let backend = Box::new(FileBackend::new(&wal_path));
+    let (recovered, report) =
+        VectorStorage::recover_with_report(backend, &config, RecoveryMode::Lenient).unwrap();
+    assert_eq!(recovered.len(), 50);
+    assert_eq!(report.entries_applied, 50);
+    assert_eq!(report.first_bad_offset, Some(offset as usize));
+    assert_eq!(report.bytes_discarded, 50 * 44);
+    assert!(report.valid_records_after);
+
+    let backend = Box::new(FileBackend::new(&wal_path));
+    let Err(err) = VectorStorage::recover_with_report(backend, &config, RecoveryMode::Strict)
+    else {
+        panic!("strict recovery must reject mid-log corruption");
+    };
+    assert!(matches!(
+        err,
+        StorageError::Wal(WalError::Corrupted { offset: o }) if o == offset as usize
+    ));
+}
+
+// INT-DUR-005: A torn tail is reported but accepted in strict mode
+#[test]
+fn test_recovery_report_torn_tail() {
+    let dir = tempfile::tempdir().unwrap();
+    let wal_path = dir.path().join("wal_torn.log");
+    let config = HnswConfig::new(4);
+
+    {
+        let backend = Box::new(FileBackend::new(&wal_path));
+        let wal = WalAppender::new(backend, 0);
+        let mut storage = VectorStorage::new(&config, Some(wal));
+        for i in 0..10 {
+            storage.insert(&[i as f32; 4]).unwrap();
+        }
+    }
+    {
+        let file = OpenOptions::new().write(true).open(&wal_path).unwrap();
+        let len = file.metadata().unwrap().len();
+        file.set_len(len - 10).unwrap();
+    }
+
+    let backend = Box::new(FileBackend::new(&wal_path));
+    let (recovered, report) =
+        VectorStorage::recover_with_report(backend, &config, RecoveryMode::Strict).unwrap();
+    assert_eq!(recovered.len(), 9);
+    assert!(report.is_torn_tail());
+    assert_eq!(report.bytes_discarded, 34);
+    assert_eq!(report.next_sequence, 9);
+}
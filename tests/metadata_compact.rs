@@ -2,9 +2,9 @@
 //!
 //! Tests that compact() handles metadata correctly per RFC-002 §2.3.
 //!
-//! NOTE: The current compact() implementation creates a NEW index and storage.
-//! Metadata is NOT preserved during compaction (this is expected behavior).
-//! The new index starts with an empty MetadataStore.
+//! compact() creates a NEW index and storage with remapped VectorIds.
+//! Metadata of live vectors is carried over under the new IDs, which are
+//! reported in `CompactionResult::id_map`.
 
 use std::collections::HashMap;
 
@@ -44,12 +44,9 @@ fn sample_metadata() -> HashMap<String, MetadataValue> {
 mod compact_metadata {
     use super::*;
 
-    /// Test that new index after compact has empty metadata.
-    ///
-    /// NOTE: Metadata is NOT preserved during compact. This is by design
-    /// because VectorIds are remapped during compaction.
+    /// Test that compact carries live metadata over to the remapped IDs.
     #[test]
-    fn test_compact_creates_empty_metadata_store() {
+    fn test_compact_preserves_live_metadata() {
         let (mut index, mut storage) = create_test_index(4);
 
         // Insert vectors with metadata
@@ -61,7 +58,7 @@ mod compact_metadata {
 
         let mut meta2 = HashMap::new();
         meta2.insert("name".to_string(), MetadataValue::String("second".into()));
-        let _id2 = index
+        let id2 = index
             .insert_with_metadata(&mut storage, &[0.0, 1.0, 0.0, 0.0], meta2)
             .unwrap();
 
@@ -78,19 +75,24 @@ mod compact_metadata {
         assert_eq!(result.tombstones_removed, 1);
         assert_eq!(new_index.node_count(), 1);
 
-        // New index has empty metadata (NOT preserved during compact)
-        assert!(new_index.metadata().is_empty());
-        assert_eq!(new_index.metadata().vector_count(), 0);
+        // Only the live vector's metadata survives, under its new ID
+        assert_eq!(new_index.metadata().vector_count(), 1);
+        let new_id2 = result.id_map[&id2];
+        assert_eq!(
+            new_index.metadata().get(meta_id(new_id2), "name"),
+            Some(&MetadataValue::String("second".into()))
+        );
+        assert!(!result.id_map.contains_key(&id1));
     }
 
-    /// Test that compact with no deletions still has empty metadata in new index.
+    /// Test that compact with no deletions keeps all metadata.
     #[test]
-    fn test_compact_no_deletions_empty_metadata() {
+    fn test_compact_no_deletions_keeps_metadata() {
         let (mut index, mut storage) = create_test_index(4);
 
         // Insert vectors with metadata (no deletions)
         let metadata = sample_metadata();
-        let _id = index
+        let id = index
             .insert_with_metadata(&mut storage, &[1.0, 2.0, 3.0, 4.0], metadata)
             .unwrap();
 
@@ -102,8 +104,12 @@ mod compact_metadata {
         assert_eq!(result.tombstones_removed, 0);
         assert_eq!(new_index.node_count(), 1);
 
-        // New index still has empty metadata (NOT preserved)
-        assert!(new_index.metadata().is_empty());
+        // Metadata is preserved under the (unchanged) ID
+        assert_eq!(result.id_map[&id], id);
+        assert_eq!(
+            new_index.metadata().get_all(meta_id(id)),
+            index.metadata().get_all(meta_id(id))
+        );
     }
 
     /// Test that original index metadata is unchanged after compact.
@@ -166,8 +172,13 @@ mod compact_metadata {
         assert_eq!(result.tombstones_removed, 2);
         assert_eq!(new_index.node_count(), 1);
 
-        // New index has empty metadata
-        assert!(new_index.metadata().is_empty());
+        // Only the third vector's metadata remains, now under ID 1
+        assert_eq!(new_index.metadata().vector_count(), 1);
+        assert_eq!(result.id_map[&ids[2]], VectorId(1));
+        assert_eq!(
+            new_index.metadata().get(1, "index"),
+            Some(&MetadataValue::Integer(2))
+        );
     }
 
     /// Test that metadata count always matches non-deleted vector count (W26.3.2).
@@ -74,7 +74,7 @@ mod backward_compatibility {
         // Modify to v0.3 format (change version_minor and recalculate CRC)
         let mut data = backend.read().expect("Failed to read");
 
-        // Change version_minor from 4 to 3
+        // Change version_minor from 5 to 3
         data[5] = 3;
 
         // Recalculate header CRC (bytes 44-47)
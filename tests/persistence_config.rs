@@ -0,0 +1,180 @@
+//! Integration tests for v0.5 config persistence.
+//!
+//! Verifies that the full `HnswConfig` (metric, ef values) and the
+//! compaction threshold survive a snapshot round-trip, and that older
+//! snapshots migrate to defaults.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex};
+use edgevec::persistence::{
+    read_section_table, read_snapshot, write_snapshot, ConfigSection, MemoryBackend,
+    PersistenceError, SectionKind, StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+
+// =============================================================================
+// Helper functions
+// =============================================================================
+
+fn custom_config(dim: u32) -> HnswConfig {
+    let mut config = HnswConfig::new(dim);
+    config.m = 8;
+    config.m0 = 16;
+    config.ef_construction = 123;
+    config.ef_search = 77;
+    config.metric = HnswConfig::METRIC_COSINE;
+    config
+}
+
+fn build_index(config: HnswConfig, count: usize) -> (HnswIndex, VectorStorage) {
+    let mut storage = VectorStorage::new(&config, None);
+    let dim = config.dimensions;
+    let mut index = HnswIndex::new(config, &storage).expect("Failed to create index");
+
+    for i in 0..count {
+        #[allow(clippy::cast_precision_loss)]
+        let vec: Vec<f32> = (0..dim).map(|d| (i + d as usize + 1) as f32).collect();
+        index.insert(&vec, &mut storage).expect("Failed to insert");
+    }
+
+    (index, storage)
+}
+
+/// Rewrites the header version and fixes both CRCs so the file parses as `minor`.
+fn patch_version(data: &mut [u8], minor: u8) {
+    data[5] = minor;
+    data[44..48].fill(0);
+    let crc = crc32fast::hash(&data[0..64]);
+    data[44..48].copy_from_slice(&crc.to_le_bytes());
+}
+
+// =============================================================================
+// Round-trip tests
+// =============================================================================
+
+#[test]
+fn test_config_roundtrip_exact() {
+    let (mut index, storage) = build_index(custom_config(8), 20);
+    index.set_compaction_threshold(0.45);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+
+    let (loaded, _) = read_snapshot(&backend).expect("Failed to read snapshot");
+
+    assert_eq!(loaded.config, index.config);
+    assert_eq!(loaded.config.metric, HnswConfig::METRIC_COSINE);
+    assert_eq!(loaded.config.ef_construction, 123);
+    assert_eq!(loaded.config.ef_search, 77);
+    assert!((loaded.compaction_threshold() - 0.45).abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_config_roundtrip_empty_index() {
+    let mut config = custom_config(4);
+    config.metric = HnswConfig::METRIC_DOT_PRODUCT;
+    let (index, storage) = build_index(config.clone(), 0);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+
+    let (loaded, _) = read_snapshot(&backend).expect("Failed to read snapshot");
+    assert_eq!(loaded.config, config);
+}
+
+#[test]
+fn test_config_section_located_through_section_table() {
+    let (index, storage) = build_index(custom_config(4), 10);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+    let data = backend.read().expect("Failed to read backend");
+
+    let table = read_section_table(&backend).expect("Valid section table");
+    let tombstones = table.get(SectionKind::TOMBSTONES).unwrap();
+    let config = table.get(SectionKind::CONFIG).unwrap();
+    assert_eq!(
+        u64::from_le_bytes(data[24..32].try_into().unwrap()),
+        tombstones.offset
+    );
+    assert!(config.offset >= tombstones.offset + (10 + 7) / 8);
+
+    let section =
+        ConfigSection::from_bytes(&data[config.offset as usize..]).expect("Valid config section");
+    assert_eq!(section.to_config(), index.config);
+}
+
+// =============================================================================
+// Migration and corruption tests
+// =============================================================================
+
+#[test]
+fn test_v04_snapshot_migrates_to_defaults() {
+    let (index, storage) = build_index(custom_config(4), 5);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+
+    // A v0.4 reader never looks past the tombstones, so the trailing config
+    // section is ignored once the version is patched down.
+    let mut data = backend.read().expect("Failed to read backend");
+    patch_version(&mut data, 4);
+    let legacy = MemoryBackend::new();
+    legacy.atomic_write("", &data).expect("Failed to write");
+
+    let (loaded, loaded_storage) = read_snapshot(&legacy).expect("v0.4 should still load");
+    let defaults = HnswConfig::new(4);
+
+    // m/m0 come from the header; everything else falls back to defaults
+    assert_eq!(loaded.config.m, 8);
+    assert_eq!(loaded.config.m0, 16);
+    assert_eq!(loaded.config.metric, HnswConfig::METRIC_L2_SQUARED);
+    assert_eq!(loaded.config.ef_construction, defaults.ef_construction);
+    assert_eq!(loaded.config.ef_search, defaults.ef_search);
+    assert_eq!(loaded_storage.len(), 5);
+}
+
+#[test]
+fn test_corrupted_config_section_rejected() {
+    let (index, storage) = build_index(custom_config(4), 3);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+    let mut data = backend.read().expect("Failed to read backend");
+
+    // Flip the metric inside the config section, then fix the outer data CRC
+    // so only the section CRC can catch it.
+    let tombstone_offset = u64::from_le_bytes(data[24..32].try_into().unwrap()) as usize;
+    let config_offset = tombstone_offset + 1;
+    data[config_offset + 28] ^= 0x01;
+
+    let data_crc = crc32fast::hash(&data[64..]);
+    data[56..60].copy_from_slice(&data_crc.to_le_bytes());
+    patch_version(&mut data, 5);
+
+    let corrupted = MemoryBackend::new();
+    corrupted.atomic_write("", &data).expect("Failed to write");
+
+    let result = read_snapshot(&corrupted);
+    assert!(
+        matches!(result, Err(PersistenceError::Corrupted(ref msg)) if msg.contains("config")),
+        "Expected config corruption error, got {:?}",
+        result.err()
+    );
+}
+
+#[test]
+fn test_unknown_metric_rejected_on_load() {
+    let mut config = HnswConfig::new(4);
+    config.metric = 99;
+    let (index, storage) = build_index(config, 0);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).expect("Failed to write snapshot");
+
+    let result = read_snapshot(&backend);
+    assert!(
+        matches!(result, Err(PersistenceError::Corrupted(ref msg)) if msg.contains("metric")),
+        "Expected unknown metric error, got {:?}",
+        result.err()
+    );
+}
@@ -0,0 +1,119 @@
+//! Integration tests for SQ8-quantized storage in snapshots.
+//!
+//! A `StorageType::QuantizedU8` storage must round-trip through snapshots as
+//! u8 codes plus its quantizer parameters, without an F32 copy.
+
+use edgevec::flat::FlatIndex;
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::{
+    read_flat_snapshot, read_section_table, read_snapshot, write_flat_snapshot, write_snapshot,
+    FileHeader, Flags, MemoryBackend, SectionKind, StorageBackend,
+};
+use edgevec::quantization::QuantizerConfig;
+use edgevec::storage::{StorageType, VectorStorage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+const DIM: u32 = 32;
+
+fn sq8_type() -> StorageType {
+    StorageType::QuantizedU8(QuantizerConfig {
+        min: -1.0,
+        max: 1.0,
+    })
+}
+
+fn random_vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect()
+}
+
+fn build_sq8(count: usize) -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    storage.set_storage_type(sq8_type());
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for v in random_vectors(count, 1) {
+        index.insert(&v, &mut storage).unwrap();
+    }
+    (index, storage)
+}
+
+#[test]
+fn test_sq8_snapshot_roundtrip() {
+    let (mut index, storage) = build_sq8(200);
+    index.soft_delete(VectorId(17)).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+
+    let data = backend.read().unwrap();
+    let header = FileHeader::from_bytes(&data[..64]).unwrap();
+    assert!(header.flags & Flags::QUANTIZED != 0);
+    // One byte per dimension: the vector block is a quarter of the F32 size
+    let table = read_section_table(&backend).unwrap();
+    let vectors = table.get(SectionKind::VECTORS).unwrap();
+    assert_eq!(vectors.length, 200 * u64::from(DIM));
+
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded_storage.storage_type(), &sq8_type());
+    assert_eq!(loaded_storage.len(), 200);
+    assert!(loaded.is_deleted(VectorId(17)).unwrap());
+    for id in (1..=200).map(VectorId) {
+        assert_eq!(
+            loaded_storage.get_quantized_vector(id),
+            storage.get_quantized_vector(id)
+        );
+    }
+
+    for query in random_vectors(10, 2) {
+        assert_eq!(
+            loaded.search(&query, 10, &loaded_storage).unwrap(),
+            index.search(&query, 10, &storage).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_sq8_insert_after_load_uses_restored_quantizer() {
+    let (index, storage) = build_sq8(20);
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let (mut loaded, mut loaded_storage) = read_snapshot(&backend).unwrap();
+
+    let mut reference = storage;
+    let v = random_vectors(1, 3).remove(0);
+    let id = loaded.insert(&v, &mut loaded_storage).unwrap();
+    let expected = reference.insert(&v).unwrap();
+
+    assert_eq!(id, expected);
+    assert_eq!(
+        loaded_storage.get_quantized_vector(id),
+        reference.get_quantized_vector(expected)
+    );
+}
+
+#[test]
+fn test_sq8_flat_snapshot_roundtrip() {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    storage.set_storage_type(sq8_type());
+    let mut index = FlatIndex::new(config, &storage).unwrap();
+    for v in random_vectors(50, 4) {
+        index.insert(&v, &mut storage).unwrap();
+    }
+
+    let mut backend = MemoryBackend::new();
+    write_flat_snapshot(&index, &storage, &mut backend).unwrap();
+    let (loaded, loaded_storage) = read_flat_snapshot(&backend).unwrap();
+
+    assert_eq!(loaded_storage.storage_type(), &sq8_type());
+    let query = random_vectors(1, 5).remove(0);
+    assert_eq!(
+        loaded.search(&query, 5, &loaded_storage).unwrap(),
+        index.search(&query, 5, &storage).unwrap()
+    );
+}
@@ -75,9 +75,9 @@ mod write_v04 {
         let data = backend.read().expect("Failed to read backend");
         assert!(data.len() >= 64, "Snapshot too small");
 
-        // Check version is 0.4
+        // Check version is 0.5 (v0.4 metadata layout + config section)
         assert_eq!(data[4], 0, "version_major should be 0");
-        assert_eq!(data[5], 4, "version_minor should be 4");
+        assert_eq!(data[5], 5, "version_minor should be 5");
 
         // Check HAS_METADATA flag is NOT set
         let flags = u16::from_le_bytes([data[6], data[7]]);
@@ -99,9 +99,9 @@ mod write_v04 {
         let data = backend.read().expect("Failed to read backend");
         assert!(data.len() >= 64, "Snapshot too small");
 
-        // Check version is 0.4
+        // Check version is 0.5 (v0.4 metadata layout + config section)
         assert_eq!(data[4], 0, "version_major should be 0");
-        assert_eq!(data[5], 4, "version_minor should be 4");
+        assert_eq!(data[5], 5, "version_minor should be 5");
 
         // Check HAS_METADATA flag IS set
         let flags = u16::from_le_bytes([data[6], data[7]]);
@@ -365,6 +365,6 @@ mod version_compatibility {
 
         // Check version
         assert_eq!(data[4], 0, "version_major should be 0");
-        assert_eq!(data[5], 4, "version_minor should be 4");
+        assert_eq!(data[5], 5, "version_minor should be 5");
     }
 }
@@ -276,18 +276,19 @@ fn test_all_deleted_persistence() {
 }
 
 #[test]
-fn test_version_is_0_4() {
+fn test_version_is_0_5() {
     // Verify the current version constants
-    // v0.4 adds metadata support (RFC-002)
+    // v0.5 adds the full HnswConfig section
     assert_eq!(
-        VERSION_MINOR, 4,
-        "Current version should be 0.4 for metadata support"
+        VERSION_MINOR, 5,
+        "Current version should be 0.5 for config persistence"
     );
 
-    // FileHeader should report soft-delete support (added in v0.3, still works in v0.4)
+    // FileHeader should report soft-delete support (added in v0.3, still works in v0.5)
     let header = FileHeader::new(4);
     assert!(header.supports_soft_delete());
-    // v0.4 is current, so no migration needed
+    assert!(header.supports_config());
+    // v0.5 is current, so no migration needed
     assert!(!header.needs_migration());
 }
 
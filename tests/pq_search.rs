@@ -0,0 +1,221 @@
+//! Integration tests for product quantization search.
+//!
+//! Covers ADC search recall against exact search, F32 rescoring, snapshot
+//! round trips of the PQ section, and PQ codes surviving compaction and
+//! updates.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::{
+    read_snapshot, write_snapshot, FileHeader, Flags, MemoryBackend, StorageBackend,
+};
+use edgevec::quantization::{PqConfig, ProductQuantizer};
+use edgevec::storage::VectorStorage;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+const DIM: u32 = 32;
+const COUNT: usize = 1000;
+
+fn random_vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect()
+}
+
+fn pq_config() -> PqConfig {
+    let mut config = PqConfig::new(8);
+    config.centroids = 64;
+    config
+}
+
+fn build(metric: u32) -> (HnswIndex, VectorStorage, Vec<Vec<f32>>) {
+    let mut config = HnswConfig::new(DIM);
+    config.metric = metric;
+    config.ef_search = 64;
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    let vectors = random_vectors(COUNT, 7);
+    for v in &vectors {
+        index.insert(v, &mut storage).unwrap();
+    }
+    index.enable_pq(&storage, &pq_config()).unwrap();
+    (index, storage, vectors)
+}
+
+fn exact_top_k(vectors: &[Vec<f32>], query: &[f32], k: usize) -> HashSet<VectorId> {
+    let mut scored: Vec<(usize, f32)> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let d: f32 = v.iter().zip(query).map(|(a, b)| (a - b) * (a - b)).sum();
+            (i, d)
+        })
+        .collect();
+    scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+    scored
+        .iter()
+        .take(k)
+        .map(|&(i, _)| VectorId(i as u64 + 1))
+        .collect()
+}
+
+fn recall(index: &HnswIndex, storage: &VectorStorage, vectors: &[Vec<f32>], factor: usize) -> f64 {
+    const K: usize = 10;
+    let queries = random_vectors(30, 99);
+    let mut hits = 0;
+    for query in &queries {
+        let truth = exact_top_k(vectors, query, K);
+        let results = if factor == 0 {
+            index.search_pq(query, K).unwrap()
+        } else {
+            index.search_pq_rescored(query, K, factor, storage).unwrap()
+        };
+        assert_eq!(results.len(), K);
+        hits += results.iter().filter(|(id, _)| truth.contains(id)).count();
+    }
+    hits as f64 / (queries.len() * K) as f64
+}
+
+#[test]
+fn test_pq_recall_with_and_without_rescoring() {
+    let (index, storage, vectors) = build(HnswConfig::METRIC_L2_SQUARED);
+    assert_eq!(index.pq_storage().unwrap().len(), COUNT);
+    // 8 bytes per vector instead of 128
+    assert_eq!(index.pq_storage().unwrap().memory_bytes(), COUNT * 8);
+
+    let adc = recall(&index, &storage, &vectors, 0);
+    let rescored = recall(&index, &storage, &vectors, 10);
+    assert!(adc > 0.3, "ADC recall too low: {adc}");
+    assert!(rescored > 0.85, "rescored recall too low: {rescored}");
+    assert!(rescored >= adc);
+}
+
+#[test]
+fn test_pq_cosine_finds_scaled_copy() {
+    let (index, storage, vectors) = build(HnswConfig::METRIC_COSINE);
+
+    // Same direction, different length: cosine ignores the scale
+    let query: Vec<f32> = vectors[123].iter().map(|x| x * 5.0).collect();
+    let results = index.search_pq_rescored(&query, 1, 10, &storage).unwrap();
+    assert_eq!(results[0].0, VectorId(124));
+    assert!((results[0].1 - 1.0).abs() < 1e-4);
+}
+
+#[test]
+fn test_pq_snapshot_roundtrip() {
+    let (mut index, storage, vectors) = build(HnswConfig::METRIC_L2_SQUARED);
+    index.soft_delete(VectorId(5)).unwrap();
+    let query = &vectors[4];
+    let expected = index.search_pq(query, 10).unwrap();
+    assert!(expected.iter().all(|(id, _)| *id != VectorId(5)));
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let bytes = backend.read().unwrap();
+    let header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    assert_ne!(header.flags & Flags::HAS_PQ, 0);
+
+    let (loaded, _) = read_snapshot(&backend).unwrap();
+    assert!(loaded.has_pq());
+    assert_eq!(loaded.pq_storage(), index.pq_storage());
+    assert_eq!(loaded.search_pq(query, 10).unwrap(), expected);
+
+    // Memory-mapped
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    std::fs::write(&path, &bytes).unwrap();
+    let (mapped, _) = read_snapshot(&FileBackend::open_mmap(&path).unwrap()).unwrap();
+    assert_eq!(mapped.search_pq(query, 10).unwrap(), expected);
+
+    #[cfg(feature = "compression")]
+    {
+        let mut compressed = MemoryBackend::new();
+        edgevec::persistence::write_snapshot_compressed(
+            &index,
+            &storage,
+            &mut compressed,
+            edgevec::persistence::Compression::Lz4,
+        )
+        .unwrap();
+        let (loaded, _) = read_snapshot(&compressed).unwrap();
+        assert_eq!(loaded.pq_storage(), index.pq_storage());
+        assert_eq!(loaded.search_pq(query, 10).unwrap(), expected);
+    }
+
+    // Without PQ the flag stays clear
+    let config = HnswConfig::new(DIM);
+    let plain_storage = VectorStorage::new(&config, None);
+    let plain = HnswIndex::new(config, &plain_storage).unwrap();
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&plain, &plain_storage, &mut backend).unwrap();
+    let bytes = backend.read().unwrap();
+    let header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    assert_eq!(header.flags & Flags::HAS_PQ, 0);
+    assert!(!read_snapshot(&backend).unwrap().0.has_pq());
+}
+
+#[test]
+fn test_pq_survives_compaction_and_update() {
+    let (mut index, mut storage, vectors) = build(HnswConfig::METRIC_L2_SQUARED);
+    let codebooks = index.pq_storage().unwrap().quantizer().clone();
+
+    for i in 1..=100 {
+        index.soft_delete(VectorId(i)).unwrap();
+    }
+    let (mut compacted, mut compacted_storage, _) = index.compact(&storage).unwrap();
+    let pq = compacted.pq_storage().unwrap();
+    assert_eq!(pq.len(), COUNT - 100);
+    assert_eq!(pq.quantizer(), &codebooks);
+
+    let results = compacted
+        .search_pq_rescored(&vectors[500], 1, 10, &compacted_storage)
+        .unwrap();
+    assert_eq!(
+        &compacted_storage.get_vector(results[0].0)[..],
+        &vectors[500][..]
+    );
+
+    // Moving a vector re-encodes its code
+    let target = vectors[900].clone();
+    index.update(VectorId(300), &target, &mut storage).unwrap();
+    let results = index.search_pq_rescored(&target, 2, 10, &storage).unwrap();
+    let ids: HashSet<VectorId> = results.iter().map(|(id, _)| *id).collect();
+    assert!(ids.contains(&VectorId(300)) && ids.contains(&VectorId(901)));
+
+    // New inserts are encoded with the existing codebooks
+    let id = compacted.insert(&target, &mut compacted_storage).unwrap();
+    assert_eq!(compacted.pq_storage().unwrap().len(), COUNT - 99);
+    let results = compacted
+        .search_pq_rescored(&target, 2, 10, &compacted_storage)
+        .unwrap();
+    assert!(results.iter().any(|(found, _)| *found == id));
+}
+
+#[test]
+fn test_with_pq_encodes_every_insert() {
+    let vectors = random_vectors(300, 3);
+    let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+    let quantizer = ProductQuantizer::train(&refs, &pq_config()).unwrap();
+
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::with_pq(config, &storage, quantizer).unwrap();
+    for v in &vectors {
+        index.insert(v, &mut storage).unwrap();
+    }
+    assert_eq!(index.pq_storage().unwrap().len(), 300);
+
+    let results = index
+        .search_pq_rescored(&vectors[42], 1, 10, &storage)
+        .unwrap();
+    assert_eq!(results[0].0, VectorId(43));
+
+    // Codebooks for another width are rejected
+    let narrow = ProductQuantizer::train(&[&[0.0; 8][..], &[1.0; 8][..]], &PqConfig::new(2));
+    let config = HnswConfig::new(DIM);
+    let storage = VectorStorage::new(&config, None);
+    assert!(HnswIndex::with_pq(config, &storage, narrow.unwrap()).is_err());
+}
@@ -0,0 +1,181 @@
+//! Integration tests for compressed snapshots.
+//!
+//! `write_snapshot_compressed` stores every section but the vectors and
+//! nodes LZ4-compressed under `Flags::COMPRESSED`; `read_snapshot` expands
+//! them.
+#![cfg(feature = "compression")]
+
+use edgevec::flat::FlatIndex;
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::metadata::MetadataValue;
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::{
+    read_file_header, read_flat_snapshot, read_section, read_section_table, read_snapshot,
+    write_flat_snapshot_compressed, write_snapshot, write_snapshot_compressed, Compression,
+    FileHeader, Flags, MemoryBackend, PersistenceError, SectionKind, StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+use std::collections::HashMap;
+
+const DIM: u32 = 16;
+
+fn vector(seed: u32) -> Vec<f32> {
+    (0..DIM)
+        .map(|d| ((seed * 37 + d * 11) % 101) as f32 / 101.0)
+        .collect()
+}
+
+fn build() -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..300 {
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            "category".to_string(),
+            MetadataValue::String(format!("group-{}", i % 5)),
+        );
+        index
+            .insert_with_metadata(&mut storage, &vector(i), metadata)
+            .unwrap();
+    }
+    for id in [4, 40, 140] {
+        index.soft_delete(VectorId(id)).unwrap();
+    }
+    index.enable_bq(&storage).unwrap();
+    (index, storage)
+}
+
+fn ids(results: &[edgevec::SearchResult]) -> Vec<VectorId> {
+    results.iter().map(|r| r.vector_id).collect()
+}
+
+#[test]
+fn test_compressed_roundtrip_matches_uncompressed() {
+    let (index, storage) = build();
+
+    let mut plain = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut plain).unwrap();
+    let mut compressed = MemoryBackend::new();
+    write_snapshot_compressed(&index, &storage, &mut compressed, Compression::Lz4).unwrap();
+
+    let plain_bytes = plain.read().unwrap();
+    let compressed_bytes = compressed.read().unwrap();
+    assert!(compressed_bytes.len() < plain_bytes.len());
+    let header = read_file_header(&compressed_bytes).unwrap();
+    assert!(header.is_compressed());
+    assert!(!read_file_header(&plain_bytes).unwrap().is_compressed());
+    // Vectors and nodes are stored as-is
+    let table = read_section_table(&compressed).unwrap();
+    for kind in [SectionKind::VECTORS, SectionKind::NODES] {
+        assert!(!table.get(kind).unwrap().is_compressed());
+        assert_eq!(
+            read_section(&compressed, kind).unwrap(),
+            read_section(&plain, kind).unwrap()
+        );
+    }
+    assert!(table.get(SectionKind::NEIGHBORS).unwrap().is_compressed());
+
+    let (loaded, loaded_storage) = read_snapshot(&compressed).unwrap();
+    assert_eq!(loaded.node_count(), 300);
+    assert_eq!(loaded.deleted_count(), 3);
+    assert!(loaded.is_deleted(VectorId(40)).unwrap());
+    assert_eq!(
+        loaded.metadata().get(12, "category"),
+        Some(&MetadataValue::String("group-1".into()))
+    );
+    assert!(loaded.has_bq());
+
+    for seed in [0, 77, 299] {
+        let query = vector(seed);
+        assert_eq!(
+            ids(&loaded.search(&query, 10, &loaded_storage).unwrap()),
+            ids(&index.search(&query, 10, &storage).unwrap())
+        );
+    }
+}
+
+#[test]
+fn test_compressed_flat_snapshot_roundtrip() {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = FlatIndex::new(config, &storage).unwrap();
+    for i in 0..50 {
+        index.insert(&vector(i), &mut storage).unwrap();
+    }
+    index.soft_delete(VectorId(3), &mut storage).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_flat_snapshot_compressed(&index, &storage, &mut backend, Compression::Lz4).unwrap();
+    let (loaded, loaded_storage) = read_flat_snapshot(&backend).unwrap();
+
+    assert_eq!(loaded.deleted_count(), 1);
+    let query = vector(9);
+    assert_eq!(
+        loaded.search(&query, 5, &loaded_storage).unwrap(),
+        index.search(&query, 5, &storage).unwrap()
+    );
+}
+
+#[test]
+fn test_compressed_snapshot_can_be_mapped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build();
+    write_snapshot_compressed(
+        &index,
+        &storage,
+        &mut FileBackend::new(&path),
+        Compression::Lz4,
+    )
+    .unwrap();
+
+    let (mapped, mapped_storage) = read_snapshot(&FileBackend::open_mmap(&path).unwrap()).unwrap();
+    assert!(mapped.is_mapped() && mapped_storage.is_mapped());
+    let query = vector(5);
+    assert_eq!(
+        ids(&mapped.search(&query, 10, &mapped_storage).unwrap()),
+        ids(&index.search(&query, 10, &storage).unwrap())
+    );
+}
+
+#[test]
+fn test_corrupt_compressed_section_is_rejected() {
+    let (index, storage) = build();
+    let mut backend = MemoryBackend::new();
+    write_snapshot_compressed(&index, &storage, &mut backend, Compression::Lz4).unwrap();
+    let mut bytes = backend.read().unwrap();
+
+    // Damage the compressed metadata, then re-seal the file-level CRC so
+    // only the section CRC can catch it
+    let entry = *read_section_table(&backend)
+        .unwrap()
+        .get(SectionKind::METADATA)
+        .unwrap();
+    assert!(entry.is_compressed());
+    bytes[(entry.offset + entry.length - 1) as usize] ^= 0xFF;
+    let mut header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    assert_ne!(header.flags & Flags::COMPRESSED, 0);
+    header.data_crc = crc32fast::hash(&bytes[64..]);
+    header.update_checksum();
+    bytes[..64].copy_from_slice(header.as_bytes());
+    backend.atomic_write("", &bytes).unwrap();
+
+    let result = read_snapshot(&backend);
+    assert!(matches!(
+        result,
+        Err(PersistenceError::Corrupted(msg)) if msg.contains("Compressed metadata CRC mismatch")
+    ));
+}
+
+#[test]
+fn test_uncompressed_option_writes_plain_snapshot() {
+    let (index, storage) = build();
+    let mut plain = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut plain).unwrap();
+    let mut none = MemoryBackend::new();
+    write_snapshot_compressed(&index, &storage, &mut none, Compression::None).unwrap();
+
+    assert!(Compression::Lz4.is_available());
+    assert_eq!(none.read().unwrap(), plain.read().unwrap());
+}
@@ -0,0 +1,156 @@
+//! Integration tests for memory-mapped snapshot loading.
+//!
+//! `FileBackend::open_mmap` lets `read_snapshot` borrow vectors and nodes
+//! from the file; mutations copy them to the heap first.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::{read_snapshot, write_snapshot, PersistenceError, StorageBackend};
+use edgevec::quantization::QuantizerConfig;
+use edgevec::storage::{StorageType, VectorStorage};
+
+const DIM: u32 = 16;
+
+fn vector(seed: u32) -> Vec<f32> {
+    (0..DIM)
+        .map(|d| ((seed * 31 + d * 7) % 23) as f32 / 23.0)
+        .collect()
+}
+
+fn build(storage_type: Option<StorageType>) -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    if let Some(storage_type) = storage_type {
+        storage.set_storage_type(storage_type);
+    }
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..200 {
+        index.insert(&vector(i), &mut storage).unwrap();
+    }
+    index.soft_delete(VectorId(7)).unwrap();
+    (index, storage)
+}
+
+#[test]
+fn test_mapped_load_matches_copied_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build(None);
+    write_snapshot(&index, &storage, &mut FileBackend::new(&path)).unwrap();
+
+    let (copied, copied_storage) = read_snapshot(&FileBackend::new(&path)).unwrap();
+    let (mapped, mapped_storage) = read_snapshot(&FileBackend::open_mmap(&path).unwrap()).unwrap();
+    assert!(!copied.is_mapped() && !copied_storage.is_mapped());
+    assert!(mapped.is_mapped() && mapped_storage.is_mapped());
+
+    assert_eq!(mapped.node_count(), 200);
+    assert!(mapped.is_deleted(VectorId(7)).unwrap());
+    for i in [0, 50, 199] {
+        let query = vector(i);
+        let a = copied.search(&query, 5, &copied_storage).unwrap();
+        let b = mapped.search(&query, 5, &mapped_storage).unwrap();
+        assert_eq!(
+            a.iter().map(|r| r.vector_id).collect::<Vec<_>>(),
+            b.iter().map(|r| r.vector_id).collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn test_mapped_index_copies_on_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build(None);
+    write_snapshot(&index, &storage, &mut FileBackend::new(&path)).unwrap();
+    let before = std::fs::read(&path).unwrap();
+
+    let (mut index, mut storage) = read_snapshot(&FileBackend::open_mmap(&path).unwrap()).unwrap();
+    let id = index.insert(&vector(500), &mut storage).unwrap();
+    index.soft_delete(VectorId(1)).unwrap();
+
+    assert!(!index.is_mapped() && !storage.is_mapped());
+    assert_eq!(storage.get_vector(id)[..], vector(500)[..]);
+    assert_eq!(std::fs::read(&path).unwrap(), before);
+}
+
+#[test]
+fn test_mapping_survives_snapshot_replacement() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build(None);
+    write_snapshot(&index, &storage, &mut FileBackend::new(&path)).unwrap();
+
+    let (copied, copied_storage) = read_snapshot(&FileBackend::new(&path)).unwrap();
+    let (mapped, mapped_storage) = read_snapshot(&FileBackend::open_mmap(&path).unwrap()).unwrap();
+
+    // A new snapshot is renamed over the mapped file
+    let (other, other_storage) = build(Some(StorageType::QuantizedU8(QuantizerConfig {
+        min: 0.0,
+        max: 1.0,
+    })));
+    write_snapshot(&other, &other_storage, &mut FileBackend::new(&path)).unwrap();
+
+    assert_eq!(mapped_storage.get_vector(VectorId(3))[..], vector(2)[..]);
+    let a = copied.search(&vector(2), 5, &copied_storage).unwrap();
+    let b = mapped.search(&vector(2), 5, &mapped_storage).unwrap();
+    assert_eq!(
+        a.iter().map(|r| r.vector_id).collect::<Vec<_>>(),
+        b.iter().map(|r| r.vector_id).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_mapped_sq8_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let sq8 = StorageType::QuantizedU8(QuantizerConfig { min: 0.0, max: 1.0 });
+    let (index, storage) = build(Some(sq8.clone()));
+    write_snapshot(&index, &storage, &mut FileBackend::new(&path)).unwrap();
+
+    let (mapped, mapped_storage) = read_snapshot(&FileBackend::open_mmap(&path).unwrap()).unwrap();
+    assert!(mapped_storage.is_mapped());
+    assert_eq!(mapped_storage.storage_type(), &sq8);
+    assert_eq!(
+        mapped_storage.get_vector(VectorId(10)),
+        storage.get_vector(VectorId(10))
+    );
+    assert_eq!(mapped.node_count(), 200);
+}
+
+#[test]
+fn test_mmap_backend_is_read_only() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build(None);
+    write_snapshot(&index, &storage, &mut FileBackend::new(&path)).unwrap();
+
+    let mut backend = FileBackend::open_mmap(&path).unwrap();
+    assert!(matches!(
+        write_snapshot(&index, &storage, &mut backend),
+        Err(PersistenceError::Unsupported(_))
+    ));
+    assert!(matches!(
+        backend.append(b"x"),
+        Err(PersistenceError::Unsupported(_))
+    ));
+    assert_eq!(backend.read().unwrap(), std::fs::read(&path).unwrap());
+
+    assert!(FileBackend::open_mmap(dir.path().join("missing.evec")).is_err());
+}
+
+#[test]
+fn test_mapped_snapshot_detects_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build(None);
+    write_snapshot(&index, &storage, &mut FileBackend::new(&path)).unwrap();
+
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[200] ^= 0x01;
+    std::fs::write(&path, &bytes).unwrap();
+
+    assert!(matches!(
+        read_snapshot(&FileBackend::open_mmap(&path).unwrap()),
+        Err(PersistenceError::ChecksumMismatch { .. })
+    ));
+}
@@ -0,0 +1,222 @@
+//! Integration tests for the snapshot section table.
+//!
+//! Snapshots locate every section through a table after the header, so
+//! single sections can be loaded on their own and unknown sections are
+//! skipped. Snapshots without a table still load.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::metadata::MetadataValue;
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::{
+    read_section, read_section_table, read_snapshot, read_snapshot_metadata, write_snapshot,
+    FileHeader, MemoryBackend, PersistenceError, SectionEntry, SectionKind, SectionTableHeader,
+    StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+use edgevec::ChunkedWriter;
+use std::collections::HashMap;
+
+const DIM: u32 = 8;
+
+fn vector(seed: u32) -> Vec<f32> {
+    (0..DIM)
+        .map(|d| ((seed * 29 + d * 13) % 41) as f32 / 41.0)
+        .collect()
+}
+
+fn build() -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..60 {
+        let mut metadata = HashMap::new();
+        metadata.insert("rank".to_string(), MetadataValue::Integer(i64::from(i)));
+        index
+            .insert_with_metadata(&mut storage, &vector(i), metadata)
+            .unwrap();
+    }
+    index.soft_delete(VectorId(9)).unwrap();
+    (index, storage)
+}
+
+/// Re-seals the data and header CRCs after editing `bytes`.
+fn reseal(bytes: &mut [u8]) {
+    let mut header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    header.data_crc = crc32fast::hash(&bytes[64..]);
+    header.update_checksum();
+    bytes[..64].copy_from_slice(header.as_bytes());
+}
+
+/// Appends a section of an unknown kind, growing the table by one entry.
+fn with_unknown_section(bytes: &[u8], payload: &[u8]) -> Vec<u8> {
+    let table = SectionTableHeader::from_bytes(&bytes[64..]).unwrap();
+    let table_end = 64 + table.table_size();
+    let shift = SectionEntry::SIZE as u64;
+
+    let mut entries: Vec<SectionEntry> = bytes[64 + SectionTableHeader::SIZE..table_end]
+        .chunks_exact(SectionEntry::SIZE)
+        .map(|chunk| {
+            let mut entry: SectionEntry = bytemuck::pod_read_unaligned(chunk);
+            entry.offset += shift;
+            entry
+        })
+        .collect();
+
+    let mut body = bytes[table_end..].to_vec();
+    body.resize((body.len() + 7) / 8 * 8, 0);
+    entries.push(SectionEntry {
+        kind: 0x7F00,
+        flags: 0,
+        crc: crc32fast::hash(payload),
+        offset: table_end as u64 + shift + body.len() as u64,
+        length: payload.len() as u64,
+    });
+    body.extend_from_slice(payload);
+
+    let entry_bytes: Vec<u8> = entries.iter().flat_map(|e| *e.as_bytes()).collect();
+    let table = SectionTableHeader::new(entries.len() as u16, crc32fast::hash(&entry_bytes));
+
+    let mut header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    header.index_offset += shift;
+    header.metadata_offset += shift;
+    let mut out = header.as_bytes().to_vec();
+    out.extend_from_slice(table.as_bytes());
+    out.extend_from_slice(&entry_bytes);
+    out.extend_from_slice(&body);
+    reseal(&mut out);
+    out
+}
+
+#[test]
+fn test_single_sections_load_on_their_own() {
+    let (index, storage) = build();
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+
+    let table = read_section_table(&backend).unwrap();
+    let kinds: Vec<SectionKind> = table.entries().iter().map(SectionEntry::kind).collect();
+    assert_eq!(
+        kinds[..6],
+        [
+            SectionKind::VECTORS,
+            SectionKind::NODES,
+            SectionKind::NEIGHBORS,
+            SectionKind::TOMBSTONES,
+            SectionKind::CONFIG,
+            SectionKind::METADATA,
+        ]
+    );
+    assert!(table.entries().iter().all(|e| e.offset % 8 == 0));
+    assert!(table.get(SectionKind::BQ).is_none());
+    assert_eq!(read_section(&backend, SectionKind::BQ).unwrap(), None);
+
+    let vectors = read_section(&backend, SectionKind::VECTORS)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &vectors[..DIM as usize * 4],
+        bytemuck::cast_slice::<f32, u8>(&vector(0))
+    );
+
+    let metadata = read_snapshot_metadata(&backend).unwrap();
+    assert_eq!(metadata.get(42, "rank"), Some(&MetadataValue::Integer(41)));
+    assert_eq!(&metadata, index.metadata());
+
+    // Damage to another section does not stop a partial load...
+    let mut bytes = backend.read().unwrap();
+    let vectors = *table.get(SectionKind::VECTORS).unwrap();
+    bytes[vectors.offset as usize + 3] ^= 0xFF;
+    backend.atomic_write("", &bytes).unwrap();
+    assert_eq!(read_snapshot_metadata(&backend).unwrap(), metadata);
+    assert!(matches!(
+        read_section(&backend, SectionKind::VECTORS),
+        Err(PersistenceError::Corrupted(msg)) if msg.contains("vectors CRC mismatch")
+    ));
+
+    // ...but a full load still checks everything
+    assert!(matches!(
+        read_snapshot(&backend),
+        Err(PersistenceError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_unknown_sections_are_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build();
+    let mut plain = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut plain).unwrap();
+
+    let bytes = with_unknown_section(&plain.read().unwrap(), b"from a newer writer");
+    std::fs::write(&path, &bytes).unwrap();
+
+    let table = read_section_table(&FileBackend::new(&path)).unwrap();
+    assert_eq!(table.entries().last().unwrap().kind, 0x7F00);
+    assert_eq!(
+        read_section(&FileBackend::new(&path), SectionKind(0x7F00)).unwrap(),
+        Some(b"from a newer writer".to_vec())
+    );
+
+    let query = vector(3);
+    let expected = index.search(&query, 5, &storage).unwrap();
+    for backend in [
+        FileBackend::new(&path),
+        FileBackend::open_mmap(&path).unwrap(),
+    ] {
+        let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+        assert!(loaded.is_deleted(VectorId(9)).unwrap());
+        assert_eq!(loaded.search(&query, 5, &loaded_storage).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_snapshot_without_table_still_loads() {
+    let (index, storage) = build();
+
+    // The chunk stream on its own is the layout without a section table
+    let mut bytes: Vec<u8> = (&storage, &index).export_chunked(4096).flatten().collect();
+    reseal(&mut bytes);
+    let backend = MemoryBackend::new();
+    backend.atomic_write("", &bytes).unwrap();
+
+    assert!(matches!(
+        read_section_table(&backend),
+        Err(PersistenceError::Unsupported(_))
+    ));
+    assert_eq!(
+        read_snapshot_metadata(&backend).unwrap().get(5, "rank"),
+        Some(&MetadataValue::Integer(4))
+    );
+
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert!(loaded.is_deleted(VectorId(9)).unwrap());
+    let query = vector(11);
+    assert_eq!(
+        loaded.search(&query, 5, &loaded_storage).unwrap(),
+        index.search(&query, 5, &storage).unwrap()
+    );
+}
+
+#[test]
+fn test_damaged_table_is_rejected() {
+    let (index, storage) = build();
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+
+    let mut bytes = backend.read().unwrap();
+    // Second entry's offset
+    bytes[64 + SectionTableHeader::SIZE + SectionEntry::SIZE + 8] ^= 0x01;
+    reseal(&mut bytes);
+    backend.atomic_write("", &bytes).unwrap();
+
+    for result in [
+        read_section_table(&backend).map(|_| ()),
+        read_snapshot(&backend).map(|_| ()),
+    ] {
+        assert!(matches!(
+            result,
+            Err(PersistenceError::Corrupted(msg)) if msg.contains("Section table CRC mismatch")
+        ));
+    }
+}
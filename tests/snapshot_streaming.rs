@@ -0,0 +1,186 @@
+//! Integration tests for streamed snapshot writes and reads.
+//!
+//! Snapshots go through `StorageBackend::atomic_writer` and
+//! `StorageBackend::open_reader` one chunk at a time, so a backend never
+//! sees the whole file as a single buffer.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex};
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::{
+    read_section_table, read_snapshot, write_snapshot, MemoryBackend, PersistenceError,
+    SectionKind, SnapshotWriter, StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+const DIM: u32 = 64;
+const CHUNK: usize = 1024 * 1024;
+
+fn build_index(count: u32) -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..count {
+        let v: Vec<f32> = (0..DIM).map(|d| ((i * 31 + d) % 97) as f32).collect();
+        index.insert(&v, &mut storage).unwrap();
+    }
+    (index, storage)
+}
+
+/// Backend that only supports streaming: whole-blob reads and writes fail,
+/// and the size of every write and read is recorded.
+#[derive(Clone, Default)]
+struct StreamingOnly {
+    data: Arc<Mutex<Vec<u8>>>,
+    largest_write: Arc<Mutex<usize>>,
+    largest_read: Arc<Mutex<usize>>,
+}
+
+struct Writer<'a> {
+    backend: &'a StreamingOnly,
+    pending: Vec<u8>,
+}
+
+impl SnapshotWriter for Writer<'_> {
+    fn write(&mut self, data: &[u8]) -> Result<(), PersistenceError> {
+        let mut largest = self.backend.largest_write.lock().unwrap();
+        *largest = (*largest).max(data.len());
+        self.pending.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn patch(&mut self, offset: usize, data: &[u8]) -> Result<(), PersistenceError> {
+        self.pending[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    fn commit(self: Box<Self>) -> Result<(), PersistenceError> {
+        *self.backend.data.lock().unwrap() = self.pending;
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    backend: &'a StreamingOnly,
+    offset: usize,
+}
+
+impl Read for Reader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let data = self.backend.data.lock().unwrap();
+        let n = buf.len().min(data.len() - self.offset);
+        buf[..n].copy_from_slice(&data[self.offset..self.offset + n]);
+        self.offset += n;
+        let mut largest = self.backend.largest_read.lock().unwrap();
+        *largest = (*largest).max(buf.len());
+        Ok(n)
+    }
+}
+
+fn unsupported() -> PersistenceError {
+    PersistenceError::Unsupported("whole-blob access".into())
+}
+
+impl StorageBackend for StreamingOnly {
+    fn append(&mut self, _data: &[u8]) -> Result<(), PersistenceError> {
+        Err(unsupported())
+    }
+
+    fn read(&self) -> Result<Vec<u8>, PersistenceError> {
+        Err(unsupported())
+    }
+
+    fn atomic_write(&self, _key: &str, _data: &[u8]) -> Result<(), PersistenceError> {
+        Err(unsupported())
+    }
+
+    fn atomic_writer<'a>(
+        &'a self,
+        _key: &str,
+    ) -> Result<Box<dyn SnapshotWriter + 'a>, PersistenceError> {
+        Ok(Box::new(Writer {
+            backend: self,
+            pending: Vec::new(),
+        }))
+    }
+
+    fn open_reader(&self) -> Result<Box<dyn Read + '_>, PersistenceError> {
+        Ok(Box::new(Reader {
+            backend: self,
+            offset: 0,
+        }))
+    }
+}
+
+#[test]
+fn test_snapshot_streams_in_bounded_chunks() {
+    // ~2.5 MB of vectors, more than two chunks
+    let (index, storage) = build_index(10_000);
+    let mut backend = StreamingOnly::default();
+
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    assert!(backend.data.lock().unwrap().len() > 2 * CHUNK);
+    assert!(*backend.largest_write.lock().unwrap() <= CHUNK);
+
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert!(*backend.largest_read.lock().unwrap() <= CHUNK);
+    assert_eq!(loaded.node_count(), index.node_count());
+    for id in [1u64, 5_000, 10_000] {
+        let id = edgevec::hnsw::VectorId(id);
+        assert_eq!(loaded_storage.get_vector(id), storage.get_vector(id));
+    }
+}
+
+#[test]
+fn test_streamed_snapshot_matches_buffered_bytes() {
+    let (index, storage) = build_index(100);
+    let mut streamed = StreamingOnly::default();
+    let mut buffered = MemoryBackend::new();
+
+    write_snapshot(&index, &storage, &mut streamed).unwrap();
+    write_snapshot(&index, &storage, &mut buffered).unwrap();
+    assert_eq!(*streamed.data.lock().unwrap(), buffered.read().unwrap());
+}
+
+#[test]
+fn test_file_snapshot_roundtrip_and_corruption() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let (index, storage) = build_index(500);
+    let mut backend = FileBackend::new(&path);
+
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    assert!(!path.with_extension("tmp").exists());
+    let (loaded, _) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded.node_count(), 500);
+
+    // Flip a byte in the vector block
+    let table = read_section_table(&backend).unwrap();
+    let offset = table.get(SectionKind::VECTORS).unwrap().offset as usize;
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[offset + 36] ^= 0xFF;
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(matches!(
+        read_snapshot(&backend),
+        Err(PersistenceError::ChecksumMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_uncommitted_file_write_keeps_previous_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    let backend = FileBackend::new(&path);
+    backend.atomic_write("", b"previous").unwrap();
+
+    {
+        let mut writer = backend.atomic_writer("").unwrap();
+        writer.write(b"partial snapshot").unwrap();
+        writer.patch(0, b"P").unwrap();
+        // Dropped without commit, e.g. after a serialization error
+    }
+
+    assert_eq!(std::fs::read(&path).unwrap(), b"previous");
+    assert!(!path.with_extension("tmp").exists());
+}
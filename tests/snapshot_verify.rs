@@ -0,0 +1,262 @@
+//! Integration tests for `verify_snapshot`.
+//!
+//! Each test damages a real snapshot and re-seals its CRCs where needed, so
+//! only the structural check under test can catch the damage.
+
+use edgevec::flat::FlatIndex;
+use edgevec::hnsw::{HnswConfig, HnswIndex, NodeId, VectorId};
+use edgevec::metadata::MetadataValue;
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::{
+    read_section_table, verify_snapshot, write_flat_snapshot, write_snapshot, FileHeader,
+    MemoryBackend, SectionEntry, SectionKind, SectionTableHeader, StorageBackend, VerifyIssue,
+};
+use edgevec::storage::VectorStorage;
+use edgevec::ChunkedWriter;
+use std::collections::HashMap;
+
+const DIM: u32 = 8;
+
+fn vector(seed: u32) -> Vec<f32> {
+    (0..DIM)
+        .map(|d| ((seed * 31 + d * 7) % 43) as f32 / 43.0)
+        .collect()
+}
+
+fn build() -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for i in 0..60 {
+        let mut metadata = HashMap::new();
+        metadata.insert("rank".to_string(), MetadataValue::Integer(i64::from(i)));
+        index
+            .insert_with_metadata(&mut storage, &vector(i), metadata)
+            .unwrap();
+    }
+    index.soft_delete(VectorId(4)).unwrap();
+    (index, storage)
+}
+
+fn snapshot() -> Vec<u8> {
+    let (index, storage) = build();
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    backend.read().unwrap()
+}
+
+fn backend(bytes: &[u8]) -> MemoryBackend {
+    let backend = MemoryBackend::new();
+    backend.atomic_write("", bytes).unwrap();
+    backend
+}
+
+/// Returns the table entry for `kind`.
+fn entry(bytes: &[u8], kind: SectionKind) -> SectionEntry {
+    *read_section_table(&backend(bytes))
+        .unwrap()
+        .get(kind)
+        .unwrap()
+}
+
+/// Recomputes every section CRC, the table CRC, the data CRC and the
+/// header CRC after editing `bytes`.
+fn reseal(bytes: &mut [u8]) {
+    let table = SectionTableHeader::from_bytes(&bytes[64..]).unwrap();
+    let entries_start = 64 + SectionTableHeader::SIZE;
+    let mut entries: Vec<SectionEntry> = bytes[entries_start..64 + table.table_size()]
+        .chunks_exact(SectionEntry::SIZE)
+        .map(bytemuck::pod_read_unaligned)
+        .collect();
+    for entry in &mut entries {
+        let start = entry.offset as usize;
+        entry.crc = crc32fast::hash(&bytes[start..start + entry.length as usize]);
+    }
+    let entry_bytes: Vec<u8> = entries.iter().flat_map(|e| *e.as_bytes()).collect();
+    let table = SectionTableHeader::new(entries.len() as u16, crc32fast::hash(&entry_bytes));
+    bytes[64..entries_start].copy_from_slice(table.as_bytes());
+    bytes[entries_start..entries_start + entry_bytes.len()].copy_from_slice(&entry_bytes);
+    reseal_header(bytes);
+}
+
+fn reseal_header(bytes: &mut [u8]) {
+    let mut header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    header.data_crc = crc32fast::hash(&bytes[64..]);
+    header.update_checksum();
+    bytes[..64].copy_from_slice(header.as_bytes());
+}
+
+/// File offset of node `i`'s 16-byte record.
+fn node_at(bytes: &[u8], i: usize) -> usize {
+    entry(bytes, SectionKind::NODES).offset as usize + i * 16
+}
+
+#[test]
+fn test_clean_snapshots_verify() {
+    let bytes = snapshot();
+    let report = verify_snapshot(&backend(&bytes));
+    assert!(report.is_clean(), "{:?}", report.issues);
+    assert_eq!(report.vector_count, 60);
+    assert_eq!(report.nodes_checked, 60);
+    assert!(report.links_checked > 60);
+
+    // Memory-mapped
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.evec");
+    std::fs::write(&path, &bytes).unwrap();
+    assert!(verify_snapshot(&FileBackend::open_mmap(&path).unwrap()).is_clean());
+
+    // Without a section table
+    let (index, storage) = build();
+    let mut legacy: Vec<u8> = (&storage, &index).export_chunked(4096).flatten().collect();
+    reseal_header(&mut legacy);
+    let report = verify_snapshot(&backend(&legacy));
+    assert!(report.is_clean(), "{:?}", report.issues);
+    assert_eq!(
+        report.links_checked,
+        verify_snapshot(&backend(&bytes)).links_checked
+    );
+
+    #[cfg(feature = "compression")]
+    {
+        let (index, storage) = build();
+        let mut compressed = MemoryBackend::new();
+        edgevec::persistence::write_snapshot_compressed(
+            &index,
+            &storage,
+            &mut compressed,
+            edgevec::persistence::Compression::Lz4,
+        )
+        .unwrap();
+        let report = verify_snapshot(&compressed);
+        assert!(report.is_clean(), "{:?}", report.issues);
+        assert_eq!(
+            report.links_checked,
+            verify_snapshot(&backend(&bytes)).links_checked
+        );
+    }
+
+    // Flat index: no nodes, tombstones carry the deleted count
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut flat = FlatIndex::new(config, &storage).unwrap();
+    for i in 0..10 {
+        flat.insert(&vector(i), &mut storage).unwrap();
+    }
+    flat.soft_delete(VectorId(3), &mut storage).unwrap();
+    let mut flat_backend = MemoryBackend::new();
+    write_flat_snapshot(&flat, &storage, &mut flat_backend).unwrap();
+    let report = verify_snapshot(&flat_backend);
+    assert!(report.is_clean(), "{:?}", report.issues);
+    assert_eq!(report.nodes_checked, 0);
+}
+
+#[test]
+fn test_unreadable_and_crc_damage_is_reported() {
+    let report = verify_snapshot(&backend(b"not a snapshot"));
+    assert!(matches!(report.issues[..], [VerifyIssue::Unreadable(_)]));
+
+    let mut bytes = snapshot();
+    bytes[0] ^= 0xFF;
+    assert!(matches!(
+        verify_snapshot(&backend(&bytes)).issues[..],
+        [VerifyIssue::Unreadable(_)]
+    ));
+
+    // A damaged metadata section fails the data CRC, its section CRC and
+    // its own CRC
+    let mut bytes = snapshot();
+    let metadata = entry(&bytes, SectionKind::METADATA);
+    bytes[(metadata.offset + metadata.length) as usize - 1] ^= 0xFF;
+    let issues = verify_snapshot(&backend(&bytes)).issues;
+    assert!(matches!(issues[0], VerifyIssue::DataCrc { .. }));
+    assert!(matches!(&issues[1], VerifyIssue::Section(msg) if msg.contains("metadata CRC")));
+    assert!(matches!(&issues[2], VerifyIssue::Metadata(msg) if msg.contains("Metadata CRC")));
+    assert_eq!(issues.len(), 3);
+}
+
+#[test]
+fn test_neighbor_damage_is_reported() {
+    let mut bytes = snapshot();
+    let pool = entry(&bytes, SectionKind::NEIGHBORS);
+
+    // Node 1: blob moved past the end of the pool
+    let node = node_at(&bytes, 1);
+    bytes[node + 8..node + 12].copy_from_slice(&(pool.length as u32).to_le_bytes());
+
+    // Node 2: a count that never terminates
+    let node = node_at(&bytes, 2);
+    let blob = pool.offset as usize
+        + u32::from_le_bytes(bytes[node + 8..node + 12].try_into().unwrap()) as usize;
+    bytes[blob..blob + 5].fill(0xFF);
+
+    // A node on layer 0 only: its single neighbor far beyond the node count
+    let (id, node) = (3..60)
+        .map(|i| (i, node_at(&bytes, i)))
+        .find(|&(_, node)| bytes[node + 14] == 0)
+        .unwrap();
+    let blob = pool.offset as usize
+        + u32::from_le_bytes(bytes[node + 8..node + 12].try_into().unwrap()) as usize;
+    bytes[blob..blob + 2].copy_from_slice(&[1, 0x7F]);
+    let len_1 = node_at(&bytes, 1) + 12;
+    let len_1 = u16::from_le_bytes(bytes[len_1..len_1 + 2].try_into().unwrap());
+    reseal(&mut bytes);
+
+    let report = verify_snapshot(&backend(&bytes));
+    assert_eq!(
+        report.issues,
+        [
+            VerifyIssue::NeighborRange {
+                node: NodeId(1),
+                offset: pool.length as u32,
+                len: len_1,
+                pool_len: pool.length as usize,
+            },
+            VerifyIssue::NeighborEncoding {
+                node: NodeId(2),
+                layer: 0,
+            },
+            VerifyIssue::NeighborOutOfRange {
+                node: NodeId(id as u32),
+                layer: 0,
+                neighbor: 0x7F,
+            },
+        ]
+    );
+    assert_eq!(report.nodes_checked, 60);
+}
+
+#[test]
+fn test_count_mismatches_are_reported() {
+    let mut bytes = snapshot();
+    // Node 7 marked deleted without the header knowing
+    let node = node_at(&bytes, 7);
+    bytes[node + 15] = 1;
+    let mut header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    header.deleted_count = 5;
+    bytes[..64].copy_from_slice(header.as_bytes());
+    reseal(&mut bytes);
+
+    assert_eq!(
+        verify_snapshot(&backend(&bytes)).issues,
+        [VerifyIssue::DeletedCount {
+            expected: 5,
+            actual: 2
+        }]
+    );
+
+    // One node short of the vector count
+    let mut bytes = snapshot();
+    let mut header: FileHeader = bytemuck::pod_read_unaligned(&bytes[..64]);
+    header.vector_count = 61;
+    bytes[..64].copy_from_slice(header.as_bytes());
+    reseal_header(&mut bytes);
+    let issues = verify_snapshot(&backend(&bytes)).issues;
+    assert_eq!(
+        issues[0],
+        VerifyIssue::NodeCount {
+            expected: 61,
+            actual: 60
+        }
+    );
+}
@@ -0,0 +1,250 @@
+//! Integration tests for dual F32 + SQ8 storage.
+//!
+//! Dual storage traverses the graph with SQ8 codes and keeps the F32 vectors
+//! for rescoring. Both representations must survive snapshots, compaction
+//! and WAL replay.
+
+use edgevec::hnsw::rescore::similarity;
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::wal::WalAppender;
+use edgevec::persistence::{
+    read_section_table, read_snapshot, recover_index, write_snapshot, FileHeader, Flags,
+    MemoryBackend, SectionKind, StorageBackend,
+};
+use edgevec::quantization::ScalarQuantizer;
+use edgevec::storage::{StorageType, VectorStorage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+const DIM: u32 = 64;
+const K: usize = 10;
+
+fn random_vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect()
+}
+
+fn l2(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+fn quantizer(vectors: &[Vec<f32>]) -> ScalarQuantizer {
+    let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+    ScalarQuantizer::train(&refs)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn build(vectors: &[Vec<f32>], storage_type: StorageType) -> (HnswIndex, VectorStorage) {
+    build_with_metric(vectors, storage_type, HnswConfig::METRIC_L2_SQUARED)
+}
+
+fn build_with_metric(
+    vectors: &[Vec<f32>],
+    storage_type: StorageType,
+    metric: u32,
+) -> (HnswIndex, VectorStorage) {
+    let mut config = HnswConfig::new(DIM);
+    config.metric = metric;
+    let mut storage = VectorStorage::new(&config, None);
+    storage.set_storage_type(storage_type);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for v in vectors {
+        index.insert(v, &mut storage).unwrap();
+    }
+    (index, storage)
+}
+
+fn exact_top_k(vectors: &[Vec<f32>], query: &[f32]) -> HashSet<VectorId> {
+    exact_top_k_by(vectors, |v| l2(query, v))
+}
+
+fn exact_top_k_by(vectors: &[Vec<f32>], distance: impl Fn(&[f32]) -> f32) -> HashSet<VectorId> {
+    let mut scored: Vec<(usize, f32)> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i, distance(v)))
+        .collect();
+    scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+    scored
+        .iter()
+        .take(K)
+        .map(|&(i, _)| VectorId(i as u64 + 1))
+        .collect()
+}
+
+#[test]
+fn test_dual_rescoring_recovers_f32_recall() {
+    let vectors = random_vectors(2000, 1);
+    let q = quantizer(&vectors);
+    let (sq8_index, sq8_storage) = build(&vectors, StorageType::from_quantizer(&q));
+    let (dual_index, dual_storage) = build(&vectors, StorageType::dual_from_quantizer(&q));
+    assert!(dual_storage.storage_type().is_dual());
+
+    let queries = random_vectors(50, 2);
+    let (mut sq8_hits, mut dual_hits) = (0, 0);
+    for query in &queries {
+        let truth = exact_top_k(&vectors, query);
+        sq8_hits += sq8_index
+            .search(query, K, &sq8_storage)
+            .unwrap()
+            .iter()
+            .filter(|r| truth.contains(&r.vector_id))
+            .count();
+
+        let results = dual_index
+            .search_sq8_rescored(query, K, 4, &dual_storage)
+            .unwrap();
+        assert_eq!(results.len(), K);
+        assert!(results.windows(2).all(|w| w[0].1 >= w[1].1));
+        for (id, score) in &results {
+            // Scores come from the exact F32 vectors
+            let exact = similarity(
+                HnswConfig::METRIC_L2_SQUARED,
+                l2(query, &vectors[id.0 as usize - 1]),
+            );
+            assert!((score - exact).abs() < 1e-6, "{score} vs {exact}");
+        }
+        dual_hits += results.iter().filter(|(id, _)| truth.contains(id)).count();
+    }
+
+    let total = (queries.len() * K) as f64;
+    let (sq8_recall, dual_recall) = (sq8_hits as f64 / total, dual_hits as f64 / total);
+    assert!(dual_recall > 0.95, "dual recall: {dual_recall}");
+    assert!(
+        dual_recall > sq8_recall,
+        "dual {dual_recall} vs SQ8 {sq8_recall}"
+    );
+}
+
+#[test]
+fn test_dual_rescoring_dot_product_recall() {
+    let vectors = random_vectors(2000, 7);
+    let storage_type = StorageType::dual_from_quantizer(&quantizer(&vectors));
+    let (index, storage) =
+        build_with_metric(&vectors, storage_type, HnswConfig::METRIC_DOT_PRODUCT);
+
+    let queries = random_vectors(50, 8);
+    let mut hits = 0;
+    for query in &queries {
+        let truth = exact_top_k_by(&vectors, |v| -dot(query, v));
+        let results = index.search_sq8_rescored(query, K, 4, &storage).unwrap();
+        assert_eq!(results.len(), K);
+        // Highest dot product first, scored with the exact F32 vectors
+        assert!(results.windows(2).all(|w| w[0].1 >= w[1].1));
+        for (id, score) in &results {
+            let exact = dot(query, &vectors[id.0 as usize - 1]);
+            assert!((score - exact).abs() < 1e-4, "{score} vs {exact}");
+        }
+        hits += results.iter().filter(|(id, _)| truth.contains(id)).count();
+    }
+
+    let recall = hits as f64 / (queries.len() * K) as f64;
+    assert!(recall > 0.9, "dot recall: {recall}");
+}
+
+#[test]
+fn test_dual_snapshot_roundtrip() {
+    let vectors = random_vectors(300, 3);
+    let storage_type = StorageType::dual_from_quantizer(&quantizer(&vectors));
+    let (mut index, storage) = build(&vectors, storage_type.clone());
+    index.soft_delete(VectorId(5)).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+
+    let data = backend.read().unwrap();
+    let header = FileHeader::from_bytes(&data[..64]).unwrap();
+    assert_ne!(header.flags & Flags::DUAL_STORAGE, 0);
+    assert_eq!(header.flags & Flags::QUANTIZED, 0);
+    // Only the F32 vectors are written; codes are re-encoded on load
+    let table = read_section_table(&backend).unwrap();
+    let block = table.get(SectionKind::VECTORS).unwrap();
+    assert_eq!(block.length, 300 * u64::from(DIM) * 4);
+    assert!(table.get(SectionKind::QUANTIZER).is_some());
+
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded_storage.storage_type(), &storage_type);
+    assert!(loaded.is_deleted(VectorId(5)).unwrap());
+    for id in (1..=300).map(VectorId) {
+        assert_eq!(loaded_storage.get_vector(id), storage.get_vector(id));
+        assert_eq!(
+            loaded_storage.get_quantized_vector(id),
+            storage.get_quantized_vector(id)
+        );
+    }
+    for query in random_vectors(10, 4) {
+        assert_eq!(
+            loaded
+                .search_sq8_rescored(&query, K, 3, &loaded_storage)
+                .unwrap(),
+            index.search_sq8_rescored(&query, K, 3, &storage).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_compaction_keeps_dual_storage() {
+    let vectors = random_vectors(200, 5);
+    let storage_type = StorageType::dual_from_quantizer(&quantizer(&vectors));
+    let (mut index, storage) = build(&vectors, storage_type.clone());
+    for id in (1..=200).step_by(4).map(VectorId) {
+        index.soft_delete(id).unwrap();
+    }
+
+    let (compacted, new_storage, _) = index.compact(&storage).unwrap();
+    assert_eq!(new_storage.storage_type(), &storage_type);
+
+    let query = &vectors[1];
+    let results = compacted
+        .search_sq8_rescored(query, 1, 3, &new_storage)
+        .unwrap();
+    assert_eq!(&new_storage.get_vector(results[0].0)[..], &query[..]);
+    assert!((results[0].1 - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_dual_storage_recovers_from_wal() {
+    let vectors = random_vectors(60, 6);
+    let storage_type = StorageType::dual_from_quantizer(&quantizer(&vectors));
+    let config = HnswConfig::new(DIM);
+    let wal = MemoryBackend::new();
+    let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+    storage.set_storage_type(storage_type.clone());
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+
+    let mut snapshot = MemoryBackend::new();
+    for v in &vectors[..40] {
+        index.insert(v, &mut storage).unwrap();
+    }
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+
+    // Only in the WAL
+    for v in &vectors[40..] {
+        index.insert(v, &mut storage).unwrap();
+    }
+    index
+        .update(VectorId(3), &vectors[59], &mut storage)
+        .unwrap();
+    drop(index);
+
+    let (recovered, recovered_storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+    assert_eq!(recovered_storage.storage_type(), &storage_type);
+    assert_eq!(recovered_storage.len(), 60);
+    for id in (1..=60).map(VectorId) {
+        assert_eq!(recovered_storage.get_vector(id), storage.get_vector(id));
+        assert_eq!(
+            recovered_storage.get_quantized_vector(id),
+            storage.get_quantized_vector(id)
+        );
+    }
+    let results = recovered
+        .search_sq8_rescored(&vectors[50], 1, 3, &recovered_storage)
+        .unwrap();
+    assert_eq!(results[0].0, VectorId(51));
+}
@@ -0,0 +1,210 @@
+//! Integration tests for SQ8 search under cosine and dot-product metrics.
+//!
+//! Quantized storages compare u8 codes directly for every metric. Distances
+//! must match the quantizer's own metric kernels, and rankings must agree
+//! with exact F32 search on normalized embeddings.
+
+use edgevec::flat::FlatIndex;
+use edgevec::hnsw::{HnswConfig, HnswIndex, SearchResult, VectorId};
+use edgevec::metric::{Cosine, Metric, NegativeDotProduct};
+use edgevec::quantization::{ScalarQuantizer, Sq8Mode, Sq8TrainConfig};
+use edgevec::storage::{StorageType, VectorStorage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+const DIM: u32 = 64;
+const COUNT: usize = 1000;
+const K: usize = 10;
+
+/// Random unit vectors, like the output of an embedding model.
+fn normalized_vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let v: Vec<f32> = (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            v.into_iter().map(|x| x / norm).collect()
+        })
+        .collect()
+}
+
+fn quantizer(vectors: &[Vec<f32>], mode: Sq8Mode) -> ScalarQuantizer {
+    let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+    let config = Sq8TrainConfig {
+        mode,
+        per_dimension: mode == Sq8Mode::Symmetric,
+        percentile: None,
+    };
+    ScalarQuantizer::train_with(&refs, &config)
+}
+
+fn config(metric: u32) -> HnswConfig {
+    let mut config = HnswConfig::new(DIM);
+    config.metric = metric;
+    config.ef_search = 64;
+    config
+}
+
+fn build(
+    vectors: &[Vec<f32>],
+    metric: u32,
+    quantizer: &ScalarQuantizer,
+) -> (HnswIndex, VectorStorage) {
+    let config = config(metric);
+    let mut storage = VectorStorage::new(&config, None);
+    storage.set_storage_type(StorageType::from_quantizer(quantizer));
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for v in vectors {
+        index.insert(v, &mut storage).unwrap();
+    }
+    (index, storage)
+}
+
+fn exact_top_k<M: Metric<f32>>(vectors: &[Vec<f32>], query: &[f32]) -> HashSet<VectorId> {
+    let mut scored: Vec<(usize, f32)> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i, M::distance(query, v)))
+        .collect();
+    scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+    scored
+        .iter()
+        .take(K)
+        .map(|&(i, _)| VectorId(i as u64 + 1))
+        .collect()
+}
+
+/// Checks distances against the quantizer and returns the hit count.
+fn check_results(
+    results: &[SearchResult],
+    metric: u32,
+    quantizer: &ScalarQuantizer,
+    storage: &VectorStorage,
+    query: &[f32],
+    truth: &HashSet<VectorId>,
+) -> usize {
+    assert_eq!(results.len(), K);
+    assert!(results.windows(2).all(|w| w[0].distance <= w[1].distance));
+    let codes = quantizer.quantize(query);
+    for r in results {
+        let expected =
+            quantizer.distance(metric, &codes, storage.get_quantized_vector(r.vector_id));
+        assert!(
+            (r.distance - expected).abs() < 1e-5,
+            "{} vs {expected}",
+            r.distance
+        );
+    }
+    results
+        .iter()
+        .filter(|r| truth.contains(&r.vector_id))
+        .count()
+}
+
+fn run<M: Metric<f32>>(metric: u32, mode: Sq8Mode) {
+    let vectors = normalized_vectors(COUNT, 5);
+    let quantizer = quantizer(&vectors, mode);
+    let (index, storage) = build(&vectors, metric, &quantizer);
+
+    let mut flat_storage = VectorStorage::new(&config(metric), None);
+    flat_storage.set_storage_type(StorageType::from_quantizer(&quantizer));
+    let mut flat = FlatIndex::new(config(metric), &flat_storage).unwrap();
+    for v in &vectors {
+        flat.insert(v, &mut flat_storage).unwrap();
+    }
+
+    // F32 baseline with the same graph parameters
+    let mut f32_storage = VectorStorage::new(&config(metric), None);
+    let mut f32_index = HnswIndex::new(config(metric), &f32_storage).unwrap();
+    for v in &vectors {
+        f32_index.insert(v, &mut f32_storage).unwrap();
+    }
+
+    let queries = normalized_vectors(30, 77);
+    let (mut hnsw_hits, mut flat_hits, mut f32_hits) = (0, 0, 0);
+    for query in &queries {
+        let truth = exact_top_k::<M>(&vectors, query);
+        let results = index.search(query, K, &storage).unwrap();
+        hnsw_hits += check_results(&results, metric, &quantizer, &storage, query, &truth);
+        let results = flat.search(query, K, &flat_storage).unwrap();
+        flat_hits += check_results(&results, metric, &quantizer, &flat_storage, query, &truth);
+        let results = f32_index.search(query, K, &f32_storage).unwrap();
+        f32_hits += results
+            .iter()
+            .filter(|r| truth.contains(&r.vector_id))
+            .count();
+    }
+
+    let total = (queries.len() * K) as f64;
+    let recall = |hits: usize| hits as f64 / total;
+    let (hnsw_recall, flat_recall) = (recall(hnsw_hits), recall(flat_hits));
+    assert!(flat_recall > 0.9, "flat recall: {flat_recall}");
+    assert!(
+        hnsw_recall > recall(f32_hits) - 0.1,
+        "SQ8 HNSW recall {hnsw_recall} vs F32 {}",
+        recall(f32_hits)
+    );
+}
+
+#[test]
+fn test_sq8_cosine_search() {
+    run::<Cosine>(HnswConfig::METRIC_COSINE, Sq8Mode::Asymmetric);
+}
+
+#[test]
+fn test_sq8_symmetric_cosine_search() {
+    run::<Cosine>(HnswConfig::METRIC_COSINE, Sq8Mode::Symmetric);
+}
+
+#[test]
+fn test_sq8_dot_product_search() {
+    run::<NegativeDotProduct>(HnswConfig::METRIC_DOT_PRODUCT, Sq8Mode::Asymmetric);
+}
+
+#[test]
+fn test_sq8_symmetric_dot_product_search() {
+    run::<NegativeDotProduct>(HnswConfig::METRIC_DOT_PRODUCT, Sq8Mode::Symmetric);
+}
+
+#[test]
+fn test_sq8_cosine_finds_scaled_copy() {
+    let vectors = normalized_vectors(200, 9);
+    let quantizer = quantizer(&vectors, Sq8Mode::Symmetric);
+    let (index, storage) = build(&vectors, HnswConfig::METRIC_COSINE, &quantizer);
+
+    // Same direction at half the length: cosine ignores the scale
+    let query: Vec<f32> = vectors[42].iter().map(|x| x * 0.5).collect();
+    let results = index.search(&query, 1, &storage).unwrap();
+    assert_eq!(results[0].vector_id, VectorId(43));
+    assert!(results[0].distance < 1e-3);
+
+    // Cosine graphs navigate well on normalized vectors
+    let mut hits = 0;
+    for query in &normalized_vectors(30, 78) {
+        let truth = exact_top_k::<Cosine>(&vectors, query);
+        let results = index.search(query, K, &storage).unwrap();
+        hits += results
+            .iter()
+            .filter(|r| truth.contains(&r.vector_id))
+            .count();
+    }
+    assert!(hits >= 270, "cosine hits: {hits}/300");
+}
+
+#[test]
+fn test_sq8_dot_product_top1_is_most_similar() {
+    let vectors = normalized_vectors(300, 10);
+    for mode in [Sq8Mode::Asymmetric, Sq8Mode::Symmetric] {
+        let quantizer = quantizer(&vectors, mode);
+        let (index, storage) = build(&vectors, HnswConfig::METRIC_DOT_PRODUCT, &quantizer);
+
+        // Each unit vector has the largest dot product with itself
+        for i in (0..300).step_by(15) {
+            let results = index.search(&vectors[i], K, &storage).unwrap();
+            assert_eq!(results[0].vector_id, VectorId(i as u64 + 1), "{mode:?}");
+            assert!(results[0].distance < results[K - 1].distance);
+            assert!(results[0].distance < -0.9, "{}", results[0].distance);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Integration tests for per-dimension and symmetric SQ8 ranges.
+//!
+//! Covers search quality when one dimension has a much wider range than the
+//! others, symmetric dot products, and snapshot round trips of the version 2
+//! quantizer section.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::{
+    read_section_table, read_snapshot, write_snapshot, MemoryBackend, QuantizerSectionHeader,
+    SectionKind, StorageBackend,
+};
+use edgevec::quantization::{ScalarQuantizer, Sq8Mode, Sq8TrainConfig};
+use edgevec::storage::{StorageType, VectorStorage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+const DIM: u32 = 32;
+const COUNT: usize = 1000;
+
+/// Random vectors in `[-1, 1)`, except dimension 0 which spans `[-500, 500)`.
+fn skewed_vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            (0..DIM)
+                .map(|d| {
+                    let x: f32 = rng.gen_range(-1.0..1.0);
+                    if d == 0 {
+                        x * 500.0
+                    } else {
+                        x
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn train(vectors: &[Vec<f32>], config: &Sq8TrainConfig) -> ScalarQuantizer {
+    let refs: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+    ScalarQuantizer::train_with(&refs, config)
+}
+
+fn per_dimension() -> Sq8TrainConfig {
+    Sq8TrainConfig {
+        per_dimension: true,
+        ..Sq8TrainConfig::default()
+    }
+}
+
+fn build(vectors: &[Vec<f32>], storage_type: StorageType) -> (HnswIndex, VectorStorage) {
+    let mut config = HnswConfig::new(DIM);
+    config.ef_search = 64;
+    let mut storage = VectorStorage::new(&config, None);
+    storage.set_storage_type(storage_type);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for v in vectors {
+        index.insert(v, &mut storage).unwrap();
+    }
+    (index, storage)
+}
+
+fn exact_top_k(vectors: &[Vec<f32>], query: &[f32], k: usize) -> HashSet<VectorId> {
+    let mut scored: Vec<(usize, f32)> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let d: f32 = v.iter().zip(query).map(|(a, b)| (a - b) * (a - b)).sum();
+            (i, d)
+        })
+        .collect();
+    scored.sort_by(|a, b| a.1.total_cmp(&b.1));
+    scored
+        .iter()
+        .take(k)
+        .map(|&(i, _)| VectorId(i as u64 + 1))
+        .collect()
+}
+
+fn recall(index: &HnswIndex, storage: &VectorStorage, vectors: &[Vec<f32>]) -> f64 {
+    const K: usize = 10;
+    let queries = skewed_vectors(30, 99);
+    let mut hits = 0;
+    for query in &queries {
+        let truth = exact_top_k(vectors, query, K);
+        let results = index.search(query, K, storage).unwrap();
+        hits += results
+            .iter()
+            .filter(|r| truth.contains(&r.vector_id))
+            .count();
+    }
+    hits as f64 / (queries.len() * K) as f64
+}
+
+#[test]
+fn test_per_dimension_ranges_keep_precision_of_narrow_dimensions() {
+    let vectors = skewed_vectors(COUNT, 7);
+
+    let global = train(&vectors, &Sq8TrainConfig::default());
+    let (global_index, global_storage) = build(&vectors, StorageType::from_quantizer(&global));
+    let per_dim = train(&vectors, &per_dimension());
+    let storage_type = StorageType::from_quantizer(&per_dim);
+    assert!(matches!(storage_type, StorageType::QuantizedU8Ranges(_)));
+    let (index, storage) = build(&vectors, storage_type);
+
+    // With one global range, a step is ~4 units: every [-1, 1) dimension
+    // collapses to one or two codes.
+    let v = &vectors[3];
+    let global_error = (global.dequantize(&global.quantize(v))[5] - v[5]).abs();
+    let per_dim_error = (per_dim.dequantize(&per_dim.quantize(v))[5] - v[5]).abs();
+    assert!(
+        per_dim_error < 0.005,
+        "per-dimension error: {per_dim_error}"
+    );
+    assert!(global_error > per_dim_error);
+
+    let global_recall = recall(&global_index, &global_storage, &vectors);
+    let per_dim_recall = recall(&index, &storage, &vectors);
+    assert!(
+        per_dim_recall > 0.8,
+        "per-dimension recall: {per_dim_recall}"
+    );
+    assert!(
+        per_dim_recall > global_recall,
+        "per-dimension {per_dim_recall} vs global {global_recall}"
+    );
+}
+
+#[test]
+fn test_symmetric_dot_product_tracks_f32() {
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let vectors: Vec<Vec<f32>> = (0..200)
+        .map(|_| (0..DIM).map(|_| rng.gen_range(-0.2..0.2)).collect())
+        .collect();
+    let config = Sq8TrainConfig {
+        mode: Sq8Mode::Symmetric,
+        per_dimension: true,
+        percentile: Some(0.999),
+    };
+    let q = train(&vectors, &config);
+    assert_eq!(q.ranges().unwrap().mode, Sq8Mode::Symmetric);
+
+    for pair in vectors.chunks(2).take(50) {
+        let exact: f32 = pair[0].iter().zip(&pair[1]).map(|(a, b)| a * b).sum();
+        let approx = q.dot_product(&q.quantize(&pair[0]), &q.quantize(&pair[1]));
+        assert!((approx - exact).abs() < 2e-3, "{approx} vs {exact}");
+    }
+}
+
+#[test]
+fn test_ranges_snapshot_roundtrip() {
+    let vectors = skewed_vectors(300, 11);
+    let config = Sq8TrainConfig {
+        mode: Sq8Mode::Symmetric,
+        per_dimension: true,
+        percentile: Some(0.99),
+    };
+    let storage_type = StorageType::from_quantizer(&train(&vectors, &config));
+    let (mut index, storage) = build(&vectors, storage_type.clone());
+    index.soft_delete(VectorId(9)).unwrap();
+
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+
+    let data = backend.read().unwrap();
+    let table = read_section_table(&backend).unwrap();
+    let section = table.get(SectionKind::QUANTIZER).unwrap();
+    #[allow(clippy::cast_possible_truncation)]
+    let header = QuantizerSectionHeader::from_bytes(&data[section.offset as usize..]).unwrap();
+    assert!(header.has_ranges());
+
+    let (loaded, loaded_storage) = read_snapshot(&backend).unwrap();
+    assert_eq!(loaded_storage.storage_type(), &storage_type);
+    for id in (1..=300).map(VectorId) {
+        assert_eq!(
+            loaded_storage.get_quantized_vector(id),
+            storage.get_quantized_vector(id)
+        );
+    }
+    for query in skewed_vectors(10, 12) {
+        assert_eq!(
+            loaded.search(&query, 10, &loaded_storage).unwrap(),
+            index.search(&query, 10, &storage).unwrap()
+        );
+    }
+
+    // Global configs keep writing the version 1 payload
+    let global = StorageType::from_quantizer(&train(&vectors, &Sq8TrainConfig::default()));
+    let (index, storage) = build(&vectors[..20], global.clone());
+    let mut backend = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut backend).unwrap();
+    let data = backend.read().unwrap();
+    let table = read_section_table(&backend).unwrap();
+    let section = table.get(SectionKind::QUANTIZER).unwrap();
+    #[allow(clippy::cast_possible_truncation)]
+    let header = QuantizerSectionHeader::from_bytes(&data[section.offset as usize..]).unwrap();
+    assert_eq!(header.version, 1);
+    assert_eq!(read_snapshot(&backend).unwrap().1.storage_type(), &global);
+}
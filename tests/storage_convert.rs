@@ -0,0 +1,170 @@
+//! Integration tests for converting a populated storage between types.
+//!
+//! `VectorStorage::convert_to` re-encodes every vector in place, so an
+//! index built in F32 keeps working after moving to SQ8, dual storage or
+//! back. Conversions are logged and must survive crashes and snapshots.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex, VectorId};
+use edgevec::persistence::wal::WalAppender;
+use edgevec::persistence::{read_snapshot, recover_index, write_snapshot, MemoryBackend};
+use edgevec::quantization::{Sq8Mode, Sq8TrainConfig};
+use edgevec::storage::{StorageType, VectorStorage};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashSet;
+
+const DIM: u32 = 32;
+const K: usize = 10;
+
+fn random_vectors(count: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| (0..DIM).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect()
+}
+
+fn build(vectors: &[Vec<f32>], wal: Option<&MemoryBackend>) -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let appender = wal.map(|wal| WalAppender::new(Box::new(wal.clone()), 0));
+    let mut storage = VectorStorage::new(&config, appender);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    for v in vectors {
+        index.insert(v, &mut storage).unwrap();
+    }
+    (index, storage)
+}
+
+fn top_k(index: &HnswIndex, storage: &VectorStorage, query: &[f32]) -> HashSet<VectorId> {
+    index
+        .search(query, K, storage)
+        .unwrap()
+        .iter()
+        .map(|r| r.vector_id)
+        .collect()
+}
+
+#[test]
+fn test_convert_populated_index_to_sq8_and_back() {
+    let vectors = random_vectors(1000, 1);
+    let (mut index, mut storage) = build(&vectors, None);
+    index.soft_delete(VectorId(7)).unwrap();
+    let queries = random_vectors(20, 2);
+    let f32_results: Vec<_> = queries.iter().map(|q| top_k(&index, &storage, q)).collect();
+
+    let quantizer = storage.train_quantizer(&Sq8TrainConfig {
+        mode: Sq8Mode::Asymmetric,
+        per_dimension: true,
+        percentile: None,
+    });
+    storage
+        .convert_to(StorageType::from_quantizer(&quantizer))
+        .unwrap();
+    assert!(matches!(
+        storage.storage_type(),
+        StorageType::QuantizedU8Ranges(_)
+    ));
+    assert_eq!(storage.len(), 1000);
+    assert_eq!(
+        storage.get_quantized_vector(VectorId(1)),
+        &quantizer.quantize(&vectors[0])[..]
+    );
+
+    // Every vector is searched through its codes, none are left in F32
+    let mut hits = 0;
+    for (query, expected) in queries.iter().zip(&f32_results) {
+        let results = top_k(&index, &storage, query);
+        assert!(!results.contains(&VectorId(7)));
+        hits += results.intersection(expected).count();
+    }
+    assert!(hits >= 180, "overlap with F32 results: {hits}/200");
+
+    // New inserts use the trained quantizer
+    let extra = random_vectors(1, 3).remove(0);
+    let id = index.insert(&extra, &mut storage).unwrap();
+    assert_eq!(
+        storage.get_quantized_vector(id),
+        &quantizer.quantize(&extra)[..]
+    );
+
+    storage.convert_to(StorageType::Float32).unwrap();
+    let restored = storage.get_vector(VectorId(1));
+    let max_error = restored
+        .iter()
+        .zip(&vectors[0])
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0f32, f32::max);
+    assert!(max_error < 0.01, "max error: {max_error}");
+    assert_eq!(index.search(&extra, 1, &storage).unwrap()[0].vector_id, id);
+}
+
+#[test]
+fn test_convert_to_dual_keeps_exact_vectors() {
+    let vectors = random_vectors(300, 4);
+    let (index, mut storage) = build(&vectors, None);
+
+    let quantizer = storage.train_quantizer(&Sq8TrainConfig::default());
+    storage
+        .convert_to(StorageType::dual_from_quantizer(&quantizer))
+        .unwrap();
+    for (i, v) in vectors.iter().enumerate() {
+        let id = VectorId(i as u64 + 1);
+        assert_eq!(&storage.get_vector(id)[..], &v[..]);
+        assert_eq!(storage.get_quantized_vector(id), &quantizer.quantize(v)[..]);
+    }
+    let results = index
+        .search_sq8_rescored(&vectors[10], 1, 3, &storage)
+        .unwrap();
+    assert_eq!(results[0].0, VectorId(11));
+
+    // Dropping the F32 copy leaves the codes untouched
+    let codes = storage.get_quantized_vector(VectorId(11)).to_vec();
+    storage
+        .convert_to(StorageType::from_quantizer(&quantizer))
+        .unwrap();
+    assert_eq!(storage.get_quantized_vector(VectorId(11)), &codes[..]);
+}
+
+#[test]
+fn test_conversion_survives_crash_and_snapshot() {
+    let vectors = random_vectors(100, 5);
+    let wal = MemoryBackend::new();
+    let (mut index, mut storage) = build(&vectors[..60], Some(&wal));
+    let mut snapshot = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+
+    // The conversion and the inserts after it only live in the WAL
+    let target = StorageType::from_quantizer(&storage.train_quantizer(&Sq8TrainConfig::default()));
+    storage.convert_to(target.clone()).unwrap();
+    for v in &vectors[60..] {
+        index.insert(v, &mut storage).unwrap();
+    }
+    drop(index);
+
+    let (recovered, mut recovered_storage) =
+        recover_index(&snapshot, Box::new(wal.clone())).unwrap();
+    assert_eq!(recovered_storage.storage_type(), &target);
+    assert_eq!(recovered_storage.len(), 100);
+    for id in (1..=100).map(VectorId) {
+        assert_eq!(
+            recovered_storage.get_quantized_vector(id),
+            storage.get_quantized_vector(id)
+        );
+    }
+
+    // Re-running the migration after a restart is a no-op
+    recovered_storage.convert_to(target.clone()).unwrap();
+    let (again, again_storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+    assert_eq!(again_storage.storage_type(), &target);
+    assert_eq!(again.node_count(), 100);
+
+    // A snapshot after the conversion stores the new type
+    let mut converted = MemoryBackend::new();
+    write_snapshot(&recovered, &recovered_storage, &mut converted).unwrap();
+    let (loaded, loaded_storage) = read_snapshot(&converted).unwrap();
+    assert_eq!(loaded_storage.storage_type(), &target);
+    let query = &vectors[80];
+    assert_eq!(
+        loaded.search(query, K, &loaded_storage).unwrap(),
+        recovered.search(query, K, &recovered_storage).unwrap()
+    );
+}
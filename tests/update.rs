@@ -0,0 +1,198 @@
+//! Integration tests for in-place vector updates and upserts.
+//!
+//! `update` must keep the `VectorId` (and therefore metadata and external
+//! IDs), overwrite storage, rewire the graph so search reflects the new
+//! vector, and be durable through the WAL.
+
+use edgevec::hnsw::{ExternalId, GraphError, HnswConfig, HnswIndex, VectorId};
+use edgevec::metadata::MetadataValue;
+use edgevec::persistence::wal::WalAppender;
+use edgevec::persistence::MemoryBackend;
+use edgevec::storage::VectorStorage;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::HashMap;
+
+fn create_env(dim: u32) -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(dim);
+    let storage = VectorStorage::new(&config, None);
+    let index = HnswIndex::new(config, &storage).expect("Failed to create index");
+    (index, storage)
+}
+
+fn random_vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| (0..dim).map(|_| rng.gen_range(-1.0..1.0)).collect())
+        .collect()
+}
+
+#[test]
+fn test_update_moves_vector_in_search() {
+    let (mut index, mut storage) = create_env(8);
+    let vectors = random_vectors(200, 8, 7);
+    let ids: Vec<VectorId> = vectors
+        .iter()
+        .map(|v| index.insert(v, &mut storage).unwrap())
+        .collect();
+
+    // Move vector 10 onto a far-away point and check it is found there
+    let target = vec![5.0; 8];
+    index.update(ids[10], &target, &mut storage).unwrap();
+
+    let results = index.search(&target, 1, &storage).unwrap();
+    assert_eq!(results[0].vector_id, ids[10]);
+    assert!(results[0].distance < 1e-6);
+
+    // Its old position now belongs to someone else
+    let results = index.search(&vectors[10], 1, &storage).unwrap();
+    assert_ne!(results[0].vector_id, ids[10]);
+
+    assert_eq!(index.len(), 200);
+    assert_eq!(storage.len(), 200);
+}
+
+#[test]
+fn test_updated_graph_stays_searchable() {
+    let (mut index, mut storage) = create_env(8);
+    let vectors = random_vectors(300, 8, 11);
+    let ids: Vec<VectorId> = vectors
+        .iter()
+        .map(|v| index.insert(v, &mut storage).unwrap())
+        .collect();
+
+    // Replace half of the vectors with fresh ones
+    let replacements = random_vectors(150, 8, 12);
+    for (id, v) in ids.iter().step_by(2).zip(&replacements) {
+        index.update(*id, v, &mut storage).unwrap();
+    }
+
+    // Every vector must be its own nearest neighbor
+    let mut found = 0;
+    for &id in &ids {
+        let query = storage.get_vector(id).into_owned();
+        let results = index.search(&query, 1, &storage).unwrap();
+        if results.first().map(|r| r.vector_id) == Some(id) {
+            found += 1;
+        }
+    }
+    assert!(
+        found >= 295,
+        "self-recall too low after updates: {found}/300"
+    );
+}
+
+#[test]
+fn test_update_keeps_metadata_and_external_id() {
+    let (mut index, mut storage) = create_env(4);
+
+    let mut meta = HashMap::new();
+    meta.insert("tag".to_string(), MetadataValue::String("a".into()));
+    let id = index
+        .insert_with_metadata(&mut storage, &[1.0, 0.0, 0.0, 0.0], meta)
+        .unwrap();
+    let keyed = index
+        .insert_with_external_id("doc", &[0.0, 1.0, 0.0, 0.0], &mut storage)
+        .unwrap();
+
+    index
+        .update(id, &[0.0, 0.0, 1.0, 0.0], &mut storage)
+        .unwrap();
+    index
+        .update(keyed, &[0.0, 0.0, 0.0, 1.0], &mut storage)
+        .unwrap();
+
+    #[allow(clippy::cast_possible_truncation)]
+    let meta_id = id.0 as u32;
+    assert!(index.metadata().has_key(meta_id, "tag"));
+    assert_eq!(
+        index.resolve_external_id(&ExternalId::from("doc")),
+        Some(keyed)
+    );
+}
+
+#[test]
+fn test_update_rejects_invalid_targets() {
+    let (mut index, mut storage) = create_env(4);
+    let id = index.insert(&[1.0; 4], &mut storage).unwrap();
+
+    assert!(matches!(
+        index.update(VectorId(99), &[0.0; 4], &mut storage),
+        Err(GraphError::InvalidVectorId)
+    ));
+    assert!(matches!(
+        index.update(id, &[0.0; 3], &mut storage),
+        Err(GraphError::DimensionMismatch {
+            expected: 4,
+            actual: 3
+        })
+    ));
+
+    index.soft_delete(id).unwrap();
+    assert!(matches!(
+        index.update(id, &[0.0; 4], &mut storage),
+        Err(GraphError::InvalidVectorId)
+    ));
+
+    // Failed updates leave storage untouched
+    assert_eq!(&storage.get_vector(id)[..], &[1.0; 4]);
+}
+
+#[test]
+fn test_update_single_node_index() {
+    let (mut index, mut storage) = create_env(2);
+    let id = index.insert(&[1.0, 1.0], &mut storage).unwrap();
+
+    index.update(id, &[2.0, 2.0], &mut storage).unwrap();
+
+    let results = index.search(&[2.0, 2.0], 1, &storage).unwrap();
+    assert_eq!(results[0].vector_id, id);
+}
+
+#[test]
+fn test_upsert_inserts_then_updates() {
+    let (mut index, mut storage) = create_env(2);
+
+    let a = index.upsert(1u64, &[1.0, 0.0], &mut storage).unwrap();
+    let b = index.upsert("b", &[0.0, 1.0], &mut storage).unwrap();
+    assert_ne!(a, b);
+
+    let a2 = index.upsert(1u64, &[-1.0, 0.0], &mut storage).unwrap();
+    assert_eq!(a, a2);
+    assert_eq!(index.len(), 2);
+
+    let results = index.search_external(&[-1.0, 0.0], 1, &storage).unwrap();
+    assert_eq!(results[0].external_id, Some(ExternalId::Num(1)));
+}
+
+#[test]
+fn test_update_is_logged_to_wal() {
+    let config = HnswConfig::new(4);
+    let backend = MemoryBackend::new();
+    let wal = WalAppender::new(Box::new(backend.clone()), 0);
+    let mut storage = VectorStorage::new(&config, Some(wal));
+    let mut index = HnswIndex::new(config.clone(), &storage).unwrap();
+
+    let id = index.insert(&[1.0; 4], &mut storage).unwrap();
+    index.insert(&[2.0; 4], &mut storage).unwrap();
+    index.update(id, &[3.0; 4], &mut storage).unwrap();
+
+    let recovered = VectorStorage::recover(Box::new(backend), &config).unwrap();
+    assert_eq!(recovered.len(), 2);
+    assert_eq!(&recovered.get_vector(id)[..], &[3.0; 4]);
+}
+
+#[test]
+fn test_update_refreshes_bq_storage() {
+    let config = HnswConfig::new(8);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::with_bq(config, &storage).unwrap();
+
+    let id = index.insert(&[1.0; 8], &mut storage).unwrap();
+    index.insert(&[0.5; 8], &mut storage).unwrap();
+    index.update(id, &[-1.0; 8], &mut storage).unwrap();
+
+    let bq = index.bq_storage().unwrap();
+    assert_eq!(bq.get_raw(0).unwrap(), &[0x00]);
+    assert_eq!(bq.get_raw(1).unwrap(), &[0xFF]);
+}
@@ -0,0 +1,144 @@
+//! Integration tests for WAL checkpoints.
+//!
+//! A snapshot records the WAL sequence it covers. Recovery skips covered
+//! records and `checkpoint` truncates them from the log.
+
+use edgevec::hnsw::{HnswConfig, HnswIndex};
+use edgevec::metadata::MetadataValue;
+use edgevec::persistence::storage::file::FileBackend;
+use edgevec::persistence::wal::WalAppender;
+use edgevec::persistence::{
+    checkpoint, read_file_header, recover_index, write_snapshot, Flags, MemoryBackend,
+    StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+
+const DIM: u32 = 4;
+
+fn logged_index(wal: &MemoryBackend) -> (HnswIndex, VectorStorage) {
+    let config = HnswConfig::new(DIM);
+    let storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+    let index = HnswIndex::new(config, &storage).unwrap();
+    (index, storage)
+}
+
+#[test]
+fn test_checkpoint_truncates_covered_records() {
+    let wal = MemoryBackend::new();
+    let (mut index, mut storage) = logged_index(&wal);
+    let mut snapshot = MemoryBackend::new();
+
+    for i in 0..5 {
+        index.insert(&[i as f32; 4], &mut storage).unwrap();
+    }
+    assert!(!wal.read().unwrap().is_empty());
+
+    let sequence = checkpoint(&index, &mut storage, &mut snapshot).unwrap();
+    assert_eq!(sequence, 5);
+    assert!(wal.read().unwrap().is_empty());
+
+    // The log now only holds changes made after the checkpoint
+    let late = index.insert(&[9.0; 4], &mut storage).unwrap();
+    index.soft_delete_logged(late, &mut storage).unwrap();
+    drop((index, storage));
+
+    let (index, storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+    assert_eq!(index.node_count(), 6);
+    assert!(index.is_deleted(late).unwrap());
+    assert_eq!(storage.wal_sequence(), 7);
+}
+
+#[test]
+fn test_recovery_skips_records_covered_by_snapshot() {
+    let wal = MemoryBackend::new();
+    let (mut index, mut storage) = logged_index(&wal);
+    let mut snapshot = MemoryBackend::new();
+
+    let a = index.insert(&[1.0; 4], &mut storage).unwrap();
+    index
+        .set_metadata_logged(a, "tag", MetadataValue::Integer(1), &mut storage)
+        .unwrap();
+    // An unlogged change that only the snapshot knows about
+    index
+        .metadata_mut()
+        .insert(1, "tag", MetadataValue::Integer(2))
+        .unwrap();
+
+    // Simulate a crash between writing the snapshot and truncating the log
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+    drop((index, storage));
+    assert!(!wal.read().unwrap().is_empty());
+
+    // Replaying the stale metadata record would overwrite the snapshot value
+    let (index, _storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+    assert_eq!(
+        index.metadata().get(1, "tag"),
+        Some(&MetadataValue::Integer(2))
+    );
+}
+
+#[test]
+fn test_recovered_sequence_continues_after_empty_log() {
+    let wal = MemoryBackend::new();
+    let (mut index, mut storage) = logged_index(&wal);
+    let mut snapshot = MemoryBackend::new();
+
+    index.insert(&[1.0; 4], &mut storage).unwrap();
+    index.insert(&[2.0; 4], &mut storage).unwrap();
+    checkpoint(&index, &mut storage, &mut snapshot).unwrap();
+    drop((index, storage));
+
+    // Sequence numbers must not restart at 0, or new records would be
+    // mistaken for covered ones on the next recovery
+    let (mut index, mut storage) = recover_index(&snapshot, Box::new(wal.clone())).unwrap();
+    assert_eq!(storage.wal_sequence(), 2);
+    index.insert(&[3.0; 4], &mut storage).unwrap();
+    drop((index, storage));
+
+    let (index, _storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+    assert_eq!(index.node_count(), 3);
+}
+
+#[test]
+fn test_snapshot_without_wal_has_no_checkpoint() {
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(&config, None);
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    index.insert(&[1.0; 4], &mut storage).unwrap();
+
+    let mut snapshot = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+
+    let header = read_file_header(&snapshot.read().unwrap()).unwrap();
+    assert_eq!(header.flags & Flags::HAS_CHECKPOINT, 0);
+}
+
+#[test]
+fn test_checkpoint_truncates_wal_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let wal_path = dir.path().join("index.wal");
+    let config = HnswConfig::new(DIM);
+    let mut storage = VectorStorage::new(
+        &config,
+        Some(WalAppender::new(Box::new(FileBackend::new(&wal_path)), 0)),
+    );
+    let mut index = HnswIndex::new(config, &storage).unwrap();
+    let mut snapshot = FileBackend::new(dir.path().join("index.evec"));
+
+    for i in 0..20 {
+        index.insert(&[i as f32; 4], &mut storage).unwrap();
+    }
+    let before = std::fs::metadata(&wal_path).unwrap().len();
+    checkpoint(&index, &mut storage, &mut snapshot).unwrap();
+    assert_eq!(std::fs::metadata(&wal_path).unwrap().len(), 0);
+
+    // Appends after truncation go to the rewritten file
+    index.insert(&[99.0; 4], &mut storage).unwrap();
+    let after = std::fs::metadata(&wal_path).unwrap().len();
+    assert!(after > 0 && after < before);
+
+    drop((index, storage));
+    let (index, _storage) =
+        recover_index(&snapshot, Box::new(FileBackend::new(&wal_path))).unwrap();
+    assert_eq!(index.node_count(), 21);
+}
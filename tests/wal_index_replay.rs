@@ -0,0 +1,321 @@
+//! Integration tests for index-level WAL records and `recover_index`.
+//!
+//! Mutations after the last snapshot only live in the WAL. Recovery must
+//! bring back the graph, tombstones, metadata, external IDs and BQ state
+//! they produced. `recover_flat_index` does the same for a `FlatIndex`.
+
+use edgevec::batch::BatchInsertable;
+use edgevec::flat::FlatIndex;
+use edgevec::hnsw::{ExternalId, HnswConfig, HnswIndex, VectorId};
+use edgevec::metadata::MetadataValue;
+use edgevec::persistence::wal::{RecoveryMode, WalAppender};
+use edgevec::persistence::{
+    recover_flat_index, recover_index, recover_index_with_report, write_flat_snapshot,
+    write_snapshot, MemoryBackend, PersistenceError, StorageBackend,
+};
+use edgevec::storage::VectorStorage;
+use std::collections::HashMap;
+
+const DIM: u32 = 8;
+
+fn vector(seed: u32) -> Vec<f32> {
+    (0..DIM)
+        .map(|d| ((seed * 31 + d * 7) % 17) as f32)
+        .collect()
+}
+
+fn logged_index() -> (HnswIndex, VectorStorage, MemoryBackend) {
+    let config = HnswConfig::new(DIM);
+    let wal = MemoryBackend::new();
+    let storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+    let index = HnswIndex::new(config, &storage).unwrap();
+    (index, storage, wal)
+}
+
+#[test]
+fn test_recover_replays_deletes_metadata_and_bq() {
+    let (mut index, mut storage, wal) = logged_index();
+    let mut snapshot = MemoryBackend::new();
+
+    let mut ids = Vec::new();
+    for i in 0..10 {
+        ids.push(index.insert(&vector(i), &mut storage).unwrap());
+    }
+    index
+        .set_metadata_logged(ids[0], "tag", MetadataValue::Integer(1), &mut storage)
+        .unwrap();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+
+    // Everything below is only in the WAL
+    let mut meta = HashMap::new();
+    meta.insert("kind".to_string(), MetadataValue::String("late".into()));
+    let late = index
+        .insert_with_metadata(&mut storage, &vector(10), meta)
+        .unwrap();
+    index
+        .set_metadata_logged(ids[0], "tag", MetadataValue::Integer(2), &mut storage)
+        .unwrap();
+    index
+        .set_metadata_logged(ids[1], "tag", MetadataValue::Integer(3), &mut storage)
+        .unwrap();
+    assert!(index
+        .delete_metadata_logged(ids[1], "tag", &mut storage)
+        .unwrap());
+    assert!(index.soft_delete_logged(ids[2], &mut storage).unwrap());
+    index.enable_bq_logged(&mut storage).unwrap();
+    drop((index, storage));
+
+    let (recovered, recovered_storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+
+    assert_eq!(recovered.node_count(), 11);
+    assert_eq!(recovered_storage.get_vector(late)[..], vector(10)[..]);
+    assert!(recovered.is_deleted(ids[2]).unwrap());
+    assert_eq!(recovered.deleted_count(), 1);
+    assert!(recovered.has_bq());
+
+    let meta = recovered.metadata();
+    assert_eq!(meta.get(1, "tag"), Some(&MetadataValue::Integer(2)));
+    assert!(!meta.has_key(2, "tag"));
+    #[allow(clippy::cast_possible_truncation)]
+    let late_id = late.0 as u32;
+    assert_eq!(
+        meta.get(late_id, "kind"),
+        Some(&MetadataValue::String("late".into()))
+    );
+
+    // The recovered graph serves searches without the deleted vector
+    let results = recovered.search(&vector(2), 3, &recovered_storage).unwrap();
+    assert!(results.iter().all(|r| r.vector_id != ids[2]));
+}
+
+#[test]
+fn test_recovered_storage_keeps_logging() {
+    let (mut index, mut storage, wal) = logged_index();
+    let mut snapshot = MemoryBackend::new();
+
+    index.insert(&vector(0), &mut storage).unwrap();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+    drop((index, storage));
+
+    let (mut index, mut storage) = recover_index(&snapshot, Box::new(wal.clone())).unwrap();
+    let second = index.insert(&vector(1), &mut storage).unwrap();
+    index.soft_delete_logged(VectorId(1), &mut storage).unwrap();
+    drop((index, storage));
+
+    // A second crash recovers the mutations made after the first recovery
+    let (index, storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+    assert_eq!(index.node_count(), 2);
+    assert!(index.is_deleted(VectorId(1)).unwrap());
+    assert_eq!(storage.get_vector(second)[..], vector(1)[..]);
+}
+
+#[test]
+fn test_recover_stops_at_torn_tail() {
+    let (mut index, mut storage, wal) = logged_index();
+    let mut snapshot = MemoryBackend::new();
+
+    let a = index.insert(&vector(0), &mut storage).unwrap();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+    index.soft_delete_logged(a, &mut storage).unwrap();
+    drop((index, storage));
+
+    // Simulate a torn write of the delete record
+    let mut data = wal.read().unwrap();
+    data.truncate(data.len() - 3);
+    let torn = MemoryBackend::new();
+    torn.atomic_write("", &data).unwrap();
+
+    let (index, _storage) = recover_index(&snapshot, Box::new(torn)).unwrap();
+    assert!(!index.is_deleted(a).unwrap());
+}
+
+#[test]
+fn test_plain_mutators_are_recovered() {
+    let (mut index, mut storage, wal) = logged_index();
+    let mut snapshot = MemoryBackend::new();
+
+    let ids: Vec<VectorId> = (0..6)
+        .map(|i| index.insert(&vector(i), &mut storage).unwrap())
+        .collect();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+
+    // None of these take the storage; they log through the index's WAL
+    index.soft_delete(ids[0]).unwrap();
+    assert_eq!(index.soft_delete_batch(&[ids[1], ids[2]]).deleted, 2);
+    let doc = index
+        .insert_with_external_id("doc", &vector(6), &mut storage)
+        .unwrap();
+    index.upsert("other", &vector(7), &mut storage).unwrap();
+    index.delete_external("other").unwrap();
+    index
+        .batch_insert(
+            vec![(500, vector(8))],
+            &mut storage,
+            None::<fn(usize, usize)>,
+        )
+        .unwrap();
+    {
+        let mut metadata = index.metadata_logged_mut();
+        metadata
+            .insert(4, "tag", MetadataValue::Integer(1))
+            .unwrap();
+        metadata
+            .insert(5, "tag", MetadataValue::Integer(2))
+            .unwrap();
+        metadata
+            .update(4, "tag", MetadataValue::Integer(3))
+            .unwrap();
+        assert!(metadata.delete(5, "tag").unwrap());
+    }
+    index.enable_bq(&storage).unwrap();
+    drop((index, storage));
+
+    let (recovered, _storage) = recover_index(&snapshot, Box::new(wal)).unwrap();
+
+    assert_eq!(recovered.deleted_count(), 4);
+    for &id in &ids[..3] {
+        assert!(recovered.is_deleted(id).unwrap());
+    }
+    assert_eq!(
+        recovered.resolve_external_id(&ExternalId::from("doc")),
+        Some(doc)
+    );
+    assert_eq!(
+        recovered.resolve_external_id(&ExternalId::from("other")),
+        None
+    );
+    assert!(recovered
+        .resolve_external_id(&ExternalId::Num(500))
+        .is_some());
+    assert_eq!(
+        recovered.metadata().get(4, "tag"),
+        Some(&MetadataValue::Integer(3))
+    );
+    assert!(!recovered.metadata().has_key(5, "tag"));
+    assert!(recovered.has_bq());
+}
+
+#[test]
+fn test_records_after_torn_tail_survive_next_recovery() {
+    let (mut index, mut storage, wal) = logged_index();
+    let mut snapshot = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+    index.insert(&vector(0), &mut storage).unwrap();
+    index.insert(&vector(1), &mut storage).unwrap();
+    drop((index, storage));
+
+    let mut data = wal.read().unwrap();
+    data.truncate(data.len() - 3);
+    wal.atomic_write("", &data).unwrap();
+
+    let (mut index, mut storage, report) =
+        recover_index_with_report(&snapshot, Box::new(wal.clone()), RecoveryMode::Strict).unwrap();
+    assert!(report.is_torn_tail());
+    assert_eq!(index.node_count(), 1);
+
+    // The torn bytes were cut, so the new record is readable next time
+    index.insert(&vector(2), &mut storage).unwrap();
+    drop((index, storage));
+    let (index, storage, report) =
+        recover_index_with_report(&snapshot, Box::new(wal), RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(index.node_count(), 2);
+    assert_eq!(storage.get_vector(VectorId(2))[..], vector(2)[..]);
+}
+
+#[test]
+fn test_strict_recovery_rejects_mid_log_corruption() {
+    let (mut index, mut storage, wal) = logged_index();
+    let mut snapshot = MemoryBackend::new();
+    write_snapshot(&index, &storage, &mut snapshot).unwrap();
+    for i in 0..5 {
+        index.insert(&vector(i), &mut storage).unwrap();
+    }
+    drop((index, storage));
+
+    // Flip a payload byte in the second record
+    let mut data = wal.read().unwrap();
+    let record = data.len() / 5;
+    data[record + 20] ^= 0xFF;
+    wal.atomic_write("", &data).unwrap();
+
+    let result = recover_index_with_report(&snapshot, Box::new(wal.clone()), RecoveryMode::Strict);
+    assert!(matches!(result, Err(PersistenceError::Corrupted(_))));
+    // The rejected log is left as it was
+    assert_eq!(wal.read().unwrap(), data);
+
+    let (index, _storage, report) =
+        recover_index_with_report(&snapshot, Box::new(wal), RecoveryMode::Lenient).unwrap();
+    assert_eq!(index.node_count(), 1);
+    assert_eq!(report.entries_applied, 1);
+    assert_eq!(report.first_bad_offset, Some(record));
+    assert_eq!(report.bytes_discarded, 4 * record);
+    assert!(report.valid_records_after);
+}
+
+#[test]
+fn test_recover_flat_index_replays_deletes_and_metadata() {
+    let config = HnswConfig::new(DIM);
+    let wal = MemoryBackend::new();
+    let mut storage = VectorStorage::new(&config, Some(WalAppender::new(Box::new(wal.clone()), 0)));
+    let mut index = FlatIndex::new(config, &storage).unwrap();
+    let mut snapshot = MemoryBackend::new();
+
+    let mut ids = Vec::new();
+    for i in 0..4 {
+        ids.push(index.insert(&vector(i), &mut storage).unwrap());
+    }
+    write_flat_snapshot(&index, &storage, &mut snapshot).unwrap();
+
+    // Everything below is only in the WAL
+    let mut meta = HashMap::new();
+    meta.insert("kind".to_string(), MetadataValue::String("late".into()));
+    let late = index
+        .insert_with_metadata(&mut storage, &vector(4), meta)
+        .unwrap();
+    assert!(index.soft_delete(ids[1], &mut storage).unwrap());
+    assert!(!index.soft_delete(ids[1], &mut storage).unwrap());
+
+    let (recovered, recovered_storage, report) =
+        recover_flat_index(&snapshot, Box::new(wal.clone()), RecoveryMode::Strict).unwrap();
+    assert!(report.is_clean());
+    assert_eq!(recovered.len(), 4);
+    assert_eq!(recovered.deleted_count(), 1);
+    assert!(recovered_storage.is_deleted(ids[1]));
+    #[allow(clippy::cast_possible_truncation)]
+    let late_key = late.0 as u32;
+    assert_eq!(
+        recovered.metadata().get(late_key, "kind"),
+        Some(&MetadataValue::String("late".into()))
+    );
+
+    let results = recovered.search(&vector(1), 4, &recovered_storage).unwrap();
+    assert!(results.iter().all(|r| r.vector_id != ids[1]));
+}
+
+#[test]
+fn test_compaction_detaches_wal_until_saved_and_recovered() {
+    let (mut index, mut storage, _wal) = logged_index();
+    let ids: Vec<VectorId> = (0..5)
+        .map(|i| index.insert(&vector(i), &mut storage).unwrap())
+        .collect();
+    index.soft_delete(ids[0]).unwrap();
+
+    let (compacted, compacted_storage, _) = index.compact(&storage).unwrap();
+    assert!(!compacted_storage.has_wal());
+
+    // Save the compacted pair and start a fresh log from it
+    let mut snapshot = MemoryBackend::new();
+    write_snapshot(&compacted, &compacted_storage, &mut snapshot).unwrap();
+    let fresh_wal = MemoryBackend::new();
+    let (mut index, mut storage) = recover_index(&snapshot, Box::new(fresh_wal.clone())).unwrap();
+    assert!(storage.has_wal());
+
+    let late = index.insert(&vector(5), &mut storage).unwrap();
+    index.soft_delete(VectorId(1)).unwrap();
+
+    let (recovered, _storage) = recover_index(&snapshot, Box::new(fresh_wal)).unwrap();
+    assert_eq!(recovered.node_count(), 5);
+    assert!(recovered.is_deleted(VectorId(1)).unwrap());
+    assert!(!recovered.is_deleted(late).unwrap());
+}